@@ -0,0 +1,1063 @@
+use anyhow::{bail, Context, Result};
+use crate::attachments::Image;
+use crate::budget::Budget;
+use crate::converse::{ConverseClient, ConverseReply, Extras, Reasoning, Resume, ToolCall};
+use crate::fetch_url;
+use crate::file_backups::SessionBackups;
+use crate::file_edits::FileEdit;
+use crate::forced_tool::ForcedTool;
+use crate::guardrails::{Guardrail, GuardrailVerdict};
+use crate::hooks::{HookOutcome, Hooks, PostResponse, PostTool};
+use crate::inference::InferenceSettings;
+use crate::logging::{self, EVENT_TARGET, WIRE_TARGET};
+use crate::mcp_tool_dispatcher::McpToolDispatcher;
+use crate::models;
+use crate::redaction::Redactor;
+use crate::response_cache::ResponseCache;
+use crate::retry::RetryPolicy;
+use crate::sub_agent::{self, SubAgent};
+use crate::tokens;
+use crate::tool_result::{self, DEFAULT_MAX_TOOL_RESULT_BYTES};
+use crate::usage::TokenUsage;
+use futures::stream::{self, StreamExt};
+use gamecode_backend::{
+    ChatRequest, ChatResponse, ContentBlock, LLMBackend, Message as BackendMessage,
+    MessageRole as BackendMessageRole, StatusCallback, Tool as BackendTool,
+};
+use serde::Serialize;
+use serde_json::Value;
+use std::collections::HashSet;
+use std::future::Future;
+use std::pin::Pin;
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+use tracing::{debug, error, info};
+
+/// Default limit on model turns that end in tool calls before `Agent::run` stops
+pub const DEFAULT_MAX_TOOL_TURNS: usize = 25;
+
+/// Default number of consecutive failed tool calls before `Agent::run` gives up
+pub const DEFAULT_MAX_TOOL_FAILURES: usize = 3;
+
+/// Default number of tool calls from one response that run at once
+pub const DEFAULT_TOOL_CONCURRENCY: usize = 4;
+
+/// Something that happened during `Agent::run`, in the order it happened.
+/// Serializes with a `type` tag, like the CLI's NDJSON events.
+#[derive(Debug, Clone, Serialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum AgentEvent {
+    /// A request is about to be sent to `model`
+    Request { model: String },
+    /// The request failed and will be sent again after `delay_ms`
+    Retry { attempt: usize, max_attempts: usize, delay_ms: u64, reason: String },
+    /// `from` stayed unavailable, so the run continues with `to`
+    Fallback { from: String, to: String, error: String },
+    /// The following response was answered from the response cache
+    Cached { model: String },
+    /// The request is nearing the model's context window
+    ContextNearlyFull { model: String, tokens: u64, context_window: u64 },
+    /// A budget limit was reached; the run goes on because the budget is forced
+    BudgetExceeded { reason: String },
+    /// Tokens used by one backend request
+    Usage {
+        model: String,
+        #[serde(flatten)]
+        usage: TokenUsage,
+    },
+    /// Extended thinking behind the following response
+    Thinking { text: String },
+    /// Text of a model response
+    Text { text: String },
+    /// A guardrail replaced the prompt's answer or a response; `stage` is
+    /// `prompt` or `response`
+    Intervened { stage: String, policies: Vec<String> },
+    /// A response is complete; tool calls, if any, follow
+    ResponseEnd { stop_reason: StopReason },
+    /// The model answered without calling the forced tool and is asked again
+    Reminder { tool: String, answer: String, reminder: String },
+    /// The model called the extraction tool; `value` is the run's output
+    Extracted { id: String, name: String, value: Value },
+    /// The model asked for a tool to be run
+    ToolCall { id: String, name: String, input: Value },
+    /// What a tool call returned (or why it was refused), as sent back to the model
+    ToolResult { id: String, name: String, content: String, is_error: bool },
+    /// Every tool call of a response has its result
+    ToolTurnEnd { calls: usize },
+}
+
+/// Why a response ended, and why `Agent::run` returned
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum StopReason {
+    /// The model answered without asking for more tools
+    EndTurn,
+    /// The model asked for tools (only ever a response's reason)
+    ToolUse,
+    /// The response was cut at a stop sequence
+    StopSequence,
+    /// A guardrail intervened on the prompt or the response
+    Guardrail,
+    /// The `post_response` hook vetoed the response
+    Vetoed,
+    /// The model called the extraction tool
+    Extracted,
+    /// The model was still calling tools when the turn limit was reached
+    MaxToolTurns,
+}
+
+impl StopReason {
+    /// Name used by the CLI's NDJSON `message_end` events
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Self::EndTurn => "end_turn",
+            Self::ToolUse => "tool_use",
+            Self::StopSequence => "stop_sequence",
+            Self::Guardrail => "guardrail",
+            Self::Vetoed => "hook",
+            Self::Extracted => "extracted",
+            Self::MaxToolTurns => "max_tool_turns",
+        }
+    }
+}
+
+/// Summary of a finished `Agent::run`
+#[derive(Debug, Clone, Serialize)]
+pub struct RunOutcome {
+    /// Text of the final response; the extracted JSON for an extraction run
+    pub text: String,
+    pub stop_reason: StopReason,
+    /// Model that gave the final response, after any fallbacks
+    pub model: String,
+    pub usage: TokenUsage,
+    pub tool_turns: usize,
+}
+
+/// What to do with a tool call the model asked for
+#[derive(Debug, Clone)]
+pub enum ToolDecision {
+    Run,
+    /// Don't run it; the outcome is reported to the model instead
+    Refuse(ToolOutcome),
+}
+
+/// Decides whether a tool call may run; given the tool name and input
+pub type ToolPolicy = Box<dyn Fn(&str, &Value) -> Result<ToolDecision> + Send + Sync>;
+
+/// The agent loop without a terminal: send messages to the model, run the
+/// tools it asks for, feed the results back, and repeat until it answers.
+///
+/// Embedders pass the conversation in and receive `AgentEvent`s as it runs;
+/// the updated messages can be stored and passed to the next call.
+///
+/// ```ignore
+/// let backend = gamecode_cli::create_backend("us-west-2").await?;
+/// let agent = Agent::new(backend.into(), gamecode_cli::DEFAULT_MODEL)
+///     .tools(tools, dispatcher)
+///     .approve_tools_with(|name, _| name.starts_with("read_"));
+/// let mut messages = vec![BackendMessage::text(BackendMessageRole::User, prompt)];
+/// let outcome = agent.run(&mut messages, &mut |event| println!("{:?}", event)).await?;
+/// ```
+pub struct Agent {
+    backend: Arc<dyn LLMBackend>,
+    model: String,
+    fallback_models: Vec<String>,
+    tools: Vec<BackendTool>,
+    dispatcher: Option<Arc<McpToolDispatcher>>,
+    inference: InferenceSettings,
+    retry: RetryPolicy,
+    timeout: Option<Duration>,
+    max_tool_turns: usize,
+    max_tool_failures: usize,
+    tool_concurrency: usize,
+    max_tool_result_bytes: usize,
+    policy: ToolPolicy,
+    write_tools: HashSet<String>,
+    backups: Option<Mutex<SessionBackups>>,
+    redactor: Option<Arc<Redactor>>,
+    guardrail: Option<Arc<Guardrail>>,
+    budget: Option<Mutex<Budget>>,
+    hooks: Option<Hooks>,
+    sub_agent: Option<Arc<SubAgent>>,
+    forced: Option<ForcedTool>,
+    converse: Option<ConverseClient>,
+    images: Vec<Image>,
+    cache: Option<ResponseCache>,
+    status_callback: Option<StatusCallback>,
+    session: Option<String>,
+    verbose: bool,
+}
+
+impl Agent {
+    /// An agent with no tools that will answer with `model` (a Bedrock model ID)
+    pub fn new(backend: Arc<dyn LLMBackend>, model: impl Into<String>) -> Self {
+        Self {
+            backend,
+            model: model.into(),
+            fallback_models: Vec::new(),
+            tools: Vec::new(),
+            dispatcher: None,
+            inference: InferenceSettings::default(),
+            retry: RetryPolicy::default(),
+            timeout: None,
+            max_tool_turns: DEFAULT_MAX_TOOL_TURNS,
+            max_tool_failures: DEFAULT_MAX_TOOL_FAILURES,
+            tool_concurrency: DEFAULT_TOOL_CONCURRENCY,
+            max_tool_result_bytes: DEFAULT_MAX_TOOL_RESULT_BYTES,
+            policy: Box::new(|_, _| Ok(ToolDecision::Run)),
+            write_tools: HashSet::new(),
+            backups: None,
+            redactor: None,
+            guardrail: None,
+            budget: None,
+            hooks: None,
+            sub_agent: None,
+            forced: None,
+            converse: None,
+            images: Vec::new(),
+            cache: None,
+            status_callback: None,
+            session: None,
+            verbose: false,
+        }
+    }
+
+    /// Offer `tools` to the model and run them through `dispatcher`.
+    /// The built-in `fetch_url` tool needs no dispatcher.
+    pub fn tools(mut self, tools: Vec<BackendTool>, dispatcher: Option<Arc<McpToolDispatcher>>) -> Self {
+        self.tools = tools;
+        self.dispatcher = dispatcher;
+        self
+    }
+
+    /// Models (Bedrock IDs) to move on to, in order, when the current one
+    /// stays throttled or unavailable
+    pub fn fallback_models(mut self, models: Vec<String>) -> Self {
+        self.fallback_models = models;
+        self
+    }
+
+    pub fn inference(mut self, settings: InferenceSettings) -> Self {
+        self.inference = settings;
+        self
+    }
+
+    pub fn retry(mut self, retry: RetryPolicy) -> Self {
+        self.retry = retry;
+        self
+    }
+
+    /// Limit on each backend request (with its retries) and each tool call
+    pub fn timeout(mut self, timeout: Option<Duration>) -> Self {
+        self.timeout = timeout;
+        self
+    }
+
+    pub fn max_tool_turns(mut self, max_tool_turns: usize) -> Self {
+        self.max_tool_turns = max_tool_turns;
+        self
+    }
+
+    /// Fail the run after this many tool calls in a row have failed
+    pub fn max_tool_failures(mut self, max_tool_failures: usize) -> Self {
+        self.max_tool_failures = max_tool_failures;
+        self
+    }
+
+    /// How many tool calls from one response run at once
+    pub fn tool_concurrency(mut self, tool_concurrency: usize) -> Self {
+        self.tool_concurrency = tool_concurrency.max(1);
+        self
+    }
+
+    /// Cap on the size of each tool result sent back to the model
+    pub fn max_tool_result_bytes(mut self, max_bytes: usize) -> Self {
+        self.max_tool_result_bytes = max_bytes;
+        self
+    }
+
+    /// Ask `policy` before each tool call; refused calls are reported to the model
+    /// as denied by the user. By default every call runs.
+    pub fn approve_tools_with(
+        mut self,
+        policy: impl Fn(&str, &Value) -> bool + Send + Sync + 'static,
+    ) -> Self {
+        self.policy = Box::new(move |name, input| {
+            Ok(match policy(name, input) {
+                true => ToolDecision::Run,
+                false => ToolDecision::Refuse(ToolOutcome::success("Tool execution denied by the user".to_string())),
+            })
+        });
+        self
+    }
+
+    /// Like `approve_tools_with`, with the policy choosing what the model is
+    /// told about a refusal. Calls are decided one at a time, so the policy
+    /// may ask the user; an error from it ends the run.
+    pub fn gate_tools_with(
+        mut self,
+        policy: impl Fn(&str, &Value) -> Result<ToolDecision> + Send + Sync + 'static,
+    ) -> Self {
+        self.policy = Box::new(policy);
+        self
+    }
+
+    /// Back up the file a call to one of `write_tools` targets before it runs,
+    /// so `gamecode sessions rollback` can undo the change
+    pub fn backups(mut self, backups: SessionBackups, write_tools: HashSet<String>) -> Self {
+        self.backups = Some(Mutex::new(backups));
+        self.write_tools = write_tools;
+        self
+    }
+
+    /// Mask secrets in tool results before they are sent back to the model
+    pub fn redact_with(mut self, redactor: Arc<Redactor>) -> Self {
+        self.redactor = Some(redactor);
+        self
+    }
+
+    /// Check the prompt and each response against a Bedrock guardrail
+    pub fn guardrail(mut self, guardrail: Arc<Guardrail>) -> Self {
+        self.guardrail = Some(guardrail);
+        self
+    }
+
+    /// Check `budget` before each request and count each response against it
+    pub fn budget(mut self, budget: Budget) -> Self {
+        self.budget = Some(Mutex::new(budget));
+        self
+    }
+
+    /// Pass responses and tool results through the `post_response` and
+    /// `post_tool` hooks
+    pub fn hooks(mut self, hooks: Hooks) -> Self {
+        self.hooks = Some(hooks);
+        self
+    }
+
+    /// Run calls to the `agent` tool with `sub_agent`; its schema must be
+    /// among the offered tools
+    pub fn sub_agent(mut self, sub_agent: Arc<SubAgent>) -> Self {
+        self.sub_agent = Some(sub_agent);
+        self
+    }
+
+    /// Offer only `forced` until the model calls it. With a Converse client
+    /// the request requires the call; the backend can't, so there the prompt
+    /// should already ask for it (`ForcedTool::instruction`).
+    pub fn force_tool(mut self, forced: ForcedTool) -> Self {
+        self.forced = Some(forced);
+        self
+    }
+
+    /// Send requests to the Converse API through `client` instead of the
+    /// backend, as runs with stop sequences, cache points or extended
+    /// thinking need. The response cache isn't used.
+    pub fn converse(mut self, client: ConverseClient) -> Self {
+        self.converse = Some(client);
+        self
+    }
+
+    /// Send `images` with the last user message at the start of a run.
+    /// Needs a Converse client: the backend's messages can't carry images.
+    pub fn images(mut self, images: Vec<Image>) -> Self {
+        self.images = images;
+        self
+    }
+
+    /// Answer repeated requests from `cache`
+    pub fn cache(mut self, cache: ResponseCache) -> Self {
+        self.cache = Some(cache);
+        self
+    }
+
+    /// Passed to the backend with each request
+    pub fn status_callback(mut self, callback: StatusCallback) -> Self {
+        self.status_callback = Some(callback);
+        self
+    }
+
+    /// Session the run belongs to, for the event log
+    pub fn session(mut self, session_id: uuid::Uuid) -> Self {
+        self.session = Some(session_id.to_string());
+        self
+    }
+
+    /// Show full tool results as they arrive
+    pub fn verbose(mut self, verbose: bool) -> Self {
+        self.verbose = verbose;
+        self
+    }
+
+    pub fn model(&self) -> &str {
+        &self.model
+    }
+
+    /// Tools offered to the model
+    pub fn offered_tools(&self) -> &[BackendTool] {
+        match &self.forced {
+            Some(forced) => self.forced_tools(forced),
+            None => &self.tools,
+        }
+    }
+
+    fn forced_tools(&self, forced: &ForcedTool) -> &[BackendTool] {
+        let start = self.tools.iter().position(|t| t.name == forced.name).unwrap_or(self.tools.len());
+        &self.tools[start..(start + 1).min(self.tools.len())]
+    }
+
+    /// Run the conversation in `messages` until the model stops calling tools.
+    /// Responses and tool results are appended to `messages`.
+    pub async fn run(
+        &self,
+        messages: &mut Vec<BackendMessage>,
+        on_event: &mut (dyn FnMut(AgentEvent) + Send),
+    ) -> Result<RunOutcome> {
+        self.run_turns(messages, on_event, true).await
+    }
+
+    /// Send `messages` once without offering tools, e.g. to ask for a plan.
+    /// The response is appended to `messages`.
+    pub async fn ask(
+        &self,
+        messages: &mut Vec<BackendMessage>,
+        on_event: &mut (dyn FnMut(AgentEvent) + Send),
+    ) -> Result<RunOutcome> {
+        self.run_turns(messages, on_event, false).await
+    }
+
+    async fn run_turns(
+        &self,
+        messages: &mut Vec<BackendMessage>,
+        on_event: &mut (dyn FnMut(AgentEvent) + Send),
+        use_tools: bool,
+    ) -> Result<RunOutcome> {
+        let mut outcome = RunOutcome {
+            text: String::new(),
+            stop_reason: StopReason::EndTurn,
+            model: self.model.clone(),
+            usage: TokenUsage::default(),
+            tool_turns: 0,
+        };
+
+        // A prompt the guardrail intervenes on is answered by the guardrail
+        if let Some(guardrail) = &self.guardrail {
+            let prompt = messages
+                .last()
+                .filter(|m| matches!(m.role, BackendMessageRole::User))
+                .map(message_text)
+                .filter(|text| !text.is_empty());
+            if let Some(prompt) = prompt {
+                debug!("Checking prompt against guardrail {}", guardrail.id());
+                if let GuardrailVerdict::Intervened { output, policies } = guardrail.check_input(&prompt).await? {
+                    on_event(AgentEvent::Intervened { stage: "prompt".to_string(), policies });
+                    on_event(AgentEvent::Text { text: output.clone() });
+                    on_event(AgentEvent::ResponseEnd { stop_reason: StopReason::Guardrail });
+                    outcome.text = output;
+                    outcome.stop_reason = StopReason::Guardrail;
+                    return Ok(outcome);
+                }
+            }
+        }
+
+        let mut fallback_models = self.fallback_models.clone();
+        let mut forcing = self.forced.as_ref().filter(|_| use_tools);
+        let mut reminded = false;
+        let mut context_warned = false;
+        let mut consecutive_failures = 0;
+        let mut extras = Extras::default();
+        if !self.images.is_empty() {
+            if self.converse.is_none() {
+                bail!("Images need a Converse client (Agent::converse); the backend can't send them");
+            }
+            if let Some(prompt) = messages.iter().rposition(|m| matches!(m.role, BackendMessageRole::User)) {
+                extras.images.push((prompt, self.images.clone()));
+            }
+        }
+
+        loop {
+            let tools: &[BackendTool] = match forcing {
+                _ if !use_tools => &[],
+                Some(forced) => self.forced_tools(forced),
+                None => &self.tools,
+            };
+
+            // Stop runaway loops before they cost more than the configured budget
+            if let Some(budget) = &self.budget {
+                let exceeded = budget.lock().map_err(|_| anyhow::anyhow!("Budget lock poisoned"))?.check()?;
+                if let Some(reason) = exceeded {
+                    on_event(AgentEvent::BudgetExceeded { reason });
+                }
+            }
+
+            // Warn once when the request approaches the model's context window
+            if let Some(info) = models::lookup(&outcome.model).filter(|_| !context_warned) {
+                let request_tokens = tokens::count_messages(messages) + tokens::count_tools(tools);
+                let context_window = info.context_window as u64;
+                if request_tokens * 100 >= context_window * tokens::CONTEXT_WARNING_PERCENT {
+                    on_event(AgentEvent::ContextNearlyFull {
+                        model: outcome.model.clone(),
+                        tokens: request_tokens,
+                        context_window,
+                    });
+                    context_warned = true;
+                }
+            }
+
+            on_event(AgentEvent::Request { model: outcome.model.clone() });
+            let reply = self
+                .request(&mut outcome.model, &mut fallback_models, messages, &extras, tools, forcing, on_event)
+                .await?;
+
+            if let Some(usage) = reply.usage {
+                outcome.usage.add(usage);
+                if let Some(budget) = &self.budget {
+                    if let Ok(mut budget) = budget.lock() {
+                        budget.add(&outcome.model, usage);
+                    }
+                }
+                on_event(AgentEvent::Usage { model: outcome.model.clone(), usage });
+            }
+            if !reply.thinking.trim().is_empty() {
+                on_event(AgentEvent::Thinking { text: reply.thinking.trim().to_string() });
+            }
+
+            let mut text = reply.text.clone();
+            let mut stop_reason = if reply.stopped {
+                StopReason::StopSequence
+            } else if reply.tool_calls.is_empty() {
+                StopReason::EndTurn
+            } else {
+                StopReason::ToolUse
+            };
+
+            // A guardrail intervention replaces the response and ends the turn
+            if let Some(guardrail) = self.guardrail.as_ref().filter(|_| !text.is_empty()) {
+                if let GuardrailVerdict::Intervened { output, policies } = guardrail.check_output(&text).await? {
+                    on_event(AgentEvent::Intervened { stage: "response".to_string(), policies });
+                    text = output;
+                    stop_reason = StopReason::Guardrail;
+                }
+            }
+
+            // So does a veto from the post_response hook, which drops the response
+            if let Some(hooks) = self.hooks.as_ref().filter(|_| stop_reason != StopReason::Guardrail && !text.is_empty()) {
+                let post_response = PostResponse { model: outcome.model.clone(), text: std::mem::take(&mut text) };
+                match hooks.post_response(post_response).await? {
+                    HookOutcome::Continue(checked) => text = checked.text,
+                    HookOutcome::Veto(reason) => {
+                        notice!("🚫 Response withheld by the post_response hook: {}", reason);
+                        stop_reason = StopReason::Vetoed;
+                    }
+                }
+            }
+
+            if !text.is_empty() {
+                on_event(AgentEvent::Text { text: text.clone() });
+            }
+            on_event(AgentEvent::ResponseEnd { stop_reason });
+            outcome.text = text.clone();
+
+            // Until the forced tool is called, a text answer gets one reminder.
+            // Through Converse the call is required, so skipping it is an error
+            let answered = matches!(stop_reason, StopReason::EndTurn | StopReason::ToolUse);
+            if let Some(forced) = forcing.filter(|_| answered) {
+                match reply.tool_calls.iter().find(|call| call.name == forced.name) {
+                    Some(call) if forced.is_extraction() => {
+                        let value = forced.extracted(&call.input);
+                        outcome.text = serde_json::to_string_pretty(&value)?;
+                        messages.push(BackendMessage::text(BackendMessageRole::Assistant, outcome.text.clone()));
+                        on_event(AgentEvent::Extracted { id: call.id.clone(), name: call.name.clone(), value });
+                        outcome.stop_reason = StopReason::Extracted;
+                        return Ok(outcome);
+                    }
+                    Some(_) => forcing = None,
+                    None if self.converse.is_some() => {
+                        bail!("{} answered without calling {}, which the request required", outcome.model, forced.name);
+                    }
+                    None if !reminded => {
+                        reminded = true;
+                        notice!("↩️  {} answered without calling {}; asking again", outcome.model, forced.name);
+                        // Turns must alternate, so an empty answer still gets a message
+                        let answer = if text.is_empty() { "(no answer)".to_string() } else { text };
+                        messages.push(BackendMessage::text(BackendMessageRole::Assistant, answer.clone()));
+                        messages.push(BackendMessage::text(BackendMessageRole::User, forced.reminder()));
+                        on_event(AgentEvent::Reminder {
+                            tool: forced.name.clone(),
+                            answer,
+                            reminder: forced.reminder(),
+                        });
+                        continue;
+                    }
+                    None if forced.is_extraction() => {
+                        bail!("{} answered without calling {}; no structured output", outcome.model, forced.name);
+                    }
+                    None => notice!("⚠️  {} answered without calling {}", outcome.model, forced.name),
+                }
+            }
+
+            if stop_reason != StopReason::ToolUse {
+                if !text.is_empty() {
+                    messages.push(BackendMessage::text(BackendMessageRole::Assistant, text));
+                }
+                outcome.stop_reason = stop_reason;
+                return Ok(outcome);
+            }
+            extras.tool_calls.push((messages.len(), reply.tool_calls.clone()));
+            if !reply.reasoning.is_empty() {
+                extras.reasoning.push((messages.len(), reply.reasoning.clone()));
+            }
+            messages.push(BackendMessage {
+                role: BackendMessageRole::Assistant,
+                content: reply.content.clone(),
+            });
+
+            // Decide on each tool call first (the policy may ask the user, one
+            // call at a time), then run the approved ones concurrently
+            let mut decisions = Vec::new();
+            for call in &reply.tool_calls {
+                on_event(AgentEvent::ToolCall {
+                    id: call.id.clone(),
+                    name: call.name.clone(),
+                    input: call.input.clone(),
+                });
+                let decision = if !tools.iter().any(|tool| tool.name == call.name) {
+                    notice!("\n🚫 Tool {} is not available in this run", call.name);
+                    ToolDecision::Refuse(ToolOutcome::error(format!(
+                        "Tool execution refused: '{}' is not an available tool",
+                        call.name
+                    )))
+                } else {
+                    (self.policy)(&call.name, &call.input)?
+                };
+                if matches!(decision, ToolDecision::Run) {
+                    self.back_up(call);
+                }
+                decisions.push(decision);
+            }
+            let outcomes: Vec<ToolOutcome> = stream::iter(reply.tool_calls.iter().zip(decisions))
+                .map(|(call, decision)| async move {
+                    match decision {
+                        ToolDecision::Run => self.call_tool(call).await,
+                        ToolDecision::Refuse(outcome) => outcome,
+                    }
+                })
+                .buffered(self.tool_concurrency)
+                .collect()
+                .await;
+
+            // Results are reported in the order the model requested them
+            let mut tool_results = Vec::new();
+            for (call, tool_outcome) in reply.tool_calls.iter().zip(outcomes) {
+                if tool_outcome.is_error {
+                    consecutive_failures += 1;
+                } else {
+                    consecutive_failures = 0;
+                }
+
+                let mut result = match tool_result::truncate_middle(&tool_outcome.content, self.max_tool_result_bytes) {
+                    Some(truncated) => {
+                        if self.verbose {
+                            notice!(
+                                "✂️  Truncated result of {} from {} to {} bytes",
+                                call.name,
+                                tool_outcome.content.len(),
+                                truncated.len()
+                            );
+                        }
+                        truncated
+                    }
+                    None => tool_outcome.content,
+                };
+                if let Some(redactor) = &self.redactor {
+                    result = redactor.redact_reporting(&result, &format!("result of {}", call.name));
+                }
+                let mut is_error = tool_outcome.is_error;
+                if let Some(hooks) = &self.hooks {
+                    let post_tool = PostTool {
+                        tool: call.name.clone(),
+                        input: call.input.clone(),
+                        result,
+                        is_error,
+                    };
+                    (result, is_error) = match hooks.post_tool(post_tool).await? {
+                        HookOutcome::Continue(checked) => (checked.result, checked.is_error),
+                        HookOutcome::Veto(reason) => {
+                            notice!("🚫 Result of {} withheld by the post_tool hook: {}", call.name, reason);
+                            (format!("Tool result withheld by policy: {}", reason), true)
+                        }
+                    };
+                }
+
+                on_event(AgentEvent::ToolResult {
+                    id: call.id.clone(),
+                    name: call.name.clone(),
+                    content: result.clone(),
+                    is_error,
+                });
+                tool_results.push(ContentBlock::ToolResult {
+                    tool_call_id: call.id.clone(),
+                    result,
+                });
+            }
+
+            messages.push(BackendMessage {
+                role: BackendMessageRole::User,
+                content: tool_results,
+            });
+            on_event(AgentEvent::ToolTurnEnd { calls: reply.tool_calls.len() });
+            outcome.tool_turns += 1;
+
+            if outcome.tool_turns >= self.max_tool_turns {
+                outcome.stop_reason = StopReason::MaxToolTurns;
+                return Ok(outcome);
+            }
+            // Tool failures are reported back to the model so it can recover;
+            // only give up when it keeps failing
+            if consecutive_failures >= self.max_tool_failures {
+                bail!("Aborting after {} consecutive tool failures", consecutive_failures);
+            }
+        }
+    }
+
+    /// One response from the backend (or the Converse client), with retries,
+    /// the request timeout, the response cache, and fallback models when
+    /// `model` stays unavailable. `model` is updated to the model that answered.
+    async fn request(
+        &self,
+        model: &mut String,
+        fallback_models: &mut Vec<String>,
+        messages: &[BackendMessage],
+        extras: &Extras,
+        tools: &[BackendTool],
+        forcing: Option<&ForcedTool>,
+        on_event: &mut (dyn FnMut(AgentEvent) + Send),
+    ) -> Result<Reply> {
+        let session = self.session.as_deref().unwrap_or_default();
+        if self.converse.is_none() && self.inference.stop_sequences.as_ref().is_some_and(|stops| !stops.is_empty()) {
+            bail!("Stop sequences need a Converse client (Agent::converse); the backend can't send them");
+        }
+        loop {
+            let offered = (!tools.is_empty()).then_some(tools);
+            let cache_key = self
+                .cache
+                .as_ref()
+                .filter(|_| self.converse.is_none())
+                .map(|_| ResponseCache::key(model, messages, offered, &self.inference));
+            if let Some(response) = cache_key.as_ref().and_then(|key| self.cache.as_ref()?.load(key)) {
+                info!(target: EVENT_TARGET, session, model = %model, "cached response");
+                on_event(AgentEvent::Cached { model: model.clone() });
+                return Ok(Reply::from_response(&response));
+            }
+
+            info!(target: EVENT_TARGET, session, model = %model, messages = messages.len(), "model request");
+            if tracing::enabled!(target: WIRE_TARGET, tracing::Level::TRACE) {
+                let payload = format!(
+                    "model={} inference={:?} messages={:?} tools={:?}",
+                    model, self.inference, messages, offered
+                );
+                tracing::trace!(target: WIRE_TARGET, "Bedrock request: {}", logging::redact(&payload));
+            }
+            let started = Instant::now();
+            let mut on_retry = |attempt: usize, max_attempts: usize, delay: Duration, reason: &str| {
+                let delay_ms = delay.as_millis() as u64;
+                tracing::warn!(target: EVENT_TARGET, attempt, max_attempts, delay_ms, reason, "retrying request");
+                on_event(AgentEvent::Retry { attempt, max_attempts, delay_ms, reason: reason.to_string() });
+            };
+            let result = match &self.converse {
+                Some(client) => {
+                    // A retry continues the answer a dropped stream left off
+                    let resume = Resume::default();
+                    let request = self.retry.run(
+                        || {
+                            let force = forcing.filter(|_| offered.is_some()).map(|forced| forced.name.as_str());
+                            client.chat(model, messages, extras, offered.unwrap_or_default(), force, &self.inference, &resume)
+                        },
+                        &mut on_retry,
+                    );
+                    with_timeout(self.timeout, request)
+                        .await
+                        .map(|result| result.map(Reply::from_converse))
+                }
+                None => {
+                    let chat_request = || ChatRequest {
+                        messages: messages.to_vec(),
+                        tools: offered.map(<[BackendTool]>::to_vec),
+                        model: Some(model.clone()),
+                        inference_config: Some(self.inference.to_backend()),
+                        session_id: None,
+                        status_callback: self.status_callback.clone(),
+                    };
+                    let request = self.retry.run(
+                        || self.backend.chat_with_retry(chat_request(), self.retry.single_attempt()),
+                        &mut on_retry,
+                    );
+                    with_timeout(self.timeout, request).await.map(|result| {
+                        let response = result?;
+                        if tracing::enabled!(target: WIRE_TARGET, tracing::Level::TRACE) {
+                            let payload = logging::redact(&format!("{:?}", response));
+                            tracing::trace!(target: WIRE_TARGET, "Bedrock response: {}", payload);
+                        }
+                        if let (Some(cache), Some(key)) = (&self.cache, &cache_key) {
+                            if let Err(e) = cache.store(key, model, &response) {
+                                debug!("Failed to cache response: {:#}", e);
+                            }
+                        }
+                        Ok(Reply::from_response(&response))
+                    })
+                }
+            };
+            let Some(result) = result else {
+                bail!(
+                    "Request to {} timed out after {}s",
+                    model,
+                    self.timeout.unwrap_or_default().as_secs()
+                );
+            };
+
+            match result {
+                Ok(reply) => {
+                    info!(
+                        target: EVENT_TARGET,
+                        session,
+                        model = %model,
+                        duration_ms = started.elapsed().as_millis() as u64,
+                        input_tokens = reply.usage.map(|u| u.input_tokens),
+                        output_tokens = reply.usage.map(|u| u.output_tokens),
+                        tool_calls = reply.tool_calls.len(),
+                        "model response"
+                    );
+                    return Ok(reply);
+                }
+                Err(e) => {
+                    error!(target: EVENT_TARGET, session, model = %model, error = %e, "model request failed");
+                    if fallback_models.is_empty() || !is_model_unavailable(&e.to_string()) {
+                        return Err(e).context("Failed to get response from backend");
+                    }
+                    let next = fallback_models.remove(0);
+                    on_event(AgentEvent::Fallback { from: model.clone(), to: next.clone(), error: format!("{:#}", e) });
+                    *model = next;
+                }
+            }
+        }
+    }
+
+    // Keep a shadow copy of the file a write tool is about to change
+    fn back_up(&self, call: &ToolCall) {
+        let Some(backups) = self.backups.as_ref().filter(|_| self.write_tools.contains(&call.name)) else {
+            return;
+        };
+        let Some(path) = FileEdit::target_path(&call.input) else {
+            return;
+        };
+        if let Ok(mut backups) = backups.lock() {
+            if let Err(e) = backups.backup(&path) {
+                notice!("⚠️  Failed to back up {}: {}", path.display(), e);
+            }
+        }
+    }
+
+    async fn call_tool(&self, call: &ToolCall) -> ToolOutcome {
+        debug!("Executing tool: {}", call.name);
+        let run = async {
+            match self.sub_agent.as_ref().filter(|_| call.name == sub_agent::TOOL_NAME) {
+                Some(sub_agent) => {
+                    // Boxed because the sub-agent runs an `Agent` of its own
+                    let call: Pin<Box<dyn Future<Output = ToolOutcome> + Send + '_>> =
+                        Box::pin(sub_agent.call(&call.input));
+                    call.await
+                }
+                None => execute_tool(self.dispatcher.as_deref(), &call.name, call.input.clone(), self.verbose).await,
+            }
+        };
+        with_timeout(self.timeout, run).await.unwrap_or_else(|| {
+            ToolOutcome::error(format!(
+                "Tool {} timed out after {}s",
+                call.name,
+                self.timeout.unwrap_or_default().as_secs()
+            ))
+        })
+    }
+}
+
+/// A model response, from either the backend or the Converse client
+struct Reply {
+    content: Vec<ContentBlock>,
+    text: String,
+    thinking: String,
+    reasoning: Vec<Reasoning>,
+    tool_calls: Vec<ToolCall>,
+    usage: Option<TokenUsage>,
+    /// Generation ended at a stop sequence
+    stopped: bool,
+}
+
+impl Reply {
+    fn from_response(response: &ChatResponse) -> Self {
+        Self {
+            content: response.message.content.clone(),
+            text: message_text(&response.message),
+            thinking: String::new(),
+            reasoning: Vec::new(),
+            tool_calls: response
+                .tool_calls
+                .iter()
+                .map(|call| ToolCall { id: call.id.clone(), name: call.name.clone(), input: call.input.clone() })
+                .collect(),
+            usage: response.usage.as_ref().map(|reported| TokenUsage {
+                input_tokens: reported.input_tokens as u64,
+                output_tokens: reported.output_tokens as u64,
+                ..Default::default()
+            }),
+            stopped: false,
+        }
+    }
+
+    // Tool calls and reasoning are kept in `Extras`, as the backend's messages can't carry them
+    fn from_converse(reply: ConverseReply) -> Self {
+        Self {
+            content: vec![ContentBlock::Text(reply.text.clone())],
+            text: reply.text,
+            thinking: reply.thinking,
+            reasoning: reply.reasoning,
+            tool_calls: reply.tool_calls,
+            usage: Some(reply.usage),
+            stopped: reply.stopped,
+        }
+    }
+}
+
+// Text blocks of a message, joined
+fn message_text(message: &BackendMessage) -> String {
+    message
+        .content
+        .iter()
+        .filter_map(|block| match block {
+            ContentBlock::Text(text) => Some(text.as_str()),
+            _ => None,
+        })
+        .collect::<Vec<_>>()
+        .join("")
+}
+
+/// Result of a single tool call, as reported back to the model
+#[derive(Debug, Clone)]
+pub struct ToolOutcome {
+    pub content: String,
+    pub is_error: bool,
+}
+
+impl ToolOutcome {
+    pub fn success(content: String) -> Self {
+        Self { content, is_error: false }
+    }
+
+    pub fn error(content: String) -> Self {
+        Self { content, is_error: true }
+    }
+}
+
+/// Run `future` to completion, or return `None` if `limit` elapses first
+pub async fn with_timeout<F: std::future::Future>(limit: Option<Duration>, future: F) -> Option<F::Output> {
+    match limit {
+        Some(limit) => tokio::time::timeout(limit, future).await.ok(),
+        None => Some(future.await),
+    }
+}
+
+/// Execute a tool call, converting every failure into an error result the
+/// model can see instead of aborting the conversation
+pub async fn execute_tool(
+    dispatcher: Option<&McpToolDispatcher>,
+    tool_name: &str,
+    input: Value,
+    verbose: bool,
+) -> ToolOutcome {
+    let started = Instant::now();
+    info!(target: EVENT_TARGET, tool = tool_name, input = %input, "tool call");
+    let outcome = run_tool(dispatcher, tool_name, input, verbose).await;
+    info!(
+        target: EVENT_TARGET,
+        tool = tool_name,
+        is_error = outcome.is_error,
+        duration_ms = started.elapsed().as_millis() as u64,
+        result_bytes = outcome.content.len(),
+        "tool result"
+    );
+    outcome
+}
+
+async fn run_tool(
+    dispatcher: Option<&McpToolDispatcher>,
+    tool_name: &str,
+    input: Value,
+    verbose: bool,
+) -> ToolOutcome {
+    if tool_name == fetch_url::TOOL_NAME {
+        return match fetch_url::call(&input).await {
+            Ok(text) => {
+                status!("\n✅ Tool {} completed successfully", tool_name);
+                ToolOutcome::success(text)
+            }
+            Err(e) => {
+                notice!("\n❌ Tool error: {:#}", e);
+                ToolOutcome::error(format!("Tool execution failed: {:#}", e))
+            }
+        };
+    }
+
+    let Some(dispatcher) = dispatcher else {
+        notice!("\n❌ No tool dispatcher available");
+        return ToolOutcome::error("Tool execution failed: no dispatcher".to_string());
+    };
+
+    match dispatcher.call_tool(tool_name, input).await {
+        Ok(result) => {
+            // Show results based on verbosity
+            let result_str = match &result {
+                Value::String(s) => s.clone(),
+                other => serde_json::to_string_pretty(other)
+                    .unwrap_or_else(|_| "null".to_string()),
+            };
+
+            // MCP servers report tool-level failures in the result rather than as JSON-RPC errors
+            if result.get("isError").and_then(|v| v.as_bool()) == Some(true) {
+                notice!("\n❌ Tool {} reported an error", tool_name);
+                if verbose {
+                    eprintln!("{}", result_str);
+                }
+                return ToolOutcome::error(format!("Tool execution failed: {}", result_str));
+            }
+
+            if verbose {
+                status!("\n✅ Tool result for {}: {}", tool_name, result_str);
+            } else {
+                status!("\n✅ Tool {} completed successfully", tool_name);
+            }
+            ToolOutcome::success(result_str)
+        }
+        Err(e) => {
+            notice!("\n❌ Tool error: {}", e);
+            ToolOutcome::error(format!("Tool execution failed: {}", e))
+        }
+    }
+}
+
+/// Errors after which retrying the same model won't help but another model may succeed
+pub fn is_model_unavailable(error: &str) -> bool {
+    const PATTERNS: &[&str] = &[
+        "throttl",
+        "too many requests",
+        "rate limit",
+        "serviceunavailable",
+        "service unavailable",
+        "modelnotready",
+        "not ready",
+        "not available",
+        "not supported in this region",
+        "don't have access to the model",
+        "modeltimeout",
+    ];
+    let error = error.to_lowercase();
+    PATTERNS.iter().any(|pattern| error.contains(pattern))
+}
@@ -0,0 +1,96 @@
+use anyhow::{Context, Result};
+use std::fs;
+use std::path::Path;
+
+/// Largest file `--file` will attach
+pub const MAX_ATTACHMENT_BYTES: u64 = 256 * 1024;
+
+/// Bytes inspected when deciding whether a file is binary
+const BINARY_SNIFF_BYTES: usize = 8 * 1024;
+
+/// Largest image `--image` will attach, Bedrock's limit per image
+pub const MAX_IMAGE_BYTES: u64 = 3_750_000;
+
+/// Image formats `--image` accepts
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ImageFormat {
+    Png,
+    Jpeg,
+}
+
+/// An image to send with the prompt
+#[derive(Debug, Clone)]
+pub struct Image {
+    pub format: ImageFormat,
+    pub bytes: Vec<u8>,
+}
+
+/// Wrap content in a labelled code fence long enough not to clash with its contents
+pub fn fenced_block(label: &str, language: &str, content: &str) -> String {
+    let mut fence = "```".to_string();
+    while content.contains(&fence) {
+        fence.push('`');
+    }
+    format!(
+        "{}:\n{}{}\n{}\n{}",
+        label,
+        fence,
+        language,
+        content.trim_end(),
+        fence
+    )
+}
+
+/// Read a text file and frame it as a code block labelled with its path
+pub fn attach_file(path: &Path) -> Result<String> {
+    let metadata = fs::metadata(path)
+        .with_context(|| format!("Cannot attach {}", path.display()))?;
+    if !metadata.is_file() {
+        anyhow::bail!("Cannot attach {}: not a regular file", path.display());
+    }
+    if metadata.len() > MAX_ATTACHMENT_BYTES {
+        anyhow::bail!(
+            "Cannot attach {}: {} bytes exceeds the {} byte limit",
+            path.display(),
+            metadata.len(),
+            MAX_ATTACHMENT_BYTES
+        );
+    }
+
+    let bytes = fs::read(path).with_context(|| format!("Failed to read {}", path.display()))?;
+    if bytes[..bytes.len().min(BINARY_SNIFF_BYTES)].contains(&0) {
+        anyhow::bail!("Cannot attach {}: looks like a binary file", path.display());
+    }
+    let content = String::from_utf8(bytes)
+        .map_err(|_| anyhow::anyhow!("Cannot attach {}: not valid UTF-8 text", path.display()))?;
+
+    let language = path.extension().and_then(|e| e.to_str()).unwrap_or("");
+    Ok(fenced_block(&path.display().to_string(), language, &content))
+}
+
+/// Read a PNG or JPEG image, recognized by its contents rather than its extension
+pub fn attach_image(path: &Path) -> Result<Image> {
+    let metadata = fs::metadata(path)
+        .with_context(|| format!("Cannot attach {}", path.display()))?;
+    if !metadata.is_file() {
+        anyhow::bail!("Cannot attach {}: not a regular file", path.display());
+    }
+    if metadata.len() > MAX_IMAGE_BYTES {
+        anyhow::bail!(
+            "Cannot attach {}: {} bytes exceeds the {} byte limit for images",
+            path.display(),
+            metadata.len(),
+            MAX_IMAGE_BYTES
+        );
+    }
+
+    let bytes = fs::read(path).with_context(|| format!("Failed to read {}", path.display()))?;
+    let format = if bytes.starts_with(b"\x89PNG\r\n\x1a\n") {
+        ImageFormat::Png
+    } else if bytes.starts_with(&[0xFF, 0xD8, 0xFF]) {
+        ImageFormat::Jpeg
+    } else {
+        anyhow::bail!("Cannot attach {}: not a PNG or JPEG image", path.display());
+    };
+    Ok(Image { format, bytes })
+}
@@ -0,0 +1,77 @@
+use anyhow::{Context, Result};
+use aws_config::sts::AssumeRoleProvider;
+use aws_config::{BehaviorVersion, Region, SdkConfig};
+use aws_credential_types::provider::{ProvideCredentials, SharedCredentialsProvider};
+use tracing::debug;
+
+/// Session name recorded in CloudTrail for assumed-role calls
+const ROLE_SESSION_NAME: &str = "gamecode-cli";
+
+/// AWS credential selection from `--aws-profile` / `--assume-role`
+#[derive(Debug, Default, Clone)]
+pub struct AwsAuth {
+    pub profile: Option<String>,
+    pub assume_role: Option<String>,
+}
+
+impl AwsAuth {
+    pub fn new(profile: Option<String>, assume_role: Option<String>) -> Self {
+        Self {
+            profile,
+            assume_role,
+        }
+    }
+
+    /// Load the AWS configuration for `region` with the selected credentials.
+    /// Every AWS client built from it (Bedrock, S3, guardrails) uses the
+    /// profile or assumed role, without touching the process environment.
+    pub async fn sdk_config(&self, region: &str) -> Result<SdkConfig> {
+        let mut loader = aws_config::defaults(BehaviorVersion::latest())
+            .region(Region::new(region.to_string()));
+        if let Some(profile) = &self.profile {
+            debug!("Using AWS profile: {}", profile);
+            loader = loader.profile_name(profile);
+        }
+        let base_config = loader.load().await;
+
+        let Some(role_arn) = &self.assume_role else {
+            return Ok(base_config);
+        };
+
+        debug!("Assuming role: {}", role_arn);
+        let provider = AssumeRoleProvider::builder(role_arn.clone())
+            .session_name(ROLE_SESSION_NAME)
+            .region(Region::new(region.to_string()))
+            .configure(&base_config)
+            .build()
+            .await;
+        // Assume the role up front so a bad ARN fails here rather than on
+        // the first request. Clients cache the credentials and refresh them
+        // through the provider when they expire
+        provider
+            .provide_credentials()
+            .await
+            .with_context(|| format!("Failed to assume role {}", role_arn))?;
+
+        Ok(base_config
+            .into_builder()
+            .credentials_provider(SharedCredentialsProvider::new(provider))
+            .build())
+    }
+}
+
+/// Fail unless the default provider chain can resolve credentials
+pub async fn check_credentials(region: &str) -> Result<()> {
+    let config = aws_config::defaults(BehaviorVersion::latest())
+        .region(Region::new(region.to_string()))
+        .load()
+        .await;
+    let provider = config
+        .credentials_provider()
+        .context("No AWS credential provider is configured")?;
+    provider
+        .provide_credentials()
+        .await
+        .context("No AWS credentials found")?;
+    Ok(())
+}
@@ -0,0 +1,132 @@
+use anyhow::Result;
+use crate::usage::{TokenUsage, UsageLedger, UsageRecord};
+use serde::{Deserialize, Serialize};
+use uuid::Uuid;
+
+/// Token and cost limits from the `[budget]` section of config
+#[derive(Debug, Default, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct BudgetConfig {
+    /// Maximum tokens (input + output) per session
+    pub session_tokens: Option<u64>,
+    /// Maximum estimated cost in USD per session
+    pub session_cost: Option<f64>,
+    /// Maximum tokens across all sessions per local calendar day
+    pub daily_tokens: Option<u64>,
+    /// Maximum estimated cost in USD across all sessions per local calendar day
+    pub daily_cost: Option<f64>,
+}
+
+/// Tokens and estimated cost accumulated against a budget
+#[derive(Debug, Default, Clone, Copy)]
+pub struct Spend {
+    pub tokens: u64,
+    pub cost: f64,
+}
+
+impl Spend {
+    pub fn add(&mut self, model: &str, usage: TokenUsage) {
+        self.tokens += usage.total();
+        self.cost += usage.cost(model).unwrap_or(0.0);
+    }
+
+    pub fn from_records<'a>(records: impl IntoIterator<Item = &'a UsageRecord>) -> Self {
+        let mut spend = Self::default();
+        for record in records {
+            spend.add(&record.model, record.usage);
+        }
+        spend
+    }
+}
+
+impl BudgetConfig {
+    /// Describe the first limit that has been reached, if any
+    pub fn exceeded(&self, session: &Spend, daily: &Spend) -> Option<String> {
+        if let Some(limit) = self.session_tokens.filter(|limit| session.tokens >= *limit) {
+            return Some(format!("session token budget reached ({} of {})", session.tokens, limit));
+        }
+        if let Some(limit) = self.session_cost.filter(|limit| session.cost >= *limit) {
+            return Some(format!("session cost budget reached (${:.2} of ${:.2})", session.cost, limit));
+        }
+        if let Some(limit) = self.daily_tokens.filter(|limit| daily.tokens >= *limit) {
+            return Some(format!("daily token budget reached ({} of {})", daily.tokens, limit));
+        }
+        if let Some(limit) = self.daily_cost.filter(|limit| daily.cost >= *limit) {
+            return Some(format!("daily cost budget reached (${:.2} of ${:.2})", daily.cost, limit));
+        }
+        None
+    }
+}
+
+/// A budget limit was reached and the run was not forced past it
+#[derive(Debug)]
+pub struct BudgetExceeded(pub String);
+
+impl std::fmt::Display for BudgetExceeded {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "Budget exceeded: {}", self.0)
+    }
+}
+
+impl std::error::Error for BudgetExceeded {}
+
+/// A `BudgetConfig` with what one session and the current day have spent
+/// against it, updated as requests are made
+#[derive(Debug, Clone)]
+pub struct Budget {
+    config: BudgetConfig,
+    session: Spend,
+    daily: Spend,
+    force: bool,
+    warned: bool,
+}
+
+impl Budget {
+    /// Spending of `session_id` and of today among `records`
+    pub fn from_records(config: BudgetConfig, records: &[UsageRecord], session_id: &Uuid) -> Self {
+        let today = chrono::Local::now().date_naive();
+        Self {
+            config,
+            session: Spend::from_records(records.iter().filter(|r| r.session_id == *session_id)),
+            daily: Spend::from_records(
+                records
+                    .iter()
+                    .filter(|r| r.timestamp.with_timezone(&chrono::Local).date_naive() == today),
+            ),
+            force: false,
+            warned: false,
+        }
+    }
+
+    /// Spending of `session_id` and of today from the usage ledger
+    pub fn load(config: BudgetConfig, session_id: &Uuid) -> Result<Self> {
+        Ok(Self::from_records(config, &UsageLedger::load()?, session_id))
+    }
+
+    /// Keep going once a limit is reached instead of failing
+    pub fn force(mut self, force: bool) -> Self {
+        self.force = force;
+        self
+    }
+
+    pub fn add(&mut self, model: &str, usage: TokenUsage) {
+        self.session.add(model, usage);
+        self.daily.add(model, usage);
+    }
+
+    /// Fails with `BudgetExceeded` once a limit is reached. A forced budget
+    /// returns the reason instead, the first time only.
+    pub fn check(&mut self) -> Result<Option<String>> {
+        let Some(reason) = self.config.exceeded(&self.session, &self.daily) else {
+            return Ok(None);
+        };
+        if !self.force {
+            return Err(BudgetExceeded(reason).into());
+        }
+        if self.warned {
+            return Ok(None);
+        }
+        self.warned = true;
+        Ok(Some(reason))
+    }
+}
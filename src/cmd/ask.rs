@@ -0,0 +1,86 @@
+use flag_rs::{CommandBuilder, Flag, FlagType, FlagValue};
+
+use gamecode_cli::attachments;
+use gamecode_cli::code_index::CodeIndex;
+use gamecode_cli::embeddings::Embedder;
+use gamecode_cli::one_shot::OneShot;
+use crate::settings::{command_error, Flags, Settings};
+
+const ASK_SYSTEM: &str = "You answer questions about a codebase. You are given excerpts retrieved \
+from the repository, each labelled with its path and line range. Base your answer on them, cite \
+files as path:line, and say so plainly when the excerpts do not contain the answer.";
+
+pub fn register(parent: &mut flag_rs::Command) {
+    let cmd = CommandBuilder::new("ask")
+        .short("Ask a question about the current repository")
+        .long("Embed the question, retrieve the most similar chunks from the index built by `gamecode index build`, and send them with the question to the chat model.")
+        .flag(
+            Flag::new("top-k")
+                .short('k')
+                .usage("Number of chunks to include")
+                .value_type(FlagType::Int)
+                .default(FlagValue::Int(8)),
+        )
+        .flag(
+            Flag::new("model")
+                .short('m')
+                .usage("Model to use")
+                .value_type(FlagType::String),
+        )
+        .flag(
+            Flag::new("region")
+                .short('r')
+                .usage("AWS region")
+                .value_type(FlagType::String)
+                .default(FlagValue::String("us-west-2".to_string())),
+        )
+        .run(|ctx| {
+            let question = ctx.args().join(" ");
+            if question.trim().is_empty() {
+                return Err(flag_rs::Error::ArgumentParsing(
+                    "Usage: gamecode ask \"<question>\"".to_string(),
+                ));
+            }
+            let top_k = Flags::new(ctx).parse("top-k").map_err(command_error)?.unwrap_or(8);
+            let Settings { region, model, .. } = Settings::for_command(ctx)?;
+
+            let root = CodeIndex::repo_root().map_err(|e| flag_rs::Error::Custom(e.to_string().into()))?;
+            let index = CodeIndex::load(&root)
+                .map_err(|e| flag_rs::Error::Custom(e.to_string().into()))?
+                .ok_or_else(|| {
+                    flag_rs::Error::Custom(
+                        format!("No index for {} (run `gamecode index build` first)", root.display()).into(),
+                    )
+                })?;
+
+            let one_shot = OneShot {
+                region: region.clone(),
+                model,
+            };
+            let result = tokio::task::block_in_place(|| {
+                tokio::runtime::Handle::current().block_on(async {
+                    let embedder = Embedder::new(&region, &index.model).await;
+                    let query = embedder.embed(&question).await?;
+
+                    let excerpts: Vec<String> = index
+                        .search(&query, top_k)
+                        .into_iter()
+                        .map(|(_, chunk)| {
+                            let label = format!("{}:{}-{}", chunk.path, chunk.start_line, chunk.end_line);
+                            let language = chunk.path.rsplit('.').next().unwrap_or("");
+                            attachments::fenced_block(&label, language, &chunk.text)
+                        })
+                        .collect();
+                    let prompt = format!("{}\n\nQuestion: {}", excerpts.join("\n\n"), question);
+                    one_shot.ask(ASK_SYSTEM, &prompt).await
+                })
+            })
+            .map_err(|e| flag_rs::Error::Custom(format!("{:#}", e).into()))?;
+
+            println!("{}", result.trim());
+            Ok(())
+        })
+        .build();
+
+    parent.add_command(cmd);
+}
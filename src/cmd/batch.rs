@@ -0,0 +1,213 @@
+use flag_rs::{CommandBuilder, Flag, FlagType, FlagValue};
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::fs::File;
+use std::io::{BufWriter, Write};
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+use tokio::sync::Semaphore;
+use tokio::task::JoinSet;
+
+use gamecode_cli::one_shot::OneShot;
+use gamecode_cli::usage::TokenUsage;
+use crate::settings::{command_error, Flags, Settings};
+
+/// One request in the input file
+#[derive(Debug, Deserialize)]
+struct BatchRequest {
+    /// Caller-chosen identifier echoed in the result
+    #[serde(default)]
+    id: Option<Value>,
+    prompt: String,
+    #[serde(default)]
+    model: Option<String>,
+    #[serde(default)]
+    system: Option<String>,
+}
+
+/// One line of NDJSON output
+#[derive(Debug, Serialize)]
+struct BatchResult {
+    /// 1-based line number in the input file
+    line: usize,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    id: Option<Value>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    model: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    text: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    error: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    usage: Option<TokenUsage>,
+    latency_ms: u128,
+    attempts: u32,
+}
+
+pub fn register(parent: &mut flag_rs::Command) {
+    let cmd = CommandBuilder::new("batch")
+        .short("Run one request per line of a JSONL file")
+        .long("Each line of the file is a JSON object with a \"prompt\" and optional \"id\", \"model\" and \"system\". Requests are independent (no tools, no session) and run concurrently. One JSON result per line is written as each request finishes, with the input line number, the answer or error, token usage and latency.")
+        .flag(
+            Flag::new("concurrency")
+                .short('j')
+                .usage("Requests to run at once")
+                .value_type(FlagType::Int)
+                .default(FlagValue::Int(4)),
+        )
+        .flag(
+            Flag::new("retries")
+                .usage("Times to retry a failed request")
+                .value_type(FlagType::Int)
+                .default(FlagValue::Int(2)),
+        )
+        .flag(
+            Flag::new("output")
+                .short('o')
+                .usage("Write results to this file instead of stdout")
+                .value_type(FlagType::String),
+        )
+        .flag(
+            Flag::new("model")
+                .short('m')
+                .usage("Model for lines that don't name one")
+                .value_type(FlagType::String),
+        )
+        .flag(
+            Flag::new("system")
+                .usage("System prompt for lines that don't set one")
+                .value_type(FlagType::String),
+        )
+        .flag(
+            Flag::new("region")
+                .short('r')
+                .usage("AWS region")
+                .value_type(FlagType::String)
+                .default(FlagValue::String("us-west-2".to_string())),
+        )
+        .run(|ctx| {
+            let path = ctx.args().first().ok_or_else(|| {
+                flag_rs::Error::ArgumentParsing("Usage: gamecode batch <file.jsonl>".to_string())
+            })?;
+            let content = std::fs::read_to_string(path)
+                .map_err(|e| flag_rs::Error::Custom(format!("Failed to read {}: {}", path, e).into()))?;
+
+            // Parse everything up front so a malformed file fails before any request is made
+            let mut requests = Vec::new();
+            for (index, line) in content.lines().enumerate() {
+                if line.trim().is_empty() {
+                    continue;
+                }
+                let request: BatchRequest = serde_json::from_str(line).map_err(|e| {
+                    flag_rs::Error::Custom(format!("{}:{}: {}", path, index + 1, e).into())
+                })?;
+                requests.push((index + 1, request));
+            }
+
+            let flags = Flags::new(ctx);
+            let concurrency = flags.parse::<usize>("concurrency").map_err(command_error)?.unwrap_or(4).max(1);
+            let retries = flags.parse("retries").map_err(command_error)?.unwrap_or(2);
+            let Settings { region, model: default_model, .. } = Settings::for_command(ctx)?;
+            let default_system = ctx.flag("system").cloned().unwrap_or_default();
+
+            let mut out: Box<dyn Write> = match ctx.flag("output") {
+                Some(output) => Box::new(BufWriter::new(File::create(output).map_err(|e| {
+                    flag_rs::Error::Custom(format!("Failed to create {}: {}", output, e).into())
+                })?)),
+                None => Box::new(std::io::stdout().lock()),
+            };
+
+            let total = requests.len();
+            let (succeeded, failed) = tokio::task::block_in_place(|| {
+                tokio::runtime::Handle::current().block_on(async {
+                    let semaphore = Arc::new(Semaphore::new(concurrency));
+                    let mut tasks = JoinSet::new();
+                    for (line, request) in requests {
+                        let semaphore = semaphore.clone();
+                        let one_shot = OneShot {
+                            region: region.clone(),
+                            model: request.model.or_else(|| default_model.clone()),
+                        };
+                        let system = request.system.unwrap_or_else(|| default_system.clone());
+                        tasks.spawn(async move {
+                            let _permit = semaphore.acquire_owned().await;
+                            run_one(line, request.id, &one_shot, &system, &request.prompt, retries).await
+                        });
+                    }
+
+                    let (mut succeeded, mut failed) = (0, 0);
+                    while let Some(joined) = tasks.join_next().await {
+                        let Ok(result) = joined else {
+                            failed += 1;
+                            continue;
+                        };
+                        if result.error.is_some() {
+                            failed += 1;
+                        } else {
+                            succeeded += 1;
+                        }
+                        status!("   [{}/{}] line {} done", succeeded + failed, total, result.line);
+                        let json = serde_json::to_string(&result)?;
+                        writeln!(out, "{}", json)?;
+                        out.flush()?;
+                    }
+                    anyhow::Ok((succeeded, failed))
+                })
+            })
+            .map_err(|e| flag_rs::Error::Custom(e.to_string().into()))?;
+
+            status!("✅ {} succeeded, {} failed", succeeded, failed);
+            if failed > 0 {
+                std::process::exit(1);
+            }
+            Ok(())
+        })
+        .build();
+
+    parent.add_command(cmd);
+}
+
+async fn run_one(
+    line: usize,
+    id: Option<Value>,
+    one_shot: &OneShot,
+    system: &str,
+    prompt: &str,
+    retries: u32,
+) -> BatchResult {
+    let started = Instant::now();
+    let mut attempts = 0;
+    loop {
+        attempts += 1;
+        match one_shot.complete(system, prompt).await {
+            Ok(reply) => {
+                return BatchResult {
+                    line,
+                    id,
+                    model: Some(reply.model),
+                    text: Some(reply.text),
+                    error: None,
+                    usage: Some(reply.usage),
+                    latency_ms: started.elapsed().as_millis(),
+                    attempts,
+                };
+            }
+            Err(e) if attempts > retries => {
+                return BatchResult {
+                    line,
+                    id,
+                    model: None,
+                    text: None,
+                    error: Some(format!("{:#}", e)),
+                    usage: None,
+                    latency_ms: started.elapsed().as_millis(),
+                    attempts,
+                };
+            }
+            Err(e) => {
+                status!("⚠️  Line {} failed (attempt {}): {:#}; retrying", line, attempts, e);
+                tokio::time::sleep(Duration::from_secs(2u64.pow(attempts))).await;
+            }
+        }
+    }
+}
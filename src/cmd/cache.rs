@@ -0,0 +1,44 @@
+use flag_rs::CommandBuilder;
+
+use gamecode_cli::response_cache::ResponseCache;
+
+pub fn register(parent: &mut flag_rs::Command) {
+    let cmd = CommandBuilder::new("cache")
+        .short("Manage the response cache")
+        .long("Responses are cached only when --cache is passed or [cache] enabled = true is set in config.toml. A request is answered from the cache when its model, messages, tools and inference settings all match an earlier one.")
+        .build();
+
+    parent.add_command(cmd);
+
+    let cache_cmd = parent.find_subcommand_mut("cache").unwrap();
+    register_info(cache_cmd);
+    register_clear(cache_cmd);
+}
+
+fn register_info(parent: &mut flag_rs::Command) {
+    let cmd = CommandBuilder::new("info")
+        .short("Show where cached responses are stored and how many there are")
+        .run(|_ctx| {
+            let dir = ResponseCache::cache_dir().map_err(|e| flag_rs::Error::Custom(e.to_string().into()))?;
+            let (count, bytes) = ResponseCache::stats().map_err(|e| flag_rs::Error::Custom(e.to_string().into()))?;
+            println!("{}", dir.display());
+            println!("{} cached responses, {:.1} KiB", count, bytes as f64 / 1024.0);
+            Ok(())
+        })
+        .build();
+
+    parent.add_command(cmd);
+}
+
+fn register_clear(parent: &mut flag_rs::Command) {
+    let cmd = CommandBuilder::new("clear")
+        .short("Delete all cached responses")
+        .run(|_ctx| {
+            let removed = ResponseCache::clear().map_err(|e| flag_rs::Error::Custom(e.to_string().into()))?;
+            println!("Removed {} cached responses", removed);
+            Ok(())
+        })
+        .build();
+
+    parent.add_command(cmd);
+}
@@ -0,0 +1,245 @@
+use flag_rs::{CommandBuilder, Flag, FlagType, FlagValue};
+use serde::Serialize;
+use std::time::Instant;
+use terminal_size::{terminal_size, Width};
+
+use gamecode_cli::one_shot::OneShot;
+use gamecode_cli::usage::TokenUsage;
+use crate::settings::Settings;
+
+/// Narrowest column worth printing side by side; below this answers are stacked
+const MIN_COLUMN_WIDTH: usize = 30;
+
+const COLUMN_GAP: &str = " │ ";
+
+#[derive(Debug, Serialize)]
+struct Answer {
+    /// Model as given on the command line
+    model: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    model_id: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    text: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    error: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    usage: Option<TokenUsage>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    cost: Option<f64>,
+    latency_ms: u128,
+}
+
+impl Answer {
+    fn header(&self) -> String {
+        let seconds = self.latency_ms as f64 / 1000.0;
+        match (&self.usage, self.cost) {
+            (Some(usage), Some(cost)) => format!(
+                "{}s, {} in / {} out, ${:.4}",
+                format_seconds(seconds), usage.input_tokens, usage.output_tokens, cost
+            ),
+            (Some(usage), None) => format!(
+                "{}s, {} in / {} out",
+                format_seconds(seconds), usage.input_tokens, usage.output_tokens
+            ),
+            _ => format!("{}s", format_seconds(seconds)),
+        }
+    }
+
+    fn body(&self) -> &str {
+        self.text.as_deref().or(self.error.as_deref()).unwrap_or("")
+    }
+}
+
+fn format_seconds(seconds: f64) -> String {
+    format!("{:.1}", seconds)
+}
+
+pub fn register(parent: &mut flag_rs::Command) {
+    let cmd = CommandBuilder::new("compare")
+        .short("Send one prompt to several models and compare the answers")
+        .long("Send the same prompt to every model in --models concurrently (no tools, no session) and print the answers side by side with latency, token usage and estimated cost. Use --output-format json for a machine-readable result.")
+        .flag(
+            Flag::new("models")
+                .usage("Models to compare (comma-separated)")
+                .value_type(FlagType::StringSlice),
+        )
+        .flag(
+            Flag::new("system")
+                .usage("System prompt to send to every model")
+                .value_type(FlagType::String),
+        )
+        .flag(
+            Flag::new("output-format")
+                .usage("Output format: text or json")
+                .value_type(FlagType::String)
+                .default(FlagValue::String("text".to_string())),
+        )
+        .flag(
+            Flag::new("region")
+                .short('r')
+                .usage("AWS region")
+                .value_type(FlagType::String)
+                .default(FlagValue::String("us-west-2".to_string())),
+        )
+        .run(|ctx| {
+            let prompt = ctx.args().join(" ");
+            if prompt.trim().is_empty() {
+                return Err(flag_rs::Error::ArgumentParsing(
+                    "Usage: gamecode compare --models a,b \"<prompt>\"".to_string(),
+                ));
+            }
+            let models: Vec<String> = ctx
+                .flag("models")
+                .map(|raw| {
+                    raw.split(',')
+                        .map(str::trim)
+                        .filter(|m| !m.is_empty())
+                        .map(str::to_string)
+                        .collect()
+                })
+                .unwrap_or_default();
+            if models.len() < 2 {
+                return Err(flag_rs::Error::ArgumentParsing(
+                    "--models needs at least two models".to_string(),
+                ));
+            }
+            let json = match ctx.flag("output-format").map(|s| s.as_str()).unwrap_or("text") {
+                "text" => false,
+                "json" => true,
+                other => {
+                    return Err(flag_rs::Error::ArgumentParsing(format!(
+                        "Unknown output format '{}' (expected text or json)",
+                        other
+                    )));
+                }
+            };
+            let region = Settings::for_command(ctx)?.region;
+            let system = ctx.flag("system").cloned().unwrap_or_default();
+
+            status!("⚖️  Asking {} models...", models.len());
+            let answers = tokio::task::block_in_place(|| {
+                tokio::runtime::Handle::current().block_on(async {
+                    let tasks: Vec<_> = models
+                        .iter()
+                        .map(|model| {
+                            let one_shot = OneShot {
+                                region: region.clone(),
+                                model: Some(model.clone()),
+                            };
+                            let (model, system, prompt) = (model.clone(), system.clone(), prompt.clone());
+                            tokio::spawn(async move {
+                                let started = Instant::now();
+                                let result = one_shot.complete(&system, &prompt).await;
+                                let latency_ms = started.elapsed().as_millis();
+                                match result {
+                                    Ok(reply) => Answer {
+                                        cost: reply.usage.cost(&reply.model),
+                                        model,
+                                        model_id: Some(reply.model),
+                                        text: Some(reply.text.trim().to_string()),
+                                        error: None,
+                                        usage: Some(reply.usage),
+                                        latency_ms,
+                                    },
+                                    Err(e) => Answer {
+                                        model,
+                                        model_id: None,
+                                        text: None,
+                                        error: Some(format!("Error: {:#}", e)),
+                                        usage: None,
+                                        cost: None,
+                                        latency_ms,
+                                    },
+                                }
+                            })
+                        })
+                        .collect();
+
+                    let mut answers = Vec::new();
+                    for task in tasks {
+                        answers.push(task.await?);
+                    }
+                    anyhow::Ok(answers)
+                })
+            })
+            .map_err(|e| flag_rs::Error::Custom(e.to_string().into()))?;
+
+            if json {
+                let output = serde_json::to_string_pretty(&answers)
+                    .map_err(|e| flag_rs::Error::Custom(Box::new(e)))?;
+                println!("{}", output);
+            } else {
+                print_answers(&answers);
+            }
+            Ok(())
+        })
+        .build();
+
+    parent.add_command(cmd);
+}
+
+/// Print answers in columns if the terminal is wide enough, otherwise one after another
+fn print_answers(answers: &[Answer]) {
+    let width = terminal_size().map(|(Width(w), _)| w as usize).unwrap_or(80);
+    let column = (width.saturating_sub(COLUMN_GAP.len() * (answers.len() - 1))) / answers.len();
+
+    if column < MIN_COLUMN_WIDTH {
+        for answer in answers {
+            println!("━━ {} ({})", answer.model, answer.header());
+            println!("{}\n", answer.body());
+        }
+        return;
+    }
+
+    let columns: Vec<Vec<String>> = answers
+        .iter()
+        .map(|answer| {
+            let mut lines = wrap(&answer.model, column);
+            lines.extend(wrap(&answer.header(), column));
+            lines.push("─".repeat(column));
+            lines.extend(wrap(answer.body(), column));
+            lines
+        })
+        .collect();
+    let height = columns.iter().map(Vec::len).max().unwrap_or(0);
+    for row in 0..height {
+        let cells: Vec<String> = columns
+            .iter()
+            .map(|lines| {
+                let cell = lines.get(row).map(String::as_str).unwrap_or("");
+                format!("{}{}", cell, " ".repeat(column - cell.chars().count()))
+            })
+            .collect();
+        println!("{}", cells.join(COLUMN_GAP).trim_end());
+    }
+}
+
+/// Word-wrap text to `width` characters, keeping existing line breaks
+fn wrap(text: &str, width: usize) -> Vec<String> {
+    let mut lines = Vec::new();
+    for paragraph in text.lines() {
+        let mut line = String::new();
+        for word in paragraph.split_whitespace() {
+            let mut word = word.to_string();
+            // Hard-split words longer than a whole line
+            while word.chars().count() > width {
+                if !line.is_empty() {
+                    lines.push(std::mem::take(&mut line));
+                }
+                let split: String = word.chars().take(width).collect();
+                word = word.chars().skip(width).collect();
+                lines.push(split);
+            }
+            let needed = if line.is_empty() { word.chars().count() } else { line.chars().count() + 1 + word.chars().count() };
+            if needed > width {
+                lines.push(std::mem::take(&mut line));
+            }
+            if !line.is_empty() {
+                line.push(' ');
+            }
+            line.push_str(&word);
+        }
+        lines.push(line);
+    }
+    lines
+}
@@ -1,21 +1,27 @@
+use anyhow::{Context, Result};
 use flag_rs::{CommandBuilder, CompletionResult, Shell};
+use std::io::Write;
+use std::path::{Path, PathBuf};
+
+/// Shells completion scripts can be generated for
+pub const SHELLS: &[(&str, &str)] = &[
+    ("bash", "Bourne Again Shell"),
+    ("zsh", "Z Shell"),
+    ("fish", "Fish Shell"),
+    ("powershell", "PowerShell 7+"),
+    ("elvish", "Elvish"),
+];
 
 pub fn register(parent: &mut flag_rs::Command) {
     let cmd = CommandBuilder::new("completion")
         .aliases(vec!["completions"])
         .short("Generate shell completion scripts")
-        .long("Generate shell completion scripts for your shell")
+        .long("Generate shell completion scripts for your shell (bash, zsh, fish, powershell or elvish)")
         .arg_completion(|_ctx, prefix| {
-            let shells = vec![
-                ("bash", "Bourne Again Shell"),
-                ("zsh", "Z Shell"),
-                ("fish", "Fish Shell"),
-            ];
-
             let mut result = CompletionResult::new();
-            for (shell, desc) in shells {
+            for (shell, desc) in SHELLS {
                 if shell.starts_with(prefix) {
-                    result = result.add_with_description(shell, desc);
+                    result = result.add_with_description(*shell, *desc);
                 }
             }
             Ok(result)
@@ -23,35 +29,221 @@ pub fn register(parent: &mut flag_rs::Command) {
         .run(|ctx| {
             let shell_name = ctx.args().first().ok_or_else(|| {
                 flag_rs::Error::ArgumentParsing(
-                    "Shell name required (bash, zsh, or fish)".to_string(),
+                    "Shell name required (bash, zsh, fish, powershell or elvish)".to_string(),
                 )
             })?;
 
-            let shell = match shell_name.as_str() {
-                "bash" => Shell::Bash,
-                "zsh" => Shell::Zsh,
-                "fish" => Shell::Fish,
-                _ => {
-                    return Err(flag_rs::Error::ArgumentParsing(format!(
-                        "Unsupported shell: {}",
-                        shell_name
-                    )));
+            let completion = generate(shell_name).ok_or_else(|| {
+                flag_rs::Error::ArgumentParsing(format!("Unsupported shell: {}", shell_name))
+            })?;
+            println!("{}", completion);
+
+            Ok(())
+        })
+        .build();
+
+    parent.add_command(cmd);
+
+    let completion_cmd = parent.find_subcommand_mut("completion").unwrap();
+    register_install(completion_cmd);
+}
+
+fn register_install(parent: &mut flag_rs::Command) {
+    let cmd = CommandBuilder::new("install")
+        .short("Install the completion script for your shell")
+        .long("Write the completion script where the shell looks for it and print the line, if any, to add to your shell's startup file. Uses the shell from $SHELL (PowerShell on Windows) unless one is given.")
+        .arg_completion(|_ctx, prefix| {
+            let mut result = CompletionResult::new();
+            for (shell, desc) in SHELLS {
+                if shell.starts_with(prefix) {
+                    result = result.add_with_description(*shell, *desc);
                 }
+            }
+            Ok(result)
+        })
+        .run(|ctx| {
+            let shell = match ctx.args().first() {
+                Some(shell) => shell.clone(),
+                None => detect_shell().ok_or_else(|| {
+                    flag_rs::Error::ArgumentParsing(
+                        "Could not detect your shell; name it: gamecode completion install <bash|zsh|fish|powershell|elvish>".to_string(),
+                    )
+                })?,
             };
 
-            // We need to rebuild the root command to generate completion
-            let root = crate::build_cli();
-            let completion = root.generate_completion(shell);
-            // // Fix flag-rs bug: replace hyphens with underscores in shell identifiers
-            // let fixed_completion = completion
-            //     .replace("GAMECODE-CLI_COMPLETE", "GAMECODE_CLI_COMPLETE")
-            //     .replace("_gamecode-cli_complete", "_gamecode_cli_complete");
-            //println!("{}", fixed_completion);
-            println!("{}", completion);
-
+            let path = install(&shell).map_err(|e| flag_rs::Error::Custom(format!("{:#}", e).into()))?;
+            println!("✅ Installed {} completions to {}", shell, path.display());
+            match rc_line(&shell, &path) {
+                Some((rc_file, line)) => {
+                    println!("\nAdd this to {} and open a new shell:\n\n    {}", rc_file.display(), line);
+                }
+                None if shell == "bash" => {
+                    println!("They load automatically in new shells when the bash-completion package is installed.");
+                }
+                None => println!("They load automatically in new shells."),
+            }
             Ok(())
         })
         .build();
 
     parent.add_command(cmd);
 }
+
+/// Completion script for `shell`, or None if it isn't supported
+pub fn generate(shell: &str) -> Option<String> {
+    let name = env!("CARGO_BIN_NAME");
+    let shell = match shell {
+        "bash" => Shell::Bash,
+        "zsh" => Shell::Zsh,
+        "fish" => Shell::Fish,
+        // flag-rs has no generator for these; both call back into the
+        // binary the same way its fish script does
+        "powershell" | "pwsh" => return Some(powershell_script(name)),
+        "elvish" => return Some(elvish_script(name)),
+        _ => return None,
+    };
+
+    // We need to rebuild the root command to generate completion
+    let root = crate::build_cli();
+    Some(root.generate_completion(shell))
+}
+
+/// Shell identifiers can't contain the hyphens a binary name may have
+fn identifier(name: &str) -> String {
+    name.chars()
+        .map(|c| if c.is_ascii_alphanumeric() { c } else { '_' })
+        .collect()
+}
+
+/// Environment variable that makes the binary print completions for its
+/// arguments instead of running, one `value<TAB>description` per line
+fn complete_var(name: &str) -> String {
+    format!("{}_COMPLETE", identifier(name).to_uppercase())
+}
+
+fn powershell_script(name: &str) -> String {
+    format!(
+        r#"# PowerShell completion for {name}
+Register-ArgumentCompleter -Native -CommandName '{name}' -ScriptBlock {{
+    param($wordToComplete, $commandAst, $cursorPosition)
+    $words = @($commandAst.CommandElements |
+        Select-Object -Skip 1 |
+        Where-Object {{ $_.Extent.EndOffset -le $cursorPosition }} |
+        ForEach-Object {{ $_.ToString() }})
+    if ($wordToComplete -eq '') {{ $words += '' }}
+    $env:{var} = 'fish'
+    try {{
+        $lines = & '{name}' @words 2>$null
+    }} finally {{
+        Remove-Item Env:{var} -ErrorAction SilentlyContinue
+    }}
+    foreach ($line in $lines) {{
+        $value, $description = $line -split "`t", 2
+        if (-not $description) {{ $description = $value }}
+        [System.Management.Automation.CompletionResult]::new($value, $value, 'ParameterValue', $description)
+    }}
+}}"#,
+        name = name,
+        var = complete_var(name),
+    )
+}
+
+fn elvish_script(name: &str) -> String {
+    format!(
+        r#"# Elvish completion for {name}
+use os
+use str
+
+set edit:completion:arg-completer[{name}] = {{|@words|
+    with E:{var} = fish {{
+        {name} $@words[1..] 2>$os:dev-null | from-lines | each {{|line|
+            var parts = [(str:split "\t" $line)]
+            if (> (count $parts) 1) {{
+                edit:complex-candidate $parts[0] &display=$parts[0]' ('$parts[1]')'
+            }} else {{
+                put $parts[0]
+            }}
+        }}
+    }}
+}}"#,
+        name = name,
+        var = complete_var(name),
+    )
+}
+
+/// The user's shell, if completions can be generated for it
+pub fn detect_shell() -> Option<String> {
+    let name = match std::env::var("SHELL") {
+        Ok(shell) => shell.rsplit(['/', '\\']).next()?.trim_end_matches(".exe").to_string(),
+        // Windows has no $SHELL; PSModulePath is set in every PowerShell session
+        Err(_) if std::env::var_os("PSModulePath").is_some() => "powershell".to_string(),
+        Err(_) => return None,
+    };
+    let name = if name == "pwsh" { "powershell".to_string() } else { name };
+    SHELLS.iter().any(|(shell, _)| *shell == name).then_some(name)
+}
+
+/// Where the completion script for `shell` is installed
+pub fn install_path(shell: &str) -> Result<PathBuf> {
+    let home = home::home_dir().context("Failed to get home directory")?;
+    let data_dir = std::env::var_os("XDG_DATA_HOME")
+        .map(PathBuf::from)
+        .unwrap_or_else(|| home.join(".local/share"));
+    Ok(match shell {
+        // bash-completion loads scripts from here on first use of the command
+        "bash" => data_dir.join("bash-completion/completions/gamecode"),
+        "zsh" => home.join(".zsh/completions/_gamecode"),
+        "fish" => home.join(".config/fish/completions/gamecode.fish"),
+        "powershell" | "pwsh" => home.join(".config/powershell/gamecode.ps1"),
+        "elvish" => home.join(".config/elvish/lib/gamecode.elv"),
+        other => anyhow::bail!("Unsupported shell: {}", other),
+    })
+}
+
+/// The startup file that must load the script at `path`, and the line to
+/// add to it. bash-completion and fish find their scripts on their own.
+pub fn rc_line(shell: &str, path: &Path) -> Option<(PathBuf, String)> {
+    let home = home::home_dir()?;
+    match shell {
+        "zsh" => Some((home.join(".zshrc"), format!("source {}", path.display()))),
+        "powershell" | "pwsh" => {
+            let profile = if cfg!(windows) {
+                home.join("Documents/PowerShell/Microsoft.PowerShell_profile.ps1")
+            } else {
+                home.join(".config/powershell/Microsoft.PowerShell_profile.ps1")
+            };
+            Some((profile, format!(". '{}'", path.display())))
+        }
+        "elvish" => Some((home.join(".config/elvish/rc.elv"), "use gamecode".to_string())),
+        _ => None,
+    }
+}
+
+/// Write the completion script for `shell` to its install path
+pub fn install(shell: &str) -> Result<PathBuf> {
+    let script = generate(shell).with_context(|| format!("Unsupported shell: {}", shell))?;
+    let path = install_path(shell)?;
+    if let Some(parent) = path.parent() {
+        std::fs::create_dir_all(parent)?;
+    }
+    std::fs::write(&path, script).with_context(|| format!("Failed to write {}", path.display()))?;
+    Ok(path)
+}
+
+/// Append `line` to `rc_file` unless it is already there
+pub fn add_rc_line(rc_file: &Path, line: &str) -> Result<()> {
+    let rc = std::fs::read_to_string(rc_file).unwrap_or_default();
+    if rc.lines().any(|existing| existing.trim() == line) {
+        return Ok(());
+    }
+    if let Some(parent) = rc_file.parent() {
+        std::fs::create_dir_all(parent)?;
+    }
+    let mut file = std::fs::OpenOptions::new()
+        .create(true)
+        .append(true)
+        .open(rc_file)
+        .with_context(|| format!("Failed to open {}", rc_file.display()))?;
+    writeln!(file, "\n# gamecode completions\n{}", line)?;
+    Ok(())
+}
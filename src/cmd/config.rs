@@ -0,0 +1,185 @@
+use flag_rs::{CommandBuilder, CompletionResult, Flag, FlagType, FlagValue};
+
+use gamecode_cli::config::Config;
+
+pub fn register(parent: &mut flag_rs::Command) {
+    let cmd = CommandBuilder::new("config")
+        .short("Manage configuration")
+        .build();
+
+    parent.add_command(cmd);
+
+    let config_cmd = parent.find_subcommand_mut("config").unwrap();
+    let profiles = CommandBuilder::new("profiles")
+        .short("Manage named profiles of settings")
+        .long("A profile is a [profiles.<name>] table in ~/.config/gamecode/config.toml holding any settings the config file takes: region, default_model, system_prompt, tools, deny_tools, read_only, approved_tools and so on. `gamecode --profile <name>` applies it on top of the config for one run; `profile = \"<name>\"` in config.toml or .gamecode.toml applies it by default.")
+        .build();
+    config_cmd.add_command(profiles);
+
+    let profiles_cmd = config_cmd.find_subcommand_mut("profiles").unwrap();
+    register_list(profiles_cmd);
+    register_create(profiles_cmd);
+    register_delete(profiles_cmd);
+}
+
+fn complete_profiles(prefix: &str) -> CompletionResult {
+    let mut result = CompletionResult::new();
+    if let Ok(config) = Config::load_global() {
+        for name in config.profiles.keys() {
+            if name.starts_with(prefix) {
+                result = result.add(name.clone());
+            }
+        }
+    }
+    result
+}
+
+fn register_list(parent: &mut flag_rs::Command) {
+    let cmd = CommandBuilder::new("list")
+        .aliases(vec!["ls"])
+        .short("List profiles and the settings they change")
+        .run(|_ctx| {
+            let profiles = Config::load_global()
+                .map_err(|e| flag_rs::Error::Custom(e.to_string().into()))?
+                .profiles;
+            // The profile in effect here, from --profile, the project file or the config
+            let active = Config::load().ok().and_then(|config| config.profile);
+
+            if profiles.is_empty() {
+                println!("No profiles. Create one with: gamecode config profiles create <name> --model <model>");
+                return Ok(());
+            }
+            for (name, settings) in &profiles {
+                let marker = if active.as_deref() == Some(name.as_str()) { "*" } else { " " };
+                let summary = settings
+                    .iter()
+                    .map(|(key, value)| format!("{}={}", key, value))
+                    .collect::<Vec<_>>()
+                    .join(" ");
+                println!("{} {:<16} {}", marker, name, summary);
+            }
+            Ok(())
+        })
+        .build();
+
+    parent.add_command(cmd);
+}
+
+fn register_create(parent: &mut flag_rs::Command) {
+    let cmd = CommandBuilder::new("create")
+        .short("Create or replace a profile")
+        .long("Create a profile from the given settings, replacing any profile of the same name. Settings not given are left to the rest of the config; edit the [profiles.<name>] table in config.toml for anything else.")
+        .flag(
+            Flag::new("region")
+                .short('r')
+                .usage("AWS region")
+                .value_type(FlagType::String),
+        )
+        .flag(
+            Flag::new("model")
+                .short('m')
+                .usage("Default model alias or ID")
+                .value_type(FlagType::String),
+        )
+        .flag(
+            Flag::new("system-prompt")
+                .short('s')
+                .usage("Stored prompt for new sessions")
+                .value_type(FlagType::String),
+        )
+        .flag(
+            Flag::new("tools")
+                .usage("Only offer tools matching these comma-separated patterns")
+                .value_type(FlagType::String),
+        )
+        .flag(
+            Flag::new("deny-tools")
+                .usage("Never offer tools matching these comma-separated patterns")
+                .value_type(FlagType::String),
+        )
+        .flag(
+            Flag::new("read-only")
+                .usage("Offer only read-only tools")
+                .value_type(FlagType::Bool)
+                .default(FlagValue::Bool(false)),
+        )
+        .run(|ctx| {
+            let name = ctx.args().first().ok_or_else(|| {
+                flag_rs::Error::ArgumentParsing("Profile name required".to_string())
+            })?;
+            let list = |raw: &String| -> toml::Value {
+                raw.split(',')
+                    .map(str::trim)
+                    .filter(|p| !p.is_empty())
+                    .map(|p| toml::Value::String(p.to_string()))
+                    .collect::<Vec<_>>()
+                    .into()
+            };
+
+            let mut settings = toml::Table::new();
+            if let Some(region) = ctx.flag("region") {
+                settings.insert("region".to_string(), region.clone().into());
+            }
+            if let Some(model) = ctx.flag("model") {
+                settings.insert("default_model".to_string(), model.clone().into());
+            }
+            if let Some(prompt) = ctx.flag("system-prompt") {
+                settings.insert("system_prompt".to_string(), prompt.clone().into());
+            }
+            if let Some(tools) = ctx.flag("tools") {
+                settings.insert("tools".to_string(), list(tools));
+            }
+            if let Some(deny) = ctx.flag("deny-tools") {
+                settings.insert("deny_tools".to_string(), list(deny));
+            }
+            if ctx.flag("read-only").is_some_and(|v| v == "true") {
+                settings.insert("read_only".to_string(), true.into());
+            }
+            if settings.is_empty() {
+                return Err(flag_rs::Error::ArgumentParsing(
+                    "Give at least one setting (--region, --model, --system-prompt, --tools, --deny-tools or --read-only)".to_string(),
+                ));
+            }
+
+            let mut config = Config::load_global().map_err(|e| flag_rs::Error::Custom(e.to_string().into()))?;
+            let replaced = config.profiles.insert(name.clone(), settings).is_some();
+            config.save().map_err(|e| flag_rs::Error::Custom(e.to_string().into()))?;
+
+            if replaced {
+                println!("Replaced profile '{}'", name);
+            } else {
+                println!("Created profile '{}'; use it with: gamecode --profile {}", name, name);
+            }
+            Ok(())
+        })
+        .build();
+
+    parent.add_command(cmd);
+}
+
+fn register_delete(parent: &mut flag_rs::Command) {
+    let cmd = CommandBuilder::new("delete")
+        .aliases(vec!["rm", "remove"])
+        .short("Delete a profile")
+        .arg_completion(|_ctx, prefix| Ok(complete_profiles(prefix)))
+        .run(|ctx| {
+            let name = ctx.args().first().ok_or_else(|| {
+                flag_rs::Error::ArgumentParsing("Profile name required".to_string())
+            })?;
+
+            let mut config = Config::load_global().map_err(|e| flag_rs::Error::Custom(e.to_string().into()))?;
+            if config.profiles.remove(name).is_none() {
+                return Err(flag_rs::Error::Custom(format!("Profile '{}' not found", name).into()));
+            }
+            if config.profile.as_deref() == Some(name.as_str()) {
+                config.profile = None;
+            }
+            config.save().map_err(|e| flag_rs::Error::Custom(e.to_string().into()))?;
+
+            println!("Deleted profile '{}'", name);
+            Ok(())
+        })
+        .build();
+
+    parent.add_command(cmd);
+}
@@ -0,0 +1,85 @@
+use flag_rs::{CommandBuilder, Flag, FlagType, FlagValue};
+
+use gamecode_cli::daemon::{self, DaemonClient, DaemonRequest};
+use gamecode_cli::server::ServerState;
+use crate::settings::Settings;
+
+pub fn register(parent: &mut flag_rs::Command) {
+    let mut cmd = CommandBuilder::new("daemon")
+        .short("Keep the agent warm behind a Unix socket")
+        .long("Run a long-lived process that keeps the AWS client and MCP server connections open and answers line-delimited JSON requests on ~/.local/share/gamecode/daemon.sock:\n\n  {\"type\": \"ping\"}\n  {\"type\": \"sessions\"}\n  {\"type\": \"run\", \"prompt\": \"...\", \"session_id\"?, \"new_session\"?, \"model\"?}\n\nA run is answered with a session line, one event line per agent event, then a done line with the outcome. Pass --daemon to a normal gamecode invocation to route it through the daemon. Without --yes, only tools in approved_tools run; other calls are refused.")
+        .flag(
+            Flag::new("model")
+                .short('m')
+                .usage("Default model for requests that don't name one")
+                .value_type(FlagType::String),
+        )
+        .flag(
+            Flag::new("region")
+                .short('r')
+                .usage("AWS region")
+                .value_type(FlagType::String)
+                .default(FlagValue::String("us-west-2".to_string())),
+        )
+        .flag(
+            Flag::new("yes")
+                .short('y')
+                .usage("Run every tool call without approval")
+                .value_type(FlagType::Bool)
+                .default(FlagValue::Bool(false)),
+        )
+        .flag(
+            Flag::new("no-tools")
+                .usage("Run the agent without tools")
+                .value_type(FlagType::Bool)
+                .default(FlagValue::Bool(false)),
+        )
+        .run(|ctx| {
+            let Settings { region, model, auto_approve: approve_all, no_tools, .. } = Settings::for_command(ctx)?;
+
+            tokio::task::block_in_place(|| {
+                tokio::runtime::Handle::current().block_on(async {
+                    let path = daemon::socket_path()?;
+                    let state = ServerState::new(&region, model, approve_all, no_tools).await?;
+                    println!("🔌 Serving {} with {} tools on {}", state.model, state.tools.len(), path.display());
+                    daemon::serve(&path, state).await
+                })
+            })
+            .map_err(|e| flag_rs::Error::Custom(format!("{:#}", e).into()))
+        })
+        .build();
+
+    register_status(&mut cmd);
+    parent.add_command(cmd);
+}
+
+fn register_status(parent: &mut flag_rs::Command) {
+    let cmd = CommandBuilder::new("status")
+        .short("Check whether the daemon is running")
+        .run(|_ctx| {
+            let reply = tokio::task::block_in_place(|| {
+                tokio::runtime::Handle::current().block_on(async {
+                    match DaemonClient::connect().await? {
+                        Some(mut client) => client.request(&DaemonRequest::Ping, |_| {}).await.map(Some),
+                        None => Ok(None),
+                    }
+                })
+            })
+            .map_err(|e: anyhow::Error| flag_rs::Error::Custom(format!("{:#}", e).into()))?;
+
+            match reply {
+                Some(pong) => println!(
+                    "Daemon running with {} and {} tools",
+                    pong.get("model").and_then(|m| m.as_str()).unwrap_or("?"),
+                    pong.get("tools").and_then(|t| t.as_u64()).unwrap_or(0)
+                ),
+                None => {
+                    return Err(flag_rs::Error::Custom("Daemon is not running (start it with gamecode daemon)".into()));
+                }
+            }
+            Ok(())
+        })
+        .build();
+
+    parent.add_command(cmd);
+}
@@ -0,0 +1,291 @@
+use flag_rs::{CommandBuilder, Flag, FlagType, FlagValue};
+use gamecode_prompt::PromptManager;
+
+use gamecode_cli::aws_auth;
+use gamecode_cli::config::Config;
+use gamecode_cli::mcp_client::find_executable;
+use gamecode_cli::mcp_config::McpConfig;
+use gamecode_cli::model_discovery::ModelCatalog;
+use gamecode_cli::one_shot::OneShot;
+use gamecode_cli::session_store;
+use crate::settings::Flags;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Status {
+    Pass,
+    Warn,
+    Fail,
+}
+
+/// Result of one diagnostic, with a suggested fix when it didn't pass
+struct Check {
+    name: &'static str,
+    status: Status,
+    detail: String,
+    fix: Option<String>,
+}
+
+impl Check {
+    fn pass(name: &'static str, detail: impl Into<String>) -> Self {
+        Self { name, status: Status::Pass, detail: detail.into(), fix: None }
+    }
+
+    fn warn(name: &'static str, detail: impl Into<String>, fix: impl Into<String>) -> Self {
+        Self { name, status: Status::Warn, detail: detail.into(), fix: Some(fix.into()) }
+    }
+
+    fn fail(name: &'static str, detail: impl Into<String>, fix: impl Into<String>) -> Self {
+        Self { name, status: Status::Fail, detail: detail.into(), fix: Some(fix.into()) }
+    }
+
+    fn print(&self) {
+        let icon = match self.status {
+            Status::Pass => "✅",
+            Status::Warn => "⚠️ ",
+            Status::Fail => "❌",
+        };
+        println!("{} {}: {}", icon, self.name, self.detail);
+        if let Some(fix) = &self.fix {
+            println!("   → {}", fix);
+        }
+    }
+}
+
+pub fn register(parent: &mut flag_rs::Command) {
+    let cmd = CommandBuilder::new("doctor")
+        .short("Check credentials, model access and local state")
+        .long("Validate AWS credentials and Bedrock access to the selected model in the selected region, check the config, session, prompt and MCP server files for problems, and verify shell completions are installed. Prints a fix for each failed check and exits non-zero if any check fails. The model check sends one tiny request.")
+        .flag(
+            Flag::new("model")
+                .short('m')
+                .usage("Model to check (default: the configured model)")
+                .value_type(FlagType::String),
+        )
+        .flag(
+            Flag::new("region")
+                .short('r')
+                .usage("AWS region to check (default: the configured region)")
+                .value_type(FlagType::String),
+        )
+        .flag(
+            Flag::new("offline")
+                .usage("Skip the checks that call AWS")
+                .value_type(FlagType::Bool)
+                .default(FlagValue::Bool(false)),
+        )
+        .run(|ctx| {
+            let offline = ctx.flag("offline").is_some_and(|v| v == "true");
+            let flags = Flags::new(ctx);
+            let model = flags.value("model");
+            let region_flag = flags.value("region");
+
+            let mut checks = Vec::new();
+            let config = match Config::load() {
+                Ok(config) => {
+                    let detail = match Config::project_path() {
+                        Some(path) => format!("config.toml and {} are valid", path.display()),
+                        None => "config.toml is valid".to_string(),
+                    };
+                    checks.push(Check::pass("Config", detail));
+                    config
+                }
+                Err(e) => {
+                    checks.push(Check::fail(
+                        "Config",
+                        format!("{:#}", e),
+                        "Fix the syntax in ~/.config/gamecode/config.toml (or the project's .gamecode.toml), or move it aside and run gamecode init",
+                    ));
+                    Config::default()
+                }
+            };
+            let region = region_flag
+                .or_else(|| config.region.clone())
+                .unwrap_or_else(|| "us-west-2".to_string());
+
+            if offline {
+                println!("Skipping AWS checks (--offline)");
+            } else {
+                tokio::task::block_in_place(|| {
+                    tokio::runtime::Handle::current()
+                        .block_on(check_aws(&region, model, &config, &mut checks))
+                });
+            }
+            checks.push(check_sessions());
+            checks.push(check_prompts());
+            checks.push(check_mcp_servers());
+            checks.push(check_completions());
+
+            for check in &checks {
+                check.print();
+            }
+            let failed = checks.iter().filter(|c| c.status == Status::Fail).count();
+            let warned = checks.iter().filter(|c| c.status == Status::Warn).count();
+            println!("\n{} passed, {} warnings, {} failed", checks.len() - failed - warned, warned, failed);
+            if failed > 0 {
+                return Err(flag_rs::Error::Custom(format!("{} check(s) failed", failed).into()));
+            }
+            Ok(())
+        })
+        .build();
+
+    parent.add_command(cmd);
+}
+
+async fn check_aws(region: &str, model: Option<String>, config: &Config, checks: &mut Vec<Check>) {
+    if let Err(e) = aws_auth::check_credentials(region).await {
+        checks.push(Check::fail(
+            "AWS credentials",
+            format!("{:#}", e),
+            "Run `aws configure` or `aws sso login`, or set AWS_PROFILE",
+        ));
+        return;
+    }
+    checks.push(Check::pass("AWS credentials", "resolved from the default provider chain"));
+
+    let one_shot = OneShot { region: region.to_string(), model };
+    let model_id = one_shot.model_id(config);
+    match ModelCatalog::fetch(region).await {
+        Ok(catalog) => {
+            // Cross-region profile IDs (us.anthropic...) are listed as profiles in the catalog
+            if catalog.models.iter().any(|m| m.id == model_id) {
+                checks.push(Check::pass("Bedrock", format!("{} is offered in {}", model_id, region)));
+            } else {
+                checks.push(Check::warn(
+                    "Bedrock",
+                    format!("{} is not listed in {}", model_id, region),
+                    format!("Pick a model from `gamecode models list --refresh --region {}`", region),
+                ));
+            }
+        }
+        Err(e) => checks.push(Check::fail(
+            "Bedrock",
+            format!("{:#}", e),
+            format!("Check that Bedrock is available in {} and your IAM policy allows bedrock:ListFoundationModels", region),
+        )),
+    }
+
+    match one_shot.complete("", "Reply with OK.").await {
+        Ok(_) => checks.push(Check::pass("Model access", format!("{} answered", model_id))),
+        Err(e) => checks.push(Check::fail(
+            "Model access",
+            format!("{:#}", e),
+            format!(
+                "Request access to {} in the Bedrock console (Model access) for {}, or choose another model with --model",
+                model_id, region
+            ),
+        )),
+    }
+}
+
+fn check_sessions() -> Check {
+    let mut manager = match session_store::open_configured() {
+        Ok(manager) => manager,
+        Err(e) => return Check::fail("Sessions", e.to_string(), "Check permissions on ~/.local/share/gamecode"),
+    };
+    let sessions = match manager.list_sessions() {
+        Ok(sessions) => sessions,
+        Err(e) => {
+            return Check::fail(
+                "Sessions",
+                e.to_string(),
+                "Move unreadable files out of the sessions directory",
+            );
+        }
+    };
+    let broken: Vec<String> = sessions
+        .iter()
+        .filter(|info| manager.load_session(&info.id).is_err())
+        .map(|info| info.id.to_string())
+        .collect();
+    if broken.is_empty() {
+        Check::pass("Sessions", format!("{} sessions readable", sessions.len()))
+    } else {
+        Check::fail(
+            "Sessions",
+            format!("{} of {} sessions can't be loaded: {}", broken.len(), sessions.len(), broken.join(", ")),
+            "Delete them with `gamecode sessions delete <id>`",
+        )
+    }
+}
+
+fn check_prompts() -> Check {
+    let manager = match PromptManager::new() {
+        Ok(manager) => manager,
+        Err(e) => return Check::fail("Prompts", e.to_string(), "Check permissions on the prompts directory"),
+    };
+    let prompts = match manager.list_prompts() {
+        Ok(prompts) => prompts,
+        Err(e) => return Check::fail("Prompts", e.to_string(), "Move unreadable files out of the prompts directory"),
+    };
+    let broken: Vec<&String> = prompts.iter().filter(|name| manager.load_prompt(name).is_err()).collect();
+    if !broken.is_empty() {
+        let names: Vec<&str> = broken.iter().map(|name| name.as_str()).collect();
+        return Check::fail(
+            "Prompts",
+            format!("can't load {}", names.join(", ")),
+            "Fix or remove them with `gamecode prompts edit|delete <name>`",
+        );
+    }
+    if manager.load_default().is_err() {
+        return Check::warn(
+            "Prompts",
+            format!("{} prompts, but no usable default", prompts.len()),
+            "Create one with `gamecode prompts edit default` or `gamecode init`",
+        );
+    }
+    Check::pass("Prompts", format!("{} prompts readable", prompts.len()))
+}
+
+fn check_mcp_servers() -> Check {
+    let config = match McpConfig::load() {
+        Ok(config) => config,
+        Err(e) => {
+            return Check::fail(
+                "MCP servers",
+                e.to_string(),
+                "Fix the JSON in ~/.config/gamecode/mcp-servers.json",
+            );
+        }
+    };
+    let enabled: Vec<_> = config.servers.iter().filter(|s| s.enabled).collect();
+    let missing: Vec<String> = enabled
+        .iter()
+        .filter(|server| find_executable(&server.command).is_none())
+        .map(|server| format!("{} ({})", server.name, server.command))
+        .collect();
+    if missing.is_empty() {
+        Check::pass("MCP servers", format!("{} enabled, all commands found", enabled.len()))
+    } else {
+        Check::fail(
+            "MCP servers",
+            format!("command not found for {}", missing.join(", ")),
+            "Install the command or disable the server with `gamecode mcp disable <name>`; `gamecode mcp doctor` tests that servers start",
+        )
+    }
+}
+
+fn check_completions() -> Check {
+    let Some(shell) = super::completion::detect_shell() else {
+        return Check::pass("Completions", "no supported shell detected; skipped");
+    };
+    let installed = match super::completion::install_path(&shell) {
+        Ok(path) => {
+            let loaded = super::completion::rc_line(&shell, &path).is_none_or(|(rc_file, line)| {
+                std::fs::read_to_string(rc_file)
+                    .map(|content| content.contains(&line) || content.contains("gamecode completion"))
+                    .unwrap_or(false)
+            });
+            path.exists() && loaded
+        }
+        Err(_) => false,
+    };
+    if installed {
+        Check::pass("Completions", format!("installed for {}", shell))
+    } else {
+        Check::warn(
+            "Completions",
+            format!("not installed for {}", shell),
+            "Run `gamecode completion install` and follow its instructions",
+        )
+    }
+}
@@ -0,0 +1,102 @@
+use flag_rs::{CommandBuilder, Flag, FlagType, FlagValue};
+use std::process::Command;
+
+use gamecode_cli::one_shot::OneShot;
+use gamecode_cli::tool_result;
+use crate::settings::Settings;
+
+/// Larger staged diffs are cut in the middle before being sent to the model
+const MAX_DIFF_BYTES: usize = 100 * 1024;
+
+const COMMIT_MSG_SYSTEM: &str = "You write git commit messages in the Conventional Commits format. \
+Given a staged diff, reply with only the commit message: a subject line of the form \
+`type(optional scope): summary` under 72 characters, then a blank line and a short body \
+explaining what changed and why when the diff is not trivial. No markdown, no code fences.";
+
+pub fn register(parent: &mut flag_rs::Command) {
+    let cmd = CommandBuilder::new("git")
+        .short("Git helpers")
+        .build();
+
+    parent.add_command(cmd);
+
+    let git_cmd = parent.find_subcommand_mut("git").unwrap();
+    register_commit_msg(git_cmd);
+}
+
+fn register_commit_msg(parent: &mut flag_rs::Command) {
+    let cmd = CommandBuilder::new("commit-msg")
+        .short("Generate a commit message for the staged changes")
+        .long("Read the staged diff and ask the model for a Conventional Commits message. The model is called without tools. With --commit, open `git commit -e` pre-filled with the message.")
+        .flag(
+            Flag::new("commit")
+                .usage("Run git commit -e -m with the generated message")
+                .value_type(FlagType::Bool)
+                .default(FlagValue::Bool(false)),
+        )
+        .flag(
+            Flag::new("model")
+                .short('m')
+                .usage("Model to use")
+                .value_type(FlagType::String),
+        )
+        .flag(
+            Flag::new("region")
+                .short('r')
+                .usage("AWS region")
+                .value_type(FlagType::String)
+                .default(FlagValue::String("us-west-2".to_string())),
+        )
+        .run(|ctx| {
+            let diff = Command::new("git")
+                .args(["diff", "--staged", "--no-color"])
+                .output()
+                .map_err(|e| flag_rs::Error::Custom(format!("Failed to run git: {}", e).into()))?;
+            if !diff.status.success() {
+                return Err(flag_rs::Error::Custom(
+                    String::from_utf8_lossy(&diff.stderr).trim().to_string().into(),
+                ));
+            }
+            let diff = String::from_utf8_lossy(&diff.stdout).into_owned();
+            if diff.trim().is_empty() {
+                return Err(flag_rs::Error::Custom(
+                    "No staged changes (stage them with git add first)".into(),
+                ));
+            }
+            let diff = tool_result::truncate_middle(&diff, MAX_DIFF_BYTES).unwrap_or(diff);
+
+            let Settings { region, model, .. } = Settings::for_command(ctx)?;
+            let one_shot = OneShot {
+                region,
+                model,
+            };
+            let reply = tokio::task::block_in_place(|| {
+                tokio::runtime::Handle::current().block_on(one_shot.ask(COMMIT_MSG_SYSTEM, &diff))
+            })
+            .map_err(|e| flag_rs::Error::Custom(format!("{:#}", e).into()))?;
+
+            let message = reply.trim().trim_matches('`').trim().to_string();
+            if message.is_empty() {
+                return Err(flag_rs::Error::Custom("The model returned an empty message".into()));
+            }
+
+            if !ctx.flag("commit").is_some_and(|v| v == "true") {
+                println!("{}", message);
+                return Ok(());
+            }
+
+            let status = Command::new("git")
+                .args(["commit", "-e", "-m", &message])
+                .status()
+                .map_err(|e| flag_rs::Error::Custom(format!("Failed to run git commit: {}", e).into()))?;
+            if !status.success() {
+                return Err(flag_rs::Error::Custom(
+                    format!("git commit exited with {}", status).into(),
+                ));
+            }
+            Ok(())
+        })
+        .build();
+
+    parent.add_command(cmd);
+}
@@ -0,0 +1,144 @@
+use flag_rs::{CommandBuilder, Flag, FlagType, FlagValue};
+use gamecode_context::session::MessageRole;
+use std::io::IsTerminal;
+use uuid::Uuid;
+
+use gamecode_cli::session_store::{self, SessionStore};
+use gamecode_cli::thinking::ThinkingLog;
+use gamecode_cli::tool_log::ToolLog;
+use gamecode_cli::{markdown, pager, status};
+
+pub fn register(parent: &mut flag_rs::Command) {
+    register_last(parent);
+    register_redo(parent);
+}
+
+fn session_flag() -> Flag {
+    Flag::new("session")
+        .usage("Session ID (default: the latest session)")
+        .value_type(FlagType::String)
+}
+
+fn load_session(
+    manager: &mut dyn SessionStore,
+    id: Option<&String>,
+) -> Result<gamecode_context::session::Session, flag_rs::Error> {
+    let session = match id {
+        Some(id) => {
+            let id = Uuid::parse_str(id)
+                .map_err(|e| flag_rs::Error::ArgumentParsing(format!("Invalid session ID: {}", e)))?;
+            manager.load_session(&id)
+        }
+        None => manager.load_latest(),
+    };
+    session.map_err(|e| flag_rs::Error::Custom(e.to_string().into()))
+}
+
+fn register_last(parent: &mut flag_rs::Command) {
+    let cmd = CommandBuilder::new("last")
+        .short("Print the previous response")
+        .long("Print the last assistant response of the latest session (or --session) without making a request.")
+        .flag(session_flag())
+        .flag(
+            Flag::new("plain")
+                .usage("Print raw markdown instead of styled terminal output")
+                .value_type(FlagType::Bool)
+                .default(FlagValue::Bool(false)),
+        )
+        .run(|ctx| {
+            let mut manager =
+                session_store::open_configured().map_err(|e| flag_rs::Error::Custom(e.to_string().into()))?;
+            let session = load_session(manager.as_mut(), ctx.flag("session"))?;
+            let Some(message) = session
+                .messages
+                .iter()
+                .rev()
+                .find(|m| matches!(m.role, MessageRole::Assistant))
+            else {
+                return Err(flag_rs::Error::Custom(
+                    format!("Session {} has no responses yet", session.id).into(),
+                ));
+            };
+
+            let plain = ctx.flag("plain").is_some_and(|v| v == "true");
+            let stdout_is_terminal = std::io::stdout().is_terminal();
+            if !plain && status::use_color(stdout_is_terminal) {
+                pager::print(&markdown::render(&message.content))
+                    .map_err(|e| flag_rs::Error::Custom(e.to_string().into()))?;
+            } else {
+                println!("{}", message.content);
+            }
+            Ok(())
+        })
+        .build();
+
+    parent.add_command(cmd);
+}
+
+fn register_redo(parent: &mut flag_rs::Command) {
+    let cmd = CommandBuilder::new("redo")
+        .short("Regenerate the previous response")
+        .long("Remove the last user turn of the latest session (or --session) and everything after it, then send that turn again. The new answer replaces the old one. Use --model to regenerate with a different model.")
+        .flag(session_flag())
+        .flag(
+            Flag::new("model")
+                .short('m')
+                .usage("Model to use for the new answer")
+                .value_type(FlagType::String),
+        )
+        .flag(
+            Flag::new("region")
+                .short('r')
+                .usage("AWS region")
+                .value_type(FlagType::String),
+        )
+        .run(|ctx| {
+            let mut manager =
+                session_store::open_configured().map_err(|e| flag_rs::Error::Custom(e.to_string().into()))?;
+            let mut session = load_session(manager.as_mut(), ctx.flag("session"))?;
+            let Some(index) = session
+                .messages
+                .iter()
+                .rposition(|m| matches!(m.role, MessageRole::User))
+            else {
+                return Err(flag_rs::Error::Custom(
+                    format!("Session {} has no user turn to redo", session.id).into(),
+                ));
+            };
+
+            let prompt = session.messages[index].content.clone();
+            session.messages.truncate(index);
+            manager
+                .save_session(&session)
+                .map_err(|e| flag_rs::Error::Custom(e.to_string().into()))?;
+            ToolLog::open(&session.id)
+                .and_then(|log| log.truncate(index))
+                .and_then(|_| ThinkingLog::open(&session.id)?.truncate(index))
+                .map_err(|e| flag_rs::Error::Custom(e.to_string().into()))?;
+
+            // Re-run through the main command so the full tool loop, output and usage handling apply
+            let exe = std::env::current_exe()
+                .map_err(|e| flag_rs::Error::Custom(format!("Failed to locate gamecode: {}", e).into()))?;
+            let mut command = std::process::Command::new(exe);
+            command.arg("--session").arg(session.id.to_string());
+            if let Some(model) = ctx.flag("model") {
+                command.arg("--model").arg(model);
+            }
+            if let Some(region) = ctx.flag("region") {
+                command.arg("--region").arg(region);
+            }
+            command.arg(&prompt);
+
+            status!("🔁 Regenerating the response to: {}", prompt.lines().next().unwrap_or(""));
+            let exit = command
+                .status()
+                .map_err(|e| flag_rs::Error::Custom(format!("Failed to run gamecode: {}", e).into()))?;
+            if !exit.success() {
+                std::process::exit(exit.code().unwrap_or(1));
+            }
+            Ok(())
+        })
+        .build();
+
+    parent.add_command(cmd);
+}
@@ -0,0 +1,97 @@
+use flag_rs::{CommandBuilder, Flag, FlagType, FlagValue};
+use std::io::Write;
+
+use gamecode_cli::code_index::CodeIndex;
+use gamecode_cli::embeddings::{Embedder, DEFAULT_EMBEDDING_MODEL};
+use crate::settings::Settings;
+
+pub fn register(parent: &mut flag_rs::Command) {
+    let cmd = CommandBuilder::new("index")
+        .short("Manage the codebase index used by `gamecode ask`")
+        .build();
+
+    parent.add_command(cmd);
+
+    let index_cmd = parent.find_subcommand_mut("index").unwrap();
+    register_build(index_cmd);
+    register_status(index_cmd);
+}
+
+fn register_build(parent: &mut flag_rs::Command) {
+    let cmd = CommandBuilder::new("build")
+        .short("Embed the current repository")
+        .long("Walk the current git repository (honoring .gitignore), split text files into overlapping chunks and embed them with a Bedrock embedding model. Chunks unchanged since the last build are not re-embedded.")
+        .flag(
+            Flag::new("embedding-model")
+                .usage("Bedrock embedding model ID")
+                .value_type(FlagType::String)
+                .default(FlagValue::String(DEFAULT_EMBEDDING_MODEL.to_string())),
+        )
+        .flag(
+            Flag::new("region")
+                .short('r')
+                .usage("AWS region")
+                .value_type(FlagType::String)
+                .default(FlagValue::String("us-west-2".to_string())),
+        )
+        .run(|ctx| {
+            let region = Settings::for_command(ctx)?.region;
+            let model = ctx
+                .flag("embedding-model")
+                .cloned()
+                .unwrap_or_else(|| DEFAULT_EMBEDDING_MODEL.to_string());
+
+            let result = tokio::task::block_in_place(|| {
+                tokio::runtime::Handle::current().block_on(async {
+                    let root = CodeIndex::repo_root()?;
+                    let previous = CodeIndex::load(&root).unwrap_or(None);
+                    let embedder = Embedder::new(&region, &model).await;
+                    println!("Indexing {} with {}", root.display(), model);
+
+                    let progress = |done: usize, total: usize| {
+                        eprint!("\r  Embedding chunk {}/{}", done, total);
+                        std::io::stderr().flush().ok();
+                    };
+                    let index = CodeIndex::build(&root, &embedder, previous.as_ref(), &progress).await?;
+                    eprintln!();
+                    let path = index.save()?;
+                    anyhow::Ok((index.chunks.len(), path))
+                })
+            });
+
+            match result {
+                Ok((chunks, path)) => {
+                    println!("✅ Indexed {} chunks into {}", chunks, path.display());
+                    Ok(())
+                }
+                Err(e) => Err(flag_rs::Error::Custom(format!("{:#}", e).into())),
+            }
+        })
+        .build();
+
+    parent.add_command(cmd);
+}
+
+fn register_status(parent: &mut flag_rs::Command) {
+    let cmd = CommandBuilder::new("status")
+        .short("Show the index for the current repository")
+        .run(|_ctx| {
+            let root = CodeIndex::repo_root().map_err(|e| flag_rs::Error::Custom(e.to_string().into()))?;
+            match CodeIndex::load(&root).map_err(|e| flag_rs::Error::Custom(e.to_string().into()))? {
+                Some(index) => {
+                    let files: std::collections::HashSet<&str> =
+                        index.chunks.iter().map(|c| c.path.as_str()).collect();
+                    println!("Repository: {}", index.root.display());
+                    println!("Model:      {}", index.model);
+                    println!("Built:      {}", index.built_at.with_timezone(&chrono::Local).format("%Y-%m-%d %H:%M"));
+                    println!("Files:      {}", files.len());
+                    println!("Chunks:     {}", index.chunks.len());
+                }
+                None => println!("No index for {} (run `gamecode index build`)", root.display()),
+            }
+            Ok(())
+        })
+        .build();
+
+    parent.add_command(cmd);
+}
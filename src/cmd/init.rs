@@ -0,0 +1,121 @@
+use anyhow::{Context, Result};
+use flag_rs::{CommandBuilder, Flag, FlagType, FlagValue};
+use gamecode_prompt::PromptManager;
+use std::io::{IsTerminal, Write};
+
+use gamecode_cli::aws_auth;
+use gamecode_cli::config::Config;
+use gamecode_cli::models;
+
+use super::completion;
+
+const STARTER_PROMPT: &str = "You are a careful coding assistant working in the user's terminal. \
+Read the relevant files before changing them, prefer small focused edits, \
+and explain what you changed and why. Ask before running commands that \
+modify or delete data.";
+
+pub fn register(parent: &mut flag_rs::Command) {
+    let cmd = CommandBuilder::new("init")
+        .short("Set up gamecode for first use")
+        .long("Check AWS credentials, choose a default region and model, write ~/.config/gamecode/config.toml, optionally install shell completions, and create a starter system prompt. Safe to run again; existing settings are offered as defaults.")
+        .flag(
+            Flag::new("yes")
+                .short('y')
+                .usage("Accept every default without asking")
+                .value_type(FlagType::Bool)
+                .default(FlagValue::Bool(false)),
+        )
+        .run(|ctx| {
+            let accept_defaults = ctx.flag("yes").is_some_and(|v| v == "true");
+            if !accept_defaults && !std::io::stdin().is_terminal() {
+                return Err(flag_rs::Error::Custom(
+                    "gamecode init is interactive; pass --yes to accept the defaults".into(),
+                ));
+            }
+            tokio::task::block_in_place(|| tokio::runtime::Handle::current().block_on(run_init(accept_defaults)))
+                .map_err(|e| flag_rs::Error::Custom(format!("{:#}", e).into()))
+        })
+        .build();
+
+    parent.add_command(cmd);
+}
+
+async fn run_init(accept_defaults: bool) -> Result<()> {
+    let ask = |question: &str, default: &str| -> Result<String> {
+        if accept_defaults {
+            println!("{} [{}]", question, default);
+            return Ok(default.to_string());
+        }
+        print!("{} [{}]: ", question, default);
+        std::io::stdout().flush().ok();
+        let mut answer = String::new();
+        std::io::stdin().read_line(&mut answer)?;
+        let answer = answer.trim();
+        Ok(if answer.is_empty() { default } else { answer }.to_string())
+    };
+    let confirm = |question: &str| -> Result<bool> {
+        let answer = ask(question, "Y/n")?;
+        Ok(!matches!(answer.to_lowercase().as_str(), "n" | "no"))
+    };
+
+    let mut config = Config::load_global()?;
+
+    // Region first: credentials are resolved for it
+    let region = ask("AWS region", config.region.as_deref().unwrap_or("us-west-2"))?;
+    match aws_auth::check_credentials(&region).await {
+        Ok(()) => println!("✅ AWS credentials found"),
+        Err(e) => {
+            println!("⚠️  {:#}", e);
+            println!("   Run `aws configure` (or `aws sso login`) or set AWS_PROFILE, then run gamecode init again.");
+        }
+    }
+
+    println!("\nKnown models:");
+    for model in models::KNOWN_MODELS {
+        println!("  {:<16} {}", model.alias, model.description);
+    }
+    let default_model = ask("Default model", config.default_model.as_deref().unwrap_or("sonnet-4"))?;
+    if models::lookup(&default_model).is_none() && !config.model_aliases.contains_key(&default_model) {
+        println!("   '{}' is not a known alias; it will be passed to Bedrock as a model ID", default_model);
+    }
+
+    config.region = Some(region);
+    config.default_model = Some(default_model);
+    config.save()?;
+    println!("✅ Wrote {}", Config::config_path()?.display());
+
+    if let Some(shell) = completion::detect_shell() {
+        if confirm(&format!("\nInstall {} completions?", shell))? {
+            match install_completions(&shell) {
+                Ok(note) => println!("✅ {}", note),
+                Err(e) => println!("⚠️  Could not install completions: {:#}", e),
+            }
+        }
+    }
+
+    let prompts = PromptManager::new().context("Failed to create prompt manager")?;
+    if prompts.list_prompts()?.iter().any(|p| p == "default") {
+        println!("\nKeeping your existing default system prompt (gamecode prompts edit default to change it)");
+    } else if confirm("\nCreate a starter default system prompt?")? {
+        prompts.save_prompt("default", STARTER_PROMPT)?;
+        println!("✅ Saved prompt 'default' (gamecode prompts edit default to change it)");
+    }
+
+    println!("\nAll set. Try: gamecode \"what does this project do?\"");
+    Ok(())
+}
+
+/// Install completions and load them from the shell's startup file,
+/// returning a note on what was done
+fn install_completions(shell: &str) -> Result<String> {
+    let path = completion::install(shell)?;
+    let Some((rc_file, line)) = completion::rc_line(shell, &path) else {
+        return Ok(format!("Installed completions to {}", path.display()));
+    };
+    completion::add_rc_line(&rc_file, &line)?;
+    Ok(format!(
+        "Installed completions to {} (loaded from {}; open a new shell to use them)",
+        path.display(),
+        rc_file.display()
+    ))
+}
@@ -1,75 +1,84 @@
-use flag_rs::{CommandBuilder, CompletionResult};
-use serde::{Deserialize, Serialize};
+use gamecode_cli::mcp_config::{McpConfig, McpServerConfig};
+use gamecode_cli::mcp_logs::McpServerLog;
+use gamecode_cli::mcp_server;
+use gamecode_cli::mcp_tool_cache::McpToolCache;
+use gamecode_cli::mcp_tool_dispatcher::McpToolDispatcher;
+use gamecode_cli::server::ServerState;
+use crate::settings::{command_error, Flags, Settings};
+use serde_json::Value;
+use flag_rs::{CommandBuilder, CompletionResult, Flag, FlagType, FlagValue};
+use serde::Deserialize;
+use std::collections::HashMap;
 use std::fs;
 use std::path::PathBuf;
 
-#[derive(Debug, Serialize, Deserialize)]
-pub struct McpServerConfig {
-    pub name: String,
-    pub command: String,
-    pub args: Vec<String>,
-    pub description: Option<String>,
-    pub enabled: bool,
+/// A server entry in a Claude Desktop style `mcpServers` block
+#[derive(Debug, Deserialize)]
+struct ExternalServerConfig {
+    command: String,
+    #[serde(default)]
+    args: Vec<String>,
+    #[serde(default)]
+    env: HashMap<String, String>,
+    #[serde(default)]
+    cwd: Option<PathBuf>,
+    #[serde(default)]
+    disabled: bool,
 }
 
-#[derive(Debug, Serialize, Deserialize, Default)]
-pub struct McpConfig {
-    pub servers: Vec<McpServerConfig>,
+#[derive(Debug, Deserialize)]
+struct ExternalConfig {
+    #[serde(rename = "mcpServers", default)]
+    mcp_servers: std::collections::BTreeMap<String, ExternalServerConfig>,
 }
 
-impl McpConfig {
-    fn config_path() -> Result<PathBuf, Box<dyn std::error::Error>> {
-        let home = home::home_dir().ok_or("Failed to get home directory")?;
-        let config_dir = home.join(".config").join("gamecode");
-        Ok(config_dir.join("mcp-servers.json"))
-    }
-
-    pub fn load() -> Result<Self, Box<dyn std::error::Error>> {
-        let path = Self::config_path()?;
-        if !path.exists() {
-            return Ok(Self::default());
-        }
-        
-        let content = fs::read_to_string(&path)?;
-        let config: McpConfig = serde_json::from_str(&content)?;
-        Ok(config)
-    }
-
-    pub fn save(&self) -> Result<(), Box<dyn std::error::Error>> {
-        let path = Self::config_path()?;
-        
-        // Ensure directory exists
-        if let Some(parent) = path.parent() {
-            fs::create_dir_all(parent)?;
-        }
-        
-        let content = serde_json::to_string_pretty(self)?;
-        fs::write(&path, content)?;
-        Ok(())
+/// Default location of Claude Desktop's configuration file on this platform
+fn claude_desktop_config_path() -> Option<PathBuf> {
+    if cfg!(target_os = "windows") {
+        std::env::var_os("APPDATA")
+            .map(|appdata| PathBuf::from(appdata).join("Claude").join("claude_desktop_config.json"))
+    } else if cfg!(target_os = "macos") {
+        home::home_dir().map(|home| {
+            home.join("Library")
+                .join("Application Support")
+                .join("Claude")
+                .join("claude_desktop_config.json")
+        })
+    } else {
+        home::home_dir().map(|home| home.join(".config").join("Claude").join("claude_desktop_config.json"))
     }
+}
 
-    pub fn add_server(&mut self, server: McpServerConfig) -> Result<(), Box<dyn std::error::Error>> {
-        // Check if server with same name already exists
-        if self.servers.iter().any(|s| s.name == server.name) {
-            return Err(format!("Server '{}' already exists", server.name).into());
-        }
-        
-        self.servers.push(server);
-        self.save()?;
-        Ok(())
-    }
+/// Parse servers from a `claude_desktop_config.json`-style document
+fn parse_external_servers(content: &str) -> Result<Vec<McpServerConfig>, Box<dyn std::error::Error>> {
+    let config: ExternalConfig = serde_json::from_str(content)?;
+    Ok(config
+        .mcp_servers
+        .into_iter()
+        .map(|(name, server)| McpServerConfig {
+            name,
+            command: server.command,
+            args: server.args,
+            description: None,
+            enabled: !server.disabled,
+            env: server.env,
+            cwd: server.cwd,
+            timeout_ms: None,
+        })
+        .collect())
+}
 
-    pub fn remove_server(&mut self, name: &str) -> Result<(), Box<dyn std::error::Error>> {
-        let initial_len = self.servers.len();
-        self.servers.retain(|s| s.name != name);
-        
-        if self.servers.len() == initial_len {
-            return Err(format!("Server '{}' not found", name).into());
+/// Complete configured server names, optionally restricted by enabled state
+fn complete_server_names(prefix: &str, enabled: Option<bool>) -> CompletionResult {
+    let mut result = CompletionResult::new();
+    if let Ok(config) = McpConfig::load() {
+        for server in config.servers {
+            if server.name.starts_with(prefix) && enabled.is_none_or(|e| server.enabled == e) {
+                result = result.add(server.name);
+            }
         }
-        
-        self.save()?;
-        Ok(())
     }
+    result
 }
 
 pub fn register(parent: &mut flag_rs::Command) {
@@ -85,6 +94,16 @@ pub fn register(parent: &mut flag_rs::Command) {
     register_add(mcp_cmd);
     register_remove(mcp_cmd);
     register_test(mcp_cmd);
+    register_refresh(mcp_cmd);
+    register_enable(mcp_cmd);
+    register_disable(mcp_cmd);
+    register_tools(mcp_cmd);
+    register_call(mcp_cmd);
+    register_prompts(mcp_cmd);
+    register_import(mcp_cmd);
+    register_logs(mcp_cmd);
+    register_doctor(mcp_cmd);
+    register_serve(mcp_cmd);
 }
 
 fn register_list(parent: &mut flag_rs::Command) {
@@ -103,6 +122,15 @@ fn register_list(parent: &mut flag_rs::Command) {
                     let status = if server.enabled { "enabled" } else { "disabled" };
                     println!("  {} [{}]", server.name, status);
                     println!("    Command: {} {}", server.command, server.args.join(" "));
+                    if let Some(cwd) = &server.cwd {
+                        println!("    Working directory: {}", cwd.display());
+                    }
+                    if !server.env.is_empty() {
+                        let mut keys: Vec<&String> = server.env.keys().collect();
+                        keys.sort();
+                        let keys: Vec<&str> = keys.into_iter().map(|k| k.as_str()).collect();
+                        println!("    Environment: {}", keys.join(", "));
+                    }
                     if let Some(desc) = &server.description {
                         println!("    Description: {}", desc);
                     }
@@ -119,6 +147,16 @@ fn register_add(parent: &mut flag_rs::Command) {
     let cmd = CommandBuilder::new("add")
         .short("Add a new MCP server")
         .long("Add a new MCP server configuration. Example: gamecode mcp add myserver /path/to/server --arg1 --arg2")
+        .flag(Flag::new("env")
+            .short('e')
+            .usage("Environment variables for the server as KEY=VALUE (comma-separated)")
+            .value_type(FlagType::StringSlice))
+        .flag(Flag::new("cwd")
+            .usage("Working directory to start the server in")
+            .value_type(FlagType::String))
+        .flag(Flag::new("timeout-ms")
+            .usage("Maximum time to wait for each server request, tool calls included, in milliseconds")
+            .value_type(FlagType::Int))
         .run(|ctx| {
             let args = ctx.args();
             if args.len() < 2 {
@@ -131,12 +169,32 @@ fn register_add(parent: &mut flag_rs::Command) {
             let command = args[1].clone();
             let server_args = args[2..].to_vec();
             
+            let mut env = HashMap::new();
+            if let Some(env_flag) = ctx.flag("env") {
+                for pair in env_flag.split(',').filter(|p| !p.is_empty()) {
+                    let (key, value) = pair.split_once('=').ok_or_else(|| {
+                        flag_rs::Error::ArgumentParsing(format!(
+                            "Invalid --env value '{}', expected KEY=VALUE",
+                            pair
+                        ))
+                    })?;
+                    env.insert(key.to_string(), value.to_string());
+                }
+            }
+            
+            let cwd = ctx.flag("cwd").map(PathBuf::from);
+            
+            let timeout_ms = Flags::new(ctx).parse::<u64>("timeout-ms").map_err(command_error)?;
+            
             let server = McpServerConfig {
                 name: name.clone(),
                 command,
                 args: server_args,
                 description: None,
                 enabled: true,
+                env,
+                cwd,
+                timeout_ms,
             };
             
             let mut config = McpConfig::load()
@@ -233,7 +291,7 @@ fn register_test(parent: &mut flag_rs::Command) {
             // Run async test
             tokio::task::block_in_place(|| {
                 tokio::runtime::Handle::current().block_on(async {
-                    crate::mcp_client::McpClient::test_server(server).await
+                    gamecode_cli::mcp_client::McpClient::test_server(server).await
                         .map_err(|e| flag_rs::Error::Custom(e.to_string().into()))
                 })
             })
@@ -241,4 +299,505 @@ fn register_test(parent: &mut flag_rs::Command) {
         .build();
     
     parent.add_command(cmd);
-}
\ No newline at end of file
+}
+
+fn register_refresh(parent: &mut flag_rs::Command) {
+    let cmd = CommandBuilder::new("refresh")
+        .short("Invalidate cached MCP tool schemas")
+        .long("Invalidate cached MCP tool schemas so the next run rediscovers tools. Refreshes all servers unless a name is given.")
+        .arg_completion(|_ctx, prefix| Ok(complete_server_names(prefix, None)))
+        .run(|ctx| {
+            if let Some(name) = ctx.args().first() {
+                let removed = McpToolCache::invalidate(name)
+                    .map_err(|e| flag_rs::Error::Custom(e.to_string().into()))?;
+                if removed {
+                    println!("Invalidated cached tools for '{}'", name);
+                } else {
+                    println!("No cached tools for '{}'", name);
+                }
+            } else {
+                let removed = McpToolCache::invalidate_all()
+                    .map_err(|e| flag_rs::Error::Custom(e.to_string().into()))?;
+                println!("Invalidated cached tools for {} server(s)", removed);
+            }
+            Ok(())
+        })
+        .build();
+    
+    parent.add_command(cmd);
+}
+
+fn register_enable(parent: &mut flag_rs::Command) {
+    let cmd = CommandBuilder::new("enable")
+        .short("Enable an MCP server")
+        .arg_completion(|_ctx, prefix| Ok(complete_server_names(prefix, Some(false))))
+        .run(|ctx| {
+            let name = ctx.args().first()
+                .ok_or_else(|| flag_rs::Error::ArgumentParsing(
+                    "Server name required".to_string()
+                ))?;
+            
+            let mut config = McpConfig::load()
+                .map_err(|e| flag_rs::Error::Custom(e.to_string().into()))?;
+            
+            config.set_enabled(name, true)
+                .map_err(|e| flag_rs::Error::Custom(e.to_string().into()))?;
+            
+            println!("Enabled MCP server '{}'", name);
+            Ok(())
+        })
+        .build();
+    
+    parent.add_command(cmd);
+}
+
+fn register_disable(parent: &mut flag_rs::Command) {
+    let cmd = CommandBuilder::new("disable")
+        .short("Disable an MCP server")
+        .arg_completion(|_ctx, prefix| Ok(complete_server_names(prefix, Some(true))))
+        .run(|ctx| {
+            let name = ctx.args().first()
+                .ok_or_else(|| flag_rs::Error::ArgumentParsing(
+                    "Server name required".to_string()
+                ))?;
+            
+            let mut config = McpConfig::load()
+                .map_err(|e| flag_rs::Error::Custom(e.to_string().into()))?;
+            
+            config.set_enabled(name, false)
+                .map_err(|e| flag_rs::Error::Custom(e.to_string().into()))?;
+            
+            println!("Disabled MCP server '{}'", name);
+            Ok(())
+        })
+        .build();
+    
+    parent.add_command(cmd);
+}
+
+fn register_tools(parent: &mut flag_rs::Command) {
+    let cmd = CommandBuilder::new("tools")
+        .short("List tools exposed by MCP servers")
+        .long("List the aggregated MCP tool registry. Pass a server name to show only that server's tools.")
+        .arg_completion(|_ctx, prefix| Ok(complete_server_names(prefix, Some(true))))
+        .run(|ctx| {
+            let server_filter = ctx.args().first().cloned();
+            
+            tokio::task::block_in_place(|| {
+                tokio::runtime::Handle::current().block_on(async {
+                    let dispatcher = McpToolDispatcher::new().await
+                        .map_err(|e| flag_rs::Error::Custom(e.to_string().into()))?;
+                    let registry = dispatcher.get_registry().await;
+                    let registry = registry.lock().await;
+                    
+                    let entries: Vec<_> = registry.entries()
+                        .into_iter()
+                        .filter(|(server, _)| server_filter.as_deref().is_none_or(|f| f == *server))
+                        .collect();
+                    
+                    if entries.is_empty() {
+                        match &server_filter {
+                            Some(server) => println!("No tools available from server '{}'.", server),
+                            None => println!("No tools available from MCP servers."),
+                        }
+                        return Ok(());
+                    }
+                    
+                    let name_width = entries.iter().map(|(_, t)| t.name.len()).max().unwrap_or(0);
+                    let server_width = entries.iter().map(|(s, _)| s.len()).max().unwrap_or(0);
+                    for (server, tool) in entries {
+                        println!(
+                            "  {:name_width$}  {:server_width$}  {}",
+                            tool.name, server, tool.description,
+                            name_width = name_width,
+                            server_width = server_width
+                        );
+                    }
+                    Ok(())
+                })
+            })
+        })
+        .build();
+    
+    parent.add_command(cmd);
+}
+
+fn register_call(parent: &mut flag_rs::Command) {
+    let cmd = CommandBuilder::new("call")
+        .short("Invoke an MCP tool directly")
+        .long("Invoke an MCP tool directly without involving the model. Example: gamecode mcp call read_file --params '{\"path\": \"README.md\"}'")
+        .flag(Flag::new("params")
+            .short('p')
+            .usage("Tool parameters as a JSON object")
+            .value_type(FlagType::String))
+        .run(|ctx| {
+            let tool_name = ctx.args().first()
+                .ok_or_else(|| flag_rs::Error::ArgumentParsing(
+                    "Tool name required".to_string()
+                ))?
+                .clone();
+            
+            let params: Value = match ctx.flag("params") {
+                Some(raw) => serde_json::from_str(raw).map_err(|e| {
+                    flag_rs::Error::ArgumentParsing(format!("Invalid --params JSON: {}", e))
+                })?,
+                None => Value::Object(Default::default()),
+            };
+            
+            tokio::task::block_in_place(|| {
+                tokio::runtime::Handle::current().block_on(async {
+                    let dispatcher = McpToolDispatcher::new().await
+                        .map_err(|e| flag_rs::Error::Custom(e.to_string().into()))?;
+                    
+                    let full_name = {
+                        let registry = dispatcher.get_registry().await;
+                        let registry = registry.lock().await;
+                        registry.resolve_tool_name(&tool_name)
+                    }
+                    .ok_or_else(|| flag_rs::Error::Custom(
+                        format!("Tool '{}' not found. Use 'gamecode mcp tools' to list available tools.", tool_name).into()
+                    ))?;
+                    
+                    let result = dispatcher.call_tool(&full_name, params).await
+                        .map_err(|e| flag_rs::Error::Custom(e.to_string().into()))?;
+                    
+                    println!(
+                        "{}",
+                        serde_json::to_string_pretty(&result).unwrap_or_else(|_| result.to_string())
+                    );
+                    Ok(())
+                })
+            })
+        })
+        .build();
+    
+    parent.add_command(cmd);
+}
+
+fn register_prompts(parent: &mut flag_rs::Command) {
+    let cmd = CommandBuilder::new("prompts")
+        .short("List prompts exported by an MCP server")
+        .long("List prompts exported by an MCP server. Use them with --system-prompt mcp:<server>/<prompt>.")
+        .arg_completion(|_ctx, prefix| Ok(complete_server_names(prefix, Some(true))))
+        .run(|ctx| {
+            let name = ctx.args().first()
+                .ok_or_else(|| flag_rs::Error::ArgumentParsing(
+                    "Server name required".to_string()
+                ))?;
+            
+            let config = McpConfig::load()
+                .map_err(|e| flag_rs::Error::Custom(e.to_string().into()))?;
+            
+            let server = config.servers.iter()
+                .find(|s| &s.name == name)
+                .ok_or_else(|| flag_rs::Error::Custom(
+                    format!("Server '{}' not found", name).into()
+                ))?;
+            
+            let prompts = tokio::task::block_in_place(|| {
+                tokio::runtime::Handle::current().block_on(async {
+                    gamecode_cli::mcp_client::McpClient::new().list_prompts(server).await
+                        .map_err(|e| flag_rs::Error::Custom(e.to_string().into()))
+                })
+            })?;
+            
+            if prompts.is_empty() {
+                println!("No prompts available from server '{}'.", name);
+                return Ok(());
+            }
+            
+            println!("Prompts from '{}':", name);
+            for prompt in prompts {
+                println!("  mcp:{}/{}", name, prompt.name);
+                if let Some(desc) = &prompt.description {
+                    println!("    {}", desc);
+                }
+                for arg in &prompt.arguments {
+                    let required = if arg.required { " (required)" } else { "" };
+                    println!(
+                        "    --prompt-arg {}=...{}{}",
+                        arg.name,
+                        required,
+                        arg.description.as_deref().map(|d| format!(" - {}", d)).unwrap_or_default()
+                    );
+                }
+            }
+            Ok(())
+        })
+        .build();
+    
+    parent.add_command(cmd);
+}
+
+fn register_import(parent: &mut flag_rs::Command) {
+    let cmd = CommandBuilder::new("import")
+        .short("Import MCP servers from another client's configuration")
+        .long("Import MCP servers from a claude_desktop_config.json-style file. Use --from claude-desktop for the default Claude Desktop location, or --from <path>.")
+        .flag(Flag::new("from")
+            .usage("Source: 'claude-desktop' or a path to a config file with an mcpServers block")
+            .value_type(FlagType::String))
+        .flag(Flag::new("replace")
+            .usage("Replace existing servers with the same name instead of skipping them")
+            .value_type(FlagType::Bool))
+        .flag_completion("from", |_ctx, prefix| {
+            let mut result = CompletionResult::new();
+            if "claude-desktop".starts_with(prefix) {
+                result = result.add_with_description("claude-desktop", "Claude Desktop configuration");
+            }
+            Ok(result)
+        })
+        .run(|ctx| {
+            let from = ctx.flag("from").map(|s| s.as_str()).unwrap_or("claude-desktop");
+            let replace = ctx.flag("replace").is_some_and(|v| v == "true");
+            
+            let path = if from == "claude-desktop" {
+                claude_desktop_config_path().ok_or_else(|| flag_rs::Error::Custom(
+                    "Could not determine Claude Desktop configuration path".into()
+                ))?
+            } else {
+                PathBuf::from(from)
+            };
+            
+            let content = fs::read_to_string(&path).map_err(|e| flag_rs::Error::Custom(
+                format!("Failed to read {}: {}", path.display(), e).into()
+            ))?;
+            let imported = parse_external_servers(&content)
+                .map_err(|e| flag_rs::Error::Custom(
+                    format!("Failed to parse {}: {}", path.display(), e).into()
+                ))?;
+            
+            if imported.is_empty() {
+                println!("No MCP servers found in {}", path.display());
+                return Ok(());
+            }
+            
+            let mut config = McpConfig::load()
+                .map_err(|e| flag_rs::Error::Custom(e.to_string().into()))?;
+            
+            let mut added = 0;
+            let mut conflicts = Vec::new();
+            for server in imported {
+                if let Some(existing) = config.servers.iter_mut().find(|s| s.name == server.name) {
+                    if replace {
+                        println!("  Replaced '{}'", server.name);
+                        *existing = server;
+                        added += 1;
+                    } else {
+                        conflicts.push(server.name);
+                    }
+                } else {
+                    println!("  Imported '{}'", server.name);
+                    config.servers.push(server);
+                    added += 1;
+                }
+            }
+            
+            config.save()
+                .map_err(|e| flag_rs::Error::Custom(e.to_string().into()))?;
+            
+            println!("Imported {} MCP server(s) from {}", added, path.display());
+            if !conflicts.is_empty() {
+                println!(
+                    "Skipped {} server(s) that already exist: {}",
+                    conflicts.len(),
+                    conflicts.join(", ")
+                );
+                println!("Use --replace to overwrite them.");
+            }
+            Ok(())
+        })
+        .build();
+    
+    parent.add_command(cmd);
+}
+
+fn register_logs(parent: &mut flag_rs::Command) {
+    let cmd = CommandBuilder::new("logs")
+        .short("Show captured stderr output from an MCP server")
+        .flag(Flag::new("follow")
+            .short('f')
+            .usage("Keep printing new output as it is written")
+            .value_type(FlagType::Bool))
+        .flag(Flag::new("lines")
+            .short('n')
+            .usage("Number of trailing lines to show")
+            .value_type(FlagType::Int)
+            .default(FlagValue::Int(50)))
+        .arg_completion(|_ctx, prefix| Ok(complete_server_names(prefix, None)))
+        .run(|ctx| {
+            let name = ctx.args().first()
+                .ok_or_else(|| flag_rs::Error::ArgumentParsing(
+                    "Server name required".to_string()
+                ))?;
+            let follow = ctx.flag("follow").is_some_and(|v| v == "true");
+            let lines = Flags::new(ctx).parse("lines").map_err(command_error)?.unwrap_or(50);
+            
+            let path = McpServerLog::log_path(name)
+                .map_err(|e| flag_rs::Error::Custom(e.to_string().into()))?;
+            
+            if !path.exists() && !follow {
+                println!("No logs captured for '{}' yet ({})", name, path.display());
+                return Ok(());
+            }
+            
+            let content = fs::read_to_string(&path).unwrap_or_default();
+            let all_lines: Vec<&str> = content.lines().collect();
+            let start = all_lines.len().saturating_sub(lines);
+            for line in &all_lines[start..] {
+                println!("{}", line);
+            }
+            
+            if follow {
+                let mut offset = content.len() as u64;
+                loop {
+                    std::thread::sleep(std::time::Duration::from_millis(500));
+                    let Ok(metadata) = fs::metadata(&path) else { continue };
+                    if metadata.len() < offset {
+                        // Log was rotated; start from the beginning of the new file
+                        offset = 0;
+                    }
+                    if metadata.len() > offset {
+                        use std::io::{Read, Seek, SeekFrom};
+                        let mut file = fs::File::open(&path)
+                            .map_err(|e| flag_rs::Error::Custom(Box::new(e)))?;
+                        file.seek(SeekFrom::Start(offset))
+                            .map_err(|e| flag_rs::Error::Custom(Box::new(e)))?;
+                        let mut new_content = String::new();
+                        file.read_to_string(&mut new_content)
+                            .map_err(|e| flag_rs::Error::Custom(Box::new(e)))?;
+                        print!("{}", new_content);
+                        std::io::Write::flush(&mut std::io::stdout()).ok();
+                        offset = metadata.len();
+                    }
+                }
+            }
+            
+            Ok(())
+        })
+        .build();
+    
+    parent.add_command(cmd);
+}
+
+fn register_doctor(parent: &mut flag_rs::Command) {
+    let cmd = CommandBuilder::new("doctor")
+        .aliases(vec!["validate"])
+        .short("Check that configured MCP servers start and expose tools")
+        .run(|_ctx| {
+            let config = McpConfig::load()
+                .map_err(|e| flag_rs::Error::Custom(e.to_string().into()))?;
+            
+            if config.servers.is_empty() {
+                println!("No MCP servers configured.");
+                println!("Use 'gamecode mcp add' to add a server.");
+                return Ok(());
+            }
+            
+            let client = gamecode_cli::mcp_client::McpClient::new();
+            let name_width = config.servers.iter().map(|s| s.name.len()).max().unwrap_or(0);
+            let mut failures = 0;
+            
+            for server in &config.servers {
+                if !server.enabled {
+                    println!("  -  {:width$}  disabled", server.name, width = name_width);
+                    continue;
+                }
+                
+                let diagnosis = tokio::task::block_in_place(|| {
+                    tokio::runtime::Handle::current().block_on(client.diagnose(server))
+                });
+                
+                let binary = match &diagnosis.binary {
+                    Ok(path) => format!("binary ✓ ({})", path.display()),
+                    Err(_) => "binary ✗".to_string(),
+                };
+                let initialize = match &diagnosis.initialize {
+                    Some(Ok(version)) => format!("init ✓ ({})", version),
+                    Some(Err(_)) => "init ✗".to_string(),
+                    None => "init -".to_string(),
+                };
+                let tools = match &diagnosis.tools {
+                    Some(Ok(count)) => format!("tools ✓ ({})", count),
+                    Some(Err(_)) => "tools ✗".to_string(),
+                    None => "tools -".to_string(),
+                };
+                
+                let mark = if diagnosis.is_healthy() { "🟢" } else { "🔴" };
+                println!(
+                    "{} {:width$}  {}  {}  {}",
+                    mark, server.name, binary, initialize, tools,
+                    width = name_width
+                );
+                
+                let error = diagnosis.binary.as_ref().err()
+                    .or_else(|| diagnosis.initialize.as_ref().and_then(|r| r.as_ref().err()))
+                    .or_else(|| diagnosis.tools.as_ref().and_then(|r| r.as_ref().err()));
+                if let Some(error) = error {
+                    failures += 1;
+                    println!("     Error: {}", error);
+                    if let Some(hint) = gamecode_cli::mcp_client::error_hint(error) {
+                        println!("     Hint: {}", hint);
+                    }
+                }
+            }
+            
+            if failures > 0 {
+                return Err(flag_rs::Error::Custom(
+                    format!("{} MCP server(s) failed checks", failures).into()
+                ));
+            }
+            println!("\nAll enabled MCP servers look healthy.");
+            Ok(())
+        })
+        .build();
+    
+    parent.add_command(cmd);
+}
+
+fn register_serve(parent: &mut flag_rs::Command) {
+    let cmd = CommandBuilder::new("serve")
+        .short("Run gamecode as an MCP server over stdio")
+        .long("Speak MCP on stdin/stdout so Claude Desktop or another MCP client can delegate to gamecode. Exposes the tools run_prompt (ask the agent, optionally continuing a session), list_sessions and list_prompts. The agent's own tools follow approved_tools unless --yes is given.")
+        .flag(
+            Flag::new("model")
+                .short('m')
+                .usage("Default model for run_prompt")
+                .value_type(FlagType::String),
+        )
+        .flag(
+            Flag::new("region")
+                .short('r')
+                .usage("AWS region")
+                .value_type(FlagType::String)
+                .default(FlagValue::String("us-west-2".to_string())),
+        )
+        .flag(
+            Flag::new("yes")
+                .short('y')
+                .usage("Let the agent run every tool call without approval")
+                .value_type(FlagType::Bool)
+                .default(FlagValue::Bool(false)),
+        )
+        .flag(
+            Flag::new("no-tools")
+                .usage("Run the agent without tools")
+                .value_type(FlagType::Bool)
+                .default(FlagValue::Bool(false)),
+        )
+        .run(|ctx| {
+            let Settings { region, model, auto_approve: approve_all, no_tools, .. } = Settings::for_command(ctx)?;
+            // MCP clients log our stderr; keep it to warnings and errors
+            gamecode_cli::status::set_quiet(true);
+
+            tokio::task::block_in_place(|| {
+                tokio::runtime::Handle::current().block_on(async {
+                    let state = ServerState::new(&region, model, approve_all, no_tools).await?;
+                    mcp_server::serve_stdio(state).await
+                })
+            })
+            .map_err(|e| flag_rs::Error::Custom(format!("{:#}", e).into()))
+        })
+        .build();
+
+    parent.add_command(cmd);
+}
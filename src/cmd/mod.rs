@@ -1,16 +1,48 @@
 use flag_rs::Command;
 
+mod ask;
+mod batch;
+mod cache;
+mod compare;
 mod completion;
+mod config;
+mod daemon;
+mod doctor;
+mod git;
+mod history;
+mod index;
+mod init;
 pub mod mcp;
 mod models;
 mod prompts;
+mod review;
+mod serve;
 mod sessions;
+mod shell;
+mod tui;
+mod usage;
 
 pub fn register_commands(root: &mut Command) {
     // Each subcommand module registers itself
+    ask::register(root);
+    batch::register(root);
+    cache::register(root);
+    compare::register(root);
     completion::register(root);
+    config::register(root);
+    daemon::register(root);
+    doctor::register(root);
+    git::register(root);
+    history::register(root);
+    index::register(root);
+    init::register(root);
     mcp::register(root);
     models::register(root);
     prompts::register(root);
+    review::register(root);
+    serve::register(root);
     sessions::register(root);
+    shell::register(root);
+    tui::register(root);
+    usage::register(root);
 }
\ No newline at end of file
@@ -1,19 +1,186 @@
-use flag_rs::CommandBuilder;
+use flag_rs::{CommandBuilder, Flag, FlagType};
+
+use gamecode_cli::config::Config;
+use gamecode_cli::model_discovery::ModelCatalog;
+use gamecode_cli::models::{ModelInfo, KNOWN_MODELS};
+use crate::settings::Settings;
 
 pub fn register(parent: &mut flag_rs::Command) {
     let cmd = CommandBuilder::new("models")
         .short("List available models")
         .run(|_ctx| {
-            println!("Available models:");
-            println!("  opus-4              - Claude Opus 4 (cross-region)");
-            println!("  claude-3.7-sonnet   - Claude 3.7 Sonnet (cross-region)");
-            println!("  claude-3.5-sonnet   - Claude 3.5 Sonnet");
-            println!("  claude-3.5-haiku    - Claude 3.5 Haiku");
-            println!("  claude-3-sonnet     - Claude 3 Sonnet");
-            println!("  claude-3-haiku      - Claude 3 Haiku");
+            print_known_models();
             Ok(())
         })
         .build();
 
     parent.add_command(cmd);
-}
\ No newline at end of file
+
+    // Register subcommands
+    let models_cmd = parent.find_subcommand_mut("models").unwrap();
+    register_list(models_cmd);
+    register_alias(models_cmd);
+}
+
+fn print_known_models() {
+    println!("Available models:");
+    println!(
+        "  {:<19} {:<28} {:>8} {:>7} {:<22} {}",
+        "ALIAS", "MODEL", "CONTEXT", "OUTPUT", "SUPPORTS", "$/M IN/OUT"
+    );
+    for model in KNOWN_MODELS {
+        let context = format!("{}k", model.context_window / 1000);
+        let output = format!("{}k", model.max_output_tokens / 1000);
+        println!(
+            "  {:<19} {:<28} {:>8} {:>7} {:<22} {}/{}",
+            model.alias,
+            model.description,
+            context,
+            output,
+            capabilities(model),
+            model.input_price,
+            model.output_price
+        );
+    }
+    println!("  * invoked through a cross-region inference profile");
+
+    if let Ok(config) = Config::load() {
+        if !config.model_aliases.is_empty() {
+            println!("\nUser-defined aliases:");
+            for (alias, id) in &config.model_aliases {
+                println!("  {:<19} - {}", alias, id);
+            }
+        }
+    }
+}
+
+// Capability tags for the model table
+fn capabilities(model: &ModelInfo) -> String {
+    let mut tags = Vec::new();
+    if model.supports_tools {
+        tags.push("tools");
+    }
+    if model.supports_images {
+        tags.push("images");
+    }
+    if model.supports_thinking {
+        tags.push("thinking");
+    }
+    if model.supports_prompt_cache {
+        tags.push("cache");
+    }
+    if model.inference_profile {
+        tags.push("*");
+    }
+    tags.join(" ")
+}
+
+fn register_list(parent: &mut flag_rs::Command) {
+    let cmd = CommandBuilder::new("list")
+        .short("List models, including those discovered from Bedrock")
+        .long("List built-in model aliases plus the foundation models and inference profiles discovered in the selected region. Use --refresh to query Bedrock and update the cache.")
+        .flag(Flag::new("refresh")
+            .usage("Query Bedrock for available models and update the cache")
+            .value_type(FlagType::Bool))
+        .run(|ctx| {
+            let region = Settings::for_command(ctx)?.region;
+            let refresh = ctx.flag("refresh").is_some_and(|v| v == "true");
+
+            print_known_models();
+
+            let catalog = if refresh {
+                let catalog = tokio::task::block_in_place(|| {
+                    tokio::runtime::Handle::current().block_on(ModelCatalog::fetch(&region))
+                })
+                .map_err(|e| flag_rs::Error::Custom(format!("{:#}", e).into()))?;
+                catalog.save().map_err(|e| flag_rs::Error::Custom(e.to_string().into()))?;
+                Some(catalog)
+            } else {
+                ModelCatalog::load_cached(&region)
+            };
+
+            let Some(catalog) = catalog else {
+                println!("\nRun 'gamecode models list --refresh' to discover models available in {}.", region);
+                return Ok(());
+            };
+
+            println!(
+                "\nDiscovered in {} (as of {}):",
+                catalog.region,
+                catalog.fetched_at.format("%Y-%m-%d %H:%M")
+            );
+            for model in &catalog.models {
+                let kind = if model.inference_profile { "profile" } else { "model" };
+                println!("  {:<55} {:<8} {}", model.id, kind, model.name);
+            }
+            Ok(())
+        })
+        .build();
+
+    parent.add_command(cmd);
+}
+
+fn register_alias(parent: &mut flag_rs::Command) {
+    let cmd = CommandBuilder::new("alias")
+        .short("Manage user-defined model aliases")
+        .build();
+
+    parent.add_command(cmd);
+
+    let alias_cmd = parent.find_subcommand_mut("alias").unwrap();
+
+    let add = CommandBuilder::new("add")
+        .short("Add or update a model alias")
+        .long("Map a short name to a model ID or ARN. Example: gamecode models alias add nova-pro us.amazon.nova-pro-v1:0")
+        .run(|ctx| {
+            let args = ctx.args();
+            if args.len() != 2 {
+                return Err(flag_rs::Error::ArgumentParsing(
+                    "Usage: gamecode models alias add <name> <model-id>".to_string(),
+                ));
+            }
+
+            let mut config = Config::load_global().map_err(|e| flag_rs::Error::Custom(e.to_string().into()))?;
+            let previous = config.model_aliases.insert(args[0].clone(), args[1].clone());
+            config.save().map_err(|e| flag_rs::Error::Custom(e.to_string().into()))?;
+
+            match previous {
+                Some(old) => println!("Updated alias '{}': {} -> {}", args[0], old, args[1]),
+                None => println!("Added alias '{}' -> {}", args[0], args[1]),
+            }
+            Ok(())
+        })
+        .build();
+    alias_cmd.add_command(add);
+
+    let remove = CommandBuilder::new("remove")
+        .aliases(vec!["rm", "delete"])
+        .short("Remove a model alias")
+        .arg_completion(|_ctx, prefix| {
+            let mut result = flag_rs::CompletionResult::new();
+            if let Ok(config) = Config::load() {
+                for alias in config.model_aliases.keys() {
+                    if alias.starts_with(prefix) {
+                        result = result.add(alias.clone());
+                    }
+                }
+            }
+            Ok(result)
+        })
+        .run(|ctx| {
+            let name = ctx.args().first().ok_or_else(|| {
+                flag_rs::Error::ArgumentParsing("Alias name required".to_string())
+            })?;
+
+            let mut config = Config::load_global().map_err(|e| flag_rs::Error::Custom(e.to_string().into()))?;
+            if config.model_aliases.remove(name).is_none() {
+                return Err(flag_rs::Error::Custom(format!("Alias '{}' not found", name).into()));
+            }
+            config.save().map_err(|e| flag_rs::Error::Custom(e.to_string().into()))?;
+
+            println!("Removed alias '{}'", name);
+            Ok(())
+        })
+        .build();
+    alias_cmd.add_command(remove);
+}
@@ -1,5 +1,6 @@
-use flag_rs::{CommandBuilder, CompletionResult};
+use flag_rs::{CommandBuilder, CompletionResult, Flag, FlagType};
 use gamecode_prompt::PromptManager;
+use std::io::Read;
 
 pub fn register(parent: &mut flag_rs::Command) {
     let cmd = CommandBuilder::new("prompts")
@@ -12,6 +13,21 @@ pub fn register(parent: &mut flag_rs::Command) {
     let prompts_cmd = parent.find_subcommand_mut("prompts").unwrap();
     register_list(prompts_cmd);
     register_show(prompts_cmd);
+    register_add(prompts_cmd);
+    register_edit(prompts_cmd);
+    register_delete(prompts_cmd);
+}
+
+fn complete_prompt_names(prefix: &str) -> CompletionResult {
+    let mut result = CompletionResult::new();
+    if let Ok(prompts) = PromptManager::new().and_then(|m| m.list_prompts()) {
+        for prompt_name in prompts {
+            if prompt_name.starts_with(prefix) {
+                result = result.add(prompt_name);
+            }
+        }
+    }
+    result
 }
 
 fn register_list(parent: &mut flag_rs::Command) {
@@ -75,3 +91,146 @@ fn register_show(parent: &mut flag_rs::Command) {
 
     parent.add_command(cmd);
 }
+
+fn register_add(parent: &mut flag_rs::Command) {
+    let cmd = CommandBuilder::new("add")
+        .short("Create a prompt from a file or stdin")
+        .long("Create a prompt from --file <path> or --stdin. Fails if the prompt already exists unless --force is given.")
+        .flag(
+            Flag::new("file")
+                .usage("Read the prompt from this file")
+                .value_type(FlagType::String),
+        )
+        .flag(
+            Flag::new("stdin")
+                .usage("Read the prompt from stdin")
+                .value_type(FlagType::Bool),
+        )
+        .flag(
+            Flag::new("force")
+                .usage("Overwrite an existing prompt")
+                .value_type(FlagType::Bool),
+        )
+        .run(|ctx| {
+            let name = ctx.args().first().ok_or_else(|| {
+                flag_rs::Error::ArgumentParsing("Prompt name required".to_string())
+            })?;
+
+            let content = if let Some(path) = ctx.flag("file") {
+                std::fs::read_to_string(path).map_err(|e| {
+                    flag_rs::Error::Custom(format!("Failed to read {}: {}", path, e).into())
+                })?
+            } else if ctx.flag("stdin").is_some_and(|v| v == "true") {
+                let mut content = String::new();
+                std::io::stdin()
+                    .read_to_string(&mut content)
+                    .map_err(|e| flag_rs::Error::Custom(Box::new(e)))?;
+                content
+            } else {
+                return Err(flag_rs::Error::ArgumentParsing(
+                    "Provide the prompt with --file <path> or --stdin (or use 'prompts edit')".to_string(),
+                ));
+            };
+
+            if content.trim().is_empty() {
+                return Err(flag_rs::Error::ArgumentParsing("Prompt is empty".to_string()));
+            }
+
+            let prompt_manager =
+                PromptManager::new().map_err(|e| flag_rs::Error::Custom(Box::new(e)))?;
+            let exists = prompt_manager
+                .list_prompts()
+                .map_err(|e| flag_rs::Error::Custom(Box::new(e)))?
+                .contains(name);
+            if exists && !ctx.flag("force").is_some_and(|v| v == "true") {
+                return Err(flag_rs::Error::ArgumentParsing(format!(
+                    "Prompt '{}' already exists (use --force to overwrite or 'prompts edit')",
+                    name
+                )));
+            }
+
+            prompt_manager
+                .save_prompt(name, &content)
+                .map_err(|e| flag_rs::Error::Custom(Box::new(e)))?;
+            println!("Saved prompt '{}'", name);
+            Ok(())
+        })
+        .build();
+
+    parent.add_command(cmd);
+}
+
+fn register_edit(parent: &mut flag_rs::Command) {
+    let cmd = CommandBuilder::new("edit")
+        .short("Edit a prompt in $EDITOR (creates it if missing)")
+        .arg_completion(|_ctx, prefix| Ok(complete_prompt_names(prefix)))
+        .run(|ctx| {
+            let name = ctx.args().first().ok_or_else(|| {
+                flag_rs::Error::ArgumentParsing("Prompt name required".to_string())
+            })?;
+
+            let prompt_manager =
+                PromptManager::new().map_err(|e| flag_rs::Error::Custom(Box::new(e)))?;
+            let original = prompt_manager.load_prompt(name).unwrap_or_default();
+
+            let path = std::env::temp_dir().join(format!("gamecode-prompt-{}.md", std::process::id()));
+            std::fs::write(&path, &original).map_err(|e| flag_rs::Error::Custom(Box::new(e)))?;
+
+            let editor = std::env::var("VISUAL")
+                .or_else(|_| std::env::var("EDITOR"))
+                .unwrap_or_else(|_| "vi".to_string());
+            let status = std::process::Command::new("sh")
+                .arg("-c")
+                .arg(format!("{} \"$1\"", editor))
+                .arg("sh")
+                .arg(&path)
+                .status()
+                .map_err(|e| flag_rs::Error::Custom(format!("Failed to run editor '{}': {}", editor, e).into()))?;
+
+            let edited = std::fs::read_to_string(&path);
+            let _ = std::fs::remove_file(&path);
+            if !status.success() {
+                return Err(flag_rs::Error::Custom(
+                    format!("Editor '{}' exited with {}; prompt not saved", editor, status).into(),
+                ));
+            }
+            let edited = edited.map_err(|e| flag_rs::Error::Custom(Box::new(e)))?;
+
+            if edited == original {
+                println!("No changes to prompt '{}'", name);
+            } else if edited.trim().is_empty() {
+                println!("Prompt is empty; not saved (use 'prompts delete' to remove it)");
+            } else {
+                prompt_manager
+                    .save_prompt(name, &edited)
+                    .map_err(|e| flag_rs::Error::Custom(Box::new(e)))?;
+                println!("Saved prompt '{}'", name);
+            }
+            Ok(())
+        })
+        .build();
+
+    parent.add_command(cmd);
+}
+
+fn register_delete(parent: &mut flag_rs::Command) {
+    let cmd = CommandBuilder::new("delete")
+        .short("Delete a prompt")
+        .arg_completion(|_ctx, prefix| Ok(complete_prompt_names(prefix)))
+        .run(|ctx| {
+            let name = ctx.args().first().ok_or_else(|| {
+                flag_rs::Error::ArgumentParsing("Prompt name required".to_string())
+            })?;
+
+            let prompt_manager =
+                PromptManager::new().map_err(|e| flag_rs::Error::Custom(Box::new(e)))?;
+            prompt_manager
+                .delete_prompt(name)
+                .map_err(|e| flag_rs::Error::Custom(Box::new(e)))?;
+            println!("Deleted prompt '{}'", name);
+            Ok(())
+        })
+        .build();
+
+    parent.add_command(cmd);
+}
@@ -0,0 +1,184 @@
+use flag_rs::{CommandBuilder, Flag, FlagType, FlagValue};
+use serde::{Deserialize, Serialize};
+use std::process::Command;
+
+use gamecode_cli::one_shot::OneShot;
+use crate::settings::Settings;
+
+/// Diff text sent to the model per request; larger diffs are split between files
+const MAX_CHUNK_BYTES: usize = 60 * 1024;
+
+const REVIEW_SYSTEM: &str = "You are a meticulous code reviewer. Review the unified diff you are given \
+and report real problems: bugs, security issues, error handling gaps, performance pitfalls and unclear code. \
+Do not comment on things that are fine. Reply with only a JSON array (no markdown, no code fences) of objects \
+with these fields: \"file\" (path as in the diff), \"line\" (line number in the new file, or null), \
+\"severity\" (\"error\", \"warning\" or \"info\"), and \"comment\". Reply with [] if there is nothing to report.";
+
+/// One review comment
+#[derive(Debug, Serialize, Deserialize)]
+pub struct Finding {
+    pub file: String,
+    #[serde(default)]
+    pub line: Option<u64>,
+    pub severity: String,
+    pub comment: String,
+}
+
+pub fn register(parent: &mut flag_rs::Command) {
+    let cmd = CommandBuilder::new("review")
+        .short("Review a diff and report findings")
+        .long("Review the working tree diff, the staged diff (--staged), a commit range (--range a..b) or a GitHub pull request (--pr <url>, requires the gh CLI). Findings are printed as file:line [severity] comment, or as JSON with --output-format json.")
+        .flag(
+            Flag::new("staged")
+                .usage("Review staged changes")
+                .value_type(FlagType::Bool)
+                .default(FlagValue::Bool(false)),
+        )
+        .flag(
+            Flag::new("range")
+                .usage("Review a commit range, e.g. main..HEAD")
+                .value_type(FlagType::String),
+        )
+        .flag(
+            Flag::new("pr")
+                .usage("Review a GitHub pull request by URL or number")
+                .value_type(FlagType::String),
+        )
+        .flag(
+            Flag::new("output-format")
+                .usage("Output format: text or json")
+                .value_type(FlagType::String)
+                .default(FlagValue::String("text".to_string())),
+        )
+        .flag(
+            Flag::new("model")
+                .short('m')
+                .usage("Model to use")
+                .value_type(FlagType::String),
+        )
+        .flag(
+            Flag::new("region")
+                .short('r')
+                .usage("AWS region")
+                .value_type(FlagType::String)
+                .default(FlagValue::String("us-west-2".to_string())),
+        )
+        .run(|ctx| {
+            let json = match ctx.flag("output-format").map(|s| s.as_str()).unwrap_or("text") {
+                "text" => false,
+                "json" => true,
+                other => {
+                    return Err(flag_rs::Error::ArgumentParsing(format!(
+                        "Unknown output format '{}' (expected text or json)",
+                        other
+                    )));
+                }
+            };
+
+            let (program, args): (&str, Vec<String>) = if let Some(pr) = ctx.flag("pr") {
+                ("gh", vec!["pr".into(), "diff".into(), pr.clone()])
+            } else if let Some(range) = ctx.flag("range") {
+                ("git", vec!["diff".into(), "--no-color".into(), range.clone()])
+            } else if ctx.flag("staged").is_some_and(|v| v == "true") {
+                ("git", vec!["diff".into(), "--no-color".into(), "--staged".into()])
+            } else {
+                ("git", vec!["diff".into(), "--no-color".into()])
+            };
+            let diff = run_diff(program, &args)?;
+            if diff.trim().is_empty() {
+                if json {
+                    println!("[]");
+                } else {
+                    println!("No changes to review.");
+                }
+                return Ok(());
+            }
+
+            let Settings { region, model, .. } = Settings::for_command(ctx)?;
+            let one_shot = OneShot {
+                region,
+                model,
+            };
+            let chunks = chunk_diff(&diff);
+            let mut findings = Vec::new();
+            for (index, chunk) in chunks.iter().enumerate() {
+                if !json && chunks.len() > 1 {
+                    eprintln!("Reviewing part {}/{}...", index + 1, chunks.len());
+                }
+                let reply = tokio::task::block_in_place(|| {
+                    tokio::runtime::Handle::current().block_on(one_shot.ask(REVIEW_SYSTEM, chunk))
+                })
+                .map_err(|e| flag_rs::Error::Custom(format!("{:#}", e).into()))?;
+                findings.extend(parse_findings(&reply)?);
+            }
+
+            if json {
+                let output = serde_json::to_string_pretty(&findings)
+                    .map_err(|e| flag_rs::Error::Custom(Box::new(e)))?;
+                println!("{}", output);
+            } else if findings.is_empty() {
+                println!("No findings.");
+            } else {
+                for finding in &findings {
+                    let location = match finding.line {
+                        Some(line) => format!("{}:{}", finding.file, line),
+                        None => finding.file.clone(),
+                    };
+                    println!("{} [{}] {}", location, finding.severity, finding.comment);
+                }
+            }
+            Ok(())
+        })
+        .build();
+
+    parent.add_command(cmd);
+}
+
+fn run_diff(program: &str, args: &[String]) -> Result<String, flag_rs::Error> {
+    let output = Command::new(program)
+        .args(args)
+        .output()
+        .map_err(|e| flag_rs::Error::Custom(format!("Failed to run {}: {}", program, e).into()))?;
+    if !output.status.success() {
+        return Err(flag_rs::Error::Custom(
+            String::from_utf8_lossy(&output.stderr).trim().to_string().into(),
+        ));
+    }
+    Ok(String::from_utf8_lossy(&output.stdout).into_owned())
+}
+
+/// Split a diff into chunks of whole files, each under `MAX_CHUNK_BYTES` where possible
+fn chunk_diff(diff: &str) -> Vec<String> {
+    let mut files: Vec<&str> = Vec::new();
+    let mut start = 0;
+    for (index, _) in diff.match_indices("\ndiff --git ") {
+        files.push(&diff[start..=index]);
+        start = index + 1;
+    }
+    files.push(&diff[start..]);
+
+    let mut chunks: Vec<String> = Vec::new();
+    let mut current = String::new();
+    for file in files {
+        if !current.is_empty() && current.len() + file.len() > MAX_CHUNK_BYTES {
+            chunks.push(std::mem::take(&mut current));
+        }
+        current.push_str(file);
+    }
+    if !current.trim().is_empty() {
+        chunks.push(current);
+    }
+    chunks
+}
+
+/// Extract the JSON array of findings, tolerating prose or fences around it
+fn parse_findings(reply: &str) -> Result<Vec<Finding>, flag_rs::Error> {
+    let (Some(start), Some(end)) = (reply.find('['), reply.rfind(']')) else {
+        return Err(flag_rs::Error::Custom(
+            format!("Could not parse review findings from the model's reply:\n{}", reply).into(),
+        ));
+    };
+    serde_json::from_str(&reply[start..=end]).map_err(|e| {
+        flag_rs::Error::Custom(format!("Invalid review findings from the model: {}", e).into())
+    })
+}
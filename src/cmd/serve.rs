@@ -0,0 +1,85 @@
+use flag_rs::{CommandBuilder, Flag, FlagType, FlagValue};
+use std::net::SocketAddr;
+
+use gamecode_cli::server::{self, Access, ServerState};
+use crate::settings::{command_error, Flags, Settings};
+
+pub fn register(parent: &mut flag_rs::Command) {
+    let cmd = CommandBuilder::new("serve")
+        .short("Serve the agent over a local HTTP API")
+        .long("Expose sessions and the agent loop over HTTP for editor plugins and web UIs:\n\n  GET  /sessions                 list sessions\n  POST /sessions                 create a session ({\"system_prompt\"} or {\"prompt\"} optional)\n  GET  /sessions/{id}            show a session's messages\n  POST /sessions/{id}/messages   send {\"content\", \"model\"?} and get the outcome as JSON\n  POST /sessions/{id}/events     same, streamed as server-sent events\n\nTools from configured MCP servers are available. Without --yes, only tools in approved_tools run; other calls are refused.\n\nWithout --token, requests must be addressed to localhost (or --host), which keeps web pages from reaching the API through DNS rebinding. Binding to any other address requires --token.")
+        .flag(
+            Flag::new("port")
+                .short('p')
+                .usage("Port to listen on")
+                .value_type(FlagType::Int)
+                .default(FlagValue::Int(8080)),
+        )
+        .flag(
+            Flag::new("host")
+                .usage("Address to bind (keep the default unless you trust the network)")
+                .value_type(FlagType::String)
+                .default(FlagValue::String("127.0.0.1".to_string())),
+        )
+        .flag(
+            Flag::new("token")
+                .usage("Require 'Authorization: Bearer <token>' on every request (or GAMECODE_TOKEN)")
+                .value_type(FlagType::String),
+        )
+        .flag(
+            Flag::new("model")
+                .short('m')
+                .usage("Default model for requests that don't name one")
+                .value_type(FlagType::String),
+        )
+        .flag(
+            Flag::new("region")
+                .short('r')
+                .usage("AWS region")
+                .value_type(FlagType::String)
+                .default(FlagValue::String("us-west-2".to_string())),
+        )
+        .flag(
+            Flag::new("yes")
+                .short('y')
+                .usage("Run every tool call without approval")
+                .value_type(FlagType::Bool)
+                .default(FlagValue::Bool(false)),
+        )
+        .flag(
+            Flag::new("no-tools")
+                .usage("Serve without tools")
+                .value_type(FlagType::Bool)
+                .default(FlagValue::Bool(false)),
+        )
+        .run(|ctx| {
+            let host = ctx.flag("host").cloned().unwrap_or_else(|| "127.0.0.1".to_string());
+            let flags = Flags::new(ctx);
+            let port = flags.parse::<u16>("port").map_err(command_error)?.unwrap_or(8080);
+            let addr: SocketAddr = format!("{}:{}", host, port)
+                .parse()
+                .map_err(|e| flag_rs::Error::ArgumentParsing(format!("Invalid address: {}", e)))?;
+            let token = flags.value("token");
+            if token.is_none() && !addr.ip().is_loopback() {
+                return Err(flag_rs::Error::ArgumentParsing(format!(
+                    "Serving on {} exposes the API beyond this machine; set --token",
+                    addr.ip()
+                )));
+            }
+            let access = Access { token, hosts: vec![host] };
+            let Settings { region, model: model_flag, auto_approve: approve_all, no_tools, .. } = Settings::for_command(ctx)?;
+
+            let result = tokio::task::block_in_place(|| {
+                tokio::runtime::Handle::current().block_on(async {
+                    let state = ServerState::new(&region, model_flag, approve_all, no_tools).await?;
+                    println!("🌐 Serving {} with {} tools on http://{}", state.model, state.tools.len(), addr);
+                    server::serve(addr, state, access).await
+                })
+            });
+
+            result.map_err(|e| flag_rs::Error::Custom(format!("{:#}", e).into()))
+        })
+        .build();
+
+    parent.add_command(cmd);
+}
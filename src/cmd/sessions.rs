@@ -1,7 +1,20 @@
-use flag_rs::{CommandBuilder, CompletionResult};
-use gamecode_context::SessionManager;
+use flag_rs::{CommandBuilder, CompletionResult, Flag, FlagType, FlagValue};
+use gamecode_context::session::MessageRole;
+use std::io::IsTerminal;
 use uuid::Uuid;
 
+use gamecode_cli::aws_auth::AwsAuth;
+use gamecode_cli::config::Config;
+use gamecode_cli::file_backups::{RollbackAction, SessionBackups};
+use gamecode_cli::replay::{ReplayOutcome, Replayer};
+use gamecode_cli::session_archive::{self, SessionArchive};
+use gamecode_cli::session_store::{self, FileStore, SqliteStore, StorageKind};
+use gamecode_cli::session_sync::{S3Location, SessionSync, SyncMode};
+use gamecode_cli::share::{self, Transcript};
+use gamecode_cli::thinking::ThinkingLog;
+use gamecode_cli::tool_log::ToolLog;
+use crate::settings::{command_error, Flags, Settings};
+
 pub fn register(parent: &mut flag_rs::Command) {
     let cmd = CommandBuilder::new("sessions")
         .short("Manage sessions")
@@ -14,28 +27,69 @@ pub fn register(parent: &mut flag_rs::Command) {
     register_list(sessions_cmd);
     register_show(sessions_cmd);
     register_delete(sessions_cmd);
+    register_rollback(sessions_cmd);
+    register_undo(sessions_cmd);
+    register_search(sessions_cmd);
+    register_stats(sessions_cmd);
+    register_migrate(sessions_cmd);
+    register_sync(sessions_cmd);
+    register_share(sessions_cmd);
+    register_archive(sessions_cmd);
+    register_unarchive(sessions_cmd);
+    register_replay(sessions_cmd);
+}
+
+/// Session IDs starting with `prefix`, for argument completion
+fn complete_session_ids(prefix: &str) -> CompletionResult {
+    let mut result = CompletionResult::new();
+    let Ok(store) = session_store::open_configured() else {
+        return result;
+    };
+    for session in store.list_sessions().unwrap_or_default() {
+        let id_str = session.id.to_string();
+        if id_str.starts_with(prefix) {
+            result = result.add(id_str);
+        }
+    }
+    result
 }
 
 fn register_list(parent: &mut flag_rs::Command) {
     let cmd = CommandBuilder::new("list")
         .short("List available sessions")
-        .run(|_ctx| {
-            let session_manager =
-                SessionManager::new().map_err(|e| flag_rs::Error::Custom(Box::new(e)))?;
-            let sessions = session_manager
-                .list_sessions()
-                .map_err(|e| flag_rs::Error::Custom(Box::new(e)))?;
+        .flag(
+            Flag::new("archived")
+                .usage("Also list archived sessions")
+                .value_type(FlagType::Bool),
+        )
+        .run(|ctx| {
+            let store = session_store::open_configured().map_err(command_error)?;
+            let sessions = store.list_sessions().map_err(command_error)?;
 
             println!("Available sessions:");
             for session_info in sessions {
-                let created = chrono::DateTime::<chrono::Utc>::from(session_info.created_at)
-                    .format("%Y-%m-%d %H:%M:%S")
-                    .to_string();
+                let created = session_info.created_at.format("%Y-%m-%d %H:%M:%S").to_string();
                 println!(
                     "  {} - {} ({} messages)",
                     session_info.id, created, session_info.message_count
                 );
             }
+
+            if Flags::new(ctx).enabled("archived").map_err(command_error)? {
+                let archived = SessionArchive::open()
+                    .and_then(|archive| archive.list())
+                    .map_err(command_error)?;
+                println!("\nArchived sessions:");
+                for session_info in archived {
+                    println!(
+                        "  {} - {} ({} messages, archived {})",
+                        session_info.id,
+                        session_info.created_at.format("%Y-%m-%d %H:%M:%S"),
+                        session_info.message_count,
+                        session_info.archived_at.format("%Y-%m-%d")
+                    );
+                }
+            }
             Ok(())
         })
         .build();
@@ -46,16 +100,14 @@ fn register_list(parent: &mut flag_rs::Command) {
 fn register_show(parent: &mut flag_rs::Command) {
     let cmd = CommandBuilder::new("show")
         .short("Show session details")
-        .arg_completion(|_ctx, prefix| match SessionManager::new() {
-            Ok(manager) => match manager.list_sessions() {
+        .arg_completion(|_ctx, prefix| match session_store::open_configured() {
+            Ok(store) => match store.list_sessions() {
                 Ok(sessions) => {
                     let mut result = CompletionResult::new();
                     for session in sessions {
                         let id_str = session.id.to_string();
                         if id_str.starts_with(prefix) {
-                            let created = chrono::DateTime::<chrono::Utc>::from(session.created_at)
-                                .format("%Y-%m-%d %H:%M")
-                                .to_string();
+                            let created = session.created_at.format("%Y-%m-%d %H:%M").to_string();
                             result = result.add_with_description(
                                 id_str,
                                 format!("{} - {} messages", created, session.message_count),
@@ -76,11 +128,11 @@ fn register_show(parent: &mut flag_rs::Command) {
             let session_id =
                 Uuid::parse_str(session_id_str).map_err(|e| flag_rs::Error::Custom(Box::new(e)))?;
 
-            let mut session_manager =
-                SessionManager::new().map_err(|e| flag_rs::Error::Custom(Box::new(e)))?;
-            let session = session_manager
-                .load_session(&session_id)
-                .map_err(|e| flag_rs::Error::Custom(Box::new(e)))?;
+            let mut store = session_store::open_configured().map_err(command_error)?;
+            let session = store.load_session(&session_id).map_err(command_error)?;
+            let thinking = ThinkingLog::open(&session_id)
+                .and_then(|log| log.load())
+                .map_err(command_error)?;
 
             println!("Session: {}", session.id);
             println!(
@@ -91,6 +143,13 @@ fn register_show(parent: &mut flag_rs::Command) {
 
             for (i, msg) in session.messages.iter().enumerate() {
                 println!("\n[{}] {:?}:", i + 1, msg.role);
+                for record in thinking.iter().filter(|record| record.message_index == i) {
+                    if std::io::stdout().is_terminal() {
+                        println!("{}\n", gamecode_cli::markdown::dim(&record.thinking));
+                    } else {
+                        println!("{}\n", record.thinking);
+                    }
+                }
                 println!("{}", msg.content);
             }
 
@@ -104,21 +163,364 @@ fn register_show(parent: &mut flag_rs::Command) {
 fn register_delete(parent: &mut flag_rs::Command) {
     let cmd = CommandBuilder::new("delete")
         .short("Delete a session")
-        .arg_completion(|_ctx, prefix| match SessionManager::new() {
-            Ok(manager) => match manager.list_sessions() {
-                Ok(sessions) => {
-                    let mut result = CompletionResult::new();
-                    for session in sessions {
-                        let id_str = session.id.to_string();
-                        if id_str.starts_with(prefix) {
-                            result = result.add(id_str);
-                        }
-                    }
-                    Ok(result)
+        .arg_completion(|_ctx, prefix| Ok(complete_session_ids(prefix)))
+        .run(|ctx| {
+            let session_id_str = ctx.args().first().ok_or_else(|| {
+                flag_rs::Error::ArgumentParsing("Session ID required".to_string())
+            })?;
+
+            let session_id =
+                Uuid::parse_str(session_id_str).map_err(|e| flag_rs::Error::Custom(Box::new(e)))?;
+
+            let mut store = session_store::open_configured().map_err(command_error)?;
+            store.delete_session(&session_id).map_err(command_error)?;
+            println!("Deleted session {}", session_id);
+            Ok(())
+        })
+        .build();
+
+    parent.add_command(cmd);
+}
+
+fn register_rollback(parent: &mut flag_rs::Command) {
+    let cmd = CommandBuilder::new("rollback")
+        .short("Restore all files modified by tools during a session")
+        .arg_completion(|_ctx, prefix| Ok(complete_session_ids(prefix)))
+        .run(|ctx| {
+            let session_id_str = ctx.args().first().ok_or_else(|| {
+                flag_rs::Error::ArgumentParsing("Session ID required".to_string())
+            })?;
+
+            let session_id =
+                Uuid::parse_str(session_id_str).map_err(|e| flag_rs::Error::Custom(Box::new(e)))?;
+
+            let backups = SessionBackups::open(&session_id)
+                .map_err(|e| flag_rs::Error::Custom(e.to_string().into()))?;
+            if backups.is_empty() {
+                println!("No file changes recorded for session {}", session_id);
+                return Ok(());
+            }
+
+            let actions = backups
+                .rollback()
+                .map_err(|e| flag_rs::Error::Custom(e.to_string().into()))?;
+            for action in &actions {
+                match action {
+                    RollbackAction::Restored(path) => println!("  restored {}", path.display()),
+                    RollbackAction::Removed(path) => println!("  removed  {}", path.display()),
                 }
-                Err(_) => Ok(CompletionResult::new()),
-            },
-            Err(_) => Ok(CompletionResult::new()),
+            }
+            println!("Rolled back {} file(s) from session {}", actions.len(), session_id);
+            Ok(())
+        })
+        .build();
+
+    parent.add_command(cmd);
+}
+
+fn register_undo(parent: &mut flag_rs::Command) {
+    let cmd = CommandBuilder::new("undo")
+        .short("Remove the last exchange from a session")
+        .long("Remove the last user turn and every message after it (the response, tool results and notes) from a session, the latest one if no ID is given. File changes are not reverted; use `sessions rollback` for that.")
+        .arg_completion(|_ctx, prefix| Ok(complete_session_ids(prefix)))
+        .run(|ctx| {
+            let mut store = session_store::open_configured().map_err(command_error)?;
+            let mut session = match ctx.args().first() {
+                Some(session_id_str) => {
+                    let session_id = Uuid::parse_str(session_id_str)
+                        .map_err(|e| flag_rs::Error::Custom(Box::new(e)))?;
+                    store.load_session(&session_id)
+                }
+                None => store.load_latest(),
+            }
+            .map_err(command_error)?;
+
+            let Some(index) = session
+                .messages
+                .iter()
+                .rposition(|m| matches!(m.role, MessageRole::User))
+            else {
+                println!("Nothing to undo in session {}", session.id);
+                return Ok(());
+            };
+
+            let removed = session.messages.len() - index;
+            let prompt = session.messages[index].content.lines().next().unwrap_or("").to_string();
+            session.messages.truncate(index);
+            store.save_session(&session).map_err(command_error)?;
+            ToolLog::open(&session.id).and_then(|log| log.truncate(index)).map_err(command_error)?;
+            ThinkingLog::open(&session.id).and_then(|log| log.truncate(index)).map_err(command_error)?;
+
+            println!("Removed {} message(s) from session {}", removed, session.id);
+            println!("  undone: {}", prompt);
+            Ok(())
+        })
+        .build();
+
+    parent.add_command(cmd);
+}
+
+fn register_search(parent: &mut flag_rs::Command) {
+    let cmd = CommandBuilder::new("search")
+        .short("Find messages containing some text")
+        .long("List the messages of any session that contain the given text (ignoring case), most recently used sessions first. Fast with SQLite storage; the file store reads every session.")
+        .flag(
+            Flag::new("limit")
+                .short('n')
+                .usage("Most messages to show")
+                .value_type(FlagType::Int)
+                .default(FlagValue::Int(20)),
+        )
+        .run(|ctx| {
+            let query = ctx.args().join(" ");
+            if query.trim().is_empty() {
+                return Err(flag_rs::Error::ArgumentParsing("Search text required".to_string()));
+            }
+            let limit = Flags::new(ctx).parse("limit").map_err(command_error)?.unwrap_or(20);
+
+            let store = session_store::open_configured().map_err(command_error)?;
+            let hits = store.search(&query, limit).map_err(command_error)?;
+            if hits.is_empty() {
+                println!("No messages contain '{}'", query);
+                return Ok(());
+            }
+            for hit in hits {
+                let line = hit
+                    .content
+                    .lines()
+                    .find(|line| line.to_lowercase().contains(&query.to_lowercase()))
+                    .unwrap_or("")
+                    .trim();
+                let line = match line.char_indices().nth(100) {
+                    Some((end, _)) => format!("{}…", &line[..end]),
+                    None => line.to_string(),
+                };
+                println!("  {} [{}] {}: {}", hit.session_id, hit.index + 1, hit.role, line);
+            }
+            Ok(())
+        })
+        .build();
+
+    parent.add_command(cmd);
+}
+
+fn register_stats(parent: &mut flag_rs::Command) {
+    let cmd = CommandBuilder::new("stats")
+        .short("Show totals over all sessions")
+        .run(|_ctx| {
+            let config = Config::load().map_err(command_error)?;
+            let store = session_store::open(&config).map_err(command_error)?;
+            let stats = store.stats().map_err(command_error)?;
+
+            let date = |time: Option<chrono::DateTime<chrono::Utc>>| {
+                time.map_or_else(|| "-".to_string(), |t| t.format("%Y-%m-%d %H:%M").to_string())
+            };
+            println!("Storage:  {:?}", config.sessions.storage);
+            println!("Sessions: {}", stats.sessions);
+            println!("Messages: {}", stats.messages);
+            println!("Content:  {} characters", stats.content_chars);
+            println!("Oldest:   {}", date(stats.oldest));
+            println!("Newest:   {}", date(stats.newest));
+            Ok(())
+        })
+        .build();
+
+    parent.add_command(cmd);
+}
+
+fn register_migrate(parent: &mut flag_rs::Command) {
+    let cmd = CommandBuilder::new("migrate")
+        .short("Copy JSON sessions into SQLite storage")
+        .long("Copy every JSON session file not yet in the SQLite database (~/.local/share/gamecode/sessions.db) into it. The files are left in place. Run it again after using file storage to pick up newer sessions; sessions already copied are skipped. Set storage = \"sqlite\" under [sessions] in config to use the database.")
+        .run(|_ctx| {
+            let config = Config::load().map_err(command_error)?;
+            let mut target = SqliteStore::open().map_err(command_error)?;
+            let source = FileStore::new().map_err(command_error)?;
+            let copied = target.import(&source).map_err(command_error)?;
+            println!("Copied {} session(s) into {}", copied, SqliteStore::db_path().map_err(command_error)?.display());
+            if config.sessions.storage != StorageKind::Sqlite {
+                println!("To use it, add to ~/.config/gamecode/config.toml:\n\n[sessions]\nstorage = \"sqlite\"");
+            }
+            Ok(())
+        })
+        .build();
+
+    parent.add_command(cmd);
+}
+
+fn register_sync(parent: &mut flag_rs::Command) {
+    let cmd = CommandBuilder::new("sync")
+        .short("Sync sessions with an S3 bucket")
+        .long("Copy sessions between this machine and s3://bucket/prefix, one JSON object per session. push uploads sessions that are newer locally, pull downloads sessions that are newer in the bucket, and merge (the default) does both. Sessions are compared by last update time with SQLite storage, otherwise by message count. Credentials come from the same AWS setup as Bedrock, including --aws-profile and --assume-role.")
+        .flag(
+            Flag::new("bucket")
+                .usage("S3 location as s3://bucket/prefix (default: sync_bucket under [sessions] in config)")
+                .value_type(FlagType::String),
+        )
+        .flag(
+            Flag::new("mode")
+                .usage("push, pull or merge")
+                .value_type(FlagType::String)
+                .default(FlagValue::String("merge".to_string())),
+        )
+        .run(|ctx| {
+            let config = Config::load().map_err(command_error)?;
+            let flags = Flags::new(ctx);
+            let bucket = flags.value("bucket").or_else(|| config.sessions.sync_bucket.clone()).ok_or_else(|| {
+                flag_rs::Error::ArgumentParsing(
+                    "--bucket required (or set sync_bucket under [sessions] in config)".to_string(),
+                )
+            })?;
+            let location: S3Location = bucket.parse().map_err(command_error)?;
+            let mode: SyncMode = flags.parse("mode").map_err(command_error)?.unwrap_or_default();
+            let region = Settings::for_command(ctx)?.region;
+            let aws_auth = AwsAuth::new(flags.value("aws-profile"), flags.value("assume-role"));
+
+            let mut store = session_store::open(&config).map_err(command_error)?;
+            let report = tokio::task::block_in_place(|| {
+                tokio::runtime::Handle::current().block_on(async {
+                    let sdk_config = aws_auth.sdk_config(&region).await?;
+                    SessionSync::new(location.clone(), &sdk_config).run(store.as_mut(), mode).await
+                })
+            })
+            .map_err(command_error)?;
+
+            for id in &report.pushed {
+                println!("  pushed {}", id);
+            }
+            for id in &report.pulled {
+                println!("  pulled {}", id);
+            }
+            println!(
+                "Synced with {}: {} pushed, {} pulled, {} unchanged",
+                location,
+                report.pushed.len(),
+                report.pulled.len(),
+                report.unchanged
+            );
+            if report.skipped > 0 {
+                println!("Skipped {} unreadable session(s)", report.skipped);
+            }
+            Ok(())
+        })
+        .build();
+
+    parent.add_command(cmd);
+}
+
+fn register_share(parent: &mut flag_rs::Command) {
+    let cmd = CommandBuilder::new("share")
+        .short("Export a session as a standalone HTML page or a secret gist")
+        .long("Write a session as a self-contained HTML page, with tool calls and the system prompt collapsed, for sharing transcripts with teammates. With --gist, upload it instead as Markdown to a secret GitHub gist (using GITHUB_TOKEN, GH_TOKEN or the GitHub CLI's login) and print its URL. Tool call details are available for sessions recorded by this version or later.")
+        .arg_completion(|_ctx, prefix| Ok(complete_session_ids(prefix)))
+        .flag(
+            Flag::new("output")
+                .short('o')
+                .usage("File to write the page to, - for stdout (default: gamecode-session-<id>.html)")
+                .value_type(FlagType::String),
+        )
+        .flag(
+            Flag::new("gist")
+                .usage("Upload to a secret GitHub gist instead of writing a file")
+                .value_type(FlagType::Bool),
+        )
+        .run(|ctx| {
+            let session_id_str = ctx.args().first().ok_or_else(|| {
+                flag_rs::Error::ArgumentParsing("Session ID required".to_string())
+            })?;
+
+            let session_id =
+                Uuid::parse_str(session_id_str).map_err(|e| flag_rs::Error::Custom(Box::new(e)))?;
+
+            let mut store = session_store::open_configured().map_err(command_error)?;
+            let session = store.load_session(&session_id).map_err(command_error)?;
+            let tool_calls = ToolLog::open(&session_id)
+                .and_then(|log| log.load())
+                .map_err(command_error)?;
+            let transcript = Transcript::new(&session, &tool_calls);
+
+            let flags = Flags::new(ctx);
+            if flags.enabled("gist").map_err(command_error)? {
+                let url = tokio::task::block_in_place(|| {
+                    tokio::runtime::Handle::current().block_on(share::create_gist(
+                        &format!("gamecode-session-{}.md", session_id),
+                        &format!("gamecode session {}", session_id),
+                        &transcript.markdown(),
+                    ))
+                })
+                .map_err(command_error)?;
+                println!("Shared session {} as a secret gist: {}", session_id, url);
+                return Ok(());
+            }
+
+            let page = transcript.html();
+            match flags.value("output").as_deref() {
+                Some("-") => print!("{}", page),
+                output => {
+                    let path = output
+                        .map(std::path::PathBuf::from)
+                        .unwrap_or_else(|| format!("gamecode-session-{}.html", session_id).into());
+                    std::fs::write(&path, page)
+                        .map_err(|e| flag_rs::Error::Custom(format!("Failed to write {}: {}", path.display(), e).into()))?;
+                    println!("Wrote session {} to {}", session_id, path.display());
+                }
+            }
+            Ok(())
+        })
+        .build();
+
+    parent.add_command(cmd);
+}
+
+fn register_archive(parent: &mut flag_rs::Command) {
+    let cmd = CommandBuilder::new("archive")
+        .short("Move old sessions into compressed archive files")
+        .long("Move sessions not used for longer than --older-than (like 30d, 12h or 2w) out of session storage into gzipped files under ~/.local/share/gamecode/archive/, together with their recorded tool calls. Archived sessions are hidden from `sessions list` unless --archived is given; `sessions unarchive <id>` restores one.")
+        .flag(
+            Flag::new("older-than")
+                .usage("Archive sessions last used longer ago than this, like 30d")
+                .value_type(FlagType::String),
+        )
+        .run(|ctx| {
+            let older_than = Flags::new(ctx).value("older-than").ok_or_else(|| {
+                flag_rs::Error::ArgumentParsing("--older-than required, like --older-than 30d".to_string())
+            })?;
+            let cutoff = chrono::Utc::now() - session_archive::parse_age(&older_than).map_err(command_error)?;
+
+            let mut store = session_store::open_configured().map_err(command_error)?;
+            let archive = SessionArchive::open().map_err(command_error)?;
+            let mut archived = 0;
+            for info in store.list_sessions().map_err(command_error)? {
+                if info.updated_at.unwrap_or(info.created_at) >= cutoff {
+                    continue;
+                }
+                archive
+                    .archive(store.as_mut(), &info)
+                    .map_err(|e| command_error(e.context(format!("Failed to archive session {}", info.id))))?;
+                println!("  archived {}", info.id);
+                archived += 1;
+            }
+            println!("Archived {} session(s) last used before {}", archived, cutoff.format("%Y-%m-%d %H:%M"));
+            Ok(())
+        })
+        .build();
+
+    parent.add_command(cmd);
+}
+
+fn register_unarchive(parent: &mut flag_rs::Command) {
+    let cmd = CommandBuilder::new("unarchive")
+        .short("Restore an archived session")
+        .arg_completion(|_ctx, prefix| {
+            let mut result = CompletionResult::new();
+            let archived = SessionArchive::open().and_then(|archive| archive.list()).unwrap_or_default();
+            for session in archived {
+                let id_str = session.id.to_string();
+                if id_str.starts_with(prefix) {
+                    result = result.add(id_str);
+                }
+            }
+            Ok(result)
         })
         .run(|ctx| {
             let session_id_str = ctx.args().first().ok_or_else(|| {
@@ -128,14 +530,97 @@ fn register_delete(parent: &mut flag_rs::Command) {
             let session_id =
                 Uuid::parse_str(session_id_str).map_err(|e| flag_rs::Error::Custom(Box::new(e)))?;
 
-            // SessionManager doesn't expose delete_session directly
-            // For now, we'll just print a message
-            // TODO: Add delete_session to SessionManager or use storage directly
-            println!("Session deletion not yet implemented");
-            println!("Would delete session: {}", session_id);
+            let mut store = session_store::open_configured().map_err(command_error)?;
+            let archive = SessionArchive::open().map_err(command_error)?;
+            let session = archive.unarchive(store.as_mut(), &session_id).map_err(command_error)?;
+            println!("Restored session {} ({} messages)", session.id, session.messages.len());
             Ok(())
         })
         .build();
 
     parent.add_command(cmd);
 }
+
+fn register_replay(parent: &mut flag_rs::Command) {
+    let cmd = CommandBuilder::new("replay")
+        .short("Run a session's recorded tool calls again without the model")
+        .long("Re-execute the tool calls recorded for a session, in order, without calling the model, to reproduce an agentic change on a fresh checkout or after a rollback. Each call is approved and file changes are confirmed as in a normal run (--yes skips both). Replay stops at the first call that fails where the original succeeded; continue with --from. Files changed are backed up, so `sessions rollback` undoes the replay.")
+        .arg_completion(|_ctx, prefix| Ok(complete_session_ids(prefix)))
+        .flag(
+            Flag::new("from")
+                .usage("Number of the first tool call to replay, from 1")
+                .value_type(FlagType::Int)
+                .default(FlagValue::Int(1)),
+        )
+        .run(|ctx| {
+            let session_id_str = ctx.args().first().ok_or_else(|| {
+                flag_rs::Error::ArgumentParsing("Session ID required".to_string())
+            })?;
+
+            let session_id =
+                Uuid::parse_str(session_id_str).map_err(|e| flag_rs::Error::Custom(Box::new(e)))?;
+
+            let from: usize = Flags::new(ctx).parse("from").map_err(command_error)?.unwrap_or(1);
+            if from == 0 {
+                return Err(flag_rs::Error::ArgumentParsing("--from counts from 1".to_string()));
+            }
+            let auto_approve = Settings::for_command(ctx)?.auto_approve;
+            let config = Config::load().map_err(command_error)?;
+
+            let calls = ToolLog::open(&session_id)
+                .and_then(|log| log.load())
+                .map_err(command_error)?;
+            if calls.is_empty() {
+                println!("No tool calls recorded for session {}", session_id);
+                return Ok(());
+            }
+            if from > calls.len() {
+                return Err(flag_rs::Error::ArgumentParsing(format!(
+                    "--from {} is past the last of {} recorded tool calls",
+                    from,
+                    calls.len()
+                )));
+            }
+
+            tokio::task::block_in_place(|| {
+                tokio::runtime::Handle::current().block_on(async {
+                    let mut replayer = Replayer::new(&session_id, auto_approve, &config).await?;
+                    let (mut ran, mut skipped, mut differed) = (0, 0, 0);
+                    for (number, call) in calls.iter().enumerate().skip(from - 1).map(|(i, call)| (i + 1, call)) {
+                        println!("[{}/{}] 🔧 {} {}", number, calls.len(), call.name, call.input);
+                        match replayer.run(call).await? {
+                            ReplayOutcome::Skipped => {
+                                println!("  skipped");
+                                skipped += 1;
+                            }
+                            ReplayOutcome::Ran { outcome, same_result } => {
+                                ran += 1;
+                                if outcome.is_error && !call.is_error {
+                                    println!("  ✗ failed: {}", outcome.content.lines().next().unwrap_or(""));
+                                    println!(
+                                        "Stopped at tool call {}; continue with: gamecode sessions replay {} --from {}",
+                                        number,
+                                        session_id,
+                                        number + 1
+                                    );
+                                    return Ok(());
+                                }
+                                if same_result {
+                                    println!("  ✓ same result as recorded");
+                                } else {
+                                    println!("  ≠ result differs from the recording");
+                                    differed += 1;
+                                }
+                            }
+                        }
+                    }
+                    println!("Replayed {} tool call(s): {} skipped, {} with different results", ran, skipped, differed);
+                    anyhow::Ok(())
+                })
+            })
+            .map_err(command_error)
+        })
+        .build();
+
+    parent.add_command(cmd);
+}
@@ -0,0 +1,239 @@
+use flag_rs::{CommandBuilder, Flag, FlagType, FlagValue};
+use gamecode_context::session::{Message as ContextMessage, MessageRole};
+use std::io::{IsTerminal, Write};
+use std::process::Command;
+
+use gamecode_cli::one_shot::OneShot;
+use gamecode_cli::session_store;
+use gamecode_cli::tool_result;
+use crate::settings::Settings;
+
+/// Command output kept in the session is capped like tool results
+const MAX_SESSION_OUTPUT_BYTES: usize = 16 * 1024;
+
+pub fn register(parent: &mut flag_rs::Command) {
+    register_do(parent);
+    register_shell_init(parent);
+}
+
+const ZSH_INIT: &str = r#"# gamecode shell integration: eval "$(gamecode shell-init zsh)"
+typeset -g __gamecode_last_cmd=""
+typeset -gi __gamecode_last_status=0
+__gamecode_preexec() {
+  # Don't let running the explain command itself replace the command being explained
+  [[ "$1" != gamecode-explain* ]] && __gamecode_last_cmd="$1"
+}
+__gamecode_precmd() { __gamecode_last_status=$? }
+autoload -Uz add-zsh-hook
+add-zsh-hook preexec __gamecode_preexec
+# Run first so $? is still the command's exit status
+precmd_functions=(__gamecode_precmd ${precmd_functions:#__gamecode_precmd})
+
+# Explain the previous command and, if it failed, how to fix it
+gamecode-explain() {
+  if [[ -z "$__gamecode_last_cmd" ]]; then
+    echo "gamecode: no previous command" >&2
+    return 1
+  fi
+  gamecode --new-session --no-tools "Explain this shell command and, if it failed, how to fix it.
+Command: $__gamecode_last_cmd
+Exit status: $__gamecode_last_status"
+}
+__gamecode_explain_widget() {
+  zle -I
+  gamecode-explain
+  zle reset-prompt
+}
+zle -N __gamecode_explain_widget
+bindkey '^Xg' __gamecode_explain_widget
+"#;
+
+const BASH_INIT: &str = r#"# gamecode shell integration: eval "$(gamecode shell-init bash)"
+__gamecode_last_cmd=""
+__gamecode_last_status=0
+__gamecode_prompt_command() {
+  __gamecode_last_status=$?
+  local last
+  last=$(HISTTIMEFORMAT= history 1 | sed 's/^ *[0-9]* *//')
+  # Don't let running the widget itself replace the command being explained
+  [[ "$last" != gamecode-explain* ]] && __gamecode_last_cmd="$last"
+}
+PROMPT_COMMAND="__gamecode_prompt_command${PROMPT_COMMAND:+;$PROMPT_COMMAND}"
+
+# Explain the previous command and, if it failed, how to fix it
+gamecode-explain() {
+  if [[ -z "$__gamecode_last_cmd" ]]; then
+    echo "gamecode: no previous command" >&2
+    return 1
+  fi
+  gamecode --new-session --no-tools "Explain this shell command and, if it failed, how to fix it.
+Command: $__gamecode_last_cmd
+Exit status: $__gamecode_last_status"
+}
+bind -x '"\C-xg": gamecode-explain'
+"#;
+
+fn register_shell_init(parent: &mut flag_rs::Command) {
+    let cmd = CommandBuilder::new("shell-init")
+        .short("Print shell integration for zsh or bash")
+        .long("Print a script that adds a gamecode-explain command and a Ctrl-X g key binding, which send the previous command and its exit status to gamecode to explain or fix. Add eval \"$(gamecode shell-init zsh)\" to your shell rc file.")
+        .arg_completion(|_ctx, prefix| {
+            let mut result = flag_rs::CompletionResult::new();
+            for shell in ["zsh", "bash"] {
+                if shell.starts_with(prefix) {
+                    result = result.add(shell.to_string());
+                }
+            }
+            Ok(result)
+        })
+        .run(|ctx| {
+            let shell = ctx.args().first().ok_or_else(|| {
+                flag_rs::Error::ArgumentParsing("Shell required: zsh or bash".to_string())
+            })?;
+            match shell.as_str() {
+                "zsh" => print!("{}", ZSH_INIT),
+                "bash" => print!("{}", BASH_INIT),
+                other => {
+                    return Err(flag_rs::Error::ArgumentParsing(format!(
+                        "Unsupported shell '{}' (expected zsh or bash)",
+                        other
+                    )));
+                }
+            }
+            Ok(())
+        })
+        .build();
+
+    parent.add_command(cmd);
+}
+
+fn register_do(parent: &mut flag_rs::Command) {
+    let cmd = CommandBuilder::new("do")
+        .short("Turn a task into a shell command and run it after confirmation")
+        .long("Ask the model for a single shell command that performs the task, print it, and run it only if you confirm. The task, command and output are added to the latest session.")
+        .flag(
+            Flag::new("model")
+                .short('m')
+                .usage("Model to use")
+                .value_type(FlagType::String),
+        )
+        .flag(
+            Flag::new("region")
+                .short('r')
+                .usage("AWS region")
+                .value_type(FlagType::String)
+                .default(FlagValue::String("us-west-2".to_string())),
+        )
+        .run(|ctx| {
+            let task = ctx.args().join(" ");
+            if task.trim().is_empty() {
+                return Err(flag_rs::Error::ArgumentParsing(
+                    "Describe the task, e.g. gamecode do \"find files over 1GB modified last week\"".to_string(),
+                ));
+            }
+
+            let Settings { region, model, .. } = Settings::for_command(ctx)?;
+            let one_shot = OneShot {
+                region,
+                model,
+            };
+            let system = format!(
+                "You turn a task into exactly one shell command for {} using sh. \
+                 Reply with the command only: no explanation, no markdown, no code fences. \
+                 Prefer safe, read-only commands when the task allows it.",
+                std::env::consts::OS
+            );
+            let reply = tokio::task::block_in_place(|| {
+                tokio::runtime::Handle::current().block_on(one_shot.ask(&system, &task))
+            })
+            .map_err(|e| flag_rs::Error::Custom(format!("{:#}", e).into()))?;
+
+            let command = clean_command(&reply);
+            if command.is_empty() {
+                return Err(flag_rs::Error::Custom("The model did not return a command".into()));
+            }
+            println!("{}", command);
+
+            if !std::io::stdin().is_terminal() {
+                eprintln!("(not running: stdin is not a terminal)");
+                return Ok(());
+            }
+            eprint!("Run this command? [y/N] ");
+            std::io::stderr().flush().ok();
+            let mut answer = String::new();
+            std::io::stdin()
+                .read_line(&mut answer)
+                .map_err(|e| flag_rs::Error::Custom(Box::new(e)))?;
+            if !matches!(answer.trim().to_lowercase().as_str(), "y" | "yes") {
+                return Ok(());
+            }
+
+            let output = Command::new("sh")
+                .arg("-c")
+                .arg(&command)
+                .output()
+                .map_err(|e| flag_rs::Error::Custom(Box::new(e)))?;
+            let stdout = String::from_utf8_lossy(&output.stdout);
+            let stderr = String::from_utf8_lossy(&output.stderr);
+            print!("{}", stdout);
+            eprint!("{}", stderr);
+            let exit_code = output.status.code().unwrap_or(-1);
+
+            let combined = format!("{}{}", stdout, stderr);
+            let recorded = tool_result::truncate_middle(&combined, MAX_SESSION_OUTPUT_BYTES)
+                .unwrap_or(combined);
+            if let Err(e) = record_in_session(&task, &command, exit_code, &recorded) {
+                eprintln!("Warning: failed to record command in session: {}", e);
+            }
+
+            if !output.status.success() {
+                return Err(flag_rs::Error::Custom(
+                    format!("Command exited with status {}", exit_code).into(),
+                ));
+            }
+            Ok(())
+        })
+        .build();
+
+    parent.add_command(cmd);
+}
+
+/// Strip the code fences or backticks models sometimes add despite instructions
+fn clean_command(reply: &str) -> String {
+    let trimmed = reply.trim();
+    let unfenced = match trimmed.strip_prefix("```") {
+        Some(rest) => {
+            let body = rest.split_once('\n').map(|(_, body)| body).unwrap_or("");
+            body.trim_end().trim_end_matches("```")
+        }
+        None => trimmed,
+    };
+    unfenced.trim().trim_matches('`').trim().to_string()
+}
+
+fn record_in_session(
+    task: &str,
+    command: &str,
+    exit_code: i32,
+    output: &str,
+) -> Result<(), Box<dyn std::error::Error>> {
+    let mut session_manager = session_store::open_configured()?;
+    let mut session = session_manager.load_latest()?;
+    session_manager.add_message(
+        &mut session,
+        ContextMessage::new(MessageRole::User, format!("Shell task: {}", task)),
+    )?;
+    session_manager.add_message(
+        &mut session,
+        ContextMessage::new(MessageRole::Assistant, command.to_string()),
+    )?;
+    session_manager.add_message(
+        &mut session,
+        ContextMessage::new(
+            MessageRole::User,
+            format!("Command exited with status {}. Output:\n{}", exit_code, output),
+        ),
+    )?;
+    session_manager.save_session(&session)?;
+    Ok(())
+}
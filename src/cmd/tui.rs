@@ -0,0 +1,80 @@
+use flag_rs::{CommandBuilder, Flag, FlagType, FlagValue};
+use uuid::Uuid;
+
+use gamecode_cli::server::ServerState;
+use gamecode_cli::{status, tui};
+use crate::settings::{Flags, Settings};
+
+pub fn register(parent: &mut flag_rs::Command) {
+    let cmd = CommandBuilder::new("tui")
+        .short("Supervise agent runs in a full-screen terminal UI")
+        .long("Chat with the agent in a full-screen UI that shows the conversation, the tool call waiting for approval (with a diff for file edits) and token usage. Press y to run a tool call, n to refuse it, or a to allow that tool for the rest of the session. Tools in approved_tools run without asking, as do all tools with --yes.")
+        .flag(
+            Flag::new("model")
+                .short('m')
+                .usage("Model alias or ID")
+                .value_type(FlagType::String),
+        )
+        .flag(
+            Flag::new("region")
+                .short('r')
+                .usage("AWS region")
+                .value_type(FlagType::String),
+        )
+        .flag(
+            Flag::new("session")
+                .usage("Session ID to continue (default: a new session)")
+                .value_type(FlagType::String),
+        )
+        .flag(
+            Flag::new("system-prompt")
+                .short('s')
+                .usage("Stored prompt for a new session")
+                .value_type(FlagType::String),
+        )
+        .flag(
+            Flag::new("yes")
+                .short('y')
+                .usage("Run every tool call without approval")
+                .value_type(FlagType::Bool)
+                .default(FlagValue::Bool(false)),
+        )
+        .flag(
+            Flag::new("no-tools")
+                .usage("Run the agent without tools")
+                .value_type(FlagType::Bool)
+                .default(FlagValue::Bool(false)),
+        )
+        .run(|ctx| {
+            let Settings { region, model, auto_approve, no_tools, .. } = Settings::for_command(ctx)?;
+            let flags = Flags::new(ctx);
+            let session_id = flags
+                .value("session")
+                .map(|s| Uuid::parse_str(&s))
+                .transpose()
+                .map_err(|e| flag_rs::Error::ArgumentParsing(format!("Invalid session ID: {}", e)))?;
+            let system_prompt = flags.value("system-prompt");
+
+            // Progress messages on stderr would tear through the UI
+            status::set_quiet(true);
+            tokio::task::block_in_place(|| {
+                tokio::runtime::Handle::current().block_on(async {
+                    let state = ServerState::new(&region, model, auto_approve, no_tools).await?;
+                    let session_id = match session_id {
+                        Some(id) => id,
+                        None => {
+                            let prompt = system_prompt.or_else(|| state.config.system_prompt.clone());
+                            state.create_session(None, prompt.as_deref()).await?.id
+                        }
+                    };
+                    tui::run(state, session_id).await?;
+                    println!("Session: {}", session_id);
+                    Ok::<_, anyhow::Error>(())
+                })
+            })
+            .map_err(|e| flag_rs::Error::Custom(format!("{:#}", e).into()))
+        })
+        .build();
+
+    parent.add_command(cmd);
+}
@@ -0,0 +1,83 @@
+use flag_rs::{CommandBuilder, Flag, FlagType};
+use std::collections::BTreeMap;
+use uuid::Uuid;
+
+use gamecode_cli::usage::{TokenUsage, UsageLedger};
+use crate::settings::{command_error, Flags};
+
+pub fn register(parent: &mut flag_rs::Command) {
+    let cmd = CommandBuilder::new("usage")
+        .short("Report token usage and estimated cost by model")
+        .flag(
+            Flag::new("session")
+                .usage("Only include this session")
+                .value_type(FlagType::String),
+        )
+        .flag(
+            Flag::new("days")
+                .usage("Only include the last N days")
+                .value_type(FlagType::Int),
+        )
+        .run(|ctx| {
+            let session = ctx
+                .flag("session")
+                .map(|s| Uuid::parse_str(s))
+                .transpose()
+                .map_err(|e| flag_rs::Error::ArgumentParsing(format!("Invalid session ID: {}", e)))?;
+            let since = Flags::new(ctx)
+                .parse::<i64>("days")
+                .map_err(command_error)?
+                .map(|days| chrono::Utc::now() - chrono::Duration::days(days));
+
+            let records = UsageLedger::load()
+                .map_err(|e| flag_rs::Error::Custom(e.to_string().into()))?;
+
+            let mut by_model: BTreeMap<String, (usize, TokenUsage)> = BTreeMap::new();
+            for record in records
+                .iter()
+                .filter(|r| session.is_none_or(|id| r.session_id == id))
+                .filter(|r| since.is_none_or(|since| r.timestamp >= since))
+            {
+                let entry = by_model.entry(record.model.clone()).or_default();
+                entry.0 += 1;
+                entry.1.add(record.usage);
+            }
+
+            if by_model.is_empty() {
+                println!("No usage recorded.");
+                return Ok(());
+            }
+
+            println!(
+                "{:<50} {:>8} {:>12} {:>12} {:>12} {:>10}",
+                "MODEL", "REQUESTS", "INPUT", "CACHE READ", "OUTPUT", "COST"
+            );
+            let mut total_cost = 0.0;
+            let mut unpriced = false;
+            for (model, (requests, usage)) in &by_model {
+                let cost = match usage.cost(model) {
+                    Some(cost) => {
+                        total_cost += cost;
+                        format!("${:.4}", cost)
+                    }
+                    None => {
+                        unpriced = true;
+                        "-".to_string()
+                    }
+                };
+                println!(
+                    "{:<50} {:>8} {:>12} {:>12} {:>12} {:>10}",
+                    model, requests, usage.input_tokens + usage.cache_write_tokens, usage.cache_read_tokens,
+                    usage.output_tokens, cost
+                );
+            }
+            println!("\nEstimated total: ${:.4}", total_cost);
+            if unpriced {
+                println!("(models marked '-' have no built-in price and are not included)");
+            }
+            Ok(())
+        })
+        .build();
+
+    parent.add_command(cmd);
+}
@@ -0,0 +1,192 @@
+use anyhow::{Context, Result};
+use crate::embeddings::{self, Embedder};
+use ignore::WalkBuilder;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::process::Command;
+use tracing::debug;
+
+/// Lines per chunk, with `CHUNK_OVERLAP` lines shared between neighbours
+const CHUNK_LINES: usize = 60;
+const CHUNK_OVERLAP: usize = 10;
+
+/// Files larger than this are skipped when indexing
+const MAX_FILE_BYTES: u64 = 256 * 1024;
+
+/// A slice of a file and its embedding
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct IndexedChunk {
+    /// Path relative to the repository root
+    pub path: String,
+    pub start_line: usize,
+    pub end_line: usize,
+    pub text: String,
+    pub vector: Vec<f32>,
+}
+
+/// Embedded chunks of a repository, stored under `~/.local/share/gamecode/index/`
+#[derive(Debug, Serialize, Deserialize)]
+pub struct CodeIndex {
+    pub root: PathBuf,
+    pub model: String,
+    pub built_at: chrono::DateTime<chrono::Utc>,
+    pub chunks: Vec<IndexedChunk>,
+}
+
+impl CodeIndex {
+    /// Top of the current git repository, or the working directory outside one
+    pub fn repo_root() -> Result<PathBuf> {
+        let output = Command::new("git").args(["rev-parse", "--show-toplevel"]).output();
+        if let Ok(output) = output {
+            if output.status.success() {
+                let root = String::from_utf8_lossy(&output.stdout).trim().to_string();
+                return Ok(PathBuf::from(root));
+            }
+        }
+        std::env::current_dir().context("Failed to get current directory")
+    }
+
+    /// Where the index for `root` is stored
+    pub fn index_path(root: &Path) -> Result<PathBuf> {
+        let home = home::home_dir().context("Failed to get home directory")?;
+        let name: String = root
+            .to_string_lossy()
+            .chars()
+            .map(|c| if c.is_ascii_alphanumeric() || c == '-' || c == '.' { c } else { '_' })
+            .collect();
+        Ok(home
+            .join(".local")
+            .join("share")
+            .join("gamecode")
+            .join("index")
+            .join(format!("{}.json", name)))
+    }
+
+    pub fn load(root: &Path) -> Result<Option<Self>> {
+        let path = Self::index_path(root)?;
+        if !path.exists() {
+            return Ok(None);
+        }
+        let content = fs::read_to_string(&path)
+            .with_context(|| format!("Failed to read {}", path.display()))?;
+        let index = serde_json::from_str(&content)
+            .with_context(|| format!("Failed to parse {}", path.display()))?;
+        Ok(Some(index))
+    }
+
+    pub fn save(&self) -> Result<PathBuf> {
+        let path = Self::index_path(&self.root)?;
+        if let Some(parent) = path.parent() {
+            fs::create_dir_all(parent)?;
+        }
+        fs::write(&path, serde_json::to_string(self)?)
+            .with_context(|| format!("Failed to write {}", path.display()))?;
+        Ok(path)
+    }
+
+    /// Walk `root` honoring .gitignore and embed every text chunk. Chunks whose text
+    /// is unchanged since `previous` reuse their vectors instead of being re-embedded.
+    pub async fn build(
+        root: &Path,
+        embedder: &Embedder,
+        previous: Option<&CodeIndex>,
+        progress: &dyn Fn(usize, usize),
+    ) -> Result<Self> {
+        let mut cached: HashMap<&str, &[f32]> = HashMap::new();
+        if let Some(previous) = previous.filter(|p| p.model == embedder.model()) {
+            for chunk in &previous.chunks {
+                cached.insert(chunk.text.as_str(), chunk.vector.as_slice());
+            }
+        }
+
+        let mut pending = Vec::new();
+        for entry in WalkBuilder::new(root).build() {
+            let entry = match entry {
+                Ok(entry) => entry,
+                Err(e) => {
+                    debug!("Skipping unreadable entry: {}", e);
+                    continue;
+                }
+            };
+            if !entry.file_type().is_some_and(|t| t.is_file()) {
+                continue;
+            }
+            if entry.metadata().map(|m| m.len() > MAX_FILE_BYTES).unwrap_or(true) {
+                debug!("Skipping large file {}", entry.path().display());
+                continue;
+            }
+            let Ok(bytes) = fs::read(entry.path()) else { continue };
+            if bytes.contains(&0) {
+                continue;
+            }
+            let Ok(content) = String::from_utf8(bytes) else { continue };
+            let relative = entry
+                .path()
+                .strip_prefix(root)
+                .unwrap_or(entry.path())
+                .to_string_lossy()
+                .into_owned();
+            pending.extend(chunk_file(&relative, &content));
+        }
+
+        let total = pending.len();
+        let mut chunks = Vec::with_capacity(total);
+        for (index, (path, start_line, end_line, text)) in pending.into_iter().enumerate() {
+            progress(index + 1, total);
+            let vector = match cached.get(text.as_str()) {
+                Some(vector) => vector.to_vec(),
+                None => embedder
+                    .embed(&format!("{}\n{}", path, text))
+                    .await
+                    .with_context(|| format!("Failed to embed {}:{}", path, start_line))?,
+            };
+            chunks.push(IndexedChunk {
+                path,
+                start_line,
+                end_line,
+                text,
+                vector,
+            });
+        }
+
+        Ok(Self {
+            root: root.to_path_buf(),
+            model: embedder.model().to_string(),
+            built_at: chrono::Utc::now(),
+            chunks,
+        })
+    }
+
+    /// The `limit` chunks most similar to `query`, best first
+    pub fn search(&self, query: &[f32], limit: usize) -> Vec<(f32, &IndexedChunk)> {
+        let mut scored: Vec<(f32, &IndexedChunk)> = self
+            .chunks
+            .iter()
+            .map(|chunk| (embeddings::similarity(query, &chunk.vector), chunk))
+            .collect();
+        scored.sort_by(|a, b| b.0.total_cmp(&a.0));
+        scored.truncate(limit);
+        scored
+    }
+}
+
+/// Split a file into overlapping line windows of (path, first line, last line, text)
+fn chunk_file(path: &str, content: &str) -> Vec<(String, usize, usize, String)> {
+    let lines: Vec<&str> = content.lines().collect();
+    let mut chunks = Vec::new();
+    let mut start = 0;
+    while start < lines.len() {
+        let end = (start + CHUNK_LINES).min(lines.len());
+        let text = lines[start..end].join("\n");
+        if !text.trim().is_empty() {
+            chunks.push((path.to_string(), start + 1, end, text));
+        }
+        if end == lines.len() {
+            break;
+        }
+        start = end - CHUNK_OVERLAP;
+    }
+    chunks
+}
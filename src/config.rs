@@ -0,0 +1,194 @@
+use anyhow::{Context, Result};
+use crate::budget::BudgetConfig;
+use crate::guardrails::GuardrailConfig;
+use crate::hooks::HooksConfig;
+use crate::inference::InferenceDefaults;
+use crate::logging::LoggingConfig;
+use crate::redaction::RedactionConfig;
+use crate::response_cache::CacheConfig;
+use crate::retry::RetryDefaults;
+use crate::session_store::SessionsConfig;
+use crate::sub_agent::SubAgentConfig;
+use crate::tool_capabilities::ToolCapability;
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, HashMap};
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::sync::OnceLock;
+use tracing::debug;
+
+/// Per-project overrides, found in the current directory or a parent up to the repository root
+pub const PROJECT_FILE: &str = ".gamecode.toml";
+
+/// Profile chosen with `--profile` for this process
+static SELECTED_PROFILE: OnceLock<String> = OnceLock::new();
+
+/// Apply the named profile in every `Config::load` from now on. Only the
+/// first call has an effect.
+pub fn select_profile(name: String) {
+    let _ = SELECTED_PROFILE.set(name);
+}
+
+/// User configuration stored in `~/.config/gamecode/config.toml`, overridden
+/// by the nearest `.gamecode.toml` and then by the selected profile
+#[derive(Debug, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct Config {
+    /// Model alias or ID used when `--model` and `GAMECODE_MODEL` are not set
+    pub default_model: Option<String>,
+    /// AWS region used when `--region` is not set
+    pub region: Option<String>,
+    /// Stored prompt used for new sessions when no system prompt option is given
+    pub system_prompt: Option<String>,
+    /// If set, only these MCP servers (by name) are started, enabled or not
+    pub mcp_servers: Option<Vec<String>>,
+    /// Profile applied when `--profile` is not given
+    pub profile: Option<String>,
+    /// Models (aliases or IDs) tried in order when the selected model stays
+    /// throttled or unavailable after retries
+    pub fallback_models: Vec<String>,
+    /// Tools that are executed without asking for approval
+    pub approved_tools: Vec<String>,
+    /// If set, only tools matching these patterns are offered to the model
+    pub tools: Option<Vec<String>>,
+    /// Tools matching these patterns are never offered to the model
+    pub deny_tools: Vec<String>,
+    /// Always run with only read-only tools
+    pub read_only: bool,
+    /// Explicit read-only/write classification for tools, overriding annotations and heuristics
+    pub tool_capabilities: HashMap<String, ToolCapability>,
+    /// Maximum size in bytes of a tool result sent back to the model
+    pub max_tool_result_bytes: Option<usize>,
+    /// Default generation parameters, globally and per model
+    pub inference: InferenceDefaults,
+    /// Maximum output tokens per model (alias or ID), overriding built-in metadata
+    pub model_limits: HashMap<String, u32>,
+    /// User-defined short names for model IDs or ARNs
+    pub model_aliases: BTreeMap<String, String>,
+    /// Bedrock guardrail applied to every prompt and response
+    pub guardrail: Option<GuardrailConfig>,
+    /// Token and cost limits per session and per day
+    pub budget: BudgetConfig,
+    /// Extra context added to the system prompt of new sessions
+    pub context: ContextConfig,
+    /// JSON log file settings
+    pub logging: LoggingConfig,
+    /// Retry and backoff for backend requests
+    pub retry: RetryDefaults,
+    /// Local cache of responses to identical requests
+    pub cache: CacheConfig,
+    /// Mark the system prompt and tools as a prompt cache prefix for models
+    /// that support prompt caching
+    pub prompt_cache: bool,
+    /// Secrets masked before prompts, attachments and tool results are sent
+    pub redaction: RedactionConfig,
+    /// External commands that can rewrite or veto prompts, responses and tool results
+    pub hooks: HooksConfig,
+    /// Where sessions are stored
+    pub sessions: SessionsConfig,
+    /// The `agent` tool that delegates tasks to sub-agents
+    pub sub_agent: SubAgentConfig,
+    /// Named bundles of settings, applied on top of everything else by `--profile`
+    pub profiles: BTreeMap<String, toml::Table>,
+}
+
+/// Settings for the `[context]` section
+#[derive(Debug, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct ContextConfig {
+    /// Describe the OS, working directory, git state and date to the model
+    pub environment: bool,
+}
+
+impl Config {
+    pub fn config_path() -> Result<PathBuf> {
+        let home = home::home_dir().context("Failed to get home directory")?;
+        Ok(home.join(".config").join("gamecode").join("config.toml"))
+    }
+
+    /// The nearest project file, searching from the current directory up to
+    /// the repository root
+    pub fn project_path() -> Option<PathBuf> {
+        let cwd = std::env::current_dir().ok()?;
+        for dir in cwd.ancestors() {
+            let candidate = dir.join(PROJECT_FILE);
+            if candidate.is_file() {
+                return Some(candidate);
+            }
+            if dir.join(".git").exists() {
+                break;
+            }
+        }
+        None
+    }
+
+    /// The global config with the project file's settings on top, then the
+    /// selected profile's. Tables are merged key by key; any other value
+    /// replaces the one below it.
+    pub fn load() -> Result<Self> {
+        let mut merged = read_table(&Self::config_path()?)?;
+        if let Some(path) = Self::project_path() {
+            debug!("Applying project config {}", path.display());
+            merge(&mut merged, read_table(&path)?);
+        }
+        let profile = SELECTED_PROFILE
+            .get()
+            .cloned()
+            .or_else(|| merged.get("profile").and_then(|p| p.as_str()).map(str::to_string));
+        if let Some(name) = profile {
+            let overrides = merged
+                .get("profiles")
+                .and_then(|profiles| profiles.get(&name))
+                .and_then(|profile| profile.as_table())
+                .cloned()
+                .with_context(|| format!("Unknown profile '{}' (see gamecode config profiles list)", name))?;
+            debug!("Applying profile {}", name);
+            merge(&mut merged, overrides);
+            merged.insert("profile".to_string(), toml::Value::String(name));
+        }
+        toml::Value::Table(merged)
+            .try_into()
+            .context("Invalid configuration after applying project overrides")
+    }
+
+    /// The global config alone, for commands that change and save it
+    pub fn load_global() -> Result<Self> {
+        let path = Self::config_path()?;
+        toml::Value::Table(read_table(&path)?)
+            .try_into()
+            .with_context(|| format!("Failed to parse {}", path.display()))
+    }
+
+    pub fn save(&self) -> Result<()> {
+        let path = Self::config_path()?;
+
+        // Ensure directory exists
+        if let Some(parent) = path.parent() {
+            fs::create_dir_all(parent)?;
+        }
+
+        let content = toml::to_string_pretty(self)?;
+        fs::write(&path, content).with_context(|| format!("Failed to write {}", path.display()))?;
+        Ok(())
+    }
+}
+
+fn read_table(path: &Path) -> Result<toml::Table> {
+    if !path.exists() {
+        return Ok(toml::Table::new());
+    }
+    let content = fs::read_to_string(path)
+        .with_context(|| format!("Failed to read {}", path.display()))?;
+    toml::from_str(&content).with_context(|| format!("Failed to parse {}", path.display()))
+}
+
+fn merge(base: &mut toml::Table, overrides: toml::Table) {
+    for (key, value) in overrides {
+        match (base.get_mut(&key), value) {
+            (Some(toml::Value::Table(base)), toml::Value::Table(overrides)) => merge(base, overrides),
+            (_, value) => {
+                base.insert(key, value);
+            }
+        }
+    }
+}
@@ -0,0 +1,472 @@
+use anyhow::{bail, Context, Result};
+use aws_config::{BehaviorVersion, Region, SdkConfig};
+use aws_sdk_bedrockruntime::types::{
+    CachePointBlock, CachePointType, ContentBlock as ConverseBlock, ContentBlockDelta, ContentBlockStart,
+    ConversationRole, ConverseStreamOutput, ImageBlock, ImageFormat as ConverseImageFormat, ImageSource,
+    InferenceConfiguration, Message as ConverseMessage, ReasoningContentBlock, ReasoningContentBlockDelta,
+    ReasoningTextBlock, SpecificToolChoice, StopReason as ConverseStopReason,
+    SystemContentBlock, Tool, ToolChoice, ToolConfiguration, ToolInputSchema, ToolResultBlock, ToolResultContentBlock, ToolSpecification, ToolUseBlock,
+};
+use aws_sdk_bedrockruntime::Client;
+use aws_smithy_types::{Blob, Document, Number};
+use crate::attachments::{Image, ImageFormat};
+use crate::inference::InferenceSettings;
+use crate::thinking;
+use crate::usage::TokenUsage;
+use gamecode_backend::{ContentBlock, Message as BackendMessage, MessageRole as BackendMessageRole, Tool as BackendTool};
+use serde_json::Value;
+use std::collections::{BTreeMap, HashMap};
+use std::sync::Mutex;
+use tracing::debug;
+
+/// A tool call the model asked for
+#[derive(Debug, Clone)]
+pub struct ToolCall {
+    pub id: String,
+    pub name: String,
+    pub input: Value,
+}
+
+/// A reasoning block from extended thinking. Blocks are sent back unchanged
+/// with the tool calls they led to, signature and all, as Anthropic requires.
+#[derive(Debug, Clone)]
+pub enum Reasoning {
+    Text { text: String, signature: Option<String> },
+    /// Reasoning the model provider encrypted
+    Redacted(Vec<u8>),
+}
+
+/// What the backend's messages can't carry, kept beside them by the index
+/// of the message it belongs to
+#[derive(Debug, Default, Clone)]
+pub struct Extras {
+    /// Tool calls made in assistant messages, so their results can be sent
+    /// as tool results rather than text
+    pub tool_calls: Vec<(usize, Vec<ToolCall>)>,
+    /// Images sent with user messages
+    pub images: Vec<(usize, Vec<Image>)>,
+    /// Reasoning behind assistant messages that called tools
+    pub reasoning: Vec<(usize, Vec<Reasoning>)>,
+}
+
+/// A response from the Converse API
+#[derive(Debug)]
+pub struct ConverseReply {
+    pub text: String,
+    /// The model's reasoning, with extended thinking
+    pub thinking: String,
+    /// The reasoning blocks, to send back on a tool turn
+    pub reasoning: Vec<Reasoning>,
+    pub tool_calls: Vec<ToolCall>,
+    pub usage: TokenUsage,
+    /// Generation ended at one of the request's stop sequences
+    pub stopped: bool,
+}
+
+/// Sends requests to the Bedrock Converse API directly.
+///
+/// The backend's `ChatRequest` has no stop sequences or cache points, no way
+/// to enable extended thinking, and its messages can't carry images or
+/// reasoning blocks, so runs that need these send their requests here
+/// instead. Reasoning is kept in `Extras` between tool turns.
+///
+/// With cache points, the system prompt and tool definitions are marked as a
+/// prompt cache prefix, so repeated agentic turns read them from the cache.
+#[derive(Clone)]
+pub struct ConverseClient {
+    client: Client,
+    thinking_budget: Option<u32>,
+    cache_points: bool,
+}
+
+impl ConverseClient {
+    pub async fn new(region: &str) -> Self {
+        let sdk_config = aws_config::defaults(BehaviorVersion::latest())
+            .region(Region::new(region.to_string()))
+            .load()
+            .await;
+        Self::from_config(&sdk_config)
+    }
+
+    /// Build from an already loaded AWS configuration, e.g. one with
+    /// `--aws-profile` or `--assume-role` credentials
+    pub fn from_config(sdk_config: &SdkConfig) -> Self {
+        Self {
+            client: Client::new(sdk_config),
+            thinking_budget: None,
+            cache_points: false,
+        }
+    }
+
+    /// Enable extended thinking with a budget of `budget` tokens
+    pub fn thinking(mut self, budget: u32) -> Result<Self> {
+        if budget < thinking::MIN_BUDGET {
+            bail!("The thinking budget must be at least {} tokens, got {}", thinking::MIN_BUDGET, budget);
+        }
+        self.thinking_budget = Some(budget);
+        Ok(self)
+    }
+
+    pub fn thinks(&self) -> bool {
+        self.thinking_budget.is_some()
+    }
+
+    /// Put a cache point after the system prompt and after the tool
+    /// definitions, for models that support prompt caching
+    pub fn cache_points(mut self, enabled: bool) -> Self {
+        self.cache_points = enabled;
+        self
+    }
+
+    /// Send one request and read the streamed response. With `force`, the
+    /// model must call that tool.
+    ///
+    /// If the stream fails after only text has arrived, the text is kept in
+    /// `resume` and the error returned. Retrying with the same `resume` sends
+    /// that text back as the start of the answer, so the model continues it
+    /// rather than starting over. Extended thinking and forced tool calls
+    /// don't allow a prefilled answer, so those start over.
+    #[allow(clippy::too_many_arguments)]
+    pub async fn chat(
+        &self,
+        model: &str,
+        messages: &[BackendMessage],
+        extras: &Extras,
+        tools: &[BackendTool],
+        force: Option<&str>,
+        inference: &InferenceSettings,
+        resume: &Resume,
+    ) -> Result<ConverseReply> {
+        let (mut system, mut conversation) = to_converse(messages, extras)?;
+        if self.cache_points && !system.is_empty() {
+            system.push(SystemContentBlock::CachePoint(cache_point()?));
+        }
+
+        let resumable = self.thinking_budget.is_none()
+            && force.is_none()
+            && conversation.last().is_some_and(|message| *message.role() == ConversationRole::User);
+        // A prefilled answer can't end in whitespace
+        let prefill = match resumable {
+            true => resume.text().trim_end().to_string(),
+            false => String::new(),
+        };
+        if !prefill.is_empty() {
+            debug!("Resuming a dropped response after {} bytes of text", prefill.len());
+            conversation.push(
+                ConverseMessage::builder()
+                    .role(ConversationRole::Assistant)
+                    .content(ConverseBlock::Text(prefill.clone()))
+                    .build()
+                    .context("Failed to build the request")?,
+            );
+        }
+
+        let mut config = InferenceConfiguration::builder()
+            .set_stop_sequences(inference.stop_sequences.clone().filter(|stops| !stops.is_empty()));
+        config = match self.thinking_budget {
+            // Anthropic rejects sampling parameters with thinking enabled
+            Some(budget) => config.max_tokens(thinking::max_tokens(budget, inference.max_tokens)? as i32),
+            None => config
+                .set_max_tokens(inference.max_tokens.map(|max| max as i32))
+                .set_temperature(inference.temperature)
+                .set_top_p(inference.top_p),
+        };
+
+        let mut request = self
+            .client
+            .converse_stream()
+            .model_id(model)
+            .set_system((!system.is_empty()).then_some(system))
+            .set_messages(Some(conversation))
+            .inference_config(config.build());
+        if !tools.is_empty() {
+            request = request.tool_config(tool_config(tools, force, self.cache_points)?);
+        }
+        if let Some(budget) = self.thinking_budget {
+            request = request.additional_model_request_fields(to_document(&serde_json::json!({
+                "thinking": { "type": "enabled", "budget_tokens": budget }
+            })));
+        }
+        let mut response = request
+            .send()
+            .await
+            .map_err(|e| anyhow::anyhow!("{}", aws_sdk_bedrockruntime::error::DisplayErrorContext(e)))?;
+
+        let mut reply = ConverseReply {
+            text: prefill,
+            thinking: String::new(),
+            reasoning: Vec::new(),
+            tool_calls: Vec::new(),
+            usage: TokenUsage::default(),
+            stopped: false,
+        };
+        // Tool calls and reasoning arrive in pieces, keyed by content block
+        let mut calls: BTreeMap<i32, (String, String, String)> = BTreeMap::new();
+        let mut reasoning: BTreeMap<i32, Reasoning> = BTreeMap::new();
+        loop {
+            let event = match response.stream.recv().await {
+                Ok(Some(event)) => event,
+                Ok(None) => break,
+                Err(e) => {
+                    // Only plain text can be continued
+                    if resumable && calls.is_empty() && reasoning.is_empty() {
+                        resume.keep(&reply.text);
+                    }
+                    bail!("{} stream failed: {}", model, aws_sdk_bedrockruntime::error::DisplayErrorContext(e));
+                }
+            };
+            match event {
+                ConverseStreamOutput::ContentBlockStart(start) => {
+                    if let Some(ContentBlockStart::ToolUse(tool_use)) = start.start() {
+                        let call = (tool_use.tool_use_id().to_string(), tool_use.name().to_string(), String::new());
+                        calls.insert(start.content_block_index(), call);
+                    }
+                }
+                ConverseStreamOutput::ContentBlockDelta(delta) => {
+                    let index = delta.content_block_index();
+                    match delta.delta() {
+                        Some(ContentBlockDelta::Text(text)) => reply.text.push_str(text),
+                        Some(ContentBlockDelta::ToolUse(tool_use)) => {
+                            if let Some((_, _, input)) = calls.get_mut(&index) {
+                                input.push_str(tool_use.input());
+                            }
+                        }
+                        Some(ContentBlockDelta::ReasoningContent(ReasoningContentBlockDelta::Text(text))) => {
+                            reply.thinking.push_str(text);
+                            let block = reasoning.entry(index).or_insert_with(|| Reasoning::Text {
+                                text: String::new(),
+                                signature: None,
+                            });
+                            if let Reasoning::Text { text: so_far, .. } = block {
+                                so_far.push_str(text);
+                            }
+                        }
+                        Some(ContentBlockDelta::ReasoningContent(ReasoningContentBlockDelta::Signature(signature))) => {
+                            if let Some(Reasoning::Text { signature: slot, .. }) = reasoning.get_mut(&index) {
+                                *slot = Some(signature.clone());
+                            }
+                        }
+                        Some(ContentBlockDelta::ReasoningContent(ReasoningContentBlockDelta::RedactedContent(data))) => {
+                            reasoning.insert(index, Reasoning::Redacted(data.as_ref().to_vec()));
+                        }
+                        other => debug!("Ignoring content delta in Converse stream: {:?}", other),
+                    }
+                }
+                ConverseStreamOutput::MessageStop(stop) => {
+                    reply.stopped = matches!(stop.stop_reason(), ConverseStopReason::StopSequence);
+                }
+                ConverseStreamOutput::Metadata(metadata) => {
+                    if let Some(usage) = metadata.usage() {
+                        reply.usage = TokenUsage {
+                            input_tokens: usage.input_tokens().max(0) as u64,
+                            output_tokens: usage.output_tokens().max(0) as u64,
+                            cache_read_tokens: usage.cache_read_input_tokens().unwrap_or_default().max(0) as u64,
+                            cache_write_tokens: usage.cache_write_input_tokens().unwrap_or_default().max(0) as u64,
+                        };
+                    }
+                }
+                _ => {}
+            }
+        }
+
+        for (id, name, input) in calls.into_values() {
+            let input = match input.trim() {
+                "" => Value::Object(Default::default()),
+                input => serde_json::from_str(input)
+                    .with_context(|| format!("{} sent invalid input for {}", model, name))?,
+            };
+            reply.tool_calls.push(ToolCall { id, name, input });
+        }
+        reply.reasoning = reasoning.into_values().collect();
+        resume.keep("");
+        Ok(reply)
+    }
+}
+
+/// Text a response stream delivered before it failed, for the retried
+/// request to continue from. See `ConverseClient::chat`.
+#[derive(Debug, Default)]
+pub struct Resume {
+    text: Mutex<String>,
+}
+
+impl Resume {
+    fn text(&self) -> String {
+        self.text.lock().map(|text| text.clone()).unwrap_or_default()
+    }
+
+    fn keep(&self, text: &str) {
+        if let Ok(mut kept) = self.text.lock() {
+            text.clone_into(&mut kept);
+        }
+    }
+}
+
+// System prompt and conversation for a request. Converse needs turns to
+// alternate, so consecutive messages from one side are joined.
+fn to_converse(messages: &[BackendMessage], extras: &Extras) -> Result<(Vec<SystemContentBlock>, Vec<ConverseMessage>)> {
+    let calls: HashMap<usize, &[ToolCall]> =
+        extras.tool_calls.iter().map(|(index, calls)| (*index, calls.as_slice())).collect();
+    let images: HashMap<usize, &[Image]> =
+        extras.images.iter().map(|(index, images)| (*index, images.as_slice())).collect();
+    let reasoning: HashMap<usize, &[Reasoning]> =
+        extras.reasoning.iter().map(|(index, blocks)| (*index, blocks.as_slice())).collect();
+    let known_calls: Vec<&str> = extras.tool_calls.iter().flat_map(|(_, calls)| calls).map(|call| call.id.as_str()).collect();
+
+    let mut system = Vec::new();
+    let mut conversation: Vec<(ConversationRole, Vec<ConverseBlock>)> = Vec::new();
+    for (index, message) in messages.iter().enumerate() {
+        let role = match message.role {
+            BackendMessageRole::System => {
+                system.push(SystemContentBlock::Text(message_text(message)));
+                continue;
+            }
+            BackendMessageRole::Assistant => ConversationRole::Assistant,
+            _ => ConversationRole::User,
+        };
+
+        // Reasoning comes first in the answer it led to, and images go
+        // before the text that asks about them
+        let mut blocks = Vec::new();
+        for block in reasoning.get(&index).copied().unwrap_or_default() {
+            blocks.push(ConverseBlock::ReasoningContent(reasoning_block(block)?));
+        }
+        for image in images.get(&index).copied().unwrap_or_default() {
+            blocks.push(ConverseBlock::Image(image_block(image)?));
+        }
+        for block in &message.content {
+            match block {
+                ContentBlock::Text(text) if text.trim().is_empty() => {}
+                ContentBlock::Text(text) => blocks.push(ConverseBlock::Text(text.clone())),
+                ContentBlock::ToolResult { tool_call_id, result } if known_calls.contains(&tool_call_id.as_str()) => {
+                    blocks.push(ConverseBlock::ToolResult(
+                        ToolResultBlock::builder()
+                            .tool_use_id(tool_call_id)
+                            .content(ToolResultContentBlock::Text(result.clone()))
+                            .build()
+                            .context("Failed to build a tool result")?,
+                    ))
+                }
+                // Tool results of calls from other runs have no call to answer
+                other => blocks.push(ConverseBlock::Text(serde_json::to_string(other).unwrap_or_default())),
+            }
+        }
+        for call in calls.get(&index).copied().unwrap_or_default() {
+            blocks.push(ConverseBlock::ToolUse(
+                ToolUseBlock::builder()
+                    .tool_use_id(&call.id)
+                    .name(&call.name)
+                    .input(to_document(&call.input))
+                    .build()
+                    .context("Failed to build a tool call")?,
+            ));
+        }
+        if blocks.is_empty() {
+            continue;
+        }
+
+        match conversation.last_mut() {
+            Some((last, existing)) if *last == role => existing.extend(blocks),
+            _ => conversation.push((role, blocks)),
+        }
+    }
+
+    let conversation = conversation
+        .into_iter()
+        .map(|(role, content)| ConverseMessage::builder().role(role).set_content(Some(content)).build())
+        .collect::<Result<Vec<_>, _>>()
+        .context("Failed to build the request")?;
+    Ok((system, conversation))
+}
+
+fn tool_config(tools: &[BackendTool], force: Option<&str>, cache_point: bool) -> Result<ToolConfiguration> {
+    let mut tools = tools
+        .iter()
+        .map(|tool| {
+            ToolSpecification::builder()
+                .name(&tool.name)
+                .description(&tool.description)
+                .input_schema(ToolInputSchema::Json(to_document(&tool.input_schema)))
+                .build()
+                .map(Tool::ToolSpec)
+        })
+        .collect::<Result<Vec<_>, _>>()
+        .context("Failed to build the tool definitions")?;
+    if cache_point {
+        tools.push(Tool::CachePoint(self::cache_point()?));
+    }
+    let choice = match force {
+        Some(name) => Some(ToolChoice::Tool(
+            SpecificToolChoice::builder().name(name).build().context("Failed to build the tool choice")?,
+        )),
+        None => None,
+    };
+    ToolConfiguration::builder()
+        .set_tools(Some(tools))
+        .set_tool_choice(choice)
+        .build()
+        .context("Failed to build the tool definitions")
+}
+
+fn image_block(image: &Image) -> Result<ImageBlock> {
+    let format = match image.format {
+        ImageFormat::Png => ConverseImageFormat::Png,
+        ImageFormat::Jpeg => ConverseImageFormat::Jpeg,
+    };
+    ImageBlock::builder()
+        .format(format)
+        .source(ImageSource::Bytes(Blob::new(image.bytes.clone())))
+        .build()
+        .context("Failed to build an image")
+}
+
+fn reasoning_block(reasoning: &Reasoning) -> Result<ReasoningContentBlock> {
+    Ok(match reasoning {
+        Reasoning::Text { text, signature } => ReasoningContentBlock::ReasoningText(
+            ReasoningTextBlock::builder()
+                .text(text)
+                .set_signature(signature.clone())
+                .build()
+                .context("Failed to build a reasoning block")?,
+        ),
+        Reasoning::Redacted(data) => ReasoningContentBlock::RedactedContent(Blob::new(data.clone())),
+    })
+}
+
+fn cache_point() -> Result<CachePointBlock> {
+    CachePointBlock::builder()
+        .r#type(CachePointType::Default)
+        .build()
+        .context("Failed to build a cache point")
+}
+
+// Text of a message; anything but text (tool calls and results from other
+// runs) is included as JSON
+fn message_text(message: &BackendMessage) -> String {
+    message
+        .content
+        .iter()
+        .map(|block| match block {
+            ContentBlock::Text(text) => text.clone(),
+            other => serde_json::to_string(other).unwrap_or_default(),
+        })
+        .collect::<Vec<_>>()
+        .join("\n")
+}
+
+fn to_document(value: &Value) -> Document {
+    match value {
+        Value::Null => Document::Null,
+        Value::Bool(b) => Document::Bool(*b),
+        Value::Number(n) => Document::Number(match (n.as_u64(), n.as_i64()) {
+            (Some(u), _) => Number::PosInt(u),
+            (None, Some(i)) => Number::NegInt(i),
+            _ => Number::Float(n.as_f64().unwrap_or_default()),
+        }),
+        Value::String(s) => Document::String(s.clone()),
+        Value::Array(items) => Document::Array(items.iter().map(to_document).collect()),
+        Value::Object(map) => Document::Object(
+            map.iter().map(|(key, value)| (key.clone(), to_document(value))).collect::<HashMap<_, _>>(),
+        ),
+    }
+}
@@ -0,0 +1,199 @@
+use anyhow::{Context, Result};
+use crate::server::ServerState;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+use tokio::io::{AsyncBufReadExt, AsyncWriteExt, BufReader};
+use tokio::net::unix::{OwnedReadHalf, OwnedWriteHalf};
+use tokio::net::{UnixListener, UnixStream};
+use tracing::debug;
+use uuid::Uuid;
+
+/// A request sent to the daemon, one JSON object per line
+#[derive(Debug, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum DaemonRequest {
+    Ping,
+    Sessions,
+    /// Run a prompt in `session_id`, a new session, or the latest session
+    Run {
+        prompt: String,
+        #[serde(default)]
+        session_id: Option<Uuid>,
+        #[serde(default)]
+        new_session: bool,
+        #[serde(default)]
+        model: Option<String>,
+    },
+}
+
+/// Where the daemon listens
+pub fn socket_path() -> Result<PathBuf> {
+    let home = home::home_dir().context("Failed to get home directory")?;
+    Ok(home.join(".local/share/gamecode/daemon.sock"))
+}
+
+/// Listen on `path` until the process is stopped. Each connection sends
+/// requests and gets line-delimited replies: `pong`, `sessions`, or for
+/// `run` a `session` line, one `event` line per `AgentEvent`, then `done`.
+/// Any request can be answered with `error` instead.
+pub async fn serve(path: &Path, state: ServerState) -> Result<()> {
+    if path.exists() {
+        if UnixStream::connect(path).await.is_ok() {
+            anyhow::bail!("A daemon is already listening on {}", path.display());
+        }
+        // Left behind by a daemon that didn't shut down cleanly
+        std::fs::remove_file(path)
+            .with_context(|| format!("Failed to remove stale socket {}", path.display()))?;
+    }
+    if let Some(parent) = path.parent() {
+        std::fs::create_dir_all(parent)?;
+    }
+    let listener = UnixListener::bind(path)
+        .with_context(|| format!("Failed to listen on {}", path.display()))?;
+    let state = Arc::new(state);
+
+    let result = loop {
+        tokio::select! {
+            accepted = listener.accept() => {
+                let stream = match accepted {
+                    Ok((stream, _)) => stream,
+                    Err(e) => break Err(anyhow::Error::from(e).context("Failed to accept connection")),
+                };
+                let state = state.clone();
+                tokio::spawn(async move {
+                    if let Err(e) = handle_connection(stream, &state).await {
+                        debug!("Daemon connection ended with error: {:#}", e);
+                    }
+                });
+            }
+            _ = tokio::signal::ctrl_c() => break Ok(()),
+        }
+    };
+    std::fs::remove_file(path).ok();
+    result
+}
+
+async fn handle_connection(stream: UnixStream, state: &ServerState) -> Result<()> {
+    let (reader, mut writer) = stream.into_split();
+    let mut lines = BufReader::new(reader).lines();
+
+    while let Some(line) = lines.next_line().await? {
+        if line.trim().is_empty() {
+            continue;
+        }
+        let result = match serde_json::from_str::<DaemonRequest>(&line) {
+            Ok(request) => handle_request(state, request, &mut writer).await,
+            Err(e) => Err(anyhow::anyhow!("Invalid request: {}", e)),
+        };
+        let last = match result {
+            Ok(reply) => reply,
+            Err(e) => serde_json::json!({ "type": "error", "message": format!("{:#}", e) }),
+        };
+        write_line(&mut writer, &last).await?;
+    }
+    Ok(())
+}
+
+/// Answer one request, writing any streamed lines as they happen and
+/// returning the final reply
+async fn handle_request(
+    state: &ServerState,
+    request: DaemonRequest,
+    writer: &mut OwnedWriteHalf,
+) -> Result<Value> {
+    match request {
+        DaemonRequest::Ping => Ok(serde_json::json!({
+            "type": "pong",
+            "model": state.model,
+            "tools": state.tools.len(),
+        })),
+        DaemonRequest::Sessions => Ok(serde_json::json!({
+            "type": "sessions",
+            "sessions": state.list_sessions().await?,
+        })),
+        DaemonRequest::Run { prompt, session_id, new_session, model } => {
+            let id = match session_id {
+                Some(id) => id,
+                None if new_session => state.create_session(None, None).await?.id,
+                None => {
+                    let latest = state.sessions.lock().await.load_latest()?;
+                    if latest.messages.is_empty() {
+                        state.create_session(None, None).await?.id
+                    } else {
+                        latest.id
+                    }
+                }
+            };
+            write_line(writer, &serde_json::json!({ "type": "session", "id": id })).await?;
+
+            // The agent reports events through a callback; forward them while it runs
+            let (sender, mut receiver) = tokio::sync::mpsc::unbounded_channel();
+            let run = state.run_turn(id, prompt, model, move |event| {
+                let line = serde_json::json!({ "type": "event", "event": event });
+                sender.send(line).ok();
+            });
+            tokio::pin!(run);
+            let outcome = loop {
+                tokio::select! {
+                    outcome = &mut run => break outcome?,
+                    Some(line) = receiver.recv() => write_line(writer, &line).await?,
+                }
+            };
+            while let Ok(line) = receiver.try_recv() {
+                write_line(writer, &line).await?;
+            }
+            Ok(serde_json::json!({ "type": "done", "outcome": outcome }))
+        }
+    }
+}
+
+async fn write_line(writer: &mut OwnedWriteHalf, value: &Value) -> Result<()> {
+    let mut text = serde_json::to_string(value)?;
+    text.push('\n');
+    writer.write_all(text.as_bytes()).await?;
+    writer.flush().await?;
+    Ok(())
+}
+
+/// A connection to a running daemon
+pub struct DaemonClient {
+    lines: tokio::io::Lines<BufReader<OwnedReadHalf>>,
+    writer: OwnedWriteHalf,
+}
+
+impl DaemonClient {
+    /// Connect to the daemon, or `None` if none is running
+    pub async fn connect() -> Result<Option<Self>> {
+        let path = socket_path()?;
+        match UnixStream::connect(&path).await {
+            Ok(stream) => {
+                let (reader, writer) = stream.into_split();
+                Ok(Some(Self { lines: BufReader::new(reader).lines(), writer }))
+            }
+            Err(e) => {
+                debug!("No daemon at {}: {}", path.display(), e);
+                Ok(None)
+            }
+        }
+    }
+
+    /// Send a request and pass every reply line to `on_reply` until the
+    /// final one, which is returned. `error` replies become errors.
+    pub async fn request(&mut self, request: &DaemonRequest, mut on_reply: impl FnMut(&Value)) -> Result<Value> {
+        write_line(&mut self.writer, &serde_json::to_value(request)?).await?;
+        while let Some(line) = self.lines.next_line().await? {
+            let reply: Value = serde_json::from_str(&line).context("Invalid reply from daemon")?;
+            match reply.get("type").and_then(Value::as_str) {
+                Some("session") | Some("event") => on_reply(&reply),
+                Some("error") => {
+                    let message = reply.get("message").and_then(Value::as_str).unwrap_or("unknown error");
+                    anyhow::bail!("Daemon error: {}", message);
+                }
+                _ => return Ok(reply),
+            }
+        }
+        anyhow::bail!("Daemon closed the connection")
+    }
+}
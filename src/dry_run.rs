@@ -0,0 +1,78 @@
+use anyhow::Result;
+use crate::inference::InferenceSettings;
+use crate::models;
+use crate::tokens;
+use crate::usage::TokenUsage;
+use gamecode_backend::{Message as BackendMessage, MessageRole as BackendMessageRole, Tool as BackendTool};
+use serde::Serialize;
+
+/// The request a `--dry-run` would have sent: everything in the Bedrock
+/// `ChatRequest` except the status callback
+#[derive(Debug, Serialize)]
+pub struct DryRun<'a> {
+    pub model: &'a str,
+    pub inference: &'a InferenceSettings,
+    pub messages: &'a [BackendMessage],
+    pub tools: Option<&'a [BackendTool]>,
+}
+
+/// Estimated input tokens of a request, by part
+#[derive(Debug, Default, Clone, Copy, Serialize)]
+pub struct TokenEstimate {
+    pub system: u64,
+    pub messages: u64,
+    pub tools: u64,
+}
+
+impl TokenEstimate {
+    pub fn total(&self) -> u64 {
+        self.system + self.messages + self.tools
+    }
+}
+
+impl DryRun<'_> {
+    /// Estimated input tokens, counted offline
+    pub fn estimate(&self) -> TokenEstimate {
+        let mut estimate = TokenEstimate::default();
+        for message in self.messages {
+            if matches!(message.role, BackendMessageRole::System) {
+                estimate.system += tokens::count_message(message);
+            } else {
+                estimate.messages += tokens::count_message(message);
+            }
+        }
+        estimate.tools = self.tools.map_or(0, tokens::count_tools);
+        estimate
+    }
+
+    /// Print the token estimate to stderr and the payload as JSON to stdout
+    pub fn print(&self) -> Result<()> {
+        let estimate = self.estimate();
+        notice!("🧪 Dry run: nothing was sent to {}", self.model);
+        eprintln!("   Estimated input tokens: ~{}", estimate.total());
+        eprintln!("     system prompt: ~{}", estimate.system);
+        eprintln!(
+            "     messages:      ~{} ({} messages)",
+            estimate.messages,
+            self.messages.iter().filter(|m| !matches!(m.role, BackendMessageRole::System)).count()
+        );
+        eprintln!(
+            "     tool schemas:  ~{} ({} tools)",
+            estimate.tools,
+            self.tools.map_or(0, |tools| tools.len())
+        );
+        let usage = TokenUsage { input_tokens: estimate.total(), ..Default::default() };
+        if let Some(cost) = usage.cost(self.model) {
+            eprintln!("   Estimated input cost: ~${:.4}", cost);
+        }
+        if let Some(info) = models::lookup(self.model) {
+            eprintln!(
+                "   Context window: {}% of {} tokens",
+                estimate.total() * 100 / info.context_window as u64,
+                info.context_window
+            );
+        }
+        println!("{}", serde_json::to_string_pretty(self)?);
+        Ok(())
+    }
+}
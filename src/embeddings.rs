@@ -0,0 +1,75 @@
+use anyhow::{Context, Result};
+use aws_config::{BehaviorVersion, Region};
+use aws_sdk_bedrockruntime::primitives::Blob;
+use aws_sdk_bedrockruntime::Client;
+use serde_json::{json, Value};
+
+/// Embedding model used when `--embedding-model` is not given
+pub const DEFAULT_EMBEDDING_MODEL: &str = "amazon.titan-embed-text-v2:0";
+
+/// Titan v2 accepts up to 8k tokens; stay well under it by characters
+const MAX_INPUT_CHARS: usize = 20_000;
+
+/// Turns text into vectors with a Bedrock embedding model via InvokeModel
+pub struct Embedder {
+    client: Client,
+    model: String,
+}
+
+impl Embedder {
+    pub async fn new(region: &str, model: &str) -> Self {
+        let sdk_config = aws_config::defaults(BehaviorVersion::latest())
+            .region(Region::new(region.to_string()))
+            .load()
+            .await;
+        Self {
+            client: Client::new(&sdk_config),
+            model: model.to_string(),
+        }
+    }
+
+    pub fn model(&self) -> &str {
+        &self.model
+    }
+
+    /// Embed a single text, normalized so similarity is a dot product
+    pub async fn embed(&self, text: &str) -> Result<Vec<f32>> {
+        let input: String = text.chars().take(MAX_INPUT_CHARS).collect();
+        let body = json!({ "inputText": input, "normalize": true });
+
+        let response = self
+            .client
+            .invoke_model()
+            .model_id(&self.model)
+            .content_type("application/json")
+            .accept("application/json")
+            .body(Blob::new(serde_json::to_vec(&body)?))
+            .send()
+            .await
+            .with_context(|| format!("Failed to call embedding model {}", self.model))?;
+
+        let value: Value = serde_json::from_slice(response.body().as_ref())
+            .context("Embedding response was not JSON")?;
+        let embedding = value
+            .get("embedding")
+            .and_then(Value::as_array)
+            .context("Embedding response had no 'embedding' field")?;
+        Ok(embedding
+            .iter()
+            .filter_map(Value::as_f64)
+            .map(|v| v as f32)
+            .collect())
+    }
+}
+
+/// Cosine similarity of two vectors
+pub fn similarity(a: &[f32], b: &[f32]) -> f32 {
+    let dot: f32 = a.iter().zip(b).map(|(x, y)| x * y).sum();
+    let norm_a = a.iter().map(|x| x * x).sum::<f32>().sqrt();
+    let norm_b = b.iter().map(|x| x * x).sum::<f32>().sqrt();
+    if norm_a == 0.0 || norm_b == 0.0 {
+        0.0
+    } else {
+        dot / (norm_a * norm_b)
+    }
+}
@@ -0,0 +1,61 @@
+use std::process::Command;
+
+/// Describe the environment the CLI runs in, for the system prompt of new sessions
+pub fn describe() -> String {
+    let cwd = std::env::current_dir()
+        .map(|p| p.display().to_string())
+        .unwrap_or_else(|_| "unknown".to_string());
+
+    let mut lines = vec![
+        "# Environment".to_string(),
+        String::new(),
+        format!("- OS: {} ({})", std::env::consts::OS, std::env::consts::ARCH),
+        format!("- Working directory: {}", cwd),
+        format!("- Date: {}", chrono::Local::now().format("%Y-%m-%d %H:%M %Z")),
+    ];
+
+    if let Some(branch) = git(&["rev-parse", "--abbrev-ref", "HEAD"]) {
+        lines.push(format!("- Git branch: {}", branch.trim()));
+        if let Some(status) = git(&["status", "--porcelain"]) {
+            lines.push(format!("- Git status: {}", summarize_status(&status)));
+        }
+    }
+
+    lines.join("\n")
+}
+
+/// Run git in the current directory, returning stdout on success
+fn git(args: &[&str]) -> Option<String> {
+    let output = Command::new("git")
+        .args(args)
+        .output()
+        .ok()?;
+    output
+        .status
+        .success()
+        .then(|| String::from_utf8_lossy(&output.stdout).into_owned())
+}
+
+fn summarize_status(porcelain: &str) -> String {
+    let (mut staged, mut modified, mut untracked) = (0, 0, 0);
+    for line in porcelain.lines() {
+        let mut codes = line.chars();
+        let (index, worktree) = (codes.next().unwrap_or(' '), codes.next().unwrap_or(' '));
+        if index == '?' {
+            untracked += 1;
+            continue;
+        }
+        if index != ' ' {
+            staged += 1;
+        }
+        if worktree != ' ' {
+            modified += 1;
+        }
+    }
+
+    if staged + modified + untracked == 0 {
+        "clean".to_string()
+    } else {
+        format!("{} staged, {} modified, {} untracked", staged, modified, untracked)
+    }
+}
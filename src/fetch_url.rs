@@ -0,0 +1,101 @@
+use anyhow::{Context, Result};
+use crate::mcp_protocol::ToolSchema;
+use serde_json::{json, Value};
+use std::time::Duration;
+use tracing::debug;
+
+/// Name the built-in tool is offered to the model under
+pub const TOOL_NAME: &str = "fetch_url";
+
+/// Response bodies are cut off after this many bytes
+pub const MAX_RESPONSE_BYTES: usize = 2 * 1024 * 1024;
+
+const REQUEST_TIMEOUT: Duration = Duration::from_secs(30);
+
+/// Column width HTML is wrapped to when converted to text
+const TEXT_WIDTH: usize = 100;
+
+/// Schema of the built-in `fetch_url` tool
+pub fn schema() -> ToolSchema {
+    ToolSchema {
+        name: TOOL_NAME.to_string(),
+        description: "Fetch a web page or other http(s) resource and return it as plain text. \
+HTML is converted to readable text; responses over 2 MiB are truncated."
+            .to_string(),
+        input_schema: json!({
+            "type": "object",
+            "properties": {
+                "url": {
+                    "type": "string",
+                    "description": "Absolute http or https URL to fetch"
+                }
+            },
+            "required": ["url"]
+        }),
+        annotations: Some(json!({ "readOnlyHint": true })),
+    }
+}
+
+/// Run the tool with the model-supplied input
+pub async fn call(input: &Value) -> Result<String> {
+    let url = input
+        .get("url")
+        .and_then(Value::as_str)
+        .context("Missing required parameter 'url'")?;
+    fetch(url).await
+}
+
+/// Fetch `url` and return its body as text, converting HTML
+pub async fn fetch(url: &str) -> Result<String> {
+    if !(url.starts_with("http://") || url.starts_with("https://")) {
+        anyhow::bail!("Only http and https URLs can be fetched: {}", url);
+    }
+    debug!("Fetching {}", url);
+
+    let client = reqwest::Client::builder()
+        .timeout(REQUEST_TIMEOUT)
+        .user_agent(concat!("gamecode/", env!("CARGO_PKG_VERSION")))
+        .build()
+        .context("Failed to create HTTP client")?;
+    let mut response = client
+        .get(url)
+        .send()
+        .await
+        .with_context(|| format!("Failed to fetch {}", url))?;
+
+    let status = response.status();
+    if !status.is_success() {
+        anyhow::bail!("Fetching {} failed with HTTP {}", url, status);
+    }
+    let is_html = response
+        .headers()
+        .get(reqwest::header::CONTENT_TYPE)
+        .and_then(|v| v.to_str().ok())
+        .is_some_and(|v| v.contains("html"));
+
+    let mut body = Vec::new();
+    let mut truncated = false;
+    while let Some(chunk) = response
+        .chunk()
+        .await
+        .with_context(|| format!("Failed to read response from {}", url))?
+    {
+        body.extend_from_slice(&chunk);
+        if body.len() > MAX_RESPONSE_BYTES {
+            body.truncate(MAX_RESPONSE_BYTES);
+            truncated = true;
+            break;
+        }
+    }
+
+    let mut text = if is_html {
+        html2text::from_read(body.as_slice(), TEXT_WIDTH)
+            .with_context(|| format!("Failed to convert HTML from {}", url))?
+    } else {
+        String::from_utf8_lossy(&body).into_owned()
+    };
+    if truncated {
+        text.push_str(&format!("\n\n[... response truncated at {} bytes ...]", MAX_RESPONSE_BYTES));
+    }
+    Ok(text)
+}
@@ -0,0 +1,141 @@
+use anyhow::{Context, Result};
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::fs;
+use std::path::{Path, PathBuf};
+use tracing::debug;
+use uuid::Uuid;
+
+const MANIFEST_FILE: &str = "manifest.json";
+
+/// What a file looked like before the session first touched it
+#[derive(Debug, Serialize, Deserialize)]
+struct BackupEntry {
+    /// Shadow copy inside the backup directory, or None if the file did not exist
+    backup: Option<String>,
+}
+
+#[derive(Debug, Default, Serialize, Deserialize)]
+struct Manifest {
+    files: BTreeMap<PathBuf, BackupEntry>,
+}
+
+/// What `rollback` did to a file
+pub enum RollbackAction {
+    Restored(PathBuf),
+    Removed(PathBuf),
+}
+
+/// Shadow copies of files modified by tools during a session, stored under
+/// `~/.local/share/gamecode/backups/<session-id>/`
+pub struct SessionBackups {
+    dir: PathBuf,
+    manifest: Manifest,
+}
+
+impl SessionBackups {
+    fn backups_dir(session_id: &Uuid) -> Result<PathBuf> {
+        let home = home::home_dir().context("Failed to get home directory")?;
+        Ok(home
+            .join(".local")
+            .join("share")
+            .join("gamecode")
+            .join("backups")
+            .join(session_id.to_string()))
+    }
+
+    pub fn open(session_id: &Uuid) -> Result<Self> {
+        let dir = Self::backups_dir(session_id)?;
+        let manifest_path = dir.join(MANIFEST_FILE);
+        let manifest = if manifest_path.exists() {
+            serde_json::from_str(&fs::read_to_string(&manifest_path)?)
+                .with_context(|| format!("Corrupt backup manifest: {}", manifest_path.display()))?
+        } else {
+            Manifest::default()
+        };
+        Ok(Self { dir, manifest })
+    }
+
+    /// Record the current state of `path` unless this session already backed it up.
+    /// Only the first backup is kept, so a rollback returns to the pre-session state.
+    pub fn backup(&mut self, path: &Path) -> Result<()> {
+        let path = absolute(path)?;
+        if self.manifest.files.contains_key(&path) {
+            return Ok(());
+        }
+
+        fs::create_dir_all(&self.dir)?;
+        let backup = if path.is_file() {
+            let name = format!("{}-{}", self.manifest.files.len(), file_name(&path));
+            fs::copy(&path, self.dir.join(&name))
+                .with_context(|| format!("Failed to back up {}", path.display()))?;
+            Some(name)
+        } else {
+            None
+        };
+
+        debug!("Backed up {} (existed: {})", path.display(), backup.is_some());
+        self.manifest.files.insert(path, BackupEntry { backup });
+        self.save()
+    }
+
+    /// Restore every file touched during the session to its original state
+    pub fn rollback(&self) -> Result<Vec<RollbackAction>> {
+        let mut actions = Vec::new();
+        for (path, entry) in &self.manifest.files {
+            match &entry.backup {
+                Some(name) => {
+                    if let Some(parent) = path.parent() {
+                        fs::create_dir_all(parent)?;
+                    }
+                    fs::copy(self.dir.join(name), path)
+                        .with_context(|| format!("Failed to restore {}", path.display()))?;
+                    actions.push(RollbackAction::Restored(path.clone()));
+                }
+                None => {
+                    if path.exists() {
+                        fs::remove_file(path)
+                            .with_context(|| format!("Failed to remove {}", path.display()))?;
+                    }
+                    actions.push(RollbackAction::Removed(path.clone()));
+                }
+            }
+        }
+        Ok(actions)
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.manifest.files.is_empty()
+    }
+
+    /// Drop the session's backups
+    pub fn remove(&self) -> Result<()> {
+        if self.dir.exists() {
+            fs::remove_dir_all(&self.dir).with_context(|| format!("Failed to remove {}", self.dir.display()))?;
+        }
+        Ok(())
+    }
+
+    fn save(&self) -> Result<()> {
+        fs::create_dir_all(&self.dir)?;
+        fs::write(
+            self.dir.join(MANIFEST_FILE),
+            serde_json::to_string_pretty(&self.manifest)?,
+        )?;
+        Ok(())
+    }
+}
+
+fn absolute(path: &Path) -> Result<PathBuf> {
+    if path.is_absolute() {
+        Ok(path.to_path_buf())
+    } else {
+        Ok(std::env::current_dir()?.join(path))
+    }
+}
+
+fn file_name(path: &Path) -> String {
+    path.file_name()
+        .map(|n| n.to_string_lossy().to_string())
+        .unwrap_or_else(|| "file".to_string())
+}
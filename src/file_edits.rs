@@ -0,0 +1,112 @@
+use anyhow::Result;
+use serde_json::Value;
+use similar::TextDiff;
+use std::io::{IsTerminal, Write};
+use std::path::PathBuf;
+
+/// Parameter names tools commonly use for the target file
+const PATH_KEYS: &[&str] = &["path", "file_path", "filepath", "file", "filename", "target"];
+
+/// Parameter names tools commonly use for full replacement content
+const CONTENT_KEYS: &[&str] = &["content", "contents", "text", "data", "file_text"];
+
+/// A file modification a tool call is about to make
+pub struct FileEdit {
+    pub path: PathBuf,
+    pub before: Option<String>,
+    pub after: String,
+}
+
+impl FileEdit {
+    /// Recognize tool calls that write or edit a file and compute the resulting content.
+    ///
+    /// Understands full writes (`path` + `content`) and search/replace edits
+    /// (`path` + `old_string`/`new_string`). Meta-tool calls of the form
+    /// `{"tool": ..., "params": {...}}` are unwrapped first.
+    pub fn detect(input: &Value) -> Option<Self> {
+        let params = Self::params(input);
+        let path = Self::target_path(input)?;
+        let before = std::fs::read_to_string(&path).ok();
+
+        let after = if let Some(content) = CONTENT_KEYS
+            .iter()
+            .find_map(|key| params.get(*key).and_then(|v| v.as_str()))
+        {
+            content.to_string()
+        } else {
+            let old = params
+                .get("old_string")
+                .or_else(|| params.get("old_str"))
+                .or_else(|| params.get("old_text"))
+                .and_then(|v| v.as_str())?;
+            let new = params
+                .get("new_string")
+                .or_else(|| params.get("new_str"))
+                .or_else(|| params.get("new_text"))
+                .and_then(|v| v.as_str())?;
+            before.as_ref()?.replacen(old, new, 1)
+        };
+
+        Some(Self {
+            path,
+            before,
+            after,
+        })
+    }
+
+    /// The file a tool call targets, if it names one
+    pub fn target_path(input: &Value) -> Option<PathBuf> {
+        let params = Self::params(input);
+        PATH_KEYS
+            .iter()
+            .find_map(|key| params.get(*key).and_then(|v| v.as_str()))
+            .map(PathBuf::from)
+    }
+
+    fn params(input: &Value) -> &Value {
+        match input.get("params") {
+            Some(params) if input.get("tool").is_some() => params,
+            _ => input,
+        }
+    }
+
+    /// Unified diff between the current file and the proposed content
+    pub fn unified_diff(&self) -> String {
+        let before = self.before.as_deref().unwrap_or("");
+        let display = self.path.display().to_string();
+        let old_header = if self.before.is_some() {
+            format!("a/{}", display)
+        } else {
+            "/dev/null".to_string()
+        };
+
+        TextDiff::from_lines(before, &self.after)
+            .unified_diff()
+            .context_radius(3)
+            .header(&old_header, &format!("b/{}", display))
+            .to_string()
+    }
+
+    /// Show the diff and ask whether to apply it
+    pub fn confirm(&self, tool_name: &str) -> Result<bool> {
+        notice!("\n📝 {} wants to modify {}:", tool_name, self.path.display());
+        let diff = self.unified_diff();
+        if diff.is_empty() {
+            eprintln!("   (no changes)");
+        } else {
+            eprintln!("{}", diff);
+        }
+
+        if !std::io::stdin().is_terminal() {
+            notice!("⚠️  Cannot confirm file change without a terminal; rejecting (use --yes to apply)");
+            return Ok(false);
+        }
+
+        eprint!("   Apply this change? [y/N] ");
+        std::io::stderr().flush().ok();
+
+        let mut answer = String::new();
+        std::io::stdin().read_line(&mut answer)?;
+        Ok(matches!(answer.trim().to_lowercase().as_str(), "y" | "yes"))
+    }
+}
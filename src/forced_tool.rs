@@ -0,0 +1,114 @@
+use anyhow::{bail, Context, Result};
+use gamecode_backend::Tool as BackendTool;
+use serde_json::{json, Value};
+use std::path::Path;
+
+/// Name of the tool `--json-schema` defines
+pub const EXTRACTION_TOOL: &str = "structured_output";
+
+// Property holding the answer when the schema doesn't describe an object,
+// since tool inputs must be objects
+const WRAPPED_PROPERTY: &str = "value";
+
+/// A tool the model must call first, from `--force-tool` or `--json-schema`.
+///
+/// Until the model calls the tool, it is the only tool offered. Through the
+/// Converse client the request's tool choice requires the call. The backend's
+/// `ChatRequest` has no tool_choice field, so there the prompt asks for it
+/// and a text-only answer is met with one reminder.
+#[derive(Debug, Clone)]
+pub struct ForcedTool {
+    pub name: String,
+    extraction: bool,
+    /// The extraction schema was wrapped in an object under WRAPPED_PROPERTY
+    wrapped: bool,
+}
+
+impl ForcedTool {
+    /// The offered tool `name` refers to, either exactly or without its
+    /// `<server>_` prefix
+    pub fn resolve(name: &str, tools: &[BackendTool]) -> Result<Self> {
+        if let Some(tool) = tools.iter().find(|tool| tool.name == name) {
+            return Ok(Self::named(&tool.name));
+        }
+        let suffix = format!("_{}", name);
+        let matches: Vec<&str> =
+            tools.iter().map(|tool| tool.name.as_str()).filter(|tool| tool.ends_with(&suffix)).collect();
+        match matches.as_slice() {
+            [tool] => Ok(Self::named(tool)),
+            [] => bail!("--force-tool {}: no such tool is offered in this run (see gamecode mcp tools)", name),
+            _ => bail!("--force-tool {} is ambiguous: {}", name, matches.join(", ")),
+        }
+    }
+
+    fn named(name: &str) -> Self {
+        Self { name: name.to_string(), extraction: false, wrapped: false }
+    }
+
+    /// An extraction tool whose input schema is the JSON Schema in `path`
+    pub fn extraction(path: &Path) -> Result<(Self, BackendTool)> {
+        let text = std::fs::read_to_string(path)
+            .with_context(|| format!("Failed to read JSON schema {}", path.display()))?;
+        let mut schema: Value = serde_json::from_str(&text)
+            .with_context(|| format!("Invalid JSON in schema {}", path.display()))?;
+        if !schema.is_object() {
+            bail!("{} is not a JSON Schema object", path.display());
+        }
+        let description = schema
+            .get("description")
+            .and_then(Value::as_str)
+            .map(|d| format!(" The data: {}", d))
+            .unwrap_or_default();
+        if let Some(map) = schema.as_object_mut() {
+            map.remove("$schema");
+        }
+
+        let wrapped = schema.get("type").and_then(Value::as_str) != Some("object");
+        if wrapped {
+            schema = json!({
+                "type": "object",
+                "properties": { WRAPPED_PROPERTY: schema },
+                "required": [WRAPPED_PROPERTY],
+            });
+        }
+
+        let tool = BackendTool {
+            name: EXTRACTION_TOOL.to_string(),
+            description: format!(
+                "Return the requested data as this tool's input, which must match its schema. \
+                 Calling it is the final answer.{}",
+                description
+            ),
+            input_schema: schema,
+        };
+        Ok((Self { name: EXTRACTION_TOOL.to_string(), extraction: true, wrapped }, tool))
+    }
+
+    /// Whether the call's arguments are the run's output
+    pub fn is_extraction(&self) -> bool {
+        self.extraction
+    }
+
+    /// Appended to the prompt
+    pub fn instruction(&self) -> String {
+        if self.extraction {
+            format!("Answer only by calling the `{}` tool with the result as its input.", self.name)
+        } else {
+            format!("Start by calling the `{}` tool.", self.name)
+        }
+    }
+
+    /// Sent when the model answered without calling the tool
+    pub fn reminder(&self) -> String {
+        format!("You didn't call the `{}` tool. Call it now.", self.name)
+    }
+
+    /// The extracted data from the extraction tool's input
+    pub fn extracted(&self, input: &Value) -> Value {
+        if self.wrapped {
+            input.get(WRAPPED_PROPERTY).cloned().unwrap_or(Value::Null)
+        } else {
+            input.clone()
+        }
+    }
+}
@@ -0,0 +1,134 @@
+use anyhow::{Context, Result};
+use aws_config::{BehaviorVersion, Region, SdkConfig};
+use aws_sdk_bedrockruntime::types::{
+    GuardrailAction, GuardrailContentBlock, GuardrailContentSource, GuardrailTextBlock,
+};
+use aws_sdk_bedrockruntime::Client;
+use serde::{Deserialize, Serialize};
+use tracing::debug;
+
+/// Guardrail settings from the `[guardrail]` section of config
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct GuardrailConfig {
+    /// Guardrail ID or ARN
+    pub id: String,
+    /// Published version number, or `DRAFT`
+    #[serde(default = "default_version")]
+    pub version: String,
+}
+
+fn default_version() -> String {
+    "DRAFT".to_string()
+}
+
+/// Result of checking content against a guardrail
+#[derive(Debug)]
+pub enum GuardrailVerdict {
+    Passed,
+    /// The guardrail blocked or masked the content; `output` is what it returned instead
+    Intervened { output: String, policies: Vec<String> },
+}
+
+/// Applies a Bedrock guardrail to prompts and responses via the ApplyGuardrail API
+pub struct Guardrail {
+    client: Client,
+    config: GuardrailConfig,
+}
+
+impl Guardrail {
+    pub async fn new(region: &str, config: GuardrailConfig) -> Self {
+        let sdk_config = aws_config::defaults(BehaviorVersion::latest())
+            .region(Region::new(region.to_string()))
+            .load()
+            .await;
+        Self::from_config(&sdk_config, config)
+    }
+
+    /// Build from an already loaded AWS configuration
+    pub fn from_config(sdk_config: &SdkConfig, config: GuardrailConfig) -> Self {
+        Self {
+            client: Client::new(sdk_config),
+            config,
+        }
+    }
+
+    pub fn id(&self) -> &str {
+        &self.config.id
+    }
+
+    /// Check a user prompt
+    pub async fn check_input(&self, text: &str) -> Result<GuardrailVerdict> {
+        self.check(text, GuardrailContentSource::Input).await
+    }
+
+    /// Check a model response
+    pub async fn check_output(&self, text: &str) -> Result<GuardrailVerdict> {
+        self.check(text, GuardrailContentSource::Output).await
+    }
+
+    async fn check(&self, text: &str, source: GuardrailContentSource) -> Result<GuardrailVerdict> {
+        let block = GuardrailTextBlock::builder()
+            .text(text)
+            .build()
+            .context("Failed to build guardrail content")?;
+
+        let response = self
+            .client
+            .apply_guardrail()
+            .guardrail_identifier(&self.config.id)
+            .guardrail_version(&self.config.version)
+            .source(source)
+            .content(GuardrailContentBlock::Text(block))
+            .send()
+            .await
+            .with_context(|| format!("Failed to apply guardrail {}", self.config.id))?;
+
+        if response.action() != &GuardrailAction::GuardrailIntervened {
+            return Ok(GuardrailVerdict::Passed);
+        }
+
+        let output = response
+            .outputs()
+            .iter()
+            .filter_map(|o| o.text())
+            .collect::<Vec<_>>()
+            .join("\n");
+
+        let mut policies = Vec::new();
+        for assessment in response.assessments() {
+            if assessment.topic_policy().is_some() {
+                policies.push("topic".to_string());
+            }
+            if assessment.content_policy().is_some() {
+                policies.push("content".to_string());
+            }
+            if assessment.word_policy().is_some() {
+                policies.push("word".to_string());
+            }
+            if assessment.sensitive_information_policy().is_some() {
+                policies.push("sensitive information".to_string());
+            }
+            if assessment.contextual_grounding_policy().is_some() {
+                policies.push("contextual grounding".to_string());
+            }
+        }
+        policies.sort();
+        policies.dedup();
+
+        debug!("Guardrail {} intervened ({:?})", self.config.id, policies);
+        Ok(GuardrailVerdict::Intervened { output, policies })
+    }
+}
+
+/// Report a guardrail intervention on stderr so it stands apart from model output
+pub fn report_intervention(stage: &str, policies: &[String]) {
+    if policies.is_empty() {
+        notice!("\n🛡️  Guardrail intervened on the {}", stage);
+    } else {
+        notice!(
+            "\n🛡️  Guardrail intervened on the {} ({} policy)",
+            stage,
+            policies.join(", ")
+        );
+    }
+}
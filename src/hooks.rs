@@ -0,0 +1,173 @@
+use anyhow::{Context, Result};
+use crate::logging::EVENT_TARGET;
+use serde::de::DeserializeOwned;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::process::Stdio;
+use std::time::Duration;
+use tokio::io::AsyncWriteExt;
+use tokio::process::Command;
+use tracing::{debug, warn};
+
+/// Default limit on how long a hook may run
+const DEFAULT_TIMEOUT_SECS: u64 = 30;
+
+/// Settings for the `[hooks]` section: shell commands that see, and may
+/// rewrite or veto, what passes through the agent loop
+#[derive(Debug, Default, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct HooksConfig {
+    /// Run on each prompt before it is added to the session
+    pub pre_request: Option<String>,
+    /// Run on each model response before it is shown or acted on
+    pub post_response: Option<String>,
+    /// Run on each tool result before it is sent back to the model
+    pub post_tool: Option<String>,
+    /// Seconds a hook may run before it counts as a veto (default: 30)
+    pub timeout_secs: Option<u64>,
+}
+
+/// Payload of the `pre_request` hook; `prompt` may be rewritten
+#[derive(Debug, Serialize, Deserialize)]
+pub struct PreRequest {
+    pub model: String,
+    pub prompt: String,
+}
+
+/// Payload of the `post_response` hook; `text` may be rewritten
+#[derive(Debug, Serialize, Deserialize)]
+pub struct PostResponse {
+    pub model: String,
+    pub text: String,
+}
+
+/// Payload of the `post_tool` hook; `result` and `is_error` may be rewritten
+#[derive(Debug, Serialize, Deserialize)]
+pub struct PostTool {
+    pub tool: String,
+    pub input: Value,
+    pub result: String,
+    pub is_error: bool,
+}
+
+/// What a hook decided
+#[derive(Debug)]
+pub enum HookOutcome<T> {
+    /// Go on with the payload, as rewritten by the hook
+    Continue(T),
+    /// Stop; the hook exited non-zero (or timed out) with this reason
+    Veto(String),
+}
+
+/// Runs the configured hooks for one session.
+///
+/// Each hook is run with `sh -c` (`cmd /C` on Windows), with the payload as a
+/// JSON object on stdin. Besides the payload fields it has `hook` (the hook
+/// name) and `session_id`. Exiting 0 with nothing on stdout keeps the payload;
+/// printing a JSON object replaces the fields it contains. Any other exit
+/// status vetoes, with stderr as the reason.
+#[derive(Debug, Clone)]
+pub struct Hooks {
+    config: HooksConfig,
+    session_id: Option<String>,
+}
+
+impl Hooks {
+    pub fn new(config: HooksConfig, session_id: Option<String>) -> Self {
+        Self { config, session_id }
+    }
+
+    pub async fn pre_request(&self, payload: PreRequest) -> Result<HookOutcome<PreRequest>> {
+        self.run("pre_request", self.config.pre_request.as_deref(), payload).await
+    }
+
+    pub async fn post_response(&self, payload: PostResponse) -> Result<HookOutcome<PostResponse>> {
+        self.run("post_response", self.config.post_response.as_deref(), payload).await
+    }
+
+    pub async fn post_tool(&self, payload: PostTool) -> Result<HookOutcome<PostTool>> {
+        self.run("post_tool", self.config.post_tool.as_deref(), payload).await
+    }
+
+    async fn run<T: Serialize + DeserializeOwned>(
+        &self,
+        hook: &str,
+        command: Option<&str>,
+        payload: T,
+    ) -> Result<HookOutcome<T>> {
+        let Some(command) = command.filter(|c| !c.trim().is_empty()) else {
+            return Ok(HookOutcome::Continue(payload));
+        };
+
+        let mut value = serde_json::to_value(&payload)?;
+        if let Value::Object(fields) = &mut value {
+            fields.insert("hook".to_string(), Value::from(hook));
+            fields.insert("session_id".to_string(), self.session_id.clone().into());
+        }
+        debug!("Running {} hook: {}", hook, command);
+
+        let mut child = shell(command)
+            .env("GAMECODE_HOOK", hook)
+            .stdin(Stdio::piped())
+            .stdout(Stdio::piped())
+            .stderr(Stdio::piped())
+            .kill_on_drop(true)
+            .spawn()
+            .with_context(|| format!("Failed to run {} hook '{}'", hook, command))?;
+        // Written from its own task, so a hook that never reads a large
+        // input still runs into the timeout below
+        let input = serde_json::to_string(&value)?;
+        let writer = child.stdin.take().map(|mut stdin| {
+            tokio::spawn(async move {
+                // A hook that exits without reading its input is fine
+                let _ = stdin.write_all(input.as_bytes()).await;
+            })
+        });
+
+        let timeout = Duration::from_secs(self.config.timeout_secs.unwrap_or(DEFAULT_TIMEOUT_SECS));
+        let output = tokio::time::timeout(timeout, child.wait_with_output()).await;
+        if let Some(writer) = writer {
+            writer.abort();
+        }
+        let output = match output {
+            Ok(output) => output.with_context(|| format!("Failed to run {} hook '{}'", hook, command))?,
+            Err(_) => {
+                warn!(target: EVENT_TARGET, hook, command, "hook timed out");
+                return Ok(HookOutcome::Veto(format!("{} hook timed out after {}s", hook, timeout.as_secs())));
+            }
+        };
+
+        if !output.status.success() {
+            let stderr = String::from_utf8_lossy(&output.stderr).trim().to_string();
+            let reason = if stderr.is_empty() { format!("{} hook exited with {}", hook, output.status) } else { stderr };
+            warn!(target: EVENT_TARGET, hook, command, reason = %reason, "hook vetoed");
+            return Ok(HookOutcome::Veto(reason));
+        }
+
+        let stdout = String::from_utf8_lossy(&output.stdout);
+        if stdout.trim().is_empty() {
+            return Ok(HookOutcome::Continue(payload));
+        }
+        let changes: serde_json::Map<String, Value> = serde_json::from_str(stdout.trim())
+            .with_context(|| format!("{} hook printed something other than a JSON object", hook))?;
+        if let Value::Object(fields) = &mut value {
+            fields.extend(changes);
+        }
+        debug!(target: EVENT_TARGET, hook, command, "hook rewrote payload");
+        let payload = serde_json::from_value(value)
+            .with_context(|| format!("{} hook returned an invalid payload", hook))?;
+        Ok(HookOutcome::Continue(payload))
+    }
+}
+
+fn shell(command: &str) -> Command {
+    if cfg!(windows) {
+        let mut shell = Command::new("cmd");
+        shell.arg("/C").arg(command);
+        shell
+    } else {
+        let mut shell = Command::new("sh");
+        shell.arg("-c").arg(command);
+        shell
+    }
+}
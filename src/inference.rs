@@ -0,0 +1,107 @@
+use crate::models;
+use gamecode_backend::InferenceConfig;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+
+/// Generation parameters; unset fields fall through to the next source
+#[derive(Debug, Default, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct InferenceSettings {
+    pub temperature: Option<f32>,
+    pub top_p: Option<f32>,
+    pub max_tokens: Option<u32>,
+    /// Generation stops at the first of these sequences
+    pub stop_sequences: Option<Vec<String>>,
+}
+
+impl InferenceSettings {
+    /// Backend inference config for a request. The backend's `InferenceConfig`
+    /// has no stop sequences; requests with them go through
+    /// `converse::ConverseClient`.
+    pub fn to_backend(&self) -> InferenceConfig {
+        InferenceConfig {
+            temperature: self.temperature,
+            max_tokens: self.max_tokens,
+            top_p: self.top_p,
+        }
+    }
+
+    /// Lower `max_tokens` to `limit`. Returns the requested value if it was lowered.
+    pub fn clamp_max_tokens(&mut self, limit: u32) -> Option<u32> {
+        match self.max_tokens {
+            Some(max_tokens) if max_tokens > limit => {
+                self.max_tokens = Some(limit);
+                Some(max_tokens)
+            }
+            _ => None,
+        }
+    }
+
+    /// Fill unset fields from `fallback`
+    pub fn or(self, fallback: &InferenceSettings) -> Self {
+        Self {
+            temperature: self.temperature.or(fallback.temperature),
+            top_p: self.top_p.or(fallback.top_p),
+            max_tokens: self.max_tokens.or(fallback.max_tokens),
+            stop_sequences: self.stop_sequences.or_else(|| fallback.stop_sequences.clone()),
+        }
+    }
+}
+
+/// The `[inference]` section of the config file:
+///
+/// ```toml
+/// [inference]
+/// temperature = 0.7
+///
+/// [inference.models."claude-3.5-haiku"]
+/// max_tokens = 8192
+/// ```
+#[derive(Debug, Default, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct InferenceDefaults {
+    #[serde(flatten)]
+    pub defaults: InferenceSettings,
+    /// Per-model overrides keyed by alias or full model ID
+    pub models: HashMap<String, InferenceSettings>,
+}
+
+/// Built-in defaults when neither flags nor config say otherwise
+fn builtin_defaults() -> InferenceSettings {
+    InferenceSettings {
+        temperature: Some(0.7),
+        top_p: Some(0.9),
+        max_tokens: Some(4096),
+        stop_sequences: None,
+    }
+}
+
+/// The largest `max_tokens` a model accepts: `model_limits` by alias or ID,
+/// then the built-in model metadata
+pub fn output_limit(limits: &HashMap<String, u32>, model_alias: Option<&str>, model_id: &str) -> Option<u32> {
+    model_alias
+        .and_then(|alias| limits.get(alias))
+        .or_else(|| limits.get(model_id))
+        .copied()
+        .or_else(|| models::lookup(model_id).map(|info| info.max_output_tokens))
+}
+
+/// Resolve generation parameters: flags, then per-model config, then global
+/// config, then built-in defaults
+pub fn resolve(
+    flags: InferenceSettings,
+    config: &InferenceDefaults,
+    model_alias: Option<&str>,
+    model_id: &str,
+) -> InferenceSettings {
+    let per_model = model_alias
+        .and_then(|alias| config.models.get(alias))
+        .or_else(|| config.models.get(model_id))
+        .cloned()
+        .unwrap_or_default();
+
+    flags
+        .or(&per_model)
+        .or(&config.defaults)
+        .or(&builtin_defaults())
+}
@@ -0,0 +1,109 @@
+//! Library half of gamecode: the backend, session, tool and MCP machinery
+//! behind the `gamecode` binary, for programs that want to embed the agent
+//! instead of shelling out to it. Start with [`agent::Agent`].
+
+use anyhow::{Context, Result};
+use crate::config::Config;
+use gamecode_backend::LLMBackend;
+use gamecode_bedrock::BedrockBackend;
+
+#[macro_use]
+pub mod status;
+
+pub mod agent;
+pub mod attachments;
+pub mod aws_auth;
+pub mod budget;
+pub mod code_index;
+pub mod config;
+pub mod converse;
+pub mod daemon;
+pub mod dry_run;
+pub mod embeddings;
+pub mod env_context;
+pub mod fetch_url;
+pub mod file_backups;
+pub mod file_edits;
+pub mod forced_tool;
+pub mod guardrails;
+pub mod hooks;
+pub mod inference;
+pub mod logging;
+pub mod markdown;
+pub mod mcp_client;
+pub mod mcp_config;
+pub mod mcp_logs;
+pub mod mcp_protocol;
+pub mod mcp_sampling;
+pub mod mcp_server;
+pub mod mcp_tool_cache;
+pub mod mcp_tool_dispatcher;
+pub mod mcp_tool_registry;
+pub mod model_discovery;
+pub mod models;
+pub mod notify;
+pub mod one_shot;
+pub mod output;
+pub mod pager;
+pub mod plan;
+pub mod project_context;
+pub mod prompt_template;
+pub mod redaction;
+pub mod replay;
+pub mod response_cache;
+pub mod retry;
+pub mod server;
+pub mod session_archive;
+pub mod session_store;
+pub mod session_sync;
+pub mod share;
+pub mod spinner;
+pub mod sub_agent;
+pub mod thinking;
+pub mod tokens;
+pub mod tool_approval;
+pub mod tool_capabilities;
+pub mod tool_filter;
+pub mod tool_log;
+pub mod tool_result;
+pub mod tui;
+pub mod usage;
+
+/// Backend factory function to create the appropriate backend
+pub async fn create_backend(region: &str) -> Result<Box<dyn LLMBackend>> {
+    // For now, we only support Bedrock, but this could be expanded
+    // to support other backends (OpenAI, etc.) based on configuration
+    let backend = BedrockBackend::new_with_region(region)
+        .await
+        .context("Failed to create backend")?;
+    Ok(Box::new(backend))
+}
+
+/// Create the backend from an already loaded AWS configuration, so it uses
+/// the credentials chosen with `--aws-profile` or `--assume-role`
+pub fn create_backend_with_config(sdk_config: &aws_config::SdkConfig) -> Box<dyn LLMBackend> {
+    Box::new(BedrockBackend::new_with_config(sdk_config))
+}
+
+/// Model used when neither flags, environment nor config choose one
+pub const DEFAULT_MODEL: &str = "us.anthropic.claude-3-7-sonnet-20250219-v1:0";
+
+/// Model precedence: `--model`, then `GAMECODE_MODEL`, then `default_model` from config
+pub fn requested_model(flag: Option<String>, config: &Config) -> Option<String> {
+    flag.or_else(|| std::env::var("GAMECODE_MODEL").ok().filter(|m| !m.is_empty()))
+        .or_else(|| config.default_model.clone())
+}
+
+/// Model mapping function: user aliases take precedence over built-in ones.
+/// A known model, given by alias, model ID or inference profile ID, maps to
+/// the ID Bedrock invokes from `region`, with the profile prefix of the
+/// region's geography when the model needs one.
+pub fn map_model_name(model: &str, config: &Config, region: &str) -> String {
+    if let Some(id) = config.model_aliases.get(model) {
+        return id.clone();
+    }
+    match models::lookup(model) {
+        Some(info) if !model.starts_with("arn:") => info.invocation_id(region),
+        _ => model.to_string(), // Pass through ARNs and unknown model names
+    }
+}
@@ -0,0 +1,208 @@
+use anyhow::{Context, Result};
+use serde::{Deserialize, Serialize};
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicU8, Ordering};
+use tracing::level_filters::LevelFilter;
+use tracing_appender::non_blocking::WorkerGuard;
+use tracing_appender::rolling::{RollingFileAppender, Rotation};
+use tracing_subscriber::filter::{EnvFilter, Targets};
+use tracing_subscriber::layer::SubscriberExt;
+use tracing_subscriber::util::SubscriberInitExt;
+use tracing_subscriber::{Layer, Registry};
+
+/// Target of the structured records written for requests, retries, tool
+/// calls and errors. They go to the log file only, never the terminal.
+pub const EVENT_TARGET: &str = "gamecode::events";
+
+/// Target of the raw request and response payloads logged at `-vvv`
+pub const WIRE_TARGET: &str = "gamecode::wire";
+
+static VERBOSITY: AtomicU8 = AtomicU8::new(0);
+
+/// Settings for the `[logging]` section
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct LoggingConfig {
+    /// Write JSON logs to a file
+    pub enabled: bool,
+    /// Log file; rotated daily with the date appended. Defaults to
+    /// `~/.local/share/gamecode/logs/gamecode.log`
+    pub path: Option<PathBuf>,
+    /// Number of rotated files to keep
+    pub max_files: usize,
+}
+
+impl Default for LoggingConfig {
+    fn default() -> Self {
+        Self {
+            enabled: true,
+            path: None,
+            max_files: 7,
+        }
+    }
+}
+
+pub fn default_log_path() -> Result<PathBuf> {
+    let home = home::home_dir().context("Failed to get home directory")?;
+    Ok(home.join(".local/share/gamecode/logs/gamecode.log"))
+}
+
+/// Terminal log level for `-v` given `verbosity` times
+pub fn terminal_level(verbosity: u8) -> LevelFilter {
+    match verbosity {
+        0 => LevelFilter::WARN,
+        1 => LevelFilter::INFO,
+        2 => LevelFilter::DEBUG,
+        _ => LevelFilter::TRACE,
+    }
+}
+
+/// Verbosity the subscriber was installed with
+pub fn verbosity() -> u8 {
+    VERBOSITY.load(Ordering::Relaxed)
+}
+
+/// Install the global subscriber for every command: human-readable logs on
+/// stderr at the level chosen by `-v` (or `RUST_LOG`, if set), and JSON at
+/// debug level for gamecode's own crates in `log_file` (or the configured
+/// file). A log file that can't be opened is reported and skipped. The
+/// returned guard flushes the file when dropped, so keep it alive until the
+/// process exits.
+pub fn init(verbosity: u8, log_file: Option<&Path>, config: &LoggingConfig) -> Option<WorkerGuard> {
+    VERBOSITY.store(verbosity, Ordering::Relaxed);
+
+    // stderr, so logs never mix with responses or protocol output on stdout
+    let terminal: Box<dyn Layer<Registry> + Send + Sync> = match std::env::var("RUST_LOG") {
+        Ok(directives) if !directives.is_empty() => {
+            let mut filter = EnvFilter::new(directives);
+            if let Ok(off) = format!("{}=off", EVENT_TARGET).parse() {
+                filter = filter.add_directive(off);
+            }
+            Box::new(tracing_subscriber::fmt::layer().with_writer(std::io::stderr).with_filter(filter))
+        }
+        _ => {
+            // TRACE is only for our own payload logging; other crates stop at DEBUG
+            let level = terminal_level(verbosity);
+            Box::new(
+                tracing_subscriber::fmt::layer().with_writer(std::io::stderr).with_filter(
+                    Targets::new()
+                        .with_default(level.min(LevelFilter::DEBUG))
+                        .with_target("gamecode", level)
+                        .with_target("gamecode_cli", level)
+                        .with_target("gamecode_backend", level)
+                        .with_target("gamecode_bedrock", level)
+                        .with_target(EVENT_TARGET, LevelFilter::OFF),
+                ),
+            )
+        }
+    };
+
+    let path = match log_file {
+        Some(path) => Some(path.to_path_buf()),
+        None if config.enabled => config.path.clone().or_else(|| default_log_path().ok()),
+        None => None,
+    };
+    let appender = path.and_then(|path| match open_rotating(&path, config.max_files) {
+        Ok(appender) => Some(appender),
+        Err(e) => {
+            eprintln!("Warning: logging to file disabled: {:#}", e);
+            None
+        }
+    });
+    let (file, guard) = match appender {
+        Some(appender) => {
+            let (writer, guard) = tracing_appender::non_blocking(appender);
+            let layer = tracing_subscriber::fmt::layer()
+                .json()
+                .with_writer(writer)
+                .with_filter(
+                    Targets::new()
+                        .with_default(LevelFilter::WARN)
+                        .with_target("gamecode", LevelFilter::DEBUG)
+                        .with_target("gamecode_cli", LevelFilter::DEBUG)
+                        .with_target("gamecode_backend", LevelFilter::DEBUG)
+                        .with_target("gamecode_bedrock", LevelFilter::DEBUG),
+                );
+            (Some(layer), Some(guard))
+        }
+        None => (None, None),
+    };
+
+    tracing_subscriber::registry().with(terminal).with(file).init();
+    guard
+}
+
+/// Daily-rotated appender writing `<stem>.<date>.<ext>` next to `path`
+fn open_rotating(path: &Path, max_files: usize) -> Result<RollingFileAppender> {
+    let dir = path.parent().filter(|p| !p.as_os_str().is_empty()).unwrap_or(Path::new("."));
+    std::fs::create_dir_all(dir).with_context(|| format!("Failed to create {}", dir.display()))?;
+    let stem = path.file_stem().and_then(|s| s.to_str()).unwrap_or("gamecode");
+
+    let mut builder = RollingFileAppender::builder()
+        .rotation(Rotation::DAILY)
+        .filename_prefix(stem)
+        .max_log_files(max_files.max(1));
+    if let Some(extension) = path.extension().and_then(|e| e.to_str()) {
+        builder = builder.filename_suffix(extension);
+    }
+    builder
+        .build(dir)
+        .with_context(|| format!("Failed to open log file {}", path.display()))
+}
+
+/// Mask credentials in text bound for the logs: AWS access key IDs and the
+/// values of secret-looking keys (`Authorization`, `aws_secret_access_key`,
+/// `x-amz-security-token`, ...)
+pub fn redact(text: &str) -> String {
+    const SECRET_KEYS: &[&str] = &[
+        "authorization",
+        "aws_secret_access_key",
+        "aws_session_token",
+        "secret_access_key",
+        "session_token",
+        "x-amz-security-token",
+    ];
+    let lower = text.to_ascii_lowercase();
+    let bytes = text.as_bytes();
+    let mut masked = vec![false; bytes.len()];
+
+    for key in SECRET_KEYS {
+        let mut from = 0;
+        while let Some(found) = lower[from..].find(key) {
+            let mut i = from + found + key.len();
+            // Skip the separator between key and value (`": "`, `=`, ...)
+            while i < bytes.len() && matches!(bytes[i], b'"' | b'\'' | b':' | b'=' | b' ') {
+                i += 1;
+            }
+            while i < bytes.len() && !matches!(bytes[i], b'"' | b'\'' | b',' | b'}' | b'\n') {
+                masked[i] = true;
+                i += 1;
+            }
+            from = i.max(from + found + 1);
+        }
+    }
+    // Access key IDs: AKIA/ASIA followed by 16 uppercase letters or digits
+    for start in 0..bytes.len().saturating_sub(19) {
+        let candidate = &bytes[start..start + 20];
+        if (candidate.starts_with(b"AKIA") || candidate.starts_with(b"ASIA"))
+            && candidate[4..].iter().all(|b| b.is_ascii_uppercase() || b.is_ascii_digit())
+        {
+            masked[start + 4..start + 20].iter_mut().for_each(|m| *m = true);
+        }
+    }
+
+    let mut result = String::with_capacity(text.len());
+    let mut in_mask = false;
+    for (i, c) in text.char_indices() {
+        if masked[i] {
+            if !in_mask {
+                result.push_str("[REDACTED]");
+                in_mask = true;
+            }
+        } else {
+            in_mask = false;
+            result.push(c);
+        }
+    }
+    result
+}
@@ -1,53 +1,453 @@
 use anyhow::{Context as AnyhowContext, Result};
 use flag_rs::{Command, CommandBuilder, CompletionResult, Context, Flag, FlagType, FlagValue};
 use gamecode_backend::{
-    BackendStatus, ChatRequest, ContentBlock, InferenceConfig, LLMBackend,
-    Message as BackendMessage, MessageRole as BackendMessageRole, RetryConfig, StatusCallback,
-    Tool as BackendTool,
-};
-use gamecode_bedrock::BedrockBackend;
-use gamecode_context::{
-    session::{Message as ContextMessage, MessageRole as ContextMessageRole, MessageRole},
-    SessionManager,
+    BackendStatus, LLMBackend, Message as BackendMessage, MessageRole as BackendMessageRole,
+    StatusCallback, Tool as BackendTool,
 };
+use gamecode_context::session::{Message as ContextMessage, MessageRole as ContextMessageRole, MessageRole, Session};
 use gamecode_prompt::PromptManager;
 use serde_json::Value;
-use crate::mcp_tool_dispatcher::McpToolDispatcher;
-use std::io::Write;
+use gamecode_cli::agent::{Agent, AgentEvent, RunOutcome, StopReason, ToolDecision, ToolOutcome};
+use gamecode_cli::aws_auth::AwsAuth;
+use gamecode_cli::budget::{Budget, BudgetExceeded};
+use gamecode_cli::mcp_config::McpConfig;
+use gamecode_cli::config::{self, Config};
+use gamecode_cli::converse::ConverseClient;
+use gamecode_cli::dry_run::DryRun;
+use gamecode_cli::file_backups::SessionBackups;
+use gamecode_cli::file_edits::FileEdit;
+use gamecode_cli::forced_tool::ForcedTool;
+use gamecode_cli::guardrails::{self, Guardrail, GuardrailConfig};
+use gamecode_cli::hooks::{HookOutcome, Hooks, PreRequest};
+use gamecode_cli::mcp_client::McpClient;
+use gamecode_cli::mcp_tool_dispatcher::McpToolDispatcher;
+use gamecode_cli::models::{self, ModelFamily};
+use gamecode_cli::spinner::{Spinner, SpinnerDetail};
+use gamecode_cli::output::{Event, OutputFormat, RunReport, ToolCallReport};
+use gamecode_cli::plan::{self, Decision, Plan};
+use gamecode_cli::redaction::Redactor;
+use gamecode_cli::response_cache::ResponseCache;
+use gamecode_cli::session_store::{self, SessionStore};
+use gamecode_cli::sub_agent::{Parent, SubAgent};
+use gamecode_cli::thinking::{self, ThinkingLog};
+use gamecode_cli::tool_approval::ToolApprover;
+use gamecode_cli::tool_capabilities::{self, ToolCapability};
+use gamecode_cli::tool_filter::ToolFilter;
+use gamecode_cli::tool_log::ToolLog;
+use gamecode_cli::usage::{self, TokenUsage, UsageLedger};
+use gamecode_cli::{
+    attachments, daemon, env_context, fetch_url, inference, logging, markdown, model_discovery, notify,
+    pager, project_context, prompt_template, retry, status,
+};
+use gamecode_cli::{create_backend_with_config, map_model_name, DEFAULT_MODEL};
+use crate::settings::{Flags, Settings};
+use std::collections::{HashMap, HashSet};
+use std::io::{IsTerminal, Read, Write};
 use std::sync::Arc;
-use std::time::Duration;
 use tracing::debug;
 use uuid::Uuid;
 
+#[macro_use]
+extern crate gamecode_cli;
+
 mod cmd;
-mod mcp_client;
-mod mcp_protocol;
-mod mcp_tool_registry;
-mod mcp_tool_dispatcher;
+mod settings;
+
+// Resolve a `mcp:<server>/<prompt>` system prompt reference through the MCP server
+async fn load_mcp_prompt(spec: &str, arguments: &HashMap<String, String>) -> Result<String> {
+    let (server_name, prompt_name) = spec
+        .split_once('/')
+        .with_context(|| format!("Invalid MCP prompt '{}', expected mcp:<server>/<prompt>", spec))?;
+    
+    let config = McpConfig::load()
+        .map_err(|e| anyhow::anyhow!("Failed to load MCP server configuration: {}", e))?;
+    let server = config.servers.iter()
+        .find(|s| s.name == server_name)
+        .with_context(|| format!("MCP server '{}' not found", server_name))?;
+    
+    if !server.enabled {
+        anyhow::bail!("MCP server '{}' is disabled", server_name);
+    }
+    
+    McpClient::new().get_prompt_text(server, prompt_name, arguments).await
+}
 
-// Backend factory function to create the appropriate backend
-async fn create_backend(region: &str) -> Result<Box<dyn LLMBackend>> {
-    // For now, we only support Bedrock, but this could be expanded
-    // to support other backends (OpenAI, etc.) based on configuration
-    let backend = BedrockBackend::new_with_region(region)
-        .await
-        .context("Failed to create backend")?;
-    Ok(Box::new(backend))
+// Parse repeated KEY=VALUE flag values (comma-separated) into a map
+fn parse_key_values(flag_name: &str, raw: Option<&String>) -> Result<HashMap<String, String>> {
+    let mut values = HashMap::new();
+    if let Some(raw) = raw {
+        for pair in raw.split(',').filter(|p| !p.is_empty()) {
+            let (key, value) = pair
+                .split_once('=')
+                .with_context(|| format!("Invalid --{} value '{}', expected KEY=VALUE", flag_name, pair))?;
+            values.insert(key.trim().to_string(), value.to_string());
+        }
+    }
+    Ok(values)
 }
 
-// Model mapping function
-fn map_model_name(model: &str) -> String {
-    let mapped = match model {
-        "opus-4" => "us.anthropic.claude-opus-4-20250514-v1:0",
-        "sonnet-4" => "us.anthropic.claude-sonnet-4-20250514-v1:0",
-        "claude-3.7-sonnet" => "us.anthropic.claude-3-7-sonnet-20250219-v1:0",
-        "claude-3.5-sonnet" => "anthropic.claude-3-5-sonnet-20240620-v1:0",
-        "claude-3.5-haiku" => "anthropic.claude-3-5-haiku-20241022-v1:0",
-        "claude-3-sonnet" => "anthropic.claude-3-sonnet-20240229-v1:0",
-        "claude-3-haiku" => "anthropic.claude-3-haiku-20240307-v1:0",
-        _ => model, // Pass through unknown model names
-    };
-    mapped.to_string()
+// Show a diff for tool calls that modify a file and ask before applying.
+// Returns true when the call is not a recognizable file edit.
+fn confirm_file_edit(tool_name: &str, input: &Value, notify: bool) -> Result<bool> {
+    match FileEdit::detect(input) {
+        Some(edit) => {
+            if notify {
+                notify::send("gamecode needs approval", &format!("Review file change from {}", tool_name));
+            }
+            edit.confirm(tool_name)
+        }
+        None => Ok(true),
+    }
+}
+
+/// Save what the conversation has so far after Ctrl-C and exit cleanly.
+/// `partial` is assistant text from a turn whose tool calls were cut short.
+fn save_interrupted(
+    session_manager: &mut dyn SessionStore,
+    session: &mut gamecode_context::session::Session,
+    partial: Option<&str>,
+) -> Result<()> {
+    if let Some(text) = partial.filter(|t| !t.is_empty()) {
+        let assistant_message = ContextMessage::new(MessageRole::Assistant, text.to_string());
+        session_manager.add_message(session, assistant_message)?;
+    }
+    let note = ContextMessage::new(MessageRole::System, "Interrupted by the user".to_string());
+    session_manager.add_message(session, note)?;
+    session_manager.save_session(session)?;
+    
+    notice!("\n⏹️  Interrupted. Session saved; to continue, use: --session {}", session.id);
+    Ok(())
+}
+
+/// Shows the `AgentEvent`s of a run as text or NDJSON, and records the
+/// conversation in the session as it happens
+struct RunRenderer {
+    session_manager: Box<dyn SessionStore>,
+    session: gamecode_context::session::Session,
+    tool_log: ToolLog,
+    report: RunReport,
+    /// Token totals of the session, including earlier runs
+    session_usage: TokenUsage,
+    plan: Option<Plan>,
+    plan_notes: Vec<String>,
+    output_format: OutputFormat,
+    render_markdown: bool,
+    use_pager: bool,
+    /// Print responses to stdout (not only to --output)
+    print_text: bool,
+    extraction: bool,
+    show_usage: bool,
+    hide_thinking: bool,
+    verbose: bool,
+    /// The plan request of --plan is underway; its response isn't shown
+    planning: bool,
+    spinner_label: &'static str,
+    /// While the spinner runs, retry status is shown on its line instead of printed
+    spinner_detail: SpinnerDetail,
+    spinner: Option<Spinner>,
+    /// Inputs of this turn's tool calls, by call ID
+    inputs: HashMap<String, Value>,
+    /// First of this turn's calls in `report.tool_calls`
+    turn_start: usize,
+    /// Text of a response whose tool calls are running
+    pending_text: String,
+    /// Model and thinking behind the response being recorded
+    pending_thinking: Option<(String, String)>,
+    /// The guardrail answered the prompt instead of the model
+    prompt_blocked: bool,
+    /// Text of the last response, for --output
+    final_text: String,
+    /// First failure to show or record an event
+    error: Option<anyhow::Error>,
+}
+
+impl RunRenderer {
+    fn on_event(&mut self, event: AgentEvent) {
+        if !matches!(event, AgentEvent::Retry { .. }) {
+            if let Some(spinner) = self.spinner.take() {
+                spinner.stop();
+            }
+        }
+        if let Err(e) = self.render(event) {
+            self.error.get_or_insert(e);
+        }
+    }
+    
+    fn render(&mut self, event: AgentEvent) -> Result<()> {
+        // The plan request isn't shown, unless the guardrail answers it instead
+        let quiet = self.planning && !self.prompt_blocked;
+        let ndjson = self.output_format == OutputFormat::Ndjson && !quiet;
+        match event {
+            AgentEvent::Request { model } => {
+                if ndjson {
+                    Event::MessageStart { session_id: self.session.id.to_string(), model: &model }.emit()?;
+                }
+                self.spinner = Some(Spinner::start(self.spinner_label, self.spinner_detail.clone()));
+            }
+            AgentEvent::Retry { attempt, max_attempts, delay_ms, reason } => {
+                let (line, short) = if retry::is_rate_limit(&reason) {
+                    (
+                        format!("⚠️  Rate limited (attempt {}/{}), retrying in {}ms...", attempt, max_attempts, delay_ms),
+                        format!("rate limited, retry {}/{}", attempt, max_attempts),
+                    )
+                } else {
+                    (
+                        format!(
+                            "⚠️  Retrying request (attempt {}/{}), retrying in {}ms... ({})",
+                            attempt, max_attempts, delay_ms, reason
+                        ),
+                        format!("retry {}/{}: {}", attempt, max_attempts, reason),
+                    )
+                };
+                match self.spinner_detail.lock() {
+                    Ok(mut detail) if self.spinner.is_some() && Spinner::enabled() => *detail = Some(short),
+                    _ => status!("{}", line),
+                }
+            }
+            AgentEvent::Fallback { from, to, error } => {
+                notice!("⚠️  {} failed: {}", from, error);
+                notice!("↪️  Falling back to {}", to);
+                self.report.model = to;
+            }
+            AgentEvent::Cached { model } => {
+                self.report.cached = true;
+                if ndjson {
+                    Event::Cached { model: &model }.emit()?;
+                } else {
+                    notice!("💾 Cached response (--no-cache to ask again)");
+                }
+            }
+            AgentEvent::ContextNearlyFull { model, tokens, context_window } => {
+                notice!(
+                    "⚠️  Warning: This request is ~{} tokens, {}% of the {}-token context window of {}.",
+                    tokens,
+                    tokens * 100 / context_window,
+                    context_window,
+                    model
+                );
+                eprintln!("   Consider using --new-session to start fresh (--dry-run shows what is being sent).");
+            }
+            AgentEvent::BudgetExceeded { reason } => {
+                notice!("⚠️  Budget exceeded: {} (continuing because of --force)", reason);
+            }
+            AgentEvent::Usage { model, usage } => {
+                self.session_usage.add(usage);
+                self.report.usage.add(usage);
+                if let Err(e) = UsageLedger::record(&self.session.id, &model, usage) {
+                    debug!("Failed to record usage: {}", e);
+                }
+                if self.show_usage {
+                    status!("\n{}", usage::format_summary(&model, usage, self.session_usage));
+                }
+                if ndjson {
+                    Event::Usage { usage }.emit()?;
+                }
+            }
+            AgentEvent::Thinking { text } => {
+                // The thinking goes to stderr, dimmed, so stdout stays the answer alone
+                if !self.hide_thinking {
+                    if self.output_format == OutputFormat::Text {
+                        if std::io::stderr().is_terminal() {
+                            eprintln!("{}\n", markdown::dim(&text));
+                        } else {
+                            eprintln!("{}\n", text);
+                        }
+                    }
+                    if ndjson {
+                        Event::Thinking { text: &text }.emit()?;
+                    }
+                    self.report.thinking = Some(match self.report.thinking.take() {
+                        Some(earlier) => format!("{}\n\n{}", earlier, text),
+                        None => text.clone(),
+                    });
+                }
+                // Reasoning before tool calls stays with the answer the run ends in
+                self.pending_thinking = Some(match self.pending_thinking.take() {
+                    Some((model, earlier)) => (model, format!("{}\n\n{}", earlier, text)),
+                    None => (self.report.model.clone(), text),
+                });
+            }
+            AgentEvent::Text { text } => {
+                // An extraction run's output is the extracted JSON alone
+                let show = !quiet && !self.extraction && self.print_text;
+                if show && self.output_format == OutputFormat::Text {
+                    let rendered = if self.render_markdown { markdown::render(&text) } else { text.clone() };
+                    if self.use_pager {
+                        pager::print(&rendered)?;
+                    } else if self.render_markdown {
+                        println!("{}", rendered);
+                    } else {
+                        print!("{}", rendered);
+                    }
+                    std::io::stdout().flush()?;
+                }
+                if ndjson {
+                    Event::ContentDelta { text: &text }.emit()?;
+                }
+                
+                // Steps the model reports done are checked off and noted in the session
+                if let Some(plan) = self.plan.as_mut() {
+                    for number in plan.check_off(&text) {
+                        let step = &plan.steps[number - 1].text;
+                        status!("\n☑️  Plan step {} done: {}", number, step);
+                        self.plan_notes.push(format!("Plan step {} done: {}", number, step));
+                    }
+                }
+                self.final_text = text.clone();
+                self.pending_text = text;
+            }
+            AgentEvent::Intervened { stage, policies } => {
+                guardrails::report_intervention(&stage, &policies);
+                if stage == "prompt" {
+                    self.prompt_blocked = true;
+                    if self.output_format == OutputFormat::Ndjson {
+                        Event::MessageStart { session_id: self.session.id.to_string(), model: &self.report.model }
+                            .emit()?;
+                    }
+                }
+            }
+            AgentEvent::ResponseEnd { stop_reason } => {
+                if ndjson {
+                    Event::MessageEnd { stop_reason: stop_reason.as_str() }.emit()?;
+                }
+                // A response with tool calls is recorded once they have run
+                if stop_reason != StopReason::ToolUse && !self.prompt_blocked {
+                    let text = std::mem::take(&mut self.pending_text);
+                    self.record_answer(text)?;
+                    self.record_plan_notes()?;
+                }
+            }
+            AgentEvent::Reminder { answer, reminder, .. } => {
+                // An empty answer was not recorded at the end of its response
+                if self.session.messages.last().is_none_or(|m| !matches!(m.role, MessageRole::Assistant)) {
+                    self.record_answer(answer)?;
+                }
+                self.session_manager.add_message(&mut self.session, ContextMessage::new(MessageRole::User, reminder))?;
+            }
+            AgentEvent::Extracted { id, name, value } => {
+                self.final_text = serde_json::to_string_pretty(&value)?;
+                if ndjson {
+                    Event::ToolCall { id: &id, name: &name, input: &value }.emit()?;
+                } else if self.print_text {
+                    println!("{}", self.final_text);
+                }
+                self.pending_text.clear();
+                self.record_answer(self.final_text.clone())?;
+            }
+            AgentEvent::ToolCall { id, name, input } => {
+                // Show tool execution info
+                if self.verbose {
+                    status!(
+                        "\n🔧 Executing tool: {} with params: {}",
+                        name,
+                        serde_json::to_string_pretty(&input).unwrap_or_else(|_| "<invalid json>".to_string())
+                    );
+                } else {
+                    status!("\n🔧 Executing tool: {} with params: {}", name, input);
+                }
+                if ndjson {
+                    Event::ToolCall { id: &id, name: &name, input: &input }.emit()?;
+                }
+                self.inputs.insert(id, input);
+            }
+            AgentEvent::ToolResult { id, name, content, is_error } => {
+                if ndjson {
+                    Event::ToolResult { id: &id, name: &name, result: &content, is_error }.emit()?;
+                }
+                let input = self.inputs.remove(&id).unwrap_or(Value::Null);
+                self.report.tool_calls.push(ToolCallReport { id, name, input, result: content, is_error });
+            }
+            AgentEvent::ToolTurnEnd { calls } => {
+                let text = std::mem::take(&mut self.pending_text);
+                self.record_answer(text)?;
+                
+                let tool_summary = format!("Tool execution results: {} tools executed", calls);
+                let summary_index = self.session.messages.len();
+                self.session_manager.add_message(&mut self.session, ContextMessage::new(MessageRole::System, tool_summary))?;
+                // The calls themselves are kept beside the session for `sessions share` and `replay`
+                if let Err(e) = self.tool_log.append(summary_index, &self.report.tool_calls[self.turn_start..]) {
+                    notice!("⚠️  Failed to record tool calls: {}", e);
+                }
+                self.turn_start = self.report.tool_calls.len();
+                self.record_plan_notes()?;
+            }
+        }
+        Ok(())
+    }
+    
+    // Add a response to the session, with the thinking behind it
+    fn record_answer(&mut self, text: String) -> Result<()> {
+        let thinking = self.pending_thinking.take();
+        if text.is_empty() {
+            return Ok(());
+        }
+        let answer_index = self.session.messages.len();
+        self.session_manager.add_message(&mut self.session, ContextMessage::new(MessageRole::Assistant, text))?;
+        if let Some((model, thinking)) = thinking {
+            if let Err(e) = ThinkingLog::open(&self.session.id).and_then(|log| log.append(answer_index, &model, &thinking)) {
+                notice!("⚠️  Failed to save thinking: {}", e);
+            }
+        }
+        Ok(())
+    }
+    
+    fn record_plan_notes(&mut self) -> Result<()> {
+        for note in self.plan_notes.drain(..) {
+            self.session_manager.add_message(&mut self.session, ContextMessage::new(MessageRole::System, note))?;
+        }
+        Ok(())
+    }
+    
+    /// Settle a run that returned (`Some`) or was interrupted with Ctrl-C (`None`).
+    /// Failed and interrupted runs save the session; only a finished run's
+    /// outcome is returned.
+    fn finish(&mut self, result: Option<Result<RunOutcome>>) -> Result<Option<RunOutcome>> {
+        if let Some(spinner) = self.spinner.take() {
+            spinner.stop();
+        }
+        if let Some(e) = self.error.take() {
+            self.session_manager.save_session(&self.session)?;
+            return Err(e);
+        }
+        match result {
+            None => {
+                let partial = std::mem::take(&mut self.pending_text);
+                save_interrupted(self.session_manager.as_mut(), &mut self.session, Some(&partial))?;
+                Ok(None)
+            }
+            Some(Ok(outcome)) => Ok(Some(outcome)),
+            Some(Err(e)) => {
+                self.session_manager.save_session(&self.session)?;
+                let id = self.session.id;
+                if e.is::<BudgetExceeded>() {
+                    anyhow::bail!("{}. Re-run with --force to continue.", e);
+                }
+                anyhow::bail!("{:#}. Session saved: {} (continue with --session {})", e, id, id);
+            }
+        }
+    }
+    
+    /// Drop a prompt the guardrail intervened on from the session, so it
+    /// isn't sent again, after the guardrail's answer has been shown
+    fn discard_prompt(mut self) -> Result<()> {
+        let prompt = self.session.messages.iter().rposition(|m| matches!(m.role, MessageRole::User));
+        if let Some(index) = prompt {
+            self.session.messages.remove(index);
+            self.tool_log.truncate(index)?;
+            ThinkingLog::open(&self.session.id)?.truncate(index)?;
+        }
+        self.session_manager.save_session(&self.session)?;
+        match self.output_format {
+            OutputFormat::Text if !self.print_text => {}
+            OutputFormat::Text => println!(),
+            OutputFormat::Json => {
+                self.report.text = self.final_text;
+                self.report.print()?;
+            }
+            OutputFormat::Ndjson => {}
+        }
+        Ok(())
+    }
 }
 
 // Helper function to convert gamecode-tools schemas to backend format
@@ -63,15 +463,110 @@ async fn main() -> Result<()> {
     
     let app = build_cli();
     
-    let args: Vec<String> = std::env::args().skip(1).collect();
+    // Logging and the profile are set up once for every command, before
+    // flag-rs parses the rest, so subcommands get -v, --log-file and --profile too.
+    // Each falls back to its environment variable (GAMECODE_VERBOSE=2 is -vv).
+    let (verbosity, args) = take_verbosity(std::env::args().skip(1).collect());
+    let verbosity = match settings::env_value("verbose") {
+        Some(level) if verbosity == 0 => level.parse()
+            .with_context(|| format!("Invalid {} value '{}'", settings::env_var("verbose"), level))?,
+        _ => verbosity,
+    };
+    let (log_file, args) = take_flag_value(args, "log-file");
+    let log_file = log_file.or_else(|| settings::env_value("log-file"));
+    let (profile, args) = take_flag_value(args, "profile");
+    if let Some(profile) = profile.or_else(|| settings::env_value("profile")) {
+        config::select_profile(profile);
+    }
+    let logging_config = Config::load().map(|c| c.logging).unwrap_or_default();
+    let log_guard = logging::init(verbosity, log_file.as_deref().map(std::path::Path::new), &logging_config);
+    
     if let Err(e) = app.execute(args) {
         eprintln!("Error?: {}", e);
+        // exit skips destructors; flush the log file first
+        drop(log_guard);
         std::process::exit(1);
     }
     
     Ok(())
 }
 
+/// Remove `-v`, `-vv`, `-vvv`, `--verbose` and `--verbose=<n>` from the
+/// arguments, returning the total verbosity
+fn take_verbosity(args: Vec<String>) -> (u8, Vec<String>) {
+    let mut level: usize = 0;
+    let mut rest = Vec::with_capacity(args.len());
+    let mut in_flags = true;
+    for arg in args {
+        if arg == "--" {
+            in_flags = false;
+        }
+        let count = match arg.as_str() {
+            _ if !in_flags => None,
+            "--verbose" => Some(1),
+            long if long.starts_with("--verbose=") => long["--verbose=".len()..].parse().ok(),
+            short if short.len() > 1 && short.starts_with('-') && short[1..].chars().all(|c| c == 'v') => {
+                Some(short.len() - 1)
+            }
+            _ => None,
+        };
+        match count {
+            Some(count) => level += count,
+            None => rest.push(arg),
+        }
+    }
+    (level.min(u8::MAX as usize) as u8, rest)
+}
+
+/// Remove `--<name> <value>` or `--<name>=<value>` from the arguments,
+/// returning the last value given
+fn take_flag_value(args: Vec<String>, name: &str) -> (Option<String>, Vec<String>) {
+    let flag = format!("--{}", name);
+    let prefix = format!("--{}=", name);
+    let mut value = None;
+    let mut rest = Vec::with_capacity(args.len());
+    let mut args = args.into_iter();
+    while let Some(arg) = args.next() {
+        if arg == "--" {
+            rest.push(arg);
+            rest.extend(args.by_ref());
+            break;
+        }
+        if arg == flag {
+            value = args.next();
+        } else if let Some(v) = arg.strip_prefix(&prefix) {
+            value = Some(v.to_string());
+        } else {
+            rest.push(arg);
+        }
+    }
+    (value, rest)
+}
+
+/// First line of a session's first user message, shortened for a completion description
+fn session_title(manager: &mut dyn SessionStore, id: &Uuid) -> Option<String> {
+    let session = manager.load_session(id).ok()?;
+    let first = session.messages.iter().find(|m| matches!(m.role, ContextMessageRole::User))?;
+    let line = first.content.lines().find(|line| !line.trim().is_empty())?.trim();
+    Some(match line.char_indices().nth(40) {
+        Some((end, _)) => format!("{}…", &line[..end]),
+        None => line.to_string(),
+    })
+}
+
+/// The most recently used session, like `load_latest`, but without creating
+/// one when there are none
+fn peek_latest(manager: &mut dyn SessionStore) -> Result<Session> {
+    let latest = manager
+        .list_sessions()?
+        .into_iter()
+        .max_by_key(|info| info.updated_at.unwrap_or(info.created_at));
+    match latest {
+        Some(info) => manager.load_session(&info.id),
+        None => Ok(Session::new()),
+    }
+}
+
 pub fn build_cli() -> Command {
     //let mut root = CommandBuilder::new("gamecode-cli")
     let mut root = CommandBuilder::new("gamecode")
@@ -81,19 +576,141 @@ pub fn build_cli() -> Command {
         // Global flags
         .flag(Flag::new("system-prompt")
             .short('s')
-            .usage("System prompt to use")
+            .usage("System prompt to use (a stored prompt name, or mcp:<server>/<prompt>)")
+            .value_type(FlagType::String))
+        
+        .flag(Flag::new("system-prompt-file")
+            .usage("Read the system prompt from a file")
             .value_type(FlagType::String))
         
+        .flag(Flag::new("system")
+            .usage("Use this literal string as the system prompt")
+            .value_type(FlagType::String))
+        
+        .flag(Flag::new("replace-system-prompt")
+            .usage("On a continued session, replace its stored system prompt")
+            .value_type(FlagType::Bool)
+            .default(FlagValue::Bool(false)))
+        
+        .flag(Flag::new("no-project-context")
+            .usage("Don't add GAMECODE.md / AGENTS.md to the system prompt of new sessions")
+            .value_type(FlagType::Bool)
+            .default(FlagValue::Bool(false)))
+        
+        .flag(Flag::new("var")
+            .usage("Value for a {{variable}} in the system prompt as KEY=VALUE (repeatable or comma-separated)")
+            .value_type(FlagType::StringSlice))
+        
+        .flag(Flag::new("prompt-arg")
+            .usage("Arguments for an MCP server prompt as KEY=VALUE (comma-separated)")
+            .value_type(FlagType::StringSlice))
+        
         .flag(Flag::new("model")
             .short('m')
             .usage("Model to use (e.g., opus-4, claude-3.7-sonnet)")
             .value_type(FlagType::String))
             
+        .flag(Flag::new("max-tokens")
+            .usage("Maximum number of tokens to generate per response")
+            .value_type(FlagType::Int))
+            
+        .flag(Flag::new("temperature")
+            .usage("Sampling temperature (0.0 - 1.0)")
+            .value_type(FlagType::Float))
+            
+        .flag(Flag::new("top-p")
+            .usage("Nucleus sampling probability mass (0.0 - 1.0)")
+            .value_type(FlagType::Float))
+            
+        .flag(Flag::new("stop")
+            .usage("Stop generating at this sequence; repeat for more (a sequence with a comma goes in [inference] stop_sequences)")
+            .value_type(FlagType::StringArray))
+            
         .flag(Flag::new("region")
             .short('r')
-            .usage("AWS region")
+            .usage("AWS region (default: region from config, else us-west-2)")
+            .value_type(FlagType::String))
+            
+        .flag(Flag::new("aws-profile")
+            .usage("AWS profile to use for credentials")
+            .value_type(FlagType::String))
+            
+        .flag(Flag::new("assume-role")
+            .usage("IAM role ARN to assume before calling Bedrock")
+            .value_type(FlagType::String))
+            
+        .flag(Flag::new("guardrail-id")
+            .usage("Bedrock guardrail ID or ARN to apply to prompts and responses")
+            .value_type(FlagType::String))
+            
+        .flag(Flag::new("guardrail-version")
+            .usage("Guardrail version (default: DRAFT)")
+            .value_type(FlagType::String))
+            
+        .flag(Flag::new("no-redact")
+            .usage("Send prompts, files and tool results without masking secrets")
+            .value_type(FlagType::Bool)
+            .default(FlagValue::Bool(false)))
+            
+        .flag(Flag::new("show-usage")
+            .usage("Print token usage and estimated cost after each request")
+            .value_type(FlagType::Bool)
+            .default(FlagValue::Bool(false)))
+            
+        .flag(Flag::new("force")
+            .usage("Continue even when a configured token or cost budget is exceeded")
+            .value_type(FlagType::Bool)
+            .default(FlagValue::Bool(false)))
+            
+        .flag(Flag::new("file")
+            .usage("Attach a text file to the prompt (repeatable or comma-separated)")
+            .value_type(FlagType::StringSlice))
+            
+        .flag(Flag::new("image")
+            .usage("Attach a PNG or JPEG image to the prompt (repeatable or comma-separated)")
+            .value_type(FlagType::StringSlice))
+            
+        .flag(Flag::new("url")
+            .usage("Fetch a web page and attach it to the prompt as text (repeatable or comma-separated)")
+            .value_type(FlagType::StringSlice))
+            
+        .flag(Flag::new("output-format")
+            .usage("Output format: text, json or ndjson")
             .value_type(FlagType::String)
-            .default(FlagValue::String("us-west-2".to_string())))
+            .default(FlagValue::String("text".to_string())))
+            
+        .flag(Flag::new("output")
+            .short('o')
+            .usage("Write the final response to this file instead of stdout")
+            .value_type(FlagType::String))
+            
+        .flag(Flag::new("tee")
+            .usage("With --output, also print the response to stdout")
+            .value_type(FlagType::Bool)
+            .default(FlagValue::Bool(false)))
+            
+        .flag(Flag::new("plain")
+            .usage("Print responses as raw markdown instead of styled terminal output")
+            .value_type(FlagType::Bool)
+            .default(FlagValue::Bool(false)))
+            
+        .flag(Flag::new("no-pager")
+            .usage("Never pipe long responses through $PAGER")
+            .value_type(FlagType::Bool)
+            .default(FlagValue::Bool(false)))
+            
+        .flag(Flag::new("notify")
+            .usage("Send a desktop notification when the run finishes or needs approval")
+            .value_type(FlagType::Bool)
+            .default(FlagValue::Bool(false)))
+            
+        .flag(Flag::new("stdin-as")
+            .usage("Label for piped stdin attached to the prompt (default: stdin)")
+            .value_type(FlagType::String))
+            
+        .flag(Flag::new("timeout")
+            .usage("Maximum seconds to wait for each backend request and each tool call")
+            .value_type(FlagType::Int))
             
         .flag(Flag::new("session")
             .usage("Session ID to continue")
@@ -104,9 +721,28 @@ pub fn build_cli() -> Command {
             .value_type(FlagType::Bool)
             .default(FlagValue::Bool(false)))
             
+        .flag(Flag::new("color")
+            .usage("When to use colors and emoji: auto, always or never (NO_COLOR is honored)")
+            .value_type(FlagType::String)
+            .default(FlagValue::String("auto".to_string())))
+            
+        .flag(Flag::new("profile")
+            .usage("Apply a named profile of settings from config (see gamecode config profiles list)")
+            .value_type(FlagType::String))
+            
+        .flag(Flag::new("log-file")
+            .usage("Write JSON logs to this file, rotated daily (default: ~/.local/share/gamecode/logs/gamecode.log)")
+            .value_type(FlagType::String))
+            
+        .flag(Flag::new("quiet")
+            .short('q')
+            .usage("Suppress progress messages on stderr (warnings and errors are still shown)")
+            .value_type(FlagType::Bool)
+            .default(FlagValue::Bool(false)))
+            
         .flag(Flag::new("verbose")
             .short('v')
-            .usage("Enable verbose output")
+            .usage("More output: -v status and usage, -vv debug logs and full tool params/results, -vvv raw Bedrock payloads")
             .value_type(FlagType::Bool)
             .default(FlagValue::Bool(false)))
             
@@ -115,77 +751,193 @@ pub fn build_cli() -> Command {
             .value_type(FlagType::Bool)
             .default(FlagValue::Bool(false)))
             
+        .flag(Flag::new("tools")
+            .usage("Only offer these tools to the model (comma-separated, * wildcards)")
+            .value_type(FlagType::StringSlice))
+            
+        .flag(Flag::new("deny-tools")
+            .usage("Never offer these tools to the model (comma-separated, * wildcards)")
+            .value_type(FlagType::StringSlice))
+            
+        .flag(Flag::new("read-only")
+            .usage("Only offer tools that cannot write files or execute commands")
+            .value_type(FlagType::Bool)
+            .default(FlagValue::Bool(false)))
+            
+        .flag(Flag::new("yes")
+            .short('y')
+            .usage("Run tool calls without asking for approval")
+            .value_type(FlagType::Bool)
+            .default(FlagValue::Bool(false)))
+            
         .flag(Flag::new("max-retries")
-            .usage("Maximum number of retry attempts")
-            .value_type(FlagType::Int)
-            .default(FlagValue::Int(20)))
+            .usage("Maximum number of retry attempts (default: 20)")
+            .value_type(FlagType::Int))
             
         .flag(Flag::new("initial-retry-delay-ms")
-            .usage("Initial retry delay in milliseconds")
+            .usage("Initial retry delay in milliseconds (default: 500)")
+            .value_type(FlagType::Int))
+            
+        .flag(Flag::new("backoff")
+            .usage("How retry delays grow: exponential, linear or fixed (default: exponential)")
+            .value_type(FlagType::String))
+            
+        .flag(Flag::new("backoff-multiplier")
+            .usage("Growth factor for exponential backoff (default: 3)")
+            .value_type(FlagType::String))
+            
+        .flag(Flag::new("max-retry-delay-ms")
+            .usage("Upper bound on a single retry delay in milliseconds (default: 60000)")
+            .value_type(FlagType::Int))
+            
+        .flag(Flag::new("retry-jitter")
+            .usage("Randomize each retry delay by up to this fraction, 0.0-1.0 (default: 0)")
+            .value_type(FlagType::String))
+            
+        .flag(Flag::new("cache")
+            .usage("Answer identical requests from the local response cache")
+            .value_type(FlagType::Bool)
+            .default(FlagValue::Bool(false)))
+            
+        .flag(Flag::new("no-cache")
+            .usage("Bypass the response cache even if enabled in config")
+            .value_type(FlagType::Bool)
+            .default(FlagValue::Bool(false)))
+            
+        .flag(Flag::new("prompt-cache")
+            .usage("Let Bedrock cache the system prompt and tool definitions between requests")
+            .value_type(FlagType::Bool)
+            .default(FlagValue::Bool(false)))
+            
+        .flag(Flag::new("dry-run")
+            .usage("Print the request that would be sent, with a token estimate, and exit without calling Bedrock")
+            .value_type(FlagType::Bool)
+            .default(FlagValue::Bool(false)))
+            
+        .flag(Flag::new("plan")
+            .usage("Ask for a numbered plan first and review it before anything runs")
+            .value_type(FlagType::Bool)
+            .default(FlagValue::Bool(false)))
+            
+        .flag(Flag::new("thinking")
+            .usage("Enable extended thinking (Claude 3.7 Sonnet and Claude 4)")
+            .value_type(FlagType::Bool)
+            .default(FlagValue::Bool(false)))
+            
+        .flag(Flag::new("thinking-budget")
+            .usage("Tokens the model may spend thinking with --thinking (default: 4096, at least 1024)")
+            .value_type(FlagType::Int))
+            
+        .flag(Flag::new("hide-thinking")
+            .usage("Don't show the model's thinking (it is still saved with the session)")
+            .value_type(FlagType::Bool)
+            .default(FlagValue::Bool(false)))
+            
+        .flag(Flag::new("force-tool")
+            .usage("Make the model call this tool first (name with or without its server prefix)")
+            .value_type(FlagType::String))
+            
+        .flag(Flag::new("json-schema")
+            .usage("Answer with JSON matching the schema in this file, printed as the output")
+            .value_type(FlagType::String))
+            
+        .flag(Flag::new("max-tool-turns")
+            .usage("Stop after this many consecutive model turns that request tools")
+            .value_type(FlagType::Int)
+            .default(FlagValue::Int(25)))
+            
+        .flag(Flag::new("max-tool-result-bytes")
+            .usage("Truncate tool results larger than this many bytes before sending them to the model")
+            .value_type(FlagType::Int))
+            
+        .flag(Flag::new("tool-concurrency")
+            .usage("Maximum number of tool calls from one turn to run concurrently")
+            .value_type(FlagType::Int)
+            .default(FlagValue::Int(4)))
+            
+        .flag(Flag::new("daemon")
+            .usage("Route the prompt through a running gamecode daemon")
+            .value_type(FlagType::Bool)
+            .default(FlagValue::Bool(false)))
+            
+        .flag(Flag::new("max-tool-failures")
+            .usage("Abort after this many consecutive failed tool calls")
             .value_type(FlagType::Int)
-            .default(FlagValue::Int(500)))
+            .default(FlagValue::Int(3)))
         
-        // Dynamic completions for system-prompt
+        // Dynamic completions for system-prompt. Completion output is read by
+        // the shell, so errors just mean no candidates
         .flag_completion("system-prompt", |_ctx, prefix| {
-            match PromptManager::new() {
-                Ok(manager) => match manager.list_prompts() {
-                    Ok(prompts) => {
-                        let mut result = CompletionResult::new();
-                        for prompt_name in prompts {
-                            if prompt_name.starts_with(prefix) {
-                                result = result.add(prompt_name);
-                            }
-                        }
-                        Ok(result)
-                    }
-                    Err(e) => panic!("Failed to list prompts: {}", e),
-                },
-                Err(e) => panic!("Failed to create prompt manager: {}", e),
+            let mut result = CompletionResult::new();
+            let prompts = PromptManager::new().ok().and_then(|manager| manager.list_prompts().ok());
+            for prompt_name in prompts.unwrap_or_default() {
+                if prompt_name.starts_with(prefix) {
+                    result = result.add(prompt_name);
+                }
             }
+            Ok(result)
         })
         
-        // Dynamic completions for session
+        // Dynamic completions for session, described by date, size and first prompt
         .flag_completion("session", |_ctx, prefix| {
-            match SessionManager::new() {
-                Ok(manager) => match manager.list_sessions() {
-                    Ok(sessions) => {
-                        let mut result = CompletionResult::new();
-                        let total = sessions.len();
-                        for session_info in sessions {
-                            let id_str = session_info.id.to_string();
-                            if id_str.starts_with(prefix) {
-                                // Debug: log exactly what we're adding
-                                eprintln!("DEBUG: Adding session ID: '{}'", id_str);
-                                eprintln!("DEBUG: ID bytes: {:?}", id_str.as_bytes());
-                                eprintln!("DEBUG: ID len: {}", id_str.len());
-                                
-                                result = result.add(id_str);
-                            }
-                        }
-                        eprintln!("DEBUG: Total sessions: {}", total);
-                        Ok(result)
-                    }
-                    Err(e) => panic!("Failed to list sessions: {}", e),
-                },
-                Err(e) => panic!("Failed to create session manager: {}", e),
+            let mut result = CompletionResult::new();
+            let Ok(mut manager) = session_store::open_configured() else {
+                return Ok(result);
+            };
+            for session_info in manager.list_sessions().unwrap_or_default() {
+                let id_str = session_info.id.to_string();
+                if !id_str.starts_with(prefix) {
+                    continue;
+                }
+                let created = chrono::DateTime::<chrono::Utc>::from(session_info.created_at)
+                    .format("%Y-%m-%d %H:%M");
+                let mut description = format!("{} - {} messages", created, session_info.message_count);
+                if let Some(title) = session_title(manager.as_mut(), &session_info.id) {
+                    description.push_str(" - ");
+                    description.push_str(&title);
+                }
+                result = result.add_with_description(id_str, description);
             }
+            Ok(result)
         })
         
+        // Dynamic completions for profile
+        .flag_completion("profile", |_ctx, prefix| {
+            let mut result = CompletionResult::new();
+            let profiles = Config::load_global().map(|config| config.profiles).unwrap_or_default();
+            for name in profiles.keys() {
+                if name.starts_with(prefix) {
+                    result = result.add(name.clone());
+                }
+            }
+            Ok(result)
+        })
+
         // Dynamic completions for model
-        .flag_completion("model", |_ctx, prefix| {
-            let models = vec![
-                "opus-4",
-                "claude-3.7-sonnet",
-                "claude-3.5-sonnet",
-                "claude-3.5-haiku",
-                "claude-3-sonnet",
-                "claude-3-haiku",
-            ];
-            
+        .flag_completion("model", |ctx, prefix| {
             let mut result = CompletionResult::new();
-            for model in models {
-                if model.starts_with(prefix) {
-                    result = result.add(model);
+            for model in models::KNOWN_MODELS {
+                if model.alias.starts_with(prefix) {
+                    result = result.add_with_description(model.alias, model.description);
+                }
+            }
+            
+            // User-defined aliases
+            if let Ok(config) = Config::load() {
+                for (alias, id) in config.model_aliases {
+                    if alias.starts_with(prefix) {
+                        result = result.add_with_description(alias, id);
+                    }
+                }
+            }
+            
+            // Models discovered with `gamecode models list --refresh`
+            let region = ctx.flag("region").map(|s| s.as_str()).unwrap_or("us-west-2");
+            if let Some(catalog) = model_discovery::ModelCatalog::load_cached(region) {
+                for model in catalog.models {
+                    if model.id.starts_with(prefix) {
+                        result = result.add_with_description(model.id, model.name);
+                    }
                 }
             }
             Ok(result)
@@ -197,7 +949,10 @@ pub fn build_cli() -> Command {
             tokio::task::block_in_place(|| {
                 tokio::runtime::Handle::current().block_on(async {
                     run_main_command(ctx).await
-                        .map_err(|e| flag_rs::Error::Custom(e.to_string().into()))
+                        .map_err(|e| {
+                            tracing::error!(target: logging::EVENT_TARGET, error = %format!("{:#}", e), "command failed");
+                            flag_rs::Error::Custom(e.to_string().into())
+                        })
                 })
             })
         })
@@ -210,6 +965,35 @@ pub fn build_cli() -> Command {
     root
 }
 
+/// Send a prompt to a running daemon and stream its answer to stdout.
+/// Returns false if no daemon is listening.
+async fn run_via_daemon(
+    prompt: String,
+    session_id: Option<Uuid>,
+    new_session: bool,
+    model: Option<String>,
+) -> Result<bool> {
+    let Some(mut client) = daemon::DaemonClient::connect().await? else {
+        return Ok(false);
+    };
+    let request = daemon::DaemonRequest::Run { prompt, session_id, new_session, model };
+    let reply = client
+        .request(&request, |reply| {
+            let Some(event) = reply.get("event") else { return };
+            match event.get("type").and_then(Value::as_str) {
+                Some("text") => println!("{}", event.get("text").and_then(Value::as_str).unwrap_or("")),
+                Some("tool_call") => status!(
+                    "\n🔧 Executing tool: {}",
+                    event.get("name").and_then(Value::as_str).unwrap_or("?")
+                ),
+                _ => {}
+            }
+        })
+        .await?;
+    debug!("Daemon finished: {}", reply);
+    Ok(true)
+}
+
 async fn run_main_command(ctx: &Context) -> Result<()> {
     // Extract prompt from remaining arguments
     let mut prompt_parts = ctx.args();
@@ -220,81 +1004,322 @@ async fn run_main_command(ctx: &Context) -> Result<()> {
         return Err(anyhow::anyhow!("Prompt is required when not using a subcommand"));
     }
     
+    // Flags fall back to GAMECODE_* environment variables, then to config
+    let flags = Flags::new(ctx);
+    
+    // Piped input (e.g. `git diff | gamecode "summarize this change"`) becomes context
+    let stdin_label = flags.value("stdin-as").unwrap_or_else(|| "stdin".to_string());
+    let piped_input = if std::io::stdin().is_terminal() {
+        None
+    } else {
+        let mut input = String::new();
+        std::io::stdin()
+            .read_to_string(&mut input)
+            .context("Failed to read piped stdin")?;
+        Some(input).filter(|s| !s.trim().is_empty())
+    };
+    
+    // Read --file attachments up front so a bad path fails before any request
+    let file_attachments = flags.value("file")
+        .map(|raw| {
+            raw.split(',')
+                .map(str::trim)
+                .filter(|p| !p.is_empty())
+                .map(|p| attachments::attach_file(std::path::Path::new(p)))
+                .collect::<Result<Vec<_>>>()
+        })
+        .transpose()?
+        .unwrap_or_default();
+    let images = flags.value("image")
+        .map(|raw| {
+            raw.split(',')
+                .map(str::trim)
+                .filter(|p| !p.is_empty())
+                .map(|p| attachments::attach_image(std::path::Path::new(p)))
+                .collect::<Result<Vec<_>>>()
+        })
+        .transpose()?
+        .unwrap_or_default();
+    let urls: Vec<String> = flags.value("url")
+        .map(|raw| {
+            raw.split(',')
+                .map(str::trim)
+                .filter(|u| !u.is_empty())
+                .map(str::to_string)
+                .collect()
+        })
+        .unwrap_or_default();
+    
     // Extract flags
-    let verbose = ctx.flag("verbose").is_some();
-        
-    let new_session = ctx.flag("new-session").is_some();
-        
-    let no_tools = ctx.flag("no-tools").is_some();
-        
-    let max_retries = ctx.flag("max-retries")
-        .and_then(|s| s.parse::<usize>().ok())
-        .unwrap_or(20);
+    let verbosity = logging::verbosity();
+    let verbose = verbosity >= 2;
+    status::set_quiet(flags.enabled("quiet")?);
+    if let Some(choice) = flags.value("color") {
+        status::set_color(choice.parse()?);
+    }
         
-    let initial_retry_delay_ms = ctx.flag("initial-retry-delay-ms")
-        .and_then(|s| s.parse::<u64>().ok())
-        .unwrap_or(500);
+    let new_session = flags.enabled("new-session")?;
     
-    let region = ctx.flag("region")
-        .map(|s| s.as_str())
-        .unwrap_or("us-west-2");
-        
-    let model = ctx.flag("model").map(|s| s.as_str());
     let system_prompt_name = ctx.flag("system-prompt").map(|s| s.as_str());
-    let session_id_str = ctx.flag("session").map(|s| s.as_str());
+    let system_prompt_file = ctx.flag("system-prompt-file");
+    let system_literal = ctx.flag("system");
+    let system_sources = [system_prompt_name.is_some(), system_prompt_file.is_some(), system_literal.is_some()];
+    if system_sources.iter().filter(|set| **set).count() > 1 {
+        anyhow::bail!("Use only one of --system-prompt, --system-prompt-file and --system");
+    }
+    let session_id_str = flags.value("session");
+    let session_id_str = session_id_str.as_deref();
+    let prompt_args = parse_key_values("prompt-arg", flags.value("prompt-arg").as_ref())?;
+    let prompt_vars = parse_key_values("var", flags.value("var").as_ref())?;
+    let dry_run = flags.enabled("dry-run")?;
+    
+    if flags.enabled("daemon")? && !dry_run {
+        // The daemon runs with its own system prompt and tools, so only plain prompts are routed
+        if file_attachments.is_empty()
+            && images.is_empty()
+            && urls.is_empty()
+            && system_sources.iter().all(|set| !set)
+            && prompt_args.is_empty()
+            && prompt_vars.is_empty()
+        {
+            let mut prompt = prompt_parts.join(" ");
+            if let Some(piped) = &piped_input {
+                prompt = format!("{}\n\n{}", prompt, attachments::fenced_block(&stdin_label, "", piped));
+            }
+            let session_id = session_id_str
+                .map(|s| Uuid::parse_str(s).with_context(|| format!("Invalid session ID: {}", s)))
+                .transpose()?;
+            if run_via_daemon(prompt, session_id, new_session, flags.value("model")).await? {
+                return Ok(());
+            }
+            notice!("⚠️  No gamecode daemon is running; continuing without it");
+        } else {
+            notice!("⚠️  --daemon ignores prompts with files, images, URLs, system prompts or template variables; continuing without it");
+        }
+    }
+    
+    let config = Config::load()?;
+    let Settings {
+        region,
+        model: model_name,
+        auto_approve,
+        no_tools,
+        read_only,
+        allow_tools,
+        deny_tools,
+        inference: inference_flags,
+        timeout: request_timeout,
+        max_tool_turns,
+        max_tool_failures,
+        tool_concurrency,
+        max_tool_result_bytes,
+        retry: mut retry_policy,
+        output_format,
+        use_cache,
+        prompt_cache,
+    } = Settings::resolve(&flags, &config)?;
+    
+    // Runs from the main command retry harder than the built-in policy
+    // unless config or --max-retries says otherwise
+    if config.retry.max_retries.is_none() && flags.value("max-retries").is_none() {
+        retry_policy.max_retries = 20;
+    }
+    
+    // With no system prompt option, GAMECODE_SYSTEM_PROMPT or a prompt named
+    // in config (or .gamecode.toml) applies
+    let default_system_prompt = settings::env_value("system-prompt").or_else(|| config.system_prompt.clone());
+    let system_prompt_name = match system_prompt_name {
+        Some(name) => Some(name),
+        None if system_sources.iter().all(|set| !set) => default_system_prompt.as_deref(),
+        None => None,
+    };
+    
+    let region = region.as_str();
+    let notify = flags.enabled("notify")?;
+    
+    let render_markdown = output_format == OutputFormat::Text
+        && status::use_color(std::io::stdout().is_terminal())
+        && !flags.enabled("plain")?;
+    let use_pager = output_format == OutputFormat::Text
+        && std::io::stdout().is_terminal()
+        && !flags.enabled("no-pager")?;
+    let output_path = flags.value("output");
+    let tee = flags.enabled("tee")?;
+    
+    let force = flags.enabled("force")?;
+    let plan_mode = flags.enabled("plan")?;
+    let force_tool = flags.value("force-tool");
+    let json_schema = flags.value("json-schema");
+    if force_tool.is_some() && json_schema.is_some() {
+        anyhow::bail!("--force-tool and --json-schema can't be combined");
+    }
+    let thinking_budget = match flags.enabled("thinking")? {
+        true => Some(flags.parse("thinking-budget")?.unwrap_or(thinking::DEFAULT_BUDGET)),
+        false => None,
+    };
+    let hide_thinking = flags.enabled("hide-thinking")?;
+    // Anthropic doesn't let a thinking model be made to call a particular tool
+    if thinking_budget.is_some() && (force_tool.is_some() || json_schema.is_some()) {
+        anyhow::bail!("--thinking can't be combined with --force-tool or --json-schema, which force a tool call");
+    }
+    let show_usage = flags.enabled("show-usage")? || verbosity >= 1;
+    
+    let tool_filter = ToolFilter::new(allow_tools, deny_tools)
+        .read_only(read_only, config.tool_capabilities.clone());
+    if read_only {
+        status!("🔒 Read-only mode: tools that write or execute are disabled");
+    }
     
-    // Setup logging
-    let log_level = if verbose {
-        tracing::Level::DEBUG
+    let aws_auth = AwsAuth::new(
+        flags.value("aws-profile"),
+        flags.value("assume-role"),
+    );
+    let sdk_config = aws_auth.sdk_config(region).await?;
+    
+    // Flags override the configured guardrail
+    let guardrail_config = match flags.value("guardrail-id") {
+        Some(id) => Some(GuardrailConfig {
+            id,
+            version: flags.value("guardrail-version").unwrap_or_else(|| "DRAFT".to_string()),
+        }),
+        None => config.guardrail.clone().map(|mut g| {
+            if let Some(version) = flags.value("guardrail-version") {
+                g.version = version;
+            }
+            g
+        }),
+    };
+    let guardrail = match guardrail_config {
+        Some(g) => Some(Arc::new(Guardrail::from_config(&sdk_config, g))),
+        None => None,
+    };
+    
+    let redactor = if flags.enabled("no-redact")? {
+        None
     } else {
-        tracing::Level::INFO
+        Redactor::from_config(&config.redaction)?.map(Arc::new)
     };
-    tracing_subscriber::fmt().with_max_level(log_level).init();
     
     // Create backend with region
     debug!("Using AWS region: {}", region);
-    let backend = create_backend(region).await?;
+    let backend: Arc<dyn LLMBackend> = Arc::from(create_backend_with_config(&sdk_config));
+    
+    let model = model_name.as_deref();
     
     // Map model name and use default if none specified
-    let selected_model = model
-        .map(|m| map_model_name(m))
-        .unwrap_or_else(|| "us.anthropic.claude-3-7-sonnet-20250219-v1:0".to_string());
+    let selected_model = map_model_name(model.unwrap_or(DEFAULT_MODEL), &config, region);
     debug!("Using model: {}", selected_model);
+    if let Some(alternatives) = models::unavailable_in(&selected_model, region) {
+        notice!("⚠️  {} does not appear to be available in {}", selected_model, region);
+        if !alternatives.is_empty() {
+            let aliases: Vec<&str> = alternatives.iter().take(5).map(|info| info.alias).collect();
+            notice!("   Models available there include: {}", aliases.join(", "));
+        }
+    }
+    
+    let mut inference_settings = inference::resolve(inference_flags, &config.inference, model, &selected_model);
     
-    // Detect cross-region models
-    let uses_cross_region_model = selected_model.starts_with("us.");
+    // Never ask for more output than the model supports
+    if let Some(limit) = inference::output_limit(&config.model_limits, model, &selected_model) {
+        if let Some(max_tokens) = inference_settings.clamp_max_tokens(limit) {
+            notice!(
+                "⚠️  max_tokens {} exceeds the limit of {} for {}; using {}",
+                max_tokens, limit, selected_model, limit
+            );
+        }
+    }
+    debug!("Inference settings: {:?}", inference_settings);
+    
+    let family = ModelFamily::from_model_id(&selected_model);
+    debug!("Model family: {:?}", family);
+    let no_tools = if !no_tools && !models::supports_tools(&selected_model) {
+        status!("ℹ️  {} does not support tool use; running without tools", selected_model);
+        true
+    } else {
+        no_tools
+    };
+    
+    if !images.is_empty() && !models::supports_images(&selected_model) {
+        anyhow::bail!("{} does not accept images (`gamecode models` tags the models that do)", selected_model);
+    }
+    
+    // The backend's requests can't carry thinking, stop sequences, cache
+    // points, images or a tool choice, so runs that use them talk to the
+    // Converse API directly
+    let prompt_cache = prompt_cache && models::supports_prompt_cache(&selected_model);
+    let converse_client = match thinking_budget {
+        Some(budget) => {
+            if !models::supports_thinking(&selected_model) {
+                anyhow::bail!("{} does not support extended thinking; use Claude 3.7 Sonnet or a Claude 4 model", selected_model);
+            }
+            if inference_settings.temperature.is_some() || inference_settings.top_p.is_some() {
+                notice!("⚠️  --temperature and --top-p are ignored with --thinking");
+            }
+            thinking::max_tokens(budget, inference_settings.max_tokens)?;
+            Some(ConverseClient::from_config(&sdk_config).thinking(budget)?)
+        }
+        None if inference_settings.stop_sequences.is_some()
+            || prompt_cache
+            || !images.is_empty()
+            || force_tool.is_some()
+            || json_schema.is_some() =>
+        {
+            Some(ConverseClient::from_config(&sdk_config))
+        }
+        None => None,
+    }
+    .map(|client| client.cache_points(prompt_cache));
+    let agent_thinks = converse_client.as_ref().is_some_and(ConverseClient::thinks);
     
     // Setup tools - always use MCP
-    let backend_tools: Vec<BackendTool>;
+    let mut backend_tools: Vec<BackendTool>;
+    // Qualified names of tools that can modify files, for diff previews
+    let mut write_tools: HashSet<String> = HashSet::new();
     let mcp_dispatcher: Option<Arc<McpToolDispatcher>>;
     
-    if no_tools {
-        eprintln!("ℹ️  Running without tools (--no-tools flag)");
+    if no_tools && (force_tool.is_some() || json_schema.is_some()) {
+        anyhow::bail!("--force-tool and --json-schema need tool use; drop --no-tools or choose a model that supports tools");
+    }
+    
+    if no_tools || json_schema.is_some() {
+        // An extraction run offers only its own tool
+        if no_tools {
+            status!("ℹ️  Running without tools (--no-tools flag)");
+        }
         backend_tools = Vec::new();
         mcp_dispatcher = None;
     } else {
-        eprintln!("🔌 Using MCP servers for tools");
+        status!("🔌 Using MCP servers for tools");
         
         // Try to create MCP dispatcher and get tools
         match McpToolDispatcher::new().await {
             Ok(dispatcher) => {
                 let registry = dispatcher.get_registry().await;
                 let registry_lock = registry.lock().await;
-                backend_tools = registry_lock.to_bedrock_tools();
+                backend_tools = registry_lock.to_bedrock_tools_filtered(&tool_filter);
+                write_tools = registry_lock.entries()
+                    .into_iter()
+                    .filter(|(server, tool)| {
+                        tool_capabilities::classify(server, tool, &config.tool_capabilities)
+                            == ToolCapability::Write
+                    })
+                    .map(|(server, tool)| format!("{}_{}", server, tool.name))
+                    .collect();
                 
                 if backend_tools.is_empty() {
-                    eprintln!("⚠️  Warning: No tools available from MCP servers");
+                    notice!("⚠️  Warning: No tools available from MCP servers");
                     eprintln!("   Configure servers with: gamecode mcp add <name> <command>");
                     eprintln!("   Example: gamecode mcp add gamecode-mcp2 /path/to/gamecode-mcp2");
                 } else {
-                    eprintln!("   {} tools available from MCP servers", backend_tools.len());
+                    status!("   {} tools available from MCP servers", backend_tools.len());
                 }
                 
                 drop(registry_lock);
+                dispatcher.enable_sampling(backend.clone(), selected_model.clone()).await;
                 mcp_dispatcher = Some(Arc::new(dispatcher));
             }
             Err(e) => {
-                eprintln!("⚠️  Warning: Failed to initialize MCP: {}", e);
+                notice!("⚠️  Warning: Failed to initialize MCP: {}", e);
                 eprintln!("   Continuing without tools. To enable tools:");
                 eprintln!("   1. Install MCP servers (e.g., cargo install --path ../gamecode-mcp2)");
                 eprintln!("   2. Configure servers with: gamecode mcp add <name> <command>");
@@ -305,14 +1330,36 @@ async fn run_main_command(ctx: &Context) -> Result<()> {
         }
     }
     
+    if !no_tools {
+        backend_tools.extend(tool_filter.builtin_tools(&config));
+    }
+    
+    // --json-schema replaces the tools with one whose input is the answer;
+    // --force-tool narrows requests to one tool until the model calls it
+    let forced = match (&json_schema, &force_tool) {
+        (Some(path), _) => {
+            let (forced, tool) = ForcedTool::extraction(std::path::Path::new(path))?;
+            backend_tools = vec![tool];
+            Some(forced)
+        }
+        (None, Some(name)) => Some(ForcedTool::resolve(name, &backend_tools)?),
+        (None, None) => None,
+    };
+    let extraction = forced.as_ref().is_some_and(ForcedTool::is_extraction);
+    
+    for tool in &mut backend_tools {
+        family.sanitize_tool_schema(&mut tool.input_schema);
+    }
+    
     // Setup session management
-    let mut session_manager = SessionManager::new()
-        .context("Failed to create session manager")?;
+    let mut session_manager = session_store::open(&config)
+        .context("Failed to open session storage")?;
     
-    // Load or create session based on arguments
+    // Load or create session based on arguments. --dry-run leaves the store
+    // untouched, so its new sessions exist only in memory
     let mut session = if new_session {
         debug!("Creating new session");
-        session_manager.new_session()?
+        if dry_run { Session::new() } else { session_manager.new_session()? }
     } else if let Some(session_id_str) = session_id_str {
         debug!("Loading session: {}", session_id_str);
         let session_id = Uuid::parse_str(session_id_str)
@@ -322,29 +1369,79 @@ async fn run_main_command(ctx: &Context) -> Result<()> {
             .with_context(|| format!("Failed to load session: {}", session_id))?
     } else {
         debug!("Loading latest session");
-        session_manager.load_latest()?
+        if dry_run { peek_latest(session_manager.as_mut())? } else { session_manager.load_latest()? }
     };
     
     debug!("Using session: {}", session.id);
     
-    // Load system prompt if this is a new session (no messages yet)
-    if session.messages.is_empty() {
+    let backups = SessionBackups::open(&session.id)?;
+    let tool_log = ToolLog::open(&session.id)?;
+    
+    let sub_agent = SubAgent::for_parent(Parent {
+        backend: backend.clone(),
+        model: &selected_model,
+        config: &config,
+        region,
+        tools: &backend_tools,
+        dispatcher: mcp_dispatcher.clone(),
+        retry: retry_policy.clone(),
+        approve_all: auto_approve,
+        write_tools: write_tools.clone(),
+        session_id: session.id,
+        redactor: redactor.clone(),
+    });
+    
+    // Running token totals for this session, including earlier runs
+    let usage_records = UsageLedger::load()?;
+    let mut session_usage = TokenUsage::default();
+    for record in usage_records.iter().filter(|r| r.session_id == session.id) {
+        session_usage.add(record.usage);
+    }
+    let budget = Budget::from_records(config.budget.clone(), &usage_records, &session.id).force(force);
+    
+    // A continued session keeps its system prompt unless asked to replace it
+    let explicit_system_prompt = system_sources.iter().any(|set| *set);
+    let replace_system_prompt = flags.enabled("replace-system-prompt")?;
+    let is_new_session = session.messages.is_empty();
+    if !is_new_session && explicit_system_prompt && !replace_system_prompt {
+        notice!("⚠️  Ignoring the system prompt option: session {} already has one", session.id);
+        notice!("   Use --replace-system-prompt to swap it for the rest of the conversation");
+    }
+    
+    // Load system prompt if this is a new session (no messages yet) or it is being replaced
+    if is_new_session || replace_system_prompt {
         let prompt_manager = PromptManager::new()
             .context("Failed to create prompt manager")?;
             
-        let system_prompt = if let Some(prompt_name) = system_prompt_name {
-            prompt_manager
+        let load_include = |name: &str| -> Result<String> { Ok(prompt_manager.load_prompt(name)?) };
+        let render_prompt = |template: &str| -> Result<String> {
+            let expanded = prompt_template::expand_includes(template, &load_include)?;
+            prompt_template::render(&expanded, &prompt_vars)
+        };
+        
+        let system_prompt = if let Some(literal) = system_literal {
+            literal.clone()
+        } else if let Some(path) = system_prompt_file {
+            let template = std::fs::read_to_string(path)
+                .with_context(|| format!("Failed to read system prompt file {}", path))?;
+            render_prompt(&template)
+                .with_context(|| format!("Failed to render system prompt file {}", path))?
+        } else if let Some(mcp_spec) = system_prompt_name.and_then(|n| n.strip_prefix("mcp:")) {
+            load_mcp_prompt(mcp_spec, &prompt_args)
+                .await
+                .with_context(|| format!("Failed to load MCP prompt '{}'", mcp_spec))?
+        } else if let Some(prompt_name) = system_prompt_name {
+            let template = prompt_manager
                 .load_prompt(prompt_name)
-                .with_context(|| format!("Failed to load prompt '{}'", prompt_name))?
-        } else if uses_cross_region_model {
-            eprintln!("ℹ️  Using minimal system prompt for cross-region model (33 chars instead of 475)");
-            prompt_manager
-                .load_prompt("minimal")
-                .context("Failed to load minimal prompt")?
+                .with_context(|| format!("Failed to load prompt '{}'", prompt_name))?;
+            render_prompt(&template)
+                .with_context(|| format!("Failed to render prompt '{}'", prompt_name))?
         } else {
-            prompt_manager
+            let template = prompt_manager
                 .load_default()
-                .context("Failed to load default prompt")?
+                .context("Failed to load default prompt")?;
+            render_prompt(&template)
+                .context("Failed to render default prompt")?
         };
         
         if verbose {
@@ -355,17 +1452,82 @@ async fn run_main_command(ctx: &Context) -> Result<()> {
             }
         }
         
+        let mut system_prompt = system_prompt;
+        if !flags.enabled("no-project-context")? {
+            if let Some((path, section)) = project_context::load()? {
+                status!("📄 Using project context from {}", path.display());
+                system_prompt = format!("{}\n\n{}", system_prompt, section);
+            }
+        }
+        
+        if config.context.environment {
+            system_prompt = format!("{}\n\n{}", system_prompt, env_context::describe());
+        }
+        
         // Add system prompt to session
         let system_message = ContextMessage::new(ContextMessageRole::System, system_prompt);
-        session_manager.add_message(&mut session, system_message)?;
+        if is_new_session && dry_run {
+            session.messages.push(system_message);
+        } else if is_new_session {
+            session_manager.add_message(&mut session, system_message)?;
+        } else {
+            let existing = session
+                .messages
+                .iter()
+                .position(|m| matches!(m.role, ContextMessageRole::System));
+            match existing {
+                Some(index) => session.messages[index] = system_message,
+                None => session.messages.insert(0, system_message),
+            }
+            if !dry_run {
+                session_manager.save_session(&session)?;
+            }
+            status!("🔁 Replaced the system prompt of session {}", session.id);
+        }
     }
     
-    // Add current user prompt to session
-    let user_prompt = prompt_parts.join(" ");
+    let hooks = Hooks::new(config.hooks.clone(), Some(session.id.to_string()));
+    
+    // Add current user prompt to session, with any piped input attached.
+    // Secrets are masked in each part before it goes into the session
+    let redact = |text: &str, source: &str| match &redactor {
+        Some(redactor) => redactor.redact_reporting(text, source),
+        None => text.to_string(),
+    };
+    let mut user_prompt = redact(&prompt_parts.join(" "), "prompt");
+    for attachment in &file_attachments {
+        user_prompt = format!("{}\n\n{}", user_prompt, redact(attachment, "attached file"));
+    }
+    for url in &urls {
+        status!("🌐 Fetching {}", url);
+        let page = redact(&fetch_url::fetch(url).await?, &format!("page from {}", url));
+        user_prompt = format!("{}\n\n{}", user_prompt, attachments::fenced_block(url, "", &page));
+    }
+    if let Some(piped) = piped_input {
+        let piped = redact(&piped, "piped input");
+        user_prompt = format!("{}\n\n{}", user_prompt, attachments::fenced_block(&stdin_label, "", &piped));
+    }
+    user_prompt = match hooks.pre_request(PreRequest { model: selected_model.clone(), prompt: user_prompt }).await? {
+        HookOutcome::Continue(checked) => checked.prompt,
+        HookOutcome::Veto(reason) => anyhow::bail!("Prompt rejected by the pre_request hook: {}", reason),
+    };
+    // Converse requires the forced call itself; the backend needs asking
+    if let Some(forced) = forced.as_ref().filter(|_| converse_client.is_none()) {
+        user_prompt = format!("{}\n\n{}", user_prompt, forced.instruction());
+    }
+    if plan_mode {
+        user_prompt = format!("{}\n\n{}", user_prompt, plan::PLANNING_INSTRUCTION);
+    }
     let user_message = ContextMessage::new(ContextMessageRole::User, user_prompt);
-    session_manager.add_message(&mut session, user_message)?;
+    if dry_run {
+        session.messages.push(user_message);
+    } else {
+        session_manager.add_message(&mut session, user_message)?;
+    }
     
     // Convert session messages to backend format
+    let supports_system = family.supports_system_prompt(&selected_model);
+    let mut pending_system: Option<String> = None;
     let mut messages = Vec::new();
     for context_msg in &session.messages {
         let role = match context_msg.role {
@@ -375,193 +1537,233 @@ async fn run_main_command(ctx: &Context) -> Result<()> {
             ContextMessageRole::Tool => BackendMessageRole::User, // Tool messages treated as user context
         };
         
-        let message = BackendMessage::text(role, context_msg.content.clone());
+        // Models without system prompt support get it prepended to the next user turn
+        if !supports_system && matches!(role, BackendMessageRole::System) {
+            pending_system = Some(context_msg.content.clone());
+            continue;
+        }
+        let content = match (pending_system.take(), &role) {
+            (Some(system), BackendMessageRole::User) => format!("{}\n\n{}", system, context_msg.content),
+            (system, _) => {
+                pending_system = system;
+                context_msg.content.clone()
+            }
+        };
+        
+        let message = BackendMessage::text(role, content);
         messages.push(message);
     }
     
-    // Tools are already converted in the setup phase above
-    
-    // Create retry configuration
-    let retry_config = RetryConfig {
-        max_retries,
-        initial_delay: Duration::from_millis(initial_retry_delay_ms),
-        backoff_strategy: gamecode_backend::BackoffStrategy::Exponential { multiplier: 3 },
-        verbose,
-    };
+    // Retries happen here rather than in the backend so backoff caps and jitter apply
+    debug!("Retry policy: {:?}", retry_policy);
     
-    // Create status callback for retry/backoff feedback
     let status_callback: StatusCallback =
-        std::sync::Arc::new(move |status: BackendStatus| match status {
-            BackendStatus::RetryAttempt {
-                attempt,
-                max_attempts,
-                delay_ms,
-                reason,
-            } => {
-                println!(
-                    "⚠️  Retrying request (attempt {}/{}), retrying in {}ms... ({})",
-                    attempt, max_attempts, delay_ms, reason
-                );
-            }
-            BackendStatus::RateLimited {
-                attempt,
-                max_attempts,
-                delay_ms,
-            } => {
-                println!(
-                    "⚠️  Rate limited (attempt {}/{}), retrying in {}ms...",
-                    attempt, max_attempts, delay_ms
-                );
-            }
-            BackendStatus::NonRetryableError { message } => {
-                println!("🚨 Non-retryable error detected, not retrying: {}", message);
+        std::sync::Arc::new(move |status: BackendStatus| {
+            if let BackendStatus::NonRetryableError { message } = status {
+                tracing::error!(target: logging::EVENT_TARGET, error = %message, "non-retryable error");
+                notice!("🚨 Non-retryable error detected, not retrying: {}", message);
             }
         });
     
-    // Main conversation loop using the backend
-    loop {
-        debug!("Starting conversation turn with {} messages", messages.len());
-        
-        // Warn if sending many messages to cross-region models
-        if uses_cross_region_model && messages.len() > 20 {
-            eprintln!("⚠️  Warning: Sending {} messages to cross-region model {}.", messages.len(), selected_model);
-            eprintln!("   Cross-region models have stricter limits. Consider using --new-session to start fresh.");
+    // Configured fallbacks that can't handle this run's tools are skipped
+    let fallback_models: Vec<String> = config.fallback_models
+        .iter()
+        .map(|m| map_model_name(m, &config, region))
+        .filter(|m| *m != selected_model)
+        .filter(|m| no_tools || models::supports_tools(m))
+        .collect();
+    
+    // Approval prompts and diff previews happen one call at a time, before
+    // the approved calls of a turn run
+    let approver = std::sync::Mutex::new(ToolApprover::new(auto_approve, &config).notify(notify));
+    let gated_write_tools = write_tools.clone();
+    let gate = move |name: &str, input: &Value| -> Result<ToolDecision> {
+        let mut approver = approver.lock().map_err(|_| anyhow::anyhow!("Tool approver lock poisoned"))?;
+        if !approver.approve(name, input)? {
+            notice!("\n🚫 Tool {} denied", name);
+            // A user denial is a decision, not a failure
+            return Ok(ToolDecision::Refuse(ToolOutcome::success("Tool execution denied by the user".to_string())));
         }
-        
-        // Log token limits for cross-region models
-        if uses_cross_region_model {
-            debug!("Using reduced max_tokens (100) for cross-region model");
+        if gated_write_tools.contains(name) && !auto_approve && !confirm_file_edit(name, input, notify)? {
+            notice!("\n🚫 File change from {} rejected", name);
+            return Ok(ToolDecision::Refuse(ToolOutcome::success("The user rejected this file change".to_string())));
         }
-        
-        // Create chat request
-        let chat_request = ChatRequest {
-            messages: messages.clone(),
-            tools: if no_tools { None } else { Some(backend_tools.clone()) },
-            model: Some(selected_model.to_string()),
-            inference_config: Some(InferenceConfig {
-                temperature: Some(0.7),
-                max_tokens: if uses_cross_region_model { Some(100) } else { Some(4096) },
-                top_p: Some(0.9),
-            }),
-            session_id: None,
-            status_callback: Some(status_callback.clone()),
+        Ok(ToolDecision::Run)
+    };
+    
+    let mut agent = Agent::new(backend.clone(), selected_model.clone())
+        .tools(backend_tools.clone(), mcp_dispatcher.clone())
+        .fallback_models(fallback_models)
+        .inference(inference_settings.clone())
+        .retry(retry_policy.clone())
+        .timeout(request_timeout)
+        .max_tool_turns(max_tool_turns)
+        .max_tool_failures(max_tool_failures)
+        .tool_concurrency(tool_concurrency)
+        .max_tool_result_bytes(max_tool_result_bytes)
+        .images(images)
+        .gate_tools_with(gate)
+        .backups(backups, write_tools)
+        .budget(budget)
+        .hooks(hooks)
+        .status_callback(status_callback)
+        .session(session.id)
+        .verbose(verbose);
+    if let Some(redactor) = &redactor {
+        agent = agent.redact_with(redactor.clone());
+    }
+    if let Some(guardrail) = guardrail.filter(|_| !dry_run) {
+        agent = agent.guardrail(guardrail);
+    }
+    if let Some(sub_agent) = sub_agent {
+        agent = agent.sub_agent(sub_agent);
+    }
+    if let Some(forced) = forced {
+        agent = agent.force_tool(forced);
+    }
+    if let Some(converse_client) = converse_client {
+        agent = agent.converse(converse_client);
+    }
+    if use_cache {
+        agent = agent.cache(ResponseCache::new(&config.cache));
+    }
+    
+    // --dry-run stops before the first request, showing it instead. Nothing
+    // from this run is saved to the session
+    if dry_run {
+        let tools = (!no_tools && !plan_mode).then_some(agent.offered_tools());
+        return DryRun { model: &selected_model, inference: &inference_settings, messages: &messages, tools }.print();
+    }
+    
+    let mut renderer = RunRenderer {
+        report: RunReport::new(session.id.to_string(), selected_model.clone()),
+        session_manager,
+        session,
+        tool_log,
+        session_usage,
+        plan: None,
+        plan_notes: Vec::new(),
+        output_format,
+        render_markdown,
+        use_pager,
+        print_text: output_path.is_none() || tee,
+        extraction,
+        show_usage,
+        hide_thinking,
+        verbose,
+        planning: false,
+        spinner_label: if agent_thinks { "Thinking" } else { "Waiting for model" },
+        spinner_detail: Arc::new(std::sync::Mutex::new(None)),
+        spinner: None,
+        inputs: HashMap::new(),
+        turn_start: 0,
+        pending_text: String::new(),
+        pending_thinking: None,
+        prompt_blocked: false,
+        final_text: String::new(),
+        error: None,
+    };
+    
+    // With --plan the first request asks for a plan, without tools. Once the
+    // user approves it, the run continues as usual with the plan to follow
+    if plan_mode {
+        renderer.planning = true;
+        renderer.spinner_label = "Planning";
+        let result = {
+            let mut on_event = |event: AgentEvent| renderer.on_event(event);
+            tokio::select! {
+                result = agent.ask(&mut messages, &mut on_event) => Some(result),
+                _ = tokio::signal::ctrl_c() => None,
+            }
         };
-        
-        // Send request with retry logic
-        let response = backend
-            .chat_with_retry(chat_request, retry_config.clone())
-            .await
-            .context("Failed to get response from backend")?;
-        
-        // Print the response text
-        let content = response
-            .message
-            .content
-            .iter()
-            .filter_map(|block| match block {
-                ContentBlock::Text(text) => Some(text.as_str()),
-                _ => None,
-            })
-            .collect::<Vec<_>>()
-            .join("");
-        
-        if !content.is_empty() {
-            print!("{}", content);
-            std::io::stdout().flush().unwrap();
+        renderer.planning = false;
+        renderer.spinner_label = "Waiting for model";
+        let outcome = renderer.finish(result)?;
+        let Some(outcome) = outcome else {
+            return Ok(());
+        };
+        if renderer.prompt_blocked {
+            return renderer.discard_prompt();
         }
         
-        // Process tool calls if any
-        if response.tool_calls.is_empty() {
-            // No tool calls, save final response and exit
-            if !content.is_empty() {
-                let assistant_message = ContextMessage::new(MessageRole::Assistant, content);
-                session_manager.add_message(&mut session, assistant_message)?;
-                debug!("Saved final assistant response to session");
-            }
-            break;
-        }
+        let proposal = outcome.text;
+        let Some(proposed) = Plan::parse(&proposal) else {
+            renderer.session_manager.save_session(&renderer.session)?;
+            eprintln!("{}", proposal);
+            anyhow::bail!("The model did not propose a numbered plan. Session saved: {}", renderer.session.id);
+        };
         
-        // Execute tool calls
-        let mut tool_results = Vec::new();
-        for tool_call in &response.tool_calls {
-            // Show tool execution info
-            if verbose {
-                println!(
-                    "\n🔧 Executing tool: {} with params: {}",
-                    tool_call.name,
-                    serde_json::to_string_pretty(&tool_call.input)
-                        .unwrap_or_else(|_| "<invalid json>".to_string())
-                );
-            } else {
-                println!(
-                    "\n🔧 Executing tool: {} with params: {}",
-                    tool_call.name, tool_call.input
-                );
-            }
-            
-            debug!("Executing tool: {}", tool_call.name);
-            
-            let result_content = if let Some(mcp_dispatcher) = &mcp_dispatcher {
-                // Use MCP dispatcher
-                match mcp_dispatcher.call_tool(&tool_call.name, tool_call.input.clone()).await {
-                    Ok(result) => {
-                        // Show results based on verbosity
-                        let result_str = match &result {
-                            Value::String(s) => s.clone(),
-                            other => serde_json::to_string_pretty(other)
-                                .unwrap_or_else(|_| "null".to_string()),
-                        };
-                        
-                        if verbose {
-                            println!("\n✅ Tool result for {}: {}", tool_call.name, result_str);
-                        } else {
-                            println!("\n✅ Tool {} completed successfully", tool_call.name);
-                        }
-                        result_str
-                    }
-                    Err(e) => {
-                        eprintln!("\n❌ Tool error: {}", e);
-                        format!("Tool execution failed: {}", e)
-                    }
+        let approved = if auto_approve {
+            status!("\n📋 Plan:\n{}", proposed.checklist());
+            proposed
+        } else {
+            match proposed.review()? {
+                Decision::Approve(approved) => approved,
+                Decision::Reject => {
+                    let note = ContextMessage::new(MessageRole::System, "Plan rejected by the user".to_string());
+                    renderer.session_manager.add_message(&mut renderer.session, note)?;
+                    renderer.session_manager.save_session(&renderer.session)?;
+                    notice!("🚫 Plan rejected; nothing was run. Session saved: {}", renderer.session.id);
+                    return Ok(());
                 }
-            } else {
-                eprintln!("\n❌ No tool dispatcher available");
-                "Tool execution failed: no dispatcher".to_string()
-            };
-            
-            tool_results.push(ContentBlock::ToolResult {
-                tool_call_id: tool_call.id.clone(),
-                result: result_content,
-            });
-        }
-        
-        // Add assistant message with tool calls to conversation
-        messages.push(BackendMessage {
-            role: BackendMessageRole::Assistant,
-            content: response.message.content.clone(),
-        });
-        
-        // Add tool results as user message
-        messages.push(BackendMessage {
-            role: BackendMessageRole::User,
-            content: tool_results,
-        });
+            }
+        };
         
-        // Save to session
-        if !content.is_empty() {
-            let assistant_message = ContextMessage::new(MessageRole::Assistant, content.clone());
-            session_manager.add_message(&mut session, assistant_message)?;
+        let execution_prompt = approved.execution_prompt();
+        messages.push(BackendMessage::text(BackendMessageRole::User, execution_prompt.clone()));
+        renderer.session_manager.add_message(&mut renderer.session, ContextMessage::new(MessageRole::User, execution_prompt))?;
+        renderer.plan = Some(approved);
+    }
+    
+    // Ctrl-C abandons the run but keeps the session
+    let result = {
+        let mut on_event = |event: AgentEvent| renderer.on_event(event);
+        tokio::select! {
+            result = agent.run(&mut messages, &mut on_event) => Some(result),
+            _ = tokio::signal::ctrl_c() => None,
         }
-        
-        let tool_summary = format!(
-            "Tool execution results: {} tools executed",
-            response.tool_calls.len()
+    };
+    let Some(outcome) = renderer.finish(result)? else {
+        return Ok(());
+    };
+    if renderer.prompt_blocked {
+        return renderer.discard_prompt();
+    }
+    if outcome.stop_reason == StopReason::MaxToolTurns {
+        notice!(
+            "\n⏸️  Stopped after {} tool turns (--max-tool-turns). The model had not finished.",
+            outcome.tool_turns
         );
-        let tool_message = ContextMessage::new(MessageRole::System, tool_summary);
-        session_manager.add_message(&mut session, tool_message)?;
-        
-        debug!("Continuing conversation with {} messages", messages.len());
-        debug!("Saved tool interaction to session");
+        eprintln!(
+            "   Session saved; to continue, use: --session {} \"continue\"",
+            renderer.session.id
+        );
+    }
+    
+    let RunRenderer { mut session_manager, session, mut report, plan, final_text, .. } = renderer;
+    
+    if let Some(plan) = &plan {
+        status!(
+            "\n📋 Plan progress ({} of {} steps done):\n{}",
+            plan.steps.len() - plan.remaining(),
+            plan.steps.len(),
+            plan.checklist()
+        );
+    }
+    
+    if let Some(path) = &output_path {
+        std::fs::write(path, &final_text)
+            .with_context(|| format!("Failed to write output to {}", path))?;
+        debug!("Wrote final response to {}", path);
+    }
+    
+    if output_format == OutputFormat::Json && !extraction {
+        report.text = final_text;
+        report.print()?;
+    }
+    
+    if notify {
+        notify::send("gamecode finished", &format!("Session {}", session.id));
     }
     
     // Final session save
@@ -569,10 +1771,10 @@ async fn run_main_command(ctx: &Context) -> Result<()> {
     debug!("Final session saved: {}", session.id);
     
     // Print session info for user
-    if verbose {
-        println!("\n📁 Session saved: {}", session.id);
-        println!("   Total messages: {}", session.messages.len());
-        println!(
+    if verbosity >= 1 {
+        notice!("\n📁 Session saved: {}", session.id);
+        eprintln!("   Total messages: {}", session.messages.len());
+        eprintln!(
             "   To continue this conversation, use: --session {}",
             session.id
         );
@@ -0,0 +1,306 @@
+use pulldown_cmark::{Alignment, CodeBlockKind, Event, HeadingLevel, Options, Parser, Tag, TagEnd};
+use std::sync::LazyLock;
+use syntect::easy::HighlightLines;
+use syntect::highlighting::ThemeSet;
+use syntect::parsing::SyntaxSet;
+use syntect::util::{as_24_bit_terminal_escaped, LinesWithEndings};
+
+static SYNTAXES: LazyLock<SyntaxSet> = LazyLock::new(SyntaxSet::load_defaults_newlines);
+static THEMES: LazyLock<ThemeSet> = LazyLock::new(ThemeSet::load_defaults);
+
+const CODE_THEME: &str = "base16-ocean.dark";
+
+const BOLD: &str = "\x1b[1m";
+const ITALIC: &str = "\x1b[3m";
+const UNDERLINE: &str = "\x1b[4m";
+const DIM: &str = "\x1b[2m";
+const STRIKE: &str = "\x1b[9m";
+const CYAN: &str = "\x1b[36m";
+const RESET: &str = "\x1b[0m";
+
+/// `text` in the dimmed style, for secondary output such as model reasoning
+pub fn dim(text: &str) -> String {
+    format!("{}{}{}", DIM, text, RESET)
+}
+
+/// Render markdown as ANSI-styled text for a terminal
+pub fn render(markdown: &str) -> String {
+    let mut renderer = Renderer::default();
+    let options = Options::ENABLE_TABLES | Options::ENABLE_STRIKETHROUGH | Options::ENABLE_TASKLISTS;
+    for event in Parser::new_ext(markdown, options) {
+        renderer.event(event);
+    }
+    renderer.finish()
+}
+
+#[derive(Default)]
+struct Renderer {
+    out: String,
+    /// Active inline styles, re-applied after a reset
+    styles: Vec<&'static str>,
+    /// Next number for each open list (`None` for bullet lists)
+    lists: Vec<Option<u64>>,
+    code_block: Option<(String, String)>,
+    table: Option<Table>,
+    quote_depth: usize,
+}
+
+#[derive(Default)]
+struct Table {
+    alignments: Vec<Alignment>,
+    rows: Vec<Vec<String>>,
+    header_rows: usize,
+}
+
+impl Renderer {
+    fn push(&mut self, text: &str) {
+        if let Some((_, code)) = self.code_block.as_mut() {
+            code.push_str(text);
+        } else if let Some(cell) = self.table.as_mut().and_then(|t| t.rows.last_mut()).and_then(|r| r.last_mut()) {
+            cell.push_str(text);
+        } else {
+            self.out.push_str(text);
+        }
+    }
+
+    fn open_style(&mut self, style: &'static str) {
+        self.styles.push(style);
+        self.push(style);
+    }
+
+    fn close_style(&mut self) {
+        self.styles.pop();
+        let restore = format!("{}{}", RESET, self.styles.concat());
+        self.push(&restore);
+    }
+
+    fn newline(&mut self) {
+        if !self.out.is_empty() && !self.out.ends_with('\n') {
+            self.out.push('\n');
+        }
+    }
+
+    fn block_end(&mut self) {
+        self.newline();
+        if self.lists.is_empty() {
+            self.out.push('\n');
+        }
+    }
+
+    fn event(&mut self, event: Event) {
+        match event {
+            Event::Start(tag) => self.start(tag),
+            Event::End(tag) => self.end(tag),
+            Event::Text(text) => {
+                if self.quote_depth > 0 && self.code_block.is_none() && self.table.is_none() {
+                    let prefix = format!("\n{}│{} ", DIM, RESET);
+                    let quoted = text.replace('\n', &prefix);
+                    self.push(&quoted);
+                } else {
+                    self.push(&text);
+                }
+            }
+            Event::Code(code) => {
+                let styled = format!("{}{}{}{}", CYAN, code, RESET, self.styles.concat());
+                self.push(&styled);
+            }
+            Event::SoftBreak => self.push(" "),
+            Event::HardBreak => self.push("\n"),
+            Event::Rule => {
+                self.newline();
+                self.out.push_str(&format!("{}{}{}\n\n", DIM, "─".repeat(40), RESET));
+            }
+            Event::TaskListMarker(done) => self.push(if done { "[x] " } else { "[ ] " }),
+            Event::Html(html) | Event::InlineHtml(html) => self.push(&html),
+            _ => {}
+        }
+    }
+
+    fn start(&mut self, tag: Tag) {
+        match tag {
+            Tag::Heading { level, .. } => {
+                self.newline();
+                let style = if level == HeadingLevel::H1 { "\x1b[1;4m" } else { BOLD };
+                self.open_style(style);
+            }
+            Tag::Paragraph => {}
+            Tag::Strong => self.open_style(BOLD),
+            Tag::Emphasis => self.open_style(ITALIC),
+            Tag::Strikethrough => self.open_style(STRIKE),
+            Tag::Link { .. } => self.open_style(UNDERLINE),
+            Tag::BlockQuote(_) => {
+                self.newline();
+                self.quote_depth += 1;
+                self.out.push_str(&format!("{}│{} ", DIM, RESET));
+            }
+            Tag::List(start) => {
+                self.newline();
+                self.lists.push(start);
+            }
+            Tag::Item => {
+                self.newline();
+                let indent = "  ".repeat(self.lists.len().saturating_sub(1));
+                let marker = match self.lists.last_mut() {
+                    Some(Some(n)) => {
+                        *n += 1;
+                        format!("{}. ", *n - 1)
+                    }
+                    _ => "• ".to_string(),
+                };
+                self.out.push_str(&indent);
+                self.out.push_str(&marker);
+            }
+            Tag::CodeBlock(kind) => {
+                self.newline();
+                let lang = match kind {
+                    CodeBlockKind::Fenced(lang) => lang.split_whitespace().next().unwrap_or("").to_string(),
+                    CodeBlockKind::Indented => String::new(),
+                };
+                self.code_block = Some((lang, String::new()));
+            }
+            Tag::Table(alignments) => {
+                self.newline();
+                self.table = Some(Table {
+                    alignments,
+                    ..Table::default()
+                });
+            }
+            Tag::TableHead | Tag::TableRow => {
+                if let Some(table) = self.table.as_mut() {
+                    table.rows.push(Vec::new());
+                }
+            }
+            Tag::TableCell => {
+                if let Some(row) = self.table.as_mut().and_then(|t| t.rows.last_mut()) {
+                    row.push(String::new());
+                }
+            }
+            _ => {}
+        }
+    }
+
+    fn end(&mut self, tag: TagEnd) {
+        match tag {
+            TagEnd::Heading(_) => {
+                self.close_style();
+                self.out.push_str("\n\n");
+            }
+            TagEnd::Paragraph => self.block_end(),
+            TagEnd::Strong | TagEnd::Emphasis | TagEnd::Strikethrough | TagEnd::Link => self.close_style(),
+            TagEnd::BlockQuote(_) => {
+                self.quote_depth = self.quote_depth.saturating_sub(1);
+                self.block_end();
+            }
+            TagEnd::List(_) => {
+                self.lists.pop();
+                if self.lists.is_empty() {
+                    self.block_end();
+                }
+            }
+            TagEnd::Item => self.newline(),
+            TagEnd::CodeBlock => {
+                if let Some((lang, code)) = self.code_block.take() {
+                    self.out.push_str(&highlight(&lang, &code));
+                    self.block_end();
+                }
+            }
+            TagEnd::TableHead => {
+                if let Some(table) = self.table.as_mut() {
+                    table.header_rows = table.rows.len();
+                }
+            }
+            TagEnd::Table => {
+                if let Some(table) = self.table.take() {
+                    self.out.push_str(&table.render());
+                    self.out.push('\n');
+                }
+            }
+            _ => {}
+        }
+    }
+
+    fn finish(mut self) -> String {
+        while self.out.ends_with("\n\n") {
+            self.out.pop();
+        }
+        self.out
+    }
+}
+
+impl Table {
+    fn render(&self) -> String {
+        let columns = self.rows.iter().map(|r| r.len()).max().unwrap_or(0);
+        let mut widths = vec![0; columns];
+        for row in &self.rows {
+            for (i, cell) in row.iter().enumerate() {
+                widths[i] = widths[i].max(visible_width(cell));
+            }
+        }
+
+        let mut out = String::new();
+        for (index, row) in self.rows.iter().enumerate() {
+            let cells: Vec<String> = (0..columns)
+                .map(|i| {
+                    let cell = row.get(i).map(String::as_str).unwrap_or("");
+                    let padding = widths[i] - visible_width(cell);
+                    let text = if index < self.header_rows {
+                        format!("{}{}{}", BOLD, cell, RESET)
+                    } else {
+                        cell.to_string()
+                    };
+                    match self.alignments.get(i) {
+                        Some(Alignment::Right) => format!("{}{}", " ".repeat(padding), text),
+                        Some(Alignment::Center) => format!(
+                            "{}{}{}",
+                            " ".repeat(padding / 2),
+                            text,
+                            " ".repeat(padding - padding / 2)
+                        ),
+                        _ => format!("{}{}", text, " ".repeat(padding)),
+                    }
+                })
+                .collect();
+            out.push_str(&cells.join(" │ "));
+            out.push('\n');
+
+            if index + 1 == self.header_rows {
+                let rule: Vec<String> = widths.iter().map(|w| "─".repeat(*w)).collect();
+                out.push_str(&format!("{}{}{}\n", DIM, rule.join("─┼─"), RESET));
+            }
+        }
+        out
+    }
+}
+
+/// Width of text as displayed, ignoring ANSI escape sequences
+fn visible_width(text: &str) -> usize {
+    let mut width = 0;
+    let mut in_escape = false;
+    for c in text.chars() {
+        match (in_escape, c) {
+            (false, '\x1b') => in_escape = true,
+            (true, 'm') => in_escape = false,
+            (true, _) => {}
+            (false, _) => width += 1,
+        }
+    }
+    width
+}
+
+/// Syntax-highlight a code block, falling back to plain text for unknown languages
+fn highlight(lang: &str, code: &str) -> String {
+    let syntax = SYNTAXES
+        .find_syntax_by_token(lang)
+        .unwrap_or_else(|| SYNTAXES.find_syntax_plain_text());
+    let mut highlighter = HighlightLines::new(syntax, &THEMES.themes[CODE_THEME]);
+
+    let mut out = String::new();
+    for line in LinesWithEndings::from(code) {
+        match highlighter.highlight_line(line, &SYNTAXES) {
+            Ok(ranges) => out.push_str(&as_24_bit_terminal_escaped(&ranges, false)),
+            Err(_) => out.push_str(line),
+        }
+    }
+    out.push_str(RESET);
+    out
+}
@@ -1,24 +1,95 @@
 use anyhow::{Context, Result};
-use crate::cmd::mcp::McpServerConfig;
-use crate::mcp_protocol::{McpConnection, ToolSchema};
+use crate::mcp_config::McpServerConfig;
+use crate::mcp_protocol::{McpConnection, PromptSchema, ToolSchema};
+use crate::mcp_sampling::SamplingHandler;
 use serde_json::{json, Value};
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
 use tracing::{debug, info, error};
 
+/// Result of checking a single MCP server with `gamecode mcp doctor`
+pub struct ServerDiagnosis {
+    pub binary: Result<PathBuf, String>,
+    pub initialize: Option<Result<String, String>>,
+    pub tools: Option<Result<usize, String>>,
+}
+
+impl ServerDiagnosis {
+    pub fn is_healthy(&self) -> bool {
+        self.binary.is_ok()
+            && matches!(self.initialize, Some(Ok(_)))
+            && matches!(self.tools, Some(Ok(_)))
+    }
+}
+
+/// Locate an executable the same way the OS would when spawning it
+pub fn find_executable(command: &str) -> Option<PathBuf> {
+    let path = Path::new(command);
+    if path.components().count() > 1 {
+        return path.is_file().then(|| path.to_path_buf());
+    }
+    
+    std::env::var_os("PATH").and_then(|paths| {
+        std::env::split_paths(&paths)
+            .map(|dir| dir.join(command))
+            .find(|candidate| candidate.is_file())
+    })
+}
+
+/// Actionable hint for common MCP server failures
+pub fn error_hint(error_msg: &str) -> Option<&'static str> {
+    if error_msg.contains("No such file or directory") || error_msg.contains("cannot find") {
+        Some("Server binary not found. Is it installed?")
+    } else if error_msg.contains("EOF") || error_msg.contains("Empty response") {
+        Some("Server exited early; it may need a tools.yaml file in its working directory (see 'gamecode mcp logs')")
+    } else if error_msg.contains("timed out") {
+        Some("Server did not respond in time; raise timeout_ms or check 'gamecode mcp logs'")
+    } else if error_msg.contains("protocol version") {
+        Some("Server speaks an MCP protocol version this client does not support")
+    } else if error_msg.contains("Permission denied") {
+        Some("Server binary is not executable (check file permissions)")
+    } else {
+        None
+    }
+}
+
 // For now, we'll use a simpler approach without storing connections
 // Each operation will create a new connection
-pub struct McpClient;
+#[derive(Clone)]
+pub struct McpClient {
+    sampling_handler: Option<SamplingHandler>,
+}
 
 impl McpClient {
     pub fn new() -> Self {
-        Self {}
+        Self {
+            sampling_handler: None,
+        }
+    }
+    
+    /// Let servers request model completions on connections made by this client
+    pub fn set_sampling_handler(&mut self, handler: SamplingHandler) {
+        self.sampling_handler = Some(handler);
+    }
+    
+    fn connect(&self, server: &McpServerConfig, process: tokio::process::Child) -> Result<McpConnection> {
+        let mut connection = McpConnection::new(process)?;
+        connection.set_server_name(&server.name);
+        connection.set_timeout(server.timeout());
+        connection.capture_stderr();
+        if let Some(handler) = &self.sampling_handler {
+            connection.set_sampling_handler(handler.clone());
+        }
+        Ok(connection)
     }
     pub async fn test_server(server: &McpServerConfig) -> Result<()> {
         println!("Testing MCP server '{}'...", server.name);
         println!("Command: {} {}", server.command, server.args.join(" "));
         
         // Start the MCP server
-        let process = Self.start_mcp_server(server).await?;
-        let mut connection = McpConnection::new(process)?;
+        let client = Self::new();
+        let process = client.start_mcp_server(server).await?;
+        let mut connection = client.connect(server, process)?;
         
         // Initialize the connection
         println!("\nInitializing MCP connection...");
@@ -27,9 +98,19 @@ impl McpClient {
                 println!("✓ Successfully initialized MCP connection");
                 debug!("Initialize response: {:?}", response);
                 
+                let caps = connection.server_capabilities();
+                println!(
+                    "  Protocol version: {}",
+                    connection.protocol_version().unwrap_or("unknown")
+                );
+                println!(
+                    "  Capabilities: tools={} prompts={} resources={} logging={}",
+                    caps.tools, caps.prompts, caps.resources, caps.logging
+                );
+                
                 // Send initialized notification as per MCP spec
                 if let Err(e) = connection.send_notification("notifications/initialized", json!({})).await {
-                    eprintln!("DEBUG: Failed to send initialized notification: {}", e);
+                    debug!("Failed to send initialized notification: {}", e);
                 }
             }
             Err(e) => {
@@ -54,14 +135,11 @@ impl McpClient {
                             println!("\nQuerying actual tools from tools.yaml...");
                             match connection.call_tool("list_tools", json!({})).await {
                                 Ok(result) => {
-                                    // eprintln!("DEBUG: list_tools raw result: {:?}", result);
-                                    
                                     // The result might be wrapped in a content array
                                     if let Some(content) = result.get("content") {
                                         if let Some(content_array) = content.as_array() {
                                             for item in content_array {
                                                 if let Some(text) = item.get("text").and_then(|t| t.as_str()) {
-                                                    // eprintln!("DEBUG: Parsing text content: {}", text);
                                                     // Parse the JSON text
                                                     if let Ok(parsed) = serde_json::from_str::<Value>(text) {
                                                         if let Some(tools_array) = parsed.get("tools") {
@@ -69,7 +147,7 @@ impl McpClient {
                                                             if let Some(tools) = tools_array.as_array() {
                                                                 if tools.is_empty() {
                                                                     println!("  (No tools found - check if tools.yaml exists in current directory)");
-                                                                    eprintln!("DEBUG: Current directory: {:?}", std::env::current_dir());
+                                                                    debug!("Current directory: {:?}", std::env::current_dir());
                                                                 } else {
                                                                     for tool in tools {
                                                                         if let Some(name) = tool.get("name") {
@@ -120,8 +198,17 @@ impl McpClient {
         cmd.stdout(std::process::Stdio::piped());
         cmd.stderr(std::process::Stdio::piped());
         
-        // Set working directory to current directory so tools.yaml can be found
-        let cwd = std::env::current_dir().context("Failed to get current directory")?;
+        // Server-specific environment (API keys etc.)
+        for (key, value) in &server.env {
+            cmd.env(key, value);
+        }
+        
+        // Use the configured working directory, falling back to the current
+        // directory so tools.yaml can be found
+        let cwd = match &server.cwd {
+            Some(cwd) => cwd.clone(),
+            None => std::env::current_dir().context("Failed to get current directory")?,
+        };
         debug!("Starting MCP server in directory: {:?}", cwd);
         cmd.current_dir(&cwd);
         
@@ -141,7 +228,7 @@ impl McpClient {
         
         // Start the MCP server and create connection
         let process = self.start_mcp_server(server).await?;
-        let mut connection = McpConnection::new(process)?;
+        let mut connection = self.connect(server, process)?;
         
         // Initialize the connection
         connection.initialize().await
@@ -165,7 +252,7 @@ impl McpClient {
         
         // Start the MCP server and create connection
         let process = self.start_mcp_server(server).await?;
-        let mut connection = McpConnection::new(process)?;
+        let mut connection = self.connect(server, process)?;
         
         // Initialize the connection
         connection.initialize().await
@@ -177,4 +264,96 @@ impl McpClient {
         // Get the list of tools
         connection.list_tools().await
     }
-}
\ No newline at end of file
+    
+    pub async fn list_prompts(&self, server: &McpServerConfig) -> Result<Vec<PromptSchema>> {
+        debug!("Listing prompts from server: {}", server.name);
+        
+        let process = self.start_mcp_server(server).await?;
+        let mut connection = self.connect(server, process)?;
+        
+        connection.initialize().await
+            .context("Failed to initialize MCP connection")?;
+        let _ = connection.send_notification("notifications/initialized", json!({})).await;
+        
+        connection.list_prompts().await
+    }
+    
+    /// Fetch a server-provided prompt and flatten its text messages into a single string
+    pub async fn get_prompt_text(
+        &self,
+        server: &McpServerConfig,
+        prompt_name: &str,
+        arguments: &HashMap<String, String>,
+    ) -> Result<String> {
+        info!("Getting prompt '{}' from server '{}'", prompt_name, server.name);
+        
+        let process = self.start_mcp_server(server).await?;
+        let mut connection = self.connect(server, process)?;
+        
+        connection.initialize().await
+            .context("Failed to initialize MCP connection")?;
+        let _ = connection.send_notification("notifications/initialized", json!({})).await;
+        
+        let result = connection.get_prompt(prompt_name, arguments).await
+            .with_context(|| format!("Failed to get prompt '{}' from server '{}'", prompt_name, server.name))?;
+        
+        let texts: Vec<&str> = result.get("messages")
+            .and_then(|m| m.as_array())
+            .into_iter()
+            .flatten()
+            .filter_map(|message| message.get("content"))
+            .filter(|content| content.get("type").and_then(|t| t.as_str()) == Some("text"))
+            .filter_map(|content| content.get("text").and_then(|t| t.as_str()))
+            .collect();
+        
+        if texts.is_empty() {
+            anyhow::bail!("Prompt '{}' from server '{}' contains no text content", prompt_name, server.name);
+        }
+        
+        Ok(texts.join("\n\n"))
+    }
+    
+    /// Check that a server can be found, started, initialized and queried for tools
+    pub async fn diagnose(&self, server: &McpServerConfig) -> ServerDiagnosis {
+        let mut diagnosis = ServerDiagnosis {
+            binary: find_executable(&server.command)
+                .ok_or_else(|| format!("'{}' not found on PATH", server.command)),
+            initialize: None,
+            tools: None,
+        };
+        if diagnosis.binary.is_err() {
+            return diagnosis;
+        }
+        
+        let mut connection = match self.start_mcp_server(server).await {
+            Ok(process) => match self.connect(server, process) {
+                Ok(connection) => connection,
+                Err(e) => {
+                    diagnosis.initialize = Some(Err(e.to_string()));
+                    return diagnosis;
+                }
+            },
+            Err(e) => {
+                diagnosis.initialize = Some(Err(format!("{:#}", e)));
+                return diagnosis;
+            }
+        };
+        
+        match connection.initialize().await {
+            Ok(_) => {
+                let version = connection.protocol_version().unwrap_or("unknown").to_string();
+                diagnosis.initialize = Some(Ok(version));
+            }
+            Err(e) => {
+                diagnosis.initialize = Some(Err(e.to_string()));
+                return diagnosis;
+            }
+        }
+        let _ = connection.send_notification("notifications/initialized", json!({})).await;
+        
+        diagnosis.tools = Some(connection.list_tools().await
+            .map(|tools| tools.len())
+            .map_err(|e| e.to_string()));
+        diagnosis
+    }
+}
@@ -0,0 +1,105 @@
+use crate::mcp_protocol::DEFAULT_TIMEOUT_MS;
+use crate::mcp_sampling::SamplingConfig;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::fs;
+use std::path::PathBuf;
+use std::time::Duration;
+
+/// How to launch one MCP server, as stored in `~/.config/gamecode/mcp-servers.json`
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct McpServerConfig {
+    pub name: String,
+    pub command: String,
+    pub args: Vec<String>,
+    pub description: Option<String>,
+    pub enabled: bool,
+    /// Extra environment variables passed to the server process (e.g. API keys)
+    #[serde(default, skip_serializing_if = "HashMap::is_empty")]
+    pub env: HashMap<String, String>,
+    /// Working directory for the server process; defaults to the current directory
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub cwd: Option<PathBuf>,
+    /// Maximum time to wait for the server to answer a request, tool calls included
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub timeout_ms: Option<u64>,
+}
+
+impl McpServerConfig {
+    pub fn timeout(&self) -> Duration {
+        Duration::from_millis(self.timeout_ms.unwrap_or(DEFAULT_TIMEOUT_MS))
+    }
+}
+
+#[derive(Debug, Serialize, Deserialize, Default)]
+pub struct McpConfig {
+    pub servers: Vec<McpServerConfig>,
+    /// Limits for server-initiated model calls (MCP sampling)
+    #[serde(default)]
+    pub sampling: SamplingConfig,
+}
+
+impl McpConfig {
+    fn config_path() -> Result<PathBuf, Box<dyn std::error::Error>> {
+        let home = home::home_dir().ok_or("Failed to get home directory")?;
+        let config_dir = home.join(".config").join("gamecode");
+        Ok(config_dir.join("mcp-servers.json"))
+    }
+
+    pub fn load() -> Result<Self, Box<dyn std::error::Error>> {
+        let path = Self::config_path()?;
+        if !path.exists() {
+            return Ok(Self::default());
+        }
+        
+        let content = fs::read_to_string(&path)?;
+        let config: McpConfig = serde_json::from_str(&content)?;
+        Ok(config)
+    }
+
+    pub fn save(&self) -> Result<(), Box<dyn std::error::Error>> {
+        let path = Self::config_path()?;
+        
+        // Ensure directory exists
+        if let Some(parent) = path.parent() {
+            fs::create_dir_all(parent)?;
+        }
+        
+        let content = serde_json::to_string_pretty(self)?;
+        fs::write(&path, content)?;
+        Ok(())
+    }
+
+    pub fn add_server(&mut self, server: McpServerConfig) -> Result<(), Box<dyn std::error::Error>> {
+        // Check if server with same name already exists
+        if self.servers.iter().any(|s| s.name == server.name) {
+            return Err(format!("Server '{}' already exists", server.name).into());
+        }
+        
+        self.servers.push(server);
+        self.save()?;
+        Ok(())
+    }
+
+    pub fn remove_server(&mut self, name: &str) -> Result<(), Box<dyn std::error::Error>> {
+        let initial_len = self.servers.len();
+        self.servers.retain(|s| s.name != name);
+        
+        if self.servers.len() == initial_len {
+            return Err(format!("Server '{}' not found", name).into());
+        }
+        
+        self.save()?;
+        Ok(())
+    }
+
+    pub fn set_enabled(&mut self, name: &str, enabled: bool) -> Result<(), Box<dyn std::error::Error>> {
+        let server = self.servers.iter_mut()
+            .find(|s| s.name == name)
+            .ok_or_else(|| format!("Server '{}' not found", name))?;
+        
+        server.enabled = enabled;
+        self.save()?;
+        Ok(())
+    }
+}
@@ -0,0 +1,85 @@
+use anyhow::{Context, Result};
+use std::collections::VecDeque;
+use std::fs::{self, OpenOptions};
+use std::io::Write;
+use std::path::PathBuf;
+use std::sync::{Arc, Mutex};
+use tokio::io::{AsyncBufReadExt, BufReader};
+use tokio::process::ChildStderr;
+use tracing::debug;
+
+/// Number of recent stderr lines kept in memory per connection
+const TAIL_LINES: usize = 50;
+
+/// Log files larger than this are rotated to `<server>.log.1` on the next spawn
+const MAX_LOG_BYTES: u64 = 1024 * 1024;
+
+/// Recent stderr lines from a server, shared with the capture task
+pub type StderrTail = Arc<Mutex<VecDeque<String>>>;
+
+/// Captures MCP server stderr into `~/.local/share/gamecode/mcp-logs/<server>.log`
+pub struct McpServerLog;
+
+impl McpServerLog {
+    pub fn log_dir() -> Result<PathBuf> {
+        let home = home::home_dir().context("Failed to get home directory")?;
+        Ok(home
+            .join(".local")
+            .join("share")
+            .join("gamecode")
+            .join("mcp-logs"))
+    }
+
+    pub fn log_path(server_name: &str) -> Result<PathBuf> {
+        Ok(Self::log_dir()?.join(format!("{}.log", server_name)))
+    }
+
+    /// Spawn a task that drains the server's stderr into its log file and a ring buffer
+    pub fn capture(server_name: &str, stderr: ChildStderr) -> StderrTail {
+        let tail: StderrTail = Arc::new(Mutex::new(VecDeque::with_capacity(TAIL_LINES)));
+        let task_tail = tail.clone();
+        let server_name = server_name.to_string();
+
+        tokio::spawn(async move {
+            let mut file = match Self::open_log(&server_name) {
+                Ok(file) => Some(file),
+                Err(e) => {
+                    debug!("Failed to open log for MCP server '{}': {}", server_name, e);
+                    None
+                }
+            };
+
+            let mut lines = BufReader::new(stderr).lines();
+            while let Ok(Some(line)) = lines.next_line().await {
+                debug!("[{}] {}", server_name, line);
+
+                if let Some(file) = file.as_mut() {
+                    let timestamp = chrono::Local::now().format("%Y-%m-%d %H:%M:%S");
+                    let _ = writeln!(file, "{} {}", timestamp, line);
+                }
+
+                if let Ok(mut tail) = task_tail.lock() {
+                    if tail.len() == TAIL_LINES {
+                        tail.pop_front();
+                    }
+                    tail.push_back(line);
+                }
+            }
+        });
+
+        tail
+    }
+
+    fn open_log(server_name: &str) -> Result<fs::File> {
+        let path = Self::log_path(server_name)?;
+        if let Some(parent) = path.parent() {
+            fs::create_dir_all(parent)?;
+        }
+
+        if fs::metadata(&path).is_ok_and(|m| m.len() > MAX_LOG_BYTES) {
+            fs::rename(&path, path.with_extension("log.1"))?;
+        }
+
+        Ok(OpenOptions::new().create(true).append(true).open(&path)?)
+    }
+}
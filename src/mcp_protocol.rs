@@ -1,9 +1,13 @@
 use anyhow::{Context, Result};
 use serde::{Deserialize, Serialize};
 use serde_json::{json, Value};
+use std::collections::HashMap;
+use std::time::Duration;
 use tokio::io::{AsyncBufReadExt, AsyncWriteExt, BufReader};
 use tokio::process::{Child, ChildStdin, ChildStdout, ChildStderr};
-use tracing::{debug, info};
+use tracing::{debug, info, warn};
+use crate::mcp_logs::{McpServerLog, StderrTail};
+use crate::mcp_sampling::SamplingHandler;
 
 #[derive(Debug, Serialize, Deserialize)]
 pub struct JsonRpcRequest {
@@ -32,12 +36,65 @@ pub struct JsonRpcError {
     data: Option<Value>,
 }
 
+impl JsonRpcResponse {
+    pub fn success(id: Value, result: Value) -> Self {
+        Self { jsonrpc: "2.0".to_string(), id, result: Some(result), error: None }
+    }
+
+    pub fn failure(id: Value, code: i32, message: String) -> Self {
+        Self {
+            jsonrpc: "2.0".to_string(),
+            id,
+            result: None,
+            error: Some(JsonRpcError { code, message, data: None }),
+        }
+    }
+}
+
+/// Default time to wait for an MCP server to answer a request, tool calls
+/// included
+pub const DEFAULT_TIMEOUT_MS: u64 = 30_000;
+
+/// Protocol versions this client speaks, newest first
+pub const SUPPORTED_PROTOCOL_VERSIONS: &[&str] = &["2025-03-26", "2024-11-05", "0.1.0"];
+
+/// Version assumed for servers that predate version negotiation and don't
+/// report one
+pub const LEGACY_PROTOCOL_VERSION: &str = "0.1.0";
+
+/// Capabilities the server advertised during `initialize`
+#[derive(Debug, Clone, Default)]
+pub struct ServerCapabilities {
+    pub tools: bool,
+    pub prompts: bool,
+    pub resources: bool,
+    pub logging: bool,
+}
+
+impl ServerCapabilities {
+    fn from_value(capabilities: Option<&Value>) -> Self {
+        let has = |key: &str| capabilities.and_then(|c| c.get(key)).is_some_and(|v| !v.is_null());
+        Self {
+            tools: has("tools"),
+            prompts: has("prompts"),
+            resources: has("resources"),
+            logging: has("logging"),
+        }
+    }
+}
+
 pub struct McpConnection {
     stdin: ChildStdin,
     stdout: BufReader<ChildStdout>,
     stderr: Option<ChildStderr>,
     _process: Child,
     request_id: u64,
+    sampling_handler: Option<SamplingHandler>,
+    protocol_version: Option<String>,
+    server_capabilities: ServerCapabilities,
+    stderr_tail: Option<StderrTail>,
+    server_name: String,
+    timeout: Duration,
 }
 
 impl McpConnection {
@@ -54,53 +111,203 @@ impl McpConnection {
             stderr,
             _process: process,
             request_id: 0,
+            sampling_handler: None,
+            protocol_version: None,
+            server_capabilities: ServerCapabilities::default(),
+            stderr_tail: None,
+            server_name: "unknown".to_string(),
+            timeout: Duration::from_millis(DEFAULT_TIMEOUT_MS),
         })
     }
     
+    /// Name used in error messages and log files for this server
+    pub fn set_server_name(&mut self, server_name: &str) {
+        self.server_name = server_name.to_string();
+    }
+    
+    /// Maximum time to wait for the server to answer a request, from sending
+    /// it to receiving its response
+    pub fn set_timeout(&mut self, timeout: Duration) {
+        self.timeout = timeout;
+    }
+    
+    /// Start draining the server's stderr into its log file so the pipe never
+    /// fills up, and keep the most recent lines for error reporting
+    pub fn capture_stderr(&mut self) {
+        if let Some(stderr) = self.stderr.take() {
+            self.stderr_tail = Some(McpServerLog::capture(&self.server_name, stderr));
+        }
+    }
+    
+    /// Most recent stderr output from the server, if captured
+    fn recent_stderr(&self) -> Option<String> {
+        let tail = self.stderr_tail.as_ref()?.lock().ok()?;
+        if tail.is_empty() {
+            return None;
+        }
+        Some(tail.iter().cloned().collect::<Vec<_>>().join("\n"))
+    }
+    
+    /// Allow the server to request model completions via `sampling/createMessage`.
+    /// Must be set before `initialize` so the capability is advertised.
+    pub fn set_sampling_handler(&mut self, handler: SamplingHandler) {
+        self.sampling_handler = Some(handler);
+    }
+    
     pub async fn initialize(&mut self) -> Result<Value> {
+        let mut capabilities = json!({
+            "tools": {}
+        });
+        if self.sampling_handler.is_some() {
+            capabilities["sampling"] = json!({});
+        }
+        
         let request = JsonRpcRequest {
             jsonrpc: "2.0".to_string(),
             id: json!(self.next_id()),
             method: "initialize".to_string(),
             params: Some(json!({
-                "protocolVersion": "0.1.0",
-                "capabilities": {
-                    "tools": {}
-                },
+                "protocolVersion": SUPPORTED_PROTOCOL_VERSIONS[0],
+                "capabilities": capabilities,
                 "clientInfo": {
                     "name": "gamecode-cli",
-                    "version": "0.2.0"
+                    "version": env!("CARGO_PKG_VERSION")
                 }
             })),
         };
         
-        self.send_request(&request).await
+        let response = self.send_request(&request).await?;
+        
+        // The server answers with the version it wants to use; we must support it
+        let server_version = match response.get("protocolVersion").and_then(|v| v.as_str()) {
+            Some(version) => version,
+            None => {
+                warn!(
+                    "MCP server '{}' did not report a protocol version; assuming {}",
+                    self.server_name, LEGACY_PROTOCOL_VERSION
+                );
+                LEGACY_PROTOCOL_VERSION
+            }
+        };
+        
+        if !SUPPORTED_PROTOCOL_VERSIONS.contains(&server_version) {
+            anyhow::bail!(
+                "Unsupported MCP protocol version '{}' (client supports: {})",
+                server_version,
+                SUPPORTED_PROTOCOL_VERSIONS.join(", ")
+            );
+        }
+        
+        debug!("Negotiated MCP protocol version {}", server_version);
+        self.protocol_version = Some(server_version.to_string());
+        self.server_capabilities = ServerCapabilities::from_value(response.get("capabilities"));
+        
+        Ok(response)
+    }
+    
+    /// Protocol version agreed during `initialize`
+    pub fn protocol_version(&self) -> Option<&str> {
+        self.protocol_version.as_deref()
+    }
+    
+    /// Capabilities the server advertised during `initialize`
+    pub fn server_capabilities(&self) -> &ServerCapabilities {
+        &self.server_capabilities
+    }
+    
+    fn require_capability(&self, supported: bool, feature: &str) -> Result<()> {
+        if !supported {
+            anyhow::bail!("MCP server does not support {}", feature);
+        }
+        Ok(())
     }
     
     pub async fn list_tools(&mut self) -> Result<Vec<ToolSchema>> {
+        let mut all_tools = Vec::new();
+        let mut cursor: Option<String> = None;
+        
+        // Follow nextCursor until the server reports no more pages
+        loop {
+            let params = match &cursor {
+                Some(cursor) => json!({ "cursor": cursor }),
+                None => json!({}),  // Empty params object instead of None
+            };
+            let request = JsonRpcRequest {
+                jsonrpc: "2.0".to_string(),
+                id: json!(self.next_id()),
+                method: "tools/list".to_string(),
+                params: Some(params),
+            };
+            
+            let response = self.send_request(&request).await?;
+            
+            // Parse the response to extract tools
+            if let Some(tools) = response.get("tools") {
+                let tools: Vec<ToolSchema> = serde_json::from_value(tools.clone())?;
+                all_tools.extend(tools);
+            }
+            
+            let next_cursor = response.get("nextCursor")
+                .and_then(|c| c.as_str())
+                .filter(|c| !c.is_empty())
+                .map(|c| c.to_string());
+            
+            match next_cursor {
+                Some(next) if cursor.as_deref() == Some(next.as_str()) => {
+                    anyhow::bail!("MCP server returned the same tools/list cursor twice: {}", next);
+                }
+                Some(next) => {
+                    debug!("Fetching next page of tools (cursor {})", next);
+                    cursor = Some(next);
+                }
+                None => break,
+            }
+        }
+        
+        Ok(all_tools)
+    }
+    
+    pub async fn call_tool(&mut self, name: &str, arguments: Value) -> Result<Value> {
+        let request = JsonRpcRequest {
+            jsonrpc: "2.0".to_string(),
+            id: json!(self.next_id()),
+            method: "tools/call".to_string(),
+            params: Some(json!({
+                "name": name,
+                "arguments": arguments
+            })),
+        };
+        
+        self.send_request(&request).await
+    }
+    
+    pub async fn list_prompts(&mut self) -> Result<Vec<PromptSchema>> {
+        self.require_capability(self.server_capabilities.prompts, "prompts")?;
+        
         let request = JsonRpcRequest {
             jsonrpc: "2.0".to_string(),
             id: json!(self.next_id()),
-            method: "tools/list".to_string(),
-            params: Some(json!({})),  // Empty params object instead of None
+            method: "prompts/list".to_string(),
+            params: Some(json!({})),
         };
         
         let response = self.send_request(&request).await?;
         
-        // Parse the response to extract tools
-        if let Some(tools) = response.get("tools") {
-            let tools: Vec<ToolSchema> = serde_json::from_value(tools.clone())?;
-            Ok(tools)
+        if let Some(prompts) = response.get("prompts") {
+            let prompts: Vec<PromptSchema> = serde_json::from_value(prompts.clone())?;
+            Ok(prompts)
         } else {
             Ok(vec![])
         }
     }
     
-    pub async fn call_tool(&mut self, name: &str, arguments: Value) -> Result<Value> {
+    pub async fn get_prompt(&mut self, name: &str, arguments: &HashMap<String, String>) -> Result<Value> {
+        self.require_capability(self.server_capabilities.prompts, "prompts")?;
+        
         let request = JsonRpcRequest {
             jsonrpc: "2.0".to_string(),
             id: json!(self.next_id()),
-            method: "tools/call".to_string(),
+            method: "prompts/get".to_string(),
             params: Some(json!({
                 "name": name,
                 "arguments": arguments
@@ -110,35 +317,94 @@ impl McpConnection {
         self.send_request(&request).await
     }
     
+    /// Send `request` and wait for its response. The timeout covers the whole
+    /// exchange, so a server that keeps sending notifications or requests
+    /// without answering still times out.
     async fn send_request(&mut self, request: &JsonRpcRequest) -> Result<Value> {
+        let limit = self.timeout;
+        match tokio::time::timeout(limit, self.exchange(request)).await {
+            Ok(result) => result,
+            Err(_) => anyhow::bail!(
+                "MCP server '{}' timed out after {}ms waiting for '{}'",
+                self.server_name,
+                limit.as_millis(),
+                request.method
+            ),
+        }
+    }
+    
+    async fn exchange(&mut self, request: &JsonRpcRequest) -> Result<Value> {
         // Send request
         let request_str = serde_json::to_string(request)?;
         debug!("Sending MCP request: {}", request_str);
-        // eprintln!("DEBUG: Sending MCP request: {}", request_str);
         
         self.stdin.write_all(request_str.as_bytes()).await?;
         self.stdin.write_all(b"\n").await?;
         self.stdin.flush().await?;
         
-        // Read response
-        let mut response_line = String::new();
-        let bytes_read = self.stdout.read_line(&mut response_line).await?;
-        
-        // eprintln!("DEBUG: Read {} bytes", bytes_read);
-        // eprintln!("DEBUG: Received MCP response: {:?}", response_line);
-        debug!("Received MCP response: {}", response_line);
-        
-        if response_line.is_empty() {
-            anyhow::bail!("Empty response from MCP server");
+        // Read until we get our response, servicing any server-initiated
+        // requests (e.g. sampling) and notifications along the way
+        loop {
+            let mut response_line = String::new();
+            let _bytes_read = self.stdout.read_line(&mut response_line).await?;
+            
+            debug!("Received MCP response: {}", response_line);
+            
+            if response_line.is_empty() {
+                match self.recent_stderr() {
+                    Some(stderr) => anyhow::bail!("Empty response from MCP server. Server stderr:\n{}", stderr),
+                    None => anyhow::bail!("Empty response from MCP server"),
+                }
+            }
+            
+            let message: Value = serde_json::from_str(&response_line)?;
+            if message.get("method").is_some() {
+                self.handle_server_message(message).await?;
+                continue;
+            }
+            
+            let response: JsonRpcResponse = serde_json::from_value(message)?;
+            
+            if let Some(error) = response.error {
+                anyhow::bail!("MCP error: {} - {}", error.code, error.message);
+            }
+            
+            return response.result.context("No result in MCP response");
         }
+    }
+    
+    /// Handle a request or notification sent by the server
+    async fn handle_server_message(&mut self, message: Value) -> Result<()> {
+        let method = message.get("method").and_then(|m| m.as_str()).unwrap_or_default().to_string();
+        
+        let Some(id) = message.get("id").cloned() else {
+            debug!("Received MCP notification: {}", method);
+            return Ok(());
+        };
         
-        let response: JsonRpcResponse = serde_json::from_str(&response_line)?;
+        let params = message.get("params").cloned().unwrap_or(Value::Null);
+        let outcome = match (method.as_str(), &self.sampling_handler) {
+            ("sampling/createMessage", Some(handler)) => handler(params).await
+                .map_err(|e| JsonRpcError { code: -32603, message: e.to_string(), data: None }),
+            ("ping", _) => Ok(json!({})),
+            _ => Err(JsonRpcError {
+                code: -32601,
+                message: format!("Method not supported by client: {}", method),
+                data: None,
+            }),
+        };
         
-        if let Some(error) = response.error {
-            anyhow::bail!("MCP error: {} - {}", error.code, error.message);
-        }
+        let response = match outcome {
+            Ok(result) => JsonRpcResponse { jsonrpc: "2.0".to_string(), id, result: Some(result), error: None },
+            Err(error) => JsonRpcResponse { jsonrpc: "2.0".to_string(), id, result: None, error: Some(error) },
+        };
         
-        response.result.context("No result in MCP response")
+        let response_str = serde_json::to_string(&response)?;
+        debug!("Sending MCP response: {}", response_str);
+        self.stdin.write_all(response_str.as_bytes()).await?;
+        self.stdin.write_all(b"\n").await?;
+        self.stdin.flush().await?;
+        Ok(())
     }
     
     fn next_id(&mut self) -> u64 {
@@ -154,7 +420,7 @@ impl McpConnection {
         });
         
         let notification_str = serde_json::to_string(&notification)?;
-        // eprintln!("DEBUG: Sending notification: {}", notification_str);
+        debug!("Sending MCP notification: {}", notification_str);
         
         self.stdin.write_all(notification_str.as_bytes()).await?;
         self.stdin.write_all(b"\n").await?;
@@ -170,6 +436,27 @@ pub struct ToolSchema {
     pub description: String,
     #[serde(rename = "inputSchema")]
     pub input_schema: Value,
+    /// Behavioural hints such as `readOnlyHint` (MCP 2025-03-26)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub annotations: Option<Value>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PromptSchema {
+    pub name: String,
+    #[serde(default)]
+    pub description: Option<String>,
+    #[serde(default)]
+    pub arguments: Vec<PromptArgument>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PromptArgument {
+    pub name: String,
+    #[serde(default)]
+    pub description: Option<String>,
+    #[serde(default)]
+    pub required: bool,
 }
 
 impl Drop for McpConnection {
@@ -0,0 +1,206 @@
+use anyhow::{Context, Result};
+use crate::config::Config;
+use crate::retry::RetryPolicy;
+use gamecode_backend::{
+    ChatRequest, ContentBlock, InferenceConfig, LLMBackend, Message as BackendMessage,
+    MessageRole as BackendMessageRole,
+};
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Value};
+use std::future::Future;
+use std::io::Write;
+use std::pin::Pin;
+use std::sync::Arc;
+use tracing::{debug, info};
+
+/// Callback invoked when a connected MCP server sends `sampling/createMessage`.
+/// Receives the request params and returns the JSON-RPC result.
+pub type SamplingHandler =
+    Arc<dyn Fn(Value) -> Pin<Box<dyn Future<Output = Result<Value>> + Send>> + Send + Sync>;
+
+/// Limits applied to server-initiated model calls (stored in mcp-servers.json)
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SamplingConfig {
+    /// Whether servers may request completions at all
+    #[serde(default = "default_enabled")]
+    pub enabled: bool,
+    /// Skip the interactive approval prompt
+    #[serde(default)]
+    pub auto_approve: bool,
+    /// Upper bound on `maxTokens` a server may request
+    #[serde(default = "default_max_tokens")]
+    pub max_tokens: u32,
+    /// Model to use for sampling; defaults to the active model
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub model: Option<String>,
+}
+
+fn default_enabled() -> bool {
+    true
+}
+
+fn default_max_tokens() -> u32 {
+    1024
+}
+
+impl Default for SamplingConfig {
+    fn default() -> Self {
+        Self {
+            enabled: default_enabled(),
+            auto_approve: false,
+            max_tokens: default_max_tokens(),
+            model: None,
+        }
+    }
+}
+
+/// Routes MCP sampling requests through the active LLM backend
+pub struct McpSampler {
+    backend: Arc<dyn LLMBackend>,
+    model: String,
+    config: SamplingConfig,
+}
+
+impl McpSampler {
+    pub fn new(backend: Arc<dyn LLMBackend>, model: String, config: SamplingConfig) -> Self {
+        Self {
+            backend,
+            model,
+            config,
+        }
+    }
+
+    /// Wrap this sampler as a handler for `McpConnection`
+    pub fn into_handler(self) -> SamplingHandler {
+        let sampler = Arc::new(self);
+        Arc::new(move |params: Value| {
+            let sampler = sampler.clone();
+            Box::pin(async move { sampler.create_message(params).await })
+        })
+    }
+
+    async fn create_message(&self, params: Value) -> Result<Value> {
+        let request: CreateMessageParams =
+            serde_json::from_value(params).context("Invalid sampling/createMessage params")?;
+
+        let model = self.config.model.clone().unwrap_or_else(|| self.model.clone());
+        let max_tokens = request.max_tokens.min(self.config.max_tokens);
+
+        if !self.config.auto_approve && !Self::confirm(&request, &model, max_tokens).await? {
+            anyhow::bail!("User rejected sampling request");
+        }
+
+        let mut messages = Vec::new();
+        if let Some(system_prompt) = &request.system_prompt {
+            messages.push(BackendMessage::text(
+                BackendMessageRole::System,
+                system_prompt.clone(),
+            ));
+        }
+        for message in &request.messages {
+            let role = match message.role.as_str() {
+                "assistant" => BackendMessageRole::Assistant,
+                _ => BackendMessageRole::User,
+            };
+            let text = message
+                .content
+                .get("text")
+                .and_then(|t| t.as_str())
+                .context("Only text content is supported in sampling requests")?;
+            messages.push(BackendMessage::text(role, text.to_string()));
+        }
+
+        info!("Sampling {} messages with model {} for MCP server", messages.len(), model);
+
+        let temperature = request.temperature.or(Some(0.7));
+        let retry = Config::load().map(|c| RetryPolicy::from_config(&c.retry)).unwrap_or_default();
+        let response = retry
+            .run(
+                || {
+                    let chat_request = ChatRequest {
+                        messages: messages.clone(),
+                        tools: None,
+                        model: Some(model.clone()),
+                        inference_config: Some(InferenceConfig {
+                            temperature,
+                            max_tokens: Some(max_tokens),
+                            top_p: Some(0.9),
+                        }),
+                        session_id: None,
+                        status_callback: None,
+                    };
+                    self.backend.chat_with_retry(chat_request, retry.single_attempt())
+                },
+                |_, _, _, _| {},
+            )
+            .await
+            .context("Sampling request to backend failed")?;
+
+        let text = response
+            .message
+            .content
+            .iter()
+            .filter_map(|block| match block {
+                ContentBlock::Text(text) => Some(text.as_str()),
+                _ => None,
+            })
+            .collect::<Vec<_>>()
+            .join("");
+
+        debug!("Sampling produced {} chars", text.len());
+
+        Ok(json!({
+            "role": "assistant",
+            "content": {
+                "type": "text",
+                "text": text
+            },
+            "model": model,
+            "stopReason": "endTurn"
+        }))
+    }
+
+    /// Ask the user whether a server may use the model on their behalf
+    async fn confirm(request: &CreateMessageParams, model: &str, max_tokens: u32) -> Result<bool> {
+        let preview = request
+            .messages
+            .last()
+            .and_then(|m| m.content.get("text"))
+            .and_then(|t| t.as_str())
+            .unwrap_or("")
+            .chars()
+            .take(200)
+            .collect::<String>();
+
+        notice!("\n🤖 MCP server requests a model completion ({}, max {} tokens)", model, max_tokens);
+        eprintln!("   Last message: {}", preview);
+        eprint!("   Allow? [y/N] ");
+        std::io::stderr().flush().ok();
+
+        let answer = tokio::task::spawn_blocking(|| {
+            let mut line = String::new();
+            std::io::stdin().read_line(&mut line).map(|_| line)
+        })
+        .await??;
+
+        Ok(matches!(answer.trim().to_lowercase().as_str(), "y" | "yes"))
+    }
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct CreateMessageParams {
+    messages: Vec<SamplingMessage>,
+    #[serde(default)]
+    system_prompt: Option<String>,
+    #[serde(default = "default_max_tokens")]
+    max_tokens: u32,
+    #[serde(default)]
+    temperature: Option<f32>,
+}
+
+#[derive(Debug, Deserialize)]
+struct SamplingMessage {
+    role: String,
+    content: Value,
+}
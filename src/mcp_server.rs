@@ -0,0 +1,162 @@
+use anyhow::{Context, Result};
+use crate::mcp_protocol::{JsonRpcResponse, ToolSchema, SUPPORTED_PROTOCOL_VERSIONS};
+use crate::server::ServerState;
+use gamecode_prompt::PromptManager;
+use serde_json::{json, Value};
+use std::sync::Arc;
+use tokio::io::{AsyncBufReadExt, AsyncWriteExt, BufReader};
+use tokio::sync::Mutex;
+use tracing::debug;
+use uuid::Uuid;
+
+/// JSON-RPC error codes used by this server
+const METHOD_NOT_FOUND: i32 = -32601;
+const INVALID_PARAMS: i32 = -32602;
+
+/// Tools gamecode offers to MCP clients
+fn tool_schemas() -> Vec<ToolSchema> {
+    vec![
+        ToolSchema {
+            name: "run_prompt".to_string(),
+            description: "Delegate a task to the gamecode agent, which can use its own configured tools. \
+Returns the final answer and the session ID; pass session_id to continue the same conversation."
+                .to_string(),
+            input_schema: json!({
+                "type": "object",
+                "properties": {
+                    "prompt": { "type": "string", "description": "What to ask or do" },
+                    "session_id": { "type": "string", "description": "Session to continue (default: a new session)" },
+                    "model": { "type": "string", "description": "Model alias or ID (default: gamecode's configured model)" }
+                },
+                "required": ["prompt"]
+            }),
+            annotations: None,
+        },
+        ToolSchema {
+            name: "list_sessions".to_string(),
+            description: "List gamecode sessions with their creation time and message count.".to_string(),
+            input_schema: json!({ "type": "object", "properties": {} }),
+            annotations: Some(json!({ "readOnlyHint": true })),
+        },
+        ToolSchema {
+            name: "list_prompts".to_string(),
+            description: "List the system prompts stored in gamecode.".to_string(),
+            input_schema: json!({ "type": "object", "properties": {} }),
+            annotations: Some(json!({ "readOnlyHint": true })),
+        },
+    ]
+}
+
+/// Speak MCP over stdin/stdout until stdin closes. Requests are handled
+/// concurrently so a long `run_prompt` doesn't block pings or listings.
+pub async fn serve_stdio(state: ServerState) -> Result<()> {
+    let state = Arc::new(state);
+    let stdout = Arc::new(Mutex::new(tokio::io::stdout()));
+    let mut lines = BufReader::new(tokio::io::stdin()).lines();
+
+    while let Some(line) = lines.next_line().await.context("Failed to read from stdin")? {
+        if line.trim().is_empty() {
+            continue;
+        }
+        let message: Value = match serde_json::from_str(&line) {
+            Ok(message) => message,
+            Err(e) => {
+                debug!("Ignoring malformed MCP message: {}", e);
+                continue;
+            }
+        };
+        let Some(method) = message.get("method").and_then(Value::as_str).map(str::to_string) else {
+            continue;
+        };
+        // Notifications (no id) such as notifications/initialized need no answer
+        let Some(id) = message.get("id").cloned() else {
+            debug!("Received notification {}", method);
+            continue;
+        };
+        let params = message.get("params").cloned().unwrap_or(Value::Null);
+
+        let state = state.clone();
+        let stdout = stdout.clone();
+        tokio::spawn(async move {
+            let response = match handle(&state, &method, params).await {
+                Ok(result) => JsonRpcResponse::success(id, result),
+                Err((code, message)) => JsonRpcResponse::failure(id, code, message),
+            };
+            let Ok(mut text) = serde_json::to_string(&response) else { return };
+            text.push('\n');
+            let mut stdout = stdout.lock().await;
+            if stdout.write_all(text.as_bytes()).await.is_ok() {
+                stdout.flush().await.ok();
+            }
+        });
+    }
+    Ok(())
+}
+
+async fn handle(state: &ServerState, method: &str, params: Value) -> Result<Value, (i32, String)> {
+    match method {
+        "initialize" => {
+            // Answer with the client's version if we speak it, else our newest
+            let requested = params.get("protocolVersion").and_then(Value::as_str);
+            let version = requested
+                .filter(|v| SUPPORTED_PROTOCOL_VERSIONS.contains(v))
+                .unwrap_or(SUPPORTED_PROTOCOL_VERSIONS[0]);
+            Ok(json!({
+                "protocolVersion": version,
+                "capabilities": { "tools": {} },
+                "serverInfo": { "name": "gamecode", "version": env!("CARGO_PKG_VERSION") }
+            }))
+        }
+        "ping" => Ok(json!({})),
+        "tools/list" => Ok(json!({ "tools": tool_schemas() })),
+        "tools/call" => {
+            let name = params.get("name").and_then(Value::as_str).unwrap_or("");
+            let arguments = params.get("arguments").cloned().unwrap_or_else(|| json!({}));
+            // Tool failures are results the client's model can see, not protocol errors
+            let result = match name {
+                "run_prompt" => run_prompt(state, &arguments).await,
+                "list_sessions" => list_sessions(state).await,
+                "list_prompts" => list_prompts(),
+                other => return Err((INVALID_PARAMS, format!("Unknown tool: {}", other))),
+            };
+            Ok(match result {
+                Ok(content) => json!({ "content": content, "isError": false }),
+                Err(e) => json!({
+                    "content": [{ "type": "text", "text": format!("{:#}", e) }],
+                    "isError": true
+                }),
+            })
+        }
+        other => Err((METHOD_NOT_FOUND, format!("Method not supported: {}", other))),
+    }
+}
+
+async fn run_prompt(state: &ServerState, arguments: &Value) -> Result<Value> {
+    let prompt = arguments
+        .get("prompt")
+        .and_then(Value::as_str)
+        .context("Missing required argument 'prompt'")?;
+    let model = arguments.get("model").and_then(Value::as_str).map(str::to_string);
+    let session_id = match arguments.get("session_id").and_then(Value::as_str) {
+        Some(id) => Uuid::parse_str(id).context("Invalid session_id")?,
+        None => state.create_session(None, None).await?.id,
+    };
+
+    let outcome = state.run_turn(session_id, prompt.to_string(), model, |_| {}).await?;
+    Ok(json!([
+        { "type": "text", "text": outcome.text },
+        { "type": "text", "text": format!("session_id: {}", session_id) }
+    ]))
+}
+
+async fn list_sessions(state: &ServerState) -> Result<Value> {
+    let sessions = state.list_sessions().await?;
+    Ok(json!([{ "type": "text", "text": serde_json::to_string_pretty(&sessions)? }]))
+}
+
+fn list_prompts() -> Result<Value> {
+    let prompts = PromptManager::new()
+        .context("Failed to create prompt manager")?
+        .list_prompts()?;
+    Ok(json!([{ "type": "text", "text": prompts.join("\n") }]))
+}
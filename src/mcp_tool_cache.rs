@@ -0,0 +1,113 @@
+use anyhow::{bail, Context, Result};
+use crate::mcp_config::McpServerConfig;
+use crate::mcp_protocol::ToolSchema;
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use std::fs;
+use std::path::PathBuf;
+use tracing::debug;
+
+/// On-disk cache entry for a single MCP server's discovered tools
+#[derive(Debug, Serialize, Deserialize)]
+struct CacheEntry {
+    /// Hash of the server's launch configuration; a mismatch invalidates the entry
+    key: String,
+    tools: Vec<ToolSchema>,
+}
+
+/// Caches tool schemas per MCP server under `~/.cache/gamecode/mcp/<server>.json`
+/// so normal runs don't have to spawn every server just to discover its tools.
+pub struct McpToolCache;
+
+impl McpToolCache {
+    pub fn cache_dir() -> Result<PathBuf> {
+        let home = home::home_dir().context("Failed to get home directory")?;
+        Ok(home.join(".cache").join("gamecode").join("mcp"))
+    }
+
+    fn cache_path(server_name: &str) -> Result<PathBuf> {
+        // The name becomes a file name, so it must not reach outside the cache
+        let safe = !server_name.is_empty()
+            && !server_name.starts_with('.')
+            && server_name.chars().all(|c| c.is_ascii_alphanumeric() || matches!(c, '-' | '_' | '.'));
+        if !safe {
+            bail!("MCP server name '{}' can't be used as a cache file name", server_name);
+        }
+        Ok(Self::cache_dir()?.join(format!("{}.json", server_name)))
+    }
+
+    /// Key derived from everything that determines what the server exposes.
+    /// SHA-256 rather than `DefaultHasher`, whose output may change between
+    /// Rust releases and would invalidate every entry on disk.
+    fn cache_key(server: &McpServerConfig) -> String {
+        let mut env: Vec<(&String, &String)> = server.env.iter().collect();
+        env.sort();
+        let fingerprint = serde_json::to_string(&(&server.command, &server.args, &server.cwd, env)).unwrap_or_default();
+        Sha256::digest(fingerprint.as_bytes()).iter().map(|byte| format!("{:02x}", byte)).collect()
+    }
+
+    /// Load cached tools for a server, if present and still valid
+    pub fn load(server: &McpServerConfig) -> Option<Vec<ToolSchema>> {
+        let path = Self::cache_path(&server.name).ok()?;
+        let content = fs::read_to_string(&path).ok()?;
+        let entry: CacheEntry = match serde_json::from_str(&content) {
+            Ok(entry) => entry,
+            Err(e) => {
+                debug!("Ignoring unreadable tool cache for '{}': {}", server.name, e);
+                return None;
+            }
+        };
+
+        if entry.key != Self::cache_key(server) {
+            debug!("Tool cache for '{}' is stale (configuration changed)", server.name);
+            return None;
+        }
+
+        Some(entry.tools)
+    }
+
+    /// Store discovered tools for a server
+    pub fn store(server: &McpServerConfig, tools: &[ToolSchema]) -> Result<()> {
+        let path = Self::cache_path(&server.name)?;
+        if let Some(parent) = path.parent() {
+            fs::create_dir_all(parent)?;
+        }
+
+        let entry = CacheEntry {
+            key: Self::cache_key(server),
+            tools: tools.to_vec(),
+        };
+        fs::write(&path, serde_json::to_string_pretty(&entry)?)
+            .with_context(|| format!("Failed to write tool cache for '{}'", server.name))?;
+        Ok(())
+    }
+
+    /// Remove the cached tools for one server. Returns true if an entry existed.
+    pub fn invalidate(server_name: &str) -> Result<bool> {
+        let path = Self::cache_path(server_name)?;
+        if path.exists() {
+            fs::remove_file(&path)?;
+            Ok(true)
+        } else {
+            Ok(false)
+        }
+    }
+
+    /// Remove all cached tool schemas. Returns the number of entries removed.
+    pub fn invalidate_all() -> Result<usize> {
+        let dir = Self::cache_dir()?;
+        if !dir.exists() {
+            return Ok(0);
+        }
+
+        let mut removed = 0;
+        for entry in fs::read_dir(&dir)? {
+            let path = entry?.path();
+            if path.extension().is_some_and(|ext| ext == "json") {
+                fs::remove_file(&path)?;
+                removed += 1;
+            }
+        }
+        Ok(removed)
+    }
+}
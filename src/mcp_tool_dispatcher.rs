@@ -1,5 +1,7 @@
 use anyhow::Result;
+use crate::mcp_sampling::McpSampler;
 use crate::mcp_tool_registry::McpToolRegistry;
+use gamecode_backend::LLMBackend;
 use serde_json::Value;
 use std::sync::Arc;
 use tokio::sync::Mutex;
@@ -23,8 +25,9 @@ impl McpToolDispatcher {
     pub async fn call_tool(&self, tool_name: &str, params: Value) -> Result<Value> {
         debug!("Dispatching tool call: {}", tool_name);
         
-        let registry = self.registry.lock().await;
-        registry.call_tool(tool_name, params).await
+        // Release the registry lock before calling so tool calls can run concurrently
+        let (client, server, tool_name) = self.registry.lock().await.resolve_call(tool_name)?;
+        client.call_tool(&server, &tool_name, params).await
     }
     
     /// Get the registry for tool listing
@@ -32,6 +35,17 @@ impl McpToolDispatcher {
         self.registry.clone()
     }
     
+    /// Route server-initiated sampling requests through the active backend
+    pub async fn enable_sampling(&self, backend: Arc<dyn LLMBackend>, model: String) {
+        let mut registry = self.registry.lock().await;
+        let config = registry.sampling_config().clone();
+        if !config.enabled {
+            debug!("MCP sampling disabled in configuration");
+            return;
+        }
+        registry.set_sampling_handler(McpSampler::new(backend, model, config).into_handler());
+    }
+    
     /// Refresh tools from all MCP servers
     pub async fn refresh_tools(&self) -> Result<()> {
         let mut registry = self.registry.lock().await;
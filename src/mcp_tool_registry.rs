@@ -1,16 +1,22 @@
 use anyhow::{Context as _, Result};
-use crate::cmd::mcp::McpConfig;
-use crate::mcp_client::McpClient;
+use crate::config::Config;
+use crate::mcp_config::{McpConfig, McpServerConfig};
+use crate::mcp_client::{error_hint, McpClient};
 use crate::mcp_protocol::ToolSchema;
+use crate::mcp_sampling::{SamplingConfig, SamplingHandler};
+use crate::mcp_tool_cache::McpToolCache;
+use crate::tool_filter::ToolFilter;
 use gamecode_backend::Tool as BackendTool;
 use serde_json::{json, Value};
 use std::collections::HashMap;
-use tracing::{debug, info, warn};
+use tracing::{debug, info, trace, warn};
 
 pub struct McpToolRegistry {
     /// Map from tool name to (server_name, tool_schema)
     tools: HashMap<String, (String, ToolSchema)>,
     config: McpConfig,
+    /// Servers named by `mcp_servers` in config; the only ones started if set
+    only: Option<Vec<String>>,
     client: McpClient,
 }
 
@@ -19,9 +25,11 @@ impl McpToolRegistry {
         let config = McpConfig::load()
             .map_err(|e| anyhow::anyhow!("Failed to load MCP server configuration: {}", e))?;
         
+        let only = Config::load().ok().and_then(|c| c.mcp_servers);
         let mut registry = Self {
             tools: HashMap::new(),
             config,
+            only,
             client: McpClient::new(),
         };
         
@@ -29,123 +37,58 @@ impl McpToolRegistry {
         Ok(registry)
     }
     
-    /// Refresh the tool list from all enabled servers
+    /// Whether a server is started: named in `mcp_servers` if that is set,
+    /// otherwise enabled in the MCP config
+    fn is_active(&self, server: &McpServerConfig) -> bool {
+        match &self.only {
+            Some(names) => names.contains(&server.name),
+            None => server.enabled,
+        }
+    }
+    
+    /// Refresh the tool list from all enabled servers, using cached schemas when available
     pub async fn refresh_tools(&mut self) -> Result<()> {
         info!("Refreshing MCP tool registry");
         self.tools.clear();
         
         for server in &self.config.servers {
-            if !server.enabled {
+            if !self.is_active(server) {
                 debug!("Skipping disabled server: {}", server.name);
                 continue;
             }
             
-            match self.client.list_tools(server).await {
-                Ok(mcp_tools) => {
-                    info!("MCP server '{}' exposes {} protocol tools", server.name, mcp_tools.len());
-                    eprintln!("\nDEBUG: Raw tools from MCP server '{}':", server.name);
-                    for tool in &mcp_tools {
-                        eprintln!("  - name: {}", tool.name);
-                        eprintln!("    description: {}", tool.description);
-                        eprintln!("    schema: {}", serde_json::to_string_pretty(&tool.input_schema).unwrap_or_default());
-                    }
-                    
-                    // Check if this server uses the meta-tool pattern (has a list_tools tool)
-                    let has_list_tools = mcp_tools.iter().any(|t| t.name == "list_tools");
-                    let has_run = mcp_tools.iter().any(|t| t.name == "run");
-                    
-                    if has_list_tools && has_run {
-                        // This is a meta-tool pattern server like gamecode-mcp
-                        // We need to call list_tools to get the actual tools
-                        info!("Server '{}' uses meta-tool pattern, fetching actual tools...", server.name);
-                        
-                        match self.client.call_tool(server, "list_tools", json!({})).await {
-                            Ok(result) => {
-                                // Parse the response to get actual tools
-                                if let Some(content) = result.get("content") {
-                                    if let Some(content_array) = content.as_array() {
-                                        for item in content_array {
-                                            if let Some(text) = item.get("text").and_then(|t| t.as_str()) {
-                                                if let Ok(parsed) = serde_json::from_str::<Value>(text) {
-                                                    if let Some(tools_array) = parsed.get("tools") {
-                                                        if let Ok(actual_tools) = serde_json::from_value::<Vec<Value>>(tools_array.clone()) {
-                                                            info!("Found {} actual tools from server '{}'", actual_tools.len(), server.name);
-                                                            
-                                                            // For meta-tool pattern, we register a special handler
-                                                            // that knows to use the "run" tool
-                                                            for tool_value in actual_tools {
-                                                                if let Ok(mut tool_def) = serde_json::from_value::<Value>(tool_value) {
-                                                                    if let Some(name) = tool_def.get("name").and_then(|n| n.as_str()) {
-                                                                        let desc = tool_def.get("description")
-                                                                            .and_then(|d| d.as_str())
-                                                                            .unwrap_or("")
-                                                                            .to_string();
-                                                                        
-                                                                        // Create a tool schema that will use the run meta-tool
-                                                                        let tool_schema = ToolSchema {
-                                                                            name: name.to_string(),
-                                                                            description: desc,
-                                                                            input_schema: json!({
-                                                                                "type": "object",
-                                                                                "properties": {
-                                                                                    "tool": {
-                                                                                        "type": "string",
-                                                                                        "const": name,
-                                                                                        "description": "Tool name"
-                                                                                    },
-                                                                                    "params": {
-                                                                                        "type": "object",
-                                                                                        "description": "Tool parameters"
-                                                                                    }
-                                                                                },
-                                                                                "required": ["tool", "params"]
-                                                                            }),
-                                                                        };
-                                                                        
-                                                                        self.tools.insert(
-                                                                            name.to_string(),
-                                                                            (server.name.clone(), tool_schema)
-                                                                        );
-                                                                    }
-                                                                }
-                                                            }
-                                                        }
-                                                    }
-                                                }
-                                            }
-                                        }
-                                    }
-                                }
-                            }
-                            Err(e) => {
-                                warn!("Failed to get actual tools from meta-tool server '{}': {}", server.name, e);
-                            }
+            let tools = if let Some(cached) = McpToolCache::load(server) {
+                debug!("Using cached tools for server '{}'", server.name);
+                cached
+            } else {
+                match Self::discover_tools(&self.client, server).await {
+                    Ok(tools) => {
+                        if let Err(e) = McpToolCache::store(server, &tools) {
+                            warn!("Failed to cache tools for server '{}': {}", server.name, e);
                         }
-                    } else {
-                        // Regular MCP server - tools are directly exposed
-                        info!("Loaded {} tools from server '{}'", mcp_tools.len(), server.name);
-                        for tool in mcp_tools {
-                            let tool_name = tool.name.clone();
-                            if let Some((existing_server, _)) = self.tools.get(&tool_name) {
-                                warn!(
-                                    "Tool '{}' already registered by server '{}', skipping from '{}'",
-                                    tool_name, existing_server, server.name
-                                );
-                            } else {
-                                self.tools.insert(tool_name, (server.name.clone(), tool));
-                            }
+                        tools
+                    }
+                    Err(e) => {
+                        warn!("Failed to list tools from server '{}': {}", server.name, e);
+                        // Check if this is a common error
+                        if let Some(hint) = error_hint(&e.to_string()) {
+                            warn!("  {}", hint);
                         }
+                        continue;
                     }
                 }
-                Err(e) => {
-                    warn!("Failed to list tools from server '{}': {}", server.name, e);
-                    // Check if this is a common error
-                    let error_msg = e.to_string();
-                    if error_msg.contains("No such file or directory") || error_msg.contains("cannot find") {
-                        warn!("  Server binary not found. Is it installed?");
-                    } else if error_msg.contains("EOF") {
-                        warn!("  Server may need a tools.yaml file in the current directory");
-                    }
+            };
+            
+            info!("Loaded {} tools from server '{}'", tools.len(), server.name);
+            for tool in tools {
+                let tool_name = tool.name.clone();
+                if let Some((existing_server, _)) = self.tools.get(&tool_name) {
+                    warn!(
+                        "Tool '{}' already registered by server '{}', skipping from '{}'",
+                        tool_name, existing_server, server.name
+                    );
+                } else {
+                    self.tools.insert(tool_name, (server.name.clone(), tool));
                 }
             }
         }
@@ -154,10 +97,111 @@ impl McpToolRegistry {
         Ok(())
     }
     
+    /// Spawn a server and discover the tools it exposes
+    async fn discover_tools(client: &McpClient, server: &McpServerConfig) -> Result<Vec<ToolSchema>> {
+        let mcp_tools = client.list_tools(server).await?;
+        info!("MCP server '{}' exposes {} protocol tools", server.name, mcp_tools.len());
+        for tool in &mcp_tools {
+            trace!(
+                "MCP server '{}' tool '{}': {} schema={}",
+                server.name,
+                tool.name,
+                tool.description,
+                tool.input_schema
+            );
+        }
+        
+        // Check if this server uses the meta-tool pattern (has a list_tools tool)
+        let has_list_tools = mcp_tools.iter().any(|t| t.name == "list_tools");
+        let has_run = mcp_tools.iter().any(|t| t.name == "run");
+        
+        if !(has_list_tools && has_run) {
+            // Regular MCP server - tools are directly exposed
+            return Ok(mcp_tools);
+        }
+        
+        // This is a meta-tool pattern server like gamecode-mcp
+        // We need to call list_tools to get the actual tools
+        info!("Server '{}' uses meta-tool pattern, fetching actual tools...", server.name);
+        
+        let result = client.call_tool(server, "list_tools", json!({})).await
+            .with_context(|| format!("Failed to get actual tools from meta-tool server '{}'", server.name))?;
+        
+        let mut tools = Vec::new();
+        
+        // Parse the response to get actual tools
+        let content_array = result.get("content").and_then(|c| c.as_array());
+        for item in content_array.into_iter().flatten() {
+            let Some(text) = item.get("text").and_then(|t| t.as_str()) else { continue };
+            let Ok(parsed) = serde_json::from_str::<Value>(text) else { continue };
+            let Some(actual_tools) = parsed.get("tools").and_then(|t| t.as_array()) else { continue };
+            info!("Found {} actual tools from server '{}'", actual_tools.len(), server.name);
+            
+            // For meta-tool pattern, we register a special handler
+            // that knows to use the "run" tool
+            for tool_def in actual_tools {
+                if let Some(name) = tool_def.get("name").and_then(|n| n.as_str()) {
+                    let desc = tool_def.get("description")
+                        .and_then(|d| d.as_str())
+                        .unwrap_or("")
+                        .to_string();
+                    
+                    // Create a tool schema that will use the run meta-tool
+                    tools.push(ToolSchema {
+                        name: name.to_string(),
+                        description: desc,
+                        annotations: tool_def.get("annotations").cloned(),
+                        input_schema: json!({
+                            "type": "object",
+                            "properties": {
+                                "tool": {
+                                    "type": "string",
+                                    "const": name,
+                                    "description": "Tool name"
+                                },
+                                "params": {
+                                    "type": "object",
+                                    "description": "Tool parameters"
+                                }
+                            },
+                            "required": ["tool", "params"]
+                        }),
+                    });
+                }
+            }
+        }
+        
+        Ok(tools)
+    }
+    
+    /// Let servers request model completions during tool calls
+    pub fn set_sampling_handler(&mut self, handler: SamplingHandler) {
+        self.client.set_sampling_handler(handler);
+    }
+    
+    /// Sampling limits from the MCP configuration
+    pub fn sampling_config(&self) -> &SamplingConfig {
+        &self.config.sampling
+    }
+    
     /// Convert MCP tools to Bedrock format
     pub fn to_bedrock_tools(&self) -> Vec<BackendTool> {
-        self.tools
+        self.to_bedrock_tools_filtered(&ToolFilter::default())
+    }
+    
+    /// Convert the MCP tools permitted by `filter` to Bedrock format.
+    /// Tools are sorted by name so the request prefix is identical across turns
+    /// and runs, which Bedrock prompt caching depends on.
+    pub fn to_bedrock_tools_filtered(&self, filter: &ToolFilter) -> Vec<BackendTool> {
+        let mut tools: Vec<BackendTool> = self.tools
             .values()
+            .filter(|(server_name, tool)| {
+                let allowed = filter.allows(server_name, tool);
+                if !allowed {
+                    debug!("Filtered out tool '{}' from server '{}'", tool.name, server_name);
+                }
+                allowed
+            })
             .map(|(server_name, tool)| {
                 debug!("Converting tool '{}' from server '{}'", tool.name, server_name);
                 
@@ -181,11 +225,20 @@ impl McpToolRegistry {
                     input_schema,
                 }
             })
-            .collect()
+            .collect();
+        tools.sort_by(|a, b| a.name.cmp(&b.name));
+        tools
     }
     
     /// Call a tool on the appropriate MCP server
     pub async fn call_tool(&self, full_tool_name: &str, params: Value) -> Result<Value> {
+        let (client, server, tool_name) = self.resolve_call(full_tool_name)?;
+        client.call_tool(&server, &tool_name, params).await
+    }
+    
+    /// Resolve everything needed to call a tool, so the call itself can run
+    /// without holding a lock on the registry
+    pub fn resolve_call(&self, full_tool_name: &str) -> Result<(McpClient, McpServerConfig, String)> {
         // Parse the tool name (format: "servername_toolname")
         let parts: Vec<&str> = full_tool_name.splitn(2, '_').collect();
         if parts.len() != 2 {
@@ -200,13 +253,12 @@ impl McpToolRegistry {
             .find(|s| s.name == server_name)
             .ok_or_else(|| anyhow::anyhow!("Server '{}' not found", server_name))?;
         
-        if !server.enabled {
+        if !self.is_active(server) {
             anyhow::bail!("Server '{}' is disabled", server_name);
         }
         
-        // Call the tool
         info!("Calling tool '{}' on server '{}'", tool_name, server_name);
-        self.client.call_tool(server, tool_name, params).await
+        Ok((self.client.clone(), server.clone(), tool_name.to_string()))
     }
     
     /// Get tool info by name
@@ -218,4 +270,26 @@ impl McpToolRegistry {
     pub fn list_tools(&self) -> Vec<String> {
         self.tools.keys().cloned().collect()
     }
+    
+    /// All registered tools as (server_name, tool_schema), sorted by server then tool name
+    pub fn entries(&self) -> Vec<(&str, &ToolSchema)> {
+        let mut entries: Vec<(&str, &ToolSchema)> = self.tools
+            .values()
+            .map(|(server_name, tool)| (server_name.as_str(), tool))
+            .collect();
+        entries.sort_by(|a, b| a.0.cmp(b.0).then_with(|| a.1.name.cmp(&b.1.name)));
+        entries
+    }
+    
+    /// Resolve a bare tool name or a "servername_toolname" name to the
+    /// fully qualified name used by `call_tool`
+    pub fn resolve_tool_name(&self, name: &str) -> Option<String> {
+        if let Some((server_name, _)) = self.tools.get(name) {
+            return Some(format!("{}_{}", server_name, name));
+        }
+        self.tools
+            .iter()
+            .find(|(tool_name, (server_name, _))| format!("{}_{}", server_name, tool_name) == name)
+            .map(|_| name.to_string())
+    }
 }
\ No newline at end of file
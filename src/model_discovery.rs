@@ -0,0 +1,114 @@
+use anyhow::{Context, Result};
+use aws_config::{BehaviorVersion, Region};
+use serde::{Deserialize, Serialize};
+use std::fs;
+use std::path::PathBuf;
+use tracing::debug;
+
+/// A model or inference profile available in a region
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct DiscoveredModel {
+    pub id: String,
+    pub name: String,
+    pub provider: Option<String>,
+    /// True for cross-region inference profiles rather than base models
+    pub inference_profile: bool,
+}
+
+/// Models discovered in one region, cached under `~/.cache/gamecode/models/<region>.json`
+#[derive(Debug, Serialize, Deserialize)]
+pub struct ModelCatalog {
+    pub region: String,
+    pub fetched_at: chrono::DateTime<chrono::Utc>,
+    pub models: Vec<DiscoveredModel>,
+}
+
+impl ModelCatalog {
+    fn cache_path(region: &str) -> Result<PathBuf> {
+        let home = home::home_dir().context("Failed to get home directory")?;
+        Ok(home
+            .join(".cache")
+            .join("gamecode")
+            .join("models")
+            .join(format!("{}.json", region)))
+    }
+
+    /// Load the cached catalog for a region, if one has been fetched
+    pub fn load_cached(region: &str) -> Option<Self> {
+        let path = Self::cache_path(region).ok()?;
+        let content = fs::read_to_string(path).ok()?;
+        serde_json::from_str(&content).ok()
+    }
+
+    pub fn save(&self) -> Result<()> {
+        let path = Self::cache_path(&self.region)?;
+        if let Some(parent) = path.parent() {
+            fs::create_dir_all(parent)?;
+        }
+        fs::write(&path, serde_json::to_string_pretty(self)?)?;
+        Ok(())
+    }
+
+    /// Query Bedrock for text-capable foundation models and inference profiles
+    pub async fn fetch(region: &str) -> Result<Self> {
+        let sdk_config = aws_config::defaults(BehaviorVersion::latest())
+            .region(Region::new(region.to_string()))
+            .load()
+            .await;
+        let client = aws_sdk_bedrock::Client::new(&sdk_config);
+
+        let mut models = Vec::new();
+
+        let foundation = client
+            .list_foundation_models()
+            .send()
+            .await
+            .context("Failed to list Bedrock foundation models")?;
+        for summary in foundation.model_summaries() {
+            let text_output = summary
+                .output_modalities()
+                .iter()
+                .any(|m| m.as_str() == "TEXT");
+            if !text_output {
+                continue;
+            }
+            models.push(DiscoveredModel {
+                id: summary.model_id().to_string(),
+                name: summary.model_name().unwrap_or_default().to_string(),
+                provider: summary.provider_name().map(|p| p.to_string()),
+                inference_profile: false,
+            });
+        }
+
+        let mut next_token = None;
+        loop {
+            let page = client
+                .list_inference_profiles()
+                .set_next_token(next_token)
+                .send()
+                .await
+                .context("Failed to list Bedrock inference profiles")?;
+            for profile in page.inference_profile_summaries() {
+                models.push(DiscoveredModel {
+                    id: profile.inference_profile_id().to_string(),
+                    name: profile.inference_profile_name().to_string(),
+                    provider: None,
+                    inference_profile: true,
+                });
+            }
+            next_token = page.next_token().map(|t| t.to_string());
+            if next_token.is_none() {
+                break;
+            }
+        }
+
+        debug!("Discovered {} models in {}", models.len(), region);
+        models.sort_by(|a, b| a.id.cmp(&b.id));
+
+        Ok(Self {
+            region: region.to_string(),
+            fetched_at: chrono::Utc::now(),
+            models,
+        })
+    }
+}
@@ -0,0 +1,415 @@
+use crate::model_discovery::ModelCatalog;
+use serde_json::{json, Value};
+
+/// Model provider family, which determines request shaping for the Converse API
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ModelFamily {
+    Anthropic,
+    Nova,
+    Llama,
+    Mistral,
+    Other,
+}
+
+impl ModelFamily {
+    /// Detect the family from a model or inference profile ID
+    /// (e.g. `us.amazon.nova-pro-v1:0`, `meta.llama3-1-70b-instruct-v1:0`)
+    pub fn from_model_id(model_id: &str) -> Self {
+        let id = model_id.rsplit('/').next().unwrap_or(model_id);
+        if id.contains("anthropic.") {
+            Self::Anthropic
+        } else if id.contains("amazon.nova") {
+            Self::Nova
+        } else if id.contains("meta.llama") {
+            Self::Llama
+        } else if id.contains("mistral.") {
+            Self::Mistral
+        } else {
+            Self::Other
+        }
+    }
+
+    /// Whether the model accepts a separate system prompt
+    pub fn supports_system_prompt(&self, model_id: &str) -> bool {
+        match self {
+            // Mistral's instruct models (7B, Mixtral) reject system messages
+            Self::Mistral => !model_id.contains("instruct"),
+            _ => true,
+        }
+    }
+
+    /// Whether the model supports tool use through the Converse API
+    pub fn supports_tools(&self, model_id: &str) -> bool {
+        match self {
+            // Unrecognized IDs (e.g. application inference profile ARNs) are given the benefit of the doubt
+            Self::Anthropic | Self::Nova | Self::Other => true,
+            // Tool use arrived with Llama 3.1
+            Self::Llama => !["llama2", "llama3-8b", "llama3-70b"]
+                .iter()
+                .any(|old| model_id.contains(old)),
+            Self::Mistral => model_id.contains("mistral-large") || model_id.contains("pixtral"),
+        }
+    }
+
+    /// Adjust a JSON tool schema to what this family's tool implementation accepts
+    pub fn sanitize_tool_schema(&self, schema: &mut Value) {
+        if *self == Self::Anthropic {
+            return;
+        }
+        sanitize_schema_node(schema);
+    }
+}
+
+/// Non-Anthropic models reject several JSON Schema keywords; `const` becomes a
+/// single-value `enum`
+fn sanitize_schema_node(node: &mut Value) {
+    match node {
+        Value::Object(obj) => {
+            obj.remove("$schema");
+            obj.remove("additionalProperties");
+            obj.remove("default");
+            if let Some(value) = obj.remove("const") {
+                obj.insert("enum".to_string(), json!([value]));
+            }
+            for child in obj.values_mut() {
+                sanitize_schema_node(child);
+            }
+        }
+        Value::Array(items) => {
+            for item in items {
+                sanitize_schema_node(item);
+            }
+        }
+        _ => {}
+    }
+}
+
+/// Static metadata about a Bedrock model: its limits, capabilities and price
+pub struct ModelInfo {
+    /// Short name accepted by `--model`
+    pub alias: &'static str,
+    /// Bedrock model ID, without an inference profile prefix
+    pub id: &'static str,
+    pub description: &'static str,
+    /// Largest `max_tokens` the model accepts
+    pub max_output_tokens: u32,
+    /// Most tokens of input and output together the model can handle
+    pub context_window: u32,
+    /// The model can only be invoked through a cross-region inference
+    /// profile, whose ID is the model ID with a prefix such as `us.`
+    pub inference_profile: bool,
+    /// Geographies (see `PROFILE_PREFIXES`) with an inference profile for
+    /// the model
+    pub geographies: &'static [&'static str],
+    /// Tool use through the Converse API
+    pub supports_tools: bool,
+    /// Image content blocks
+    pub supports_images: bool,
+    /// Anthropic extended thinking
+    pub supports_thinking: bool,
+    /// Cache points in the Converse API
+    pub supports_prompt_cache: bool,
+    /// On-demand price in USD per million input tokens
+    pub input_price: f64,
+    /// On-demand price in USD per million output tokens
+    pub output_price: f64,
+}
+
+impl ModelInfo {
+    /// Estimated on-demand cost in USD for the given token counts
+    pub fn cost(&self, input_tokens: u64, output_tokens: u64) -> f64 {
+        (input_tokens as f64 * self.input_price + output_tokens as f64 * self.output_price)
+            / 1_000_000.0
+    }
+
+    /// The ID to send to Bedrock from `region`: the model ID, or the ID of
+    /// the inference profile for the region's geography when the model needs one
+    pub fn invocation_id(&self, region: &str) -> String {
+        if self.inference_profile {
+            let prefix = profile_prefix(region).unwrap_or(DEFAULT_PROFILE_PREFIX);
+            format!("{}.{}", prefix, self.id)
+        } else {
+            self.id.to_string()
+        }
+    }
+
+    /// Whether the static registry says the model can be invoked from
+    /// `region`. Only inference profile geographies are tracked, so models
+    /// invoked directly are assumed to be available.
+    pub fn available_in(&self, region: &str) -> bool {
+        !self.inference_profile
+            || profile_prefix(region).is_some_and(|prefix| self.geographies.contains(&prefix))
+    }
+}
+
+/// Geography prefixes of cross-region inference profile IDs
+pub const PROFILE_PREFIXES: &[&str] = &["us", "us-gov", "eu", "apac"];
+
+/// Geography used for models that need an inference profile when the region
+/// has none
+const DEFAULT_PROFILE_PREFIX: &str = "us";
+
+/// The inference profile geography of an AWS region, e.g. `eu` for `eu-west-1`
+pub fn profile_prefix(region: &str) -> Option<&'static str> {
+    if region.starts_with("us-gov-") {
+        Some("us-gov")
+    } else if region.starts_with("us-") {
+        Some("us")
+    } else if region.starts_with("eu-") {
+        Some("eu")
+    } else if region.starts_with("ap-") {
+        Some("apac")
+    } else {
+        None
+    }
+}
+
+pub const KNOWN_MODELS: &[ModelInfo] = &[
+    ModelInfo {
+        alias: "opus-4",
+        id: "anthropic.claude-opus-4-20250514-v1:0",
+        description: "Claude Opus 4",
+        max_output_tokens: 32_000,
+        context_window: 200_000,
+        inference_profile: true,
+        geographies: &["us"],
+        supports_tools: true,
+        supports_images: true,
+        supports_thinking: true,
+        supports_prompt_cache: true,
+        input_price: 15.0,
+        output_price: 75.0,
+    },
+    ModelInfo {
+        alias: "sonnet-4",
+        id: "anthropic.claude-sonnet-4-20250514-v1:0",
+        description: "Claude Sonnet 4",
+        max_output_tokens: 64_000,
+        context_window: 200_000,
+        inference_profile: true,
+        geographies: &["us", "eu", "apac"],
+        supports_tools: true,
+        supports_images: true,
+        supports_thinking: true,
+        supports_prompt_cache: true,
+        input_price: 3.0,
+        output_price: 15.0,
+    },
+    ModelInfo {
+        alias: "claude-3.7-sonnet",
+        id: "anthropic.claude-3-7-sonnet-20250219-v1:0",
+        description: "Claude 3.7 Sonnet",
+        max_output_tokens: 64_000,
+        context_window: 200_000,
+        inference_profile: true,
+        geographies: &["us", "eu", "apac"],
+        supports_tools: true,
+        supports_images: true,
+        supports_thinking: true,
+        supports_prompt_cache: true,
+        input_price: 3.0,
+        output_price: 15.0,
+    },
+    ModelInfo {
+        alias: "claude-3.5-sonnet",
+        id: "anthropic.claude-3-5-sonnet-20240620-v1:0",
+        description: "Claude 3.5 Sonnet",
+        max_output_tokens: 4_096,
+        context_window: 200_000,
+        inference_profile: false,
+        geographies: &[],
+        supports_tools: true,
+        supports_images: true,
+        supports_thinking: false,
+        supports_prompt_cache: false,
+        input_price: 3.0,
+        output_price: 15.0,
+    },
+    ModelInfo {
+        alias: "claude-3.5-haiku",
+        id: "anthropic.claude-3-5-haiku-20241022-v1:0",
+        description: "Claude 3.5 Haiku",
+        max_output_tokens: 8_192,
+        context_window: 200_000,
+        inference_profile: false,
+        geographies: &[],
+        supports_tools: true,
+        supports_images: false,
+        supports_thinking: false,
+        supports_prompt_cache: true,
+        input_price: 0.8,
+        output_price: 4.0,
+    },
+    ModelInfo {
+        alias: "claude-3-sonnet",
+        id: "anthropic.claude-3-sonnet-20240229-v1:0",
+        description: "Claude 3 Sonnet",
+        max_output_tokens: 4_096,
+        context_window: 200_000,
+        inference_profile: false,
+        geographies: &[],
+        supports_tools: true,
+        supports_images: true,
+        supports_thinking: false,
+        supports_prompt_cache: false,
+        input_price: 3.0,
+        output_price: 15.0,
+    },
+    ModelInfo {
+        alias: "claude-3-haiku",
+        id: "anthropic.claude-3-haiku-20240307-v1:0",
+        description: "Claude 3 Haiku",
+        max_output_tokens: 4_096,
+        context_window: 200_000,
+        inference_profile: false,
+        geographies: &[],
+        supports_tools: true,
+        supports_images: true,
+        supports_thinking: false,
+        supports_prompt_cache: false,
+        input_price: 0.25,
+        output_price: 1.25,
+    },
+    ModelInfo {
+        alias: "nova-pro",
+        id: "amazon.nova-pro-v1:0",
+        description: "Amazon Nova Pro",
+        max_output_tokens: 5_120,
+        context_window: 300_000,
+        inference_profile: true,
+        geographies: &["us", "eu", "apac"],
+        supports_tools: true,
+        supports_images: true,
+        supports_thinking: false,
+        supports_prompt_cache: true,
+        input_price: 0.8,
+        output_price: 3.2,
+    },
+    ModelInfo {
+        alias: "nova-lite",
+        id: "amazon.nova-lite-v1:0",
+        description: "Amazon Nova Lite",
+        max_output_tokens: 5_120,
+        context_window: 300_000,
+        inference_profile: true,
+        geographies: &["us", "eu", "apac"],
+        supports_tools: true,
+        supports_images: true,
+        supports_thinking: false,
+        supports_prompt_cache: true,
+        input_price: 0.06,
+        output_price: 0.24,
+    },
+    ModelInfo {
+        alias: "nova-micro",
+        id: "amazon.nova-micro-v1:0",
+        description: "Amazon Nova Micro",
+        max_output_tokens: 5_120,
+        context_window: 128_000,
+        inference_profile: true,
+        geographies: &["us", "eu", "apac"],
+        supports_tools: true,
+        supports_images: false,
+        supports_thinking: false,
+        supports_prompt_cache: true,
+        input_price: 0.035,
+        output_price: 0.14,
+    },
+    ModelInfo {
+        alias: "llama-3.3-70b",
+        id: "meta.llama3-3-70b-instruct-v1:0",
+        description: "Meta Llama 3.3 70B Instruct",
+        max_output_tokens: 8_192,
+        context_window: 128_000,
+        inference_profile: true,
+        geographies: &["us"],
+        supports_tools: true,
+        supports_images: false,
+        supports_thinking: false,
+        supports_prompt_cache: false,
+        input_price: 0.72,
+        output_price: 0.72,
+    },
+    ModelInfo {
+        alias: "mistral-large",
+        id: "mistral.mistral-large-2407-v1:0",
+        description: "Mistral Large (24.07)",
+        max_output_tokens: 8_192,
+        context_window: 128_000,
+        inference_profile: false,
+        geographies: &[],
+        supports_tools: true,
+        supports_images: false,
+        supports_thinking: false,
+        supports_prompt_cache: false,
+        input_price: 2.0,
+        output_price: 6.0,
+    },
+];
+
+/// The model ID within a model, inference profile or foundation model ARN
+/// ID, with any inference profile prefix removed
+pub fn base_id(model_id: &str) -> &str {
+    let id = model_id.rsplit('/').next().unwrap_or(model_id);
+    id.split_once('.')
+        .filter(|(prefix, _)| PROFILE_PREFIXES.contains(prefix))
+        .map_or(id, |(_, rest)| rest)
+}
+
+/// Look up a model by alias, model ID, inference profile ID or ARN
+pub fn lookup(name: &str) -> Option<&'static ModelInfo> {
+    let id = base_id(name);
+    KNOWN_MODELS
+        .iter()
+        .find(|m| m.alias == name || m.id == id)
+}
+
+/// Whether the model supports tool use. Models outside the registry are
+/// judged by their family.
+pub fn supports_tools(model_id: &str) -> bool {
+    match lookup(model_id) {
+        Some(info) => info.supports_tools,
+        None => ModelFamily::from_model_id(model_id).supports_tools(model_id),
+    }
+}
+
+/// Whether the model supports extended thinking; only registry models do
+pub fn supports_thinking(model_id: &str) -> bool {
+    lookup(model_id).is_some_and(|info| info.supports_thinking)
+}
+
+/// Whether the model accepts images; only registry models do
+pub fn supports_images(model_id: &str) -> bool {
+    lookup(model_id).is_some_and(|info| info.supports_images)
+}
+
+/// Whether the model takes cache points; only registry models do
+pub fn supports_prompt_cache(model_id: &str) -> bool {
+    lookup(model_id).is_some_and(|info| info.supports_prompt_cache)
+}
+
+/// When `model_id` can't be invoked from `region`, the known models that can,
+/// those of the same family first; `None` when it can or gamecode can't tell.
+/// The catalog cached by `gamecode models list --refresh` is used if there is
+/// one for the region, the registry otherwise.
+pub fn unavailable_in(model_id: &str, region: &str) -> Option<Vec<&'static ModelInfo>> {
+    if model_id.starts_with("arn:") {
+        return None;
+    }
+    let catalog = ModelCatalog::load_cached(region);
+    let available = |id: &str, info: Option<&ModelInfo>| match &catalog {
+        Some(catalog) => catalog.models.iter().any(|model| model.id == id),
+        None => info.is_none_or(|info| info.available_in(region)),
+    };
+    if available(model_id, lookup(model_id)) {
+        return None;
+    }
+
+    let family = ModelFamily::from_model_id(model_id);
+    let mut alternatives: Vec<&'static ModelInfo> = KNOWN_MODELS
+        .iter()
+        .filter(|info| available(&info.invocation_id(region), Some(info)))
+        .collect();
+    alternatives.sort_by_key(|info| ModelFamily::from_model_id(info.id) != family);
+    Some(alternatives)
+}
@@ -0,0 +1,35 @@
+use std::process::{Command, Stdio};
+use tracing::debug;
+
+/// Show a desktop notification (macOS via osascript, elsewhere via notify-send).
+/// Failures are logged and otherwise ignored; a missing notifier shouldn't break a run.
+pub fn send(title: &str, body: &str) {
+    let mut command = if cfg!(target_os = "macos") {
+        let mut command = Command::new("osascript");
+        command.arg("-e").arg(format!(
+            "display notification {} with title {}",
+            applescript_string(body),
+            applescript_string(title)
+        ));
+        command
+    } else {
+        let mut command = Command::new("notify-send");
+        command.arg("--app-name=gamecode").arg(title).arg(body);
+        command
+    };
+
+    let result = command
+        .stdin(Stdio::null())
+        .stdout(Stdio::null())
+        .stderr(Stdio::null())
+        .status();
+    match result {
+        Ok(status) if status.success() => {}
+        Ok(status) => debug!("Notification command exited with {}", status),
+        Err(e) => debug!("Failed to send desktop notification: {}", e),
+    }
+}
+
+fn applescript_string(text: &str) -> String {
+    format!("\"{}\"", text.replace('\\', "\\\\").replace('"', "\\\""))
+}
@@ -0,0 +1,136 @@
+use anyhow::{Context, Result};
+use crate::config::Config;
+use crate::converse::{ConverseClient, Extras, Resume};
+use crate::inference::{self, InferenceSettings};
+use crate::models::ModelFamily;
+use crate::usage::TokenUsage;
+use crate::retry::RetryPolicy;
+use gamecode_backend::{
+    ChatRequest, ContentBlock, Message as BackendMessage, MessageRole as BackendMessageRole,
+};
+use tracing::debug;
+
+/// A single request/response exchange with the configured model and no tools,
+/// for subcommands that need the model but not the agent loop
+pub struct OneShot {
+    pub region: String,
+    /// `--model` value, if the subcommand was given one
+    pub model: Option<String>,
+}
+
+/// Response to a one-shot request
+#[derive(Debug)]
+pub struct Reply {
+    /// Bedrock model ID that answered
+    pub model: String,
+    pub text: String,
+    pub usage: TokenUsage,
+}
+
+impl OneShot {
+    /// Resolved Bedrock model ID, using the same precedence as the main command
+    pub fn model_id(&self, config: &Config) -> String {
+        let model = crate::requested_model(self.model.clone(), config);
+        crate::map_model_name(model.as_deref().unwrap_or(crate::DEFAULT_MODEL), config, &self.region)
+    }
+
+    pub async fn ask(&self, system: &str, prompt: &str) -> Result<String> {
+        Ok(self.complete(system, prompt).await?.text)
+    }
+
+    /// Like `ask`, but also returns the model used and the tokens consumed.
+    /// An empty `system` sends the prompt on its own.
+    pub async fn complete(&self, system: &str, prompt: &str) -> Result<Reply> {
+        let config = Config::load()?;
+        let alias = crate::requested_model(self.model.clone(), &config);
+        let model_id = self.model_id(&config);
+        debug!("One-shot request to {}", model_id);
+
+        let backend = crate::create_backend(&self.region).await?;
+        let settings = inference::resolve(
+            InferenceSettings::default(),
+            &config.inference,
+            alias.as_deref(),
+            &model_id,
+        );
+
+        let family = ModelFamily::from_model_id(&model_id);
+        let messages = if system.is_empty() {
+            vec![BackendMessage::text(BackendMessageRole::User, prompt.to_string())]
+        } else if family.supports_system_prompt(&model_id) {
+            vec![
+                BackendMessage::text(BackendMessageRole::System, system.to_string()),
+                BackendMessage::text(BackendMessageRole::User, prompt.to_string()),
+            ]
+        } else {
+            vec![BackendMessage::text(
+                BackendMessageRole::User,
+                format!("{}\n\n{}", system, prompt),
+            )]
+        };
+
+        let retry = RetryPolicy::from_config(&config.retry);
+
+        // The backend's requests can't carry stop sequences
+        if settings.stop_sequences.is_some() {
+            let client = ConverseClient::new(&self.region).await;
+            let resume = Resume::default();
+            let reply = retry
+                .run(
+                    || client.chat(&model_id, &messages, &Extras::default(), &[], None, &settings, &resume),
+                    |_, _, _, _| {},
+                )
+                .await
+                .context("Failed to get response from Bedrock")?;
+            return Ok(Reply {
+                model: model_id,
+                text: reply.text,
+                usage: reply.usage,
+            });
+        }
+
+        let response = retry
+            .run(
+                || {
+                    let request = ChatRequest {
+                        messages: messages.clone(),
+                        tools: None,
+                        model: Some(model_id.clone()),
+                        inference_config: Some(settings.to_backend()),
+                        session_id: None,
+                        status_callback: None,
+                    };
+                    backend.chat_with_retry(request, retry.single_attempt())
+                },
+                |_, _, _, _| {},
+            )
+            .await
+            .context("Failed to get response from backend")?;
+
+        let text = response
+            .message
+            .content
+            .iter()
+            .filter_map(|block| match block {
+                ContentBlock::Text(text) => Some(text.as_str()),
+                _ => None,
+            })
+            .collect::<Vec<_>>()
+            .join("");
+        let usage = response
+            .usage
+            .as_ref()
+            .map(|reported| TokenUsage {
+                input_tokens: reported.input_tokens as u64,
+                output_tokens: reported.output_tokens as u64,
+                ..Default::default()
+            })
+            .unwrap_or_default();
+
+        Ok(Reply {
+            model: model_id,
+            text,
+            usage,
+        })
+    }
+}
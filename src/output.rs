@@ -0,0 +1,126 @@
+use anyhow::Result;
+use crate::usage::TokenUsage;
+use serde::Serialize;
+use serde_json::Value;
+use std::io::Write;
+use std::str::FromStr;
+
+/// How the result of a run is written to stdout
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum OutputFormat {
+    /// Model text streamed as it arrives
+    Text,
+    /// A single JSON document once the run finishes
+    Json,
+    /// One JSON event per line as the run progresses
+    Ndjson,
+}
+
+impl FromStr for OutputFormat {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        match s {
+            "text" => Ok(Self::Text),
+            "json" => Ok(Self::Json),
+            "ndjson" => Ok(Self::Ndjson),
+            other => anyhow::bail!("Unknown output format '{}' (expected text, json or ndjson)", other),
+        }
+    }
+}
+
+/// A tool call made during the run and what it returned
+#[derive(Debug, Serialize)]
+pub struct ToolCallReport {
+    pub id: String,
+    pub name: String,
+    pub input: Value,
+    pub result: String,
+    pub is_error: bool,
+}
+
+/// Machine-readable summary of a run for `--output-format json`
+#[derive(Debug, Serialize)]
+pub struct RunReport {
+    pub session_id: String,
+    pub model: String,
+    /// Text of the final response
+    pub text: String,
+    pub tool_calls: Vec<ToolCallReport>,
+    /// Tokens used by this run (not the whole session)
+    pub usage: TokenUsage,
+    /// A response came from the local response cache
+    pub cached: bool,
+    /// Extended thinking behind the response, with `--thinking`
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub thinking: Option<String>,
+}
+
+impl RunReport {
+    pub fn new(session_id: String, model: String) -> Self {
+        Self {
+            session_id,
+            model,
+            text: String::new(),
+            tool_calls: Vec::new(),
+            usage: TokenUsage::default(),
+            cached: false,
+            thinking: None,
+        }
+    }
+
+    pub fn print(&self) -> Result<()> {
+        println!("{}", serde_json::to_string_pretty(self)?);
+        Ok(())
+    }
+}
+
+/// Events written by `--output-format ndjson`, one per line
+#[derive(Debug, Serialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum Event<'a> {
+    MessageStart {
+        session_id: String,
+        model: &'a str,
+    },
+    /// The following response was answered from the local cache
+    Cached {
+        model: &'a str,
+    },
+    ContentDelta {
+        text: &'a str,
+    },
+    /// Extended thinking behind the response, with `--thinking`
+    Thinking {
+        text: &'a str,
+    },
+    ToolCall {
+        id: &'a str,
+        name: &'a str,
+        input: &'a Value,
+    },
+    ToolResult {
+        id: &'a str,
+        name: &'a str,
+        result: &'a str,
+        is_error: bool,
+    },
+    Usage {
+        #[serde(flatten)]
+        usage: TokenUsage,
+    },
+    MessageEnd {
+        /// `end_turn`, `tool_use`, `stop_sequence`, `guardrail` or `hook`
+        stop_reason: &'a str,
+    },
+}
+
+impl Event<'_> {
+    /// Write the event as a single line and flush so consumers see it immediately
+    pub fn emit(&self) -> Result<()> {
+        let mut stdout = std::io::stdout().lock();
+        writeln!(stdout, "{}", serde_json::to_string(self)?)?;
+        stdout.flush()?;
+        Ok(())
+    }
+}
@@ -0,0 +1,57 @@
+use anyhow::{Context, Result};
+use std::io::Write;
+use std::process::{Command, Stdio};
+use terminal_size::{terminal_size, Height};
+
+/// Pager used when `$PAGER` is unset. `-R` passes ANSI styling through.
+const DEFAULT_PAGER: &str = "less -R";
+
+/// Whether `text` is taller than the terminal
+pub fn needs_paging(text: &str) -> bool {
+    match terminal_size() {
+        Some((_, Height(rows))) => text.lines().count() >= rows as usize,
+        None => false,
+    }
+}
+
+/// Print `text`, through `$PAGER` if it doesn't fit on one screen.
+///
+/// Callers must pass the complete response: the decision depends on its final
+/// height, so a streamed response has to be buffered before it is paged.
+pub fn print(text: &str) -> Result<()> {
+    if !needs_paging(text) {
+        println!("{}", text);
+        return Ok(());
+    }
+
+    let pager = std::env::var("PAGER")
+        .ok()
+        .filter(|p| !p.trim().is_empty())
+        .unwrap_or_else(|| DEFAULT_PAGER.to_string());
+    if pager == "cat" {
+        println!("{}", text);
+        return Ok(());
+    }
+
+    // Like git: quit if one screen, keep colors, don't clear the screen on exit
+    let mut child = match Command::new("sh")
+        .arg("-c")
+        .arg(&pager)
+        .env("LESS", std::env::var("LESS").unwrap_or_else(|_| "FRX".to_string()))
+        .stdin(Stdio::piped())
+        .spawn()
+    {
+        Ok(child) => child,
+        Err(_) => {
+            println!("{}", text);
+            return Ok(());
+        }
+    };
+
+    if let Some(mut stdin) = child.stdin.take() {
+        // The user quitting the pager early closes the pipe; that's not an error
+        let _ = writeln!(stdin, "{}", text);
+    }
+    child.wait().with_context(|| format!("Pager '{}' failed", pager))?;
+    Ok(())
+}
@@ -0,0 +1,153 @@
+use anyhow::{bail, Context, Result};
+use std::io::{IsTerminal, Write};
+
+/// Appended to the prompt of a `--plan` run so the first response is a plan
+/// rather than work
+pub const PLANNING_INSTRUCTION: &str = "Before doing anything, reply with only a numbered plan \
+(1., 2., ...) of the steps you will take to do this, one short line per step. \
+Don't call any tools yet: the plan will be reviewed before you start.";
+
+/// One step of a plan
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Step {
+    pub text: String,
+    pub done: bool,
+}
+
+/// A numbered plan proposed by the model and approved (perhaps edited) by the
+/// user, with steps checked off as the model reports them done
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Plan {
+    pub steps: Vec<Step>,
+}
+
+/// What the user decided about a proposed plan
+pub enum Decision {
+    Approve(Plan),
+    Reject,
+}
+
+impl Plan {
+    /// The numbered steps in `text` (`1.` or `1)`, optionally as a checklist
+    /// item); other lines are ignored. None if there are none.
+    pub fn parse(text: &str) -> Option<Self> {
+        let steps: Vec<Step> = text
+            .lines()
+            .filter_map(|line| {
+                let line = line.trim();
+                let digits = line.len() - line.trim_start_matches(|c: char| c.is_ascii_digit()).len();
+                if digits == 0 {
+                    return None;
+                }
+                let rest = line[digits..].strip_prefix(['.', ')'])?.trim_start();
+                let (done, rest) = if let Some(rest) = rest.strip_prefix("[x]").or_else(|| rest.strip_prefix("[X]")) {
+                    (true, rest)
+                } else {
+                    (false, rest.strip_prefix("[ ]").unwrap_or(rest))
+                };
+                let text = rest.trim();
+                (!text.is_empty()).then(|| Step { text: text.to_string(), done })
+            })
+            .collect();
+        (!steps.is_empty()).then_some(Self { steps })
+    }
+
+    /// The plan as a numbered checklist, in the form `parse` reads back
+    pub fn checklist(&self) -> String {
+        self.steps
+            .iter()
+            .enumerate()
+            .map(|(i, step)| format!("{}. [{}] {}", i + 1, if step.done { "x" } else { " " }, step.text))
+            .collect::<Vec<_>>()
+            .join("\n")
+    }
+
+    /// Prompt that starts execution of the approved plan
+    pub fn execution_prompt(&self) -> String {
+        format!(
+            "The plan was approved. Carry it out step by step:\n\n{}\n\n\
+             Each time you finish a step, write `Done: step N` on a line of its own.",
+            self.checklist()
+        )
+    }
+
+    /// Check off the steps `text` reports as done (`Done: step N`). Returns
+    /// the numbers (1-based) of steps newly checked off.
+    pub fn check_off(&mut self, text: &str) -> Vec<usize> {
+        let mut completed = Vec::new();
+        for line in text.lines() {
+            let line = line.trim().trim_matches(|c| c == '`' || c == '*').to_lowercase();
+            let Some(number) = line
+                .strip_prefix("done: step")
+                .and_then(|rest| rest.trim().trim_end_matches('.').parse::<usize>().ok())
+            else {
+                continue;
+            };
+            if let Some(step) = number.checked_sub(1).and_then(|i| self.steps.get_mut(i)) {
+                if !step.done {
+                    step.done = true;
+                    completed.push(number);
+                }
+            }
+        }
+        completed
+    }
+
+    pub fn remaining(&self) -> usize {
+        self.steps.iter().filter(|step| !step.done).count()
+    }
+
+    /// Show the plan and ask whether to execute it, edit it first or drop it.
+    /// Needs a terminal; `--yes` runs skip this.
+    pub fn review(mut self) -> Result<Decision> {
+        if !std::io::stdin().is_terminal() {
+            bail!("Reviewing a plan needs a terminal; use --yes to execute it without review");
+        }
+
+        loop {
+            notice!("\n📋 Proposed plan:");
+            eprintln!("{}", self.checklist());
+            eprint!("   Execute this plan? [y]es / [n]o / [e]dit: ");
+            std::io::stderr().flush().ok();
+
+            let mut answer = String::new();
+            if std::io::stdin().read_line(&mut answer)? == 0 {
+                return Ok(Decision::Reject);
+            }
+            match answer.trim().to_lowercase().as_str() {
+                "y" | "yes" => return Ok(Decision::Approve(self)),
+                "n" | "no" => return Ok(Decision::Reject),
+                "e" | "edit" => match self.edit()? {
+                    Some(edited) => self = edited,
+                    None => notice!("⚠️  The edited plan has no numbered steps; keeping the previous one"),
+                },
+                _ => continue,
+            }
+        }
+    }
+
+    /// Open the plan in `$VISUAL`/`$EDITOR`; None if no steps are left
+    fn edit(&self) -> Result<Option<Self>> {
+        let path = std::env::temp_dir().join(format!("gamecode-plan-{}.md", std::process::id()));
+        std::fs::write(&path, format!("{}\n", self.checklist()))
+            .with_context(|| format!("Failed to write {}", path.display()))?;
+
+        let editor = std::env::var("VISUAL")
+            .or_else(|_| std::env::var("EDITOR"))
+            .unwrap_or_else(|_| "vi".to_string());
+        let status = std::process::Command::new("sh")
+            .arg("-c")
+            .arg(format!("{} \"$1\"", editor))
+            .arg("sh")
+            .arg(&path)
+            .status()
+            .with_context(|| format!("Failed to run editor '{}'", editor))?;
+
+        let edited = std::fs::read_to_string(&path);
+        let _ = std::fs::remove_file(&path);
+        if !status.success() {
+            bail!("Editor '{}' exited with {}", editor, status);
+        }
+        Ok(Self::parse(&edited?))
+    }
+}
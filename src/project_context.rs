@@ -0,0 +1,54 @@
+use anyhow::{Context, Result};
+use std::fs;
+use std::path::{Path, PathBuf};
+
+/// Project instruction files, in order of preference
+pub const CONTEXT_FILES: &[&str] = &["GAMECODE.md", "AGENTS.md"];
+
+/// Larger files are skipped rather than flooding the system prompt
+const MAX_CONTEXT_BYTES: u64 = 64 * 1024;
+
+/// Find the nearest project context file, searching from `start` up to the repository root
+pub fn find(start: &Path) -> Option<PathBuf> {
+    for dir in start.ancestors() {
+        for name in CONTEXT_FILES {
+            let candidate = dir.join(name);
+            if candidate.is_file() {
+                return Some(candidate);
+            }
+        }
+        // Don't pick up files from outside the repository
+        if dir.join(".git").exists() {
+            break;
+        }
+    }
+    None
+}
+
+/// Load the project context for the current directory as a system prompt section
+pub fn load() -> Result<Option<(PathBuf, String)>> {
+    let cwd = std::env::current_dir().context("Failed to get current directory")?;
+    let Some(path) = find(&cwd) else {
+        return Ok(None);
+    };
+
+    let size = fs::metadata(&path)?.len();
+    if size > MAX_CONTEXT_BYTES {
+        notice!(
+            "⚠️  Ignoring {} ({} bytes exceeds the {} byte limit)",
+            path.display(),
+            size,
+            MAX_CONTEXT_BYTES
+        );
+        return Ok(None);
+    }
+
+    let content = fs::read_to_string(&path)
+        .with_context(|| format!("Failed to read {}", path.display()))?;
+    let section = format!(
+        "# Project context\n\nThe following instructions come from {} in this project:\n\n{}",
+        path.display(),
+        content.trim()
+    );
+    Ok(Some((path, section)))
+}
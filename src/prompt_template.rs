@@ -0,0 +1,90 @@
+use anyhow::{Context, Result};
+use std::collections::{BTreeSet, HashMap};
+
+/// Deepest chain of `{{> include}}` directives followed before giving up
+const MAX_INCLUDE_DEPTH: usize = 16;
+
+/// Replace `{{> name}}` directives with the named prompts, recursively.
+/// `load` fetches a stored prompt by name; include cycles are an error.
+pub fn expand_includes(template: &str, load: &dyn Fn(&str) -> Result<String>) -> Result<String> {
+    expand(template, load, &mut Vec::new())
+}
+
+fn expand(template: &str, load: &dyn Fn(&str) -> Result<String>, stack: &mut Vec<String>) -> Result<String> {
+    let mut out = String::with_capacity(template.len());
+    let mut rest = template;
+
+    while let Some(start) = rest.find("{{>") {
+        out.push_str(&rest[..start]);
+        let after = &rest[start + 3..];
+        let end = after
+            .find("}}")
+            .context("Unterminated {{> include}} directive in prompt")?;
+        let name = after[..end].trim();
+
+        if stack.iter().any(|n| n == name) {
+            anyhow::bail!("Prompt include cycle: {} -> {}", stack.join(" -> "), name);
+        }
+        if stack.len() >= MAX_INCLUDE_DEPTH {
+            anyhow::bail!("Prompt includes nested more than {} deep", MAX_INCLUDE_DEPTH);
+        }
+
+        let included = load(name).with_context(|| format!("Failed to include prompt '{}'", name))?;
+        stack.push(name.to_string());
+        out.push_str(expand(&included, load, stack)?.trim_end());
+        stack.pop();
+
+        rest = &after[end + 2..];
+    }
+    out.push_str(rest);
+    Ok(out)
+}
+
+/// Substitute `{{name}}` placeholders in a prompt with values from `--var`.
+/// Every placeholder must have a value; the error lists all that are missing.
+pub fn render(template: &str, vars: &HashMap<String, String>) -> Result<String> {
+    let mut out = String::with_capacity(template.len());
+    let mut missing = BTreeSet::new();
+    let mut rest = template;
+
+    while let Some(start) = rest.find("{{") {
+        out.push_str(&rest[..start]);
+        let after = &rest[start + 2..];
+        let Some(end) = after.find("}}") else {
+            out.push_str(&rest[start..]);
+            rest = "";
+            break;
+        };
+
+        let name = after[..end].trim();
+        if is_variable_name(name) {
+            match vars.get(name) {
+                Some(value) => out.push_str(value),
+                None => {
+                    missing.insert(name.to_string());
+                }
+            }
+        } else {
+            // Not a variable (e.g. literal braces in an example); keep it as written
+            out.push_str(&rest[start..start + 2 + end + 2]);
+        }
+        rest = &after[end + 2..];
+    }
+    out.push_str(rest);
+
+    if !missing.is_empty() {
+        let required: Vec<String> = missing.into_iter().collect();
+        anyhow::bail!(
+            "Prompt requires variables that were not provided: {} (pass them with --var KEY=VALUE)",
+            required.join(", ")
+        );
+    }
+    Ok(out)
+}
+
+fn is_variable_name(name: &str) -> bool {
+    !name.is_empty()
+        && name
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || c == '_' || c == '-' || c == '.')
+}
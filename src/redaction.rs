@@ -0,0 +1,112 @@
+use anyhow::{Context, Result};
+use crate::logging::EVENT_TARGET;
+use regex::Regex;
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use tracing::info;
+
+/// Patterns masked by default, by name. They match credentials that have a
+/// recognizable shape, so ordinary code and prose pass through untouched.
+const BUILTIN_PATTERNS: &[(&str, &str)] = &[
+    ("aws-access-key-id", r"\b(?:AKIA|ASIA)[A-Z0-9]{16}\b"),
+    (
+        "aws-secret-access-key",
+        r#"(?i)aws_?secret_?access_?key["']?\s*[:=]\s*["']?[A-Za-z0-9/+=]{40}"#,
+    ),
+    (
+        "private-key",
+        r"(?s)-----BEGIN [A-Z ]*PRIVATE KEY-----.*?-----END [A-Z ]*PRIVATE KEY-----",
+    ),
+    ("github-token", r"\b(?:gh[pousr]_[A-Za-z0-9]{36,}|github_pat_[A-Za-z0-9_]{40,})\b"),
+    ("slack-token", r"\bxox[abposr]-[A-Za-z0-9-]{10,}"),
+    ("bearer-token", r"(?i)\bbearer\s+[A-Za-z0-9._~+/-]{20,}=*"),
+    ("jwt", r"\beyJ[A-Za-z0-9_-]{10,}\.eyJ[A-Za-z0-9_-]{10,}\.[A-Za-z0-9_-]{10,}"),
+];
+
+/// Settings for the `[redaction]` section
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct RedactionConfig {
+    /// Mask secrets in prompts, attachments and tool results
+    pub enabled: bool,
+    /// Extra patterns to mask, by name
+    pub patterns: BTreeMap<String, String>,
+    /// Built-in patterns to leave out (e.g. `jwt`)
+    pub disable: Vec<String>,
+}
+
+impl Default for RedactionConfig {
+    fn default() -> Self {
+        Self {
+            enabled: true,
+            patterns: BTreeMap::new(),
+            disable: Vec::new(),
+        }
+    }
+}
+
+/// Masks secrets in text before it is sent to the model, replacing each
+/// match with `[REDACTED:<pattern name>]`
+pub struct Redactor {
+    patterns: Vec<(String, Regex)>,
+}
+
+impl Redactor {
+    /// The redactor configured by `config`, or None if redaction is disabled.
+    /// Fails if a custom pattern isn't a valid regex.
+    pub fn from_config(config: &RedactionConfig) -> Result<Option<Self>> {
+        if !config.enabled {
+            return Ok(None);
+        }
+
+        let mut patterns = Vec::new();
+        for (name, pattern) in BUILTIN_PATTERNS {
+            if !config.disable.iter().any(|disabled| disabled == name) {
+                patterns.push((name.to_string(), Regex::new(pattern).expect("built-in pattern is valid")));
+            }
+        }
+        for (name, pattern) in &config.patterns {
+            let regex = Regex::new(pattern)
+                .with_context(|| format!("Invalid redaction pattern '{}'", name))?;
+            patterns.push((name.clone(), regex));
+        }
+        Ok(Some(Self { patterns }))
+    }
+
+    /// Mask secrets in `text`, returning the masked text and how many
+    /// matches each pattern had
+    pub fn redact(&self, text: &str) -> (String, BTreeMap<String, usize>) {
+        let mut redacted = text.to_string();
+        let mut found = BTreeMap::new();
+        for (name, regex) in &self.patterns {
+            let count = regex.find_iter(&redacted).count();
+            if count == 0 {
+                continue;
+            }
+            redacted = regex
+                .replace_all(&redacted, format!("[REDACTED:{}]", name).as_str())
+                .into_owned();
+            found.insert(name.clone(), count);
+        }
+        (redacted, found)
+    }
+
+    /// Like `redact`, recording what was masked in `source` (e.g. "prompt",
+    /// "tool result of read_file") in the event log and, with `-v`, on stderr.
+    /// Only pattern names and counts are reported, never the secrets.
+    pub fn redact_reporting(&self, text: &str, source: &str) -> String {
+        let (redacted, found) = self.redact(text);
+        if !found.is_empty() {
+            let summary = found
+                .iter()
+                .map(|(name, count)| format!("{} {}", count, name))
+                .collect::<Vec<_>>()
+                .join(", ");
+            info!(target: EVENT_TARGET, source, redacted = %summary, "secrets redacted");
+            if crate::logging::verbosity() >= 1 {
+                notice!("🔒 Redacted {} from the {}", summary, source);
+            }
+        }
+        redacted
+    }
+}
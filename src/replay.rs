@@ -0,0 +1,80 @@
+use anyhow::Result;
+use crate::agent::{execute_tool, ToolOutcome};
+use crate::config::Config;
+use crate::file_backups::SessionBackups;
+use crate::file_edits::FileEdit;
+use crate::mcp_tool_dispatcher::McpToolDispatcher;
+use crate::tool_approval::ToolApprover;
+use crate::tool_capabilities::{self, ToolCapability};
+use crate::tool_log::ToolCallRecord;
+use std::collections::HashSet;
+use uuid::Uuid;
+
+/// What happened to a replayed call
+pub enum ReplayOutcome {
+    /// The user denied the call or rejected its file change
+    Skipped,
+    Ran {
+        outcome: ToolOutcome,
+        /// Whether the result matches the recorded one
+        same_result: bool,
+    },
+}
+
+/// Runs recorded tool calls again without the model, with the same approval
+/// and file change confirmation as a normal run. Files changed are backed up
+/// under the replayed session, so `sessions rollback` undoes a replay too.
+pub struct Replayer {
+    dispatcher: McpToolDispatcher,
+    approver: ToolApprover,
+    write_tools: HashSet<String>,
+    backups: SessionBackups,
+    auto_approve: bool,
+}
+
+impl Replayer {
+    pub async fn new(session_id: &Uuid, auto_approve: bool, config: &Config) -> Result<Self> {
+        let dispatcher = McpToolDispatcher::new().await?;
+        let write_tools = {
+            let registry = dispatcher.get_registry().await;
+            let registry = registry.lock().await;
+            registry
+                .entries()
+                .into_iter()
+                .filter(|(server, tool)| {
+                    tool_capabilities::classify(server, tool, &config.tool_capabilities) == ToolCapability::Write
+                })
+                .map(|(server, tool)| format!("{}_{}", server, tool.name))
+                .collect()
+        };
+        Ok(Self {
+            dispatcher,
+            approver: ToolApprover::new(auto_approve, config),
+            write_tools,
+            backups: SessionBackups::open(session_id)?,
+            auto_approve,
+        })
+    }
+
+    pub async fn run(&mut self, call: &ToolCallRecord) -> Result<ReplayOutcome> {
+        if !self.approver.approve(&call.name, &call.input)? {
+            return Ok(ReplayOutcome::Skipped);
+        }
+        if self.write_tools.contains(&call.name) {
+            if let Some(edit) = FileEdit::detect(&call.input).filter(|_| !self.auto_approve) {
+                if !edit.confirm(&call.name)? {
+                    return Ok(ReplayOutcome::Skipped);
+                }
+            }
+            if let Some(path) = FileEdit::target_path(&call.input) {
+                if let Err(e) = self.backups.backup(&path) {
+                    notice!("⚠️  Failed to back up {}: {}", path.display(), e);
+                }
+            }
+        }
+
+        let outcome = execute_tool(Some(&self.dispatcher), &call.name, call.input.clone(), false).await;
+        let same_result = outcome.is_error == call.is_error && outcome.content == call.result;
+        Ok(ReplayOutcome::Ran { outcome, same_result })
+    }
+}
@@ -0,0 +1,146 @@
+use anyhow::{Context, Result};
+use crate::inference::InferenceSettings;
+use chrono::{DateTime, Utc};
+use gamecode_backend::{ChatResponse, Message as BackendMessage, Tool as BackendTool};
+use serde::{Deserialize, Serialize};
+use std::collections::hash_map::DefaultHasher;
+use std::fs;
+use std::hash::{Hash, Hasher};
+use std::path::PathBuf;
+use std::time::Duration;
+use tracing::debug;
+
+/// Settings for the `[cache]` section
+#[derive(Debug, Default, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct CacheConfig {
+    /// Answer identical requests from the cache without `--cache`
+    pub enabled: bool,
+    /// Entries older than this are ignored; unset means they never expire
+    pub max_age_hours: Option<u64>,
+}
+
+/// On-disk cache entry for one backend response
+#[derive(Debug, Serialize, Deserialize)]
+struct CacheEntry {
+    created_at: DateTime<Utc>,
+    model: String,
+    response: ChatResponse,
+}
+
+/// Caches backend responses under `~/.cache/gamecode/responses/<key>.json`,
+/// keyed by the model, messages, tools and inference settings of the request,
+/// so scripts that repeat a prompt get the earlier answer without a call.
+pub struct ResponseCache {
+    max_age: Option<Duration>,
+}
+
+impl ResponseCache {
+    pub fn new(config: &CacheConfig) -> Self {
+        Self {
+            max_age: config.max_age_hours.map(|hours| Duration::from_secs(hours * 3600)),
+        }
+    }
+
+    pub fn cache_dir() -> Result<PathBuf> {
+        let home = home::home_dir().context("Failed to get home directory")?;
+        Ok(home.join(".cache").join("gamecode").join("responses"))
+    }
+
+    /// Key derived from everything that determines the response
+    pub fn key(
+        model: &str,
+        messages: &[BackendMessage],
+        tools: Option<&[BackendTool]>,
+        inference: &InferenceSettings,
+    ) -> String {
+        let hash = |value: serde_json::Result<String>| {
+            let mut hasher = DefaultHasher::new();
+            value.unwrap_or_default().hash(&mut hasher);
+            hasher.finish()
+        };
+        format!(
+            "{:016x}{:016x}{:016x}",
+            hash(serde_json::to_string(&(model, inference))),
+            hash(serde_json::to_string(messages)),
+            hash(serde_json::to_string(&tools)),
+        )
+    }
+
+    /// Load the response cached under `key`, if present and not expired.
+    /// Cached responses cost nothing, so their usage is dropped.
+    pub fn load(&self, key: &str) -> Option<ChatResponse> {
+        let path = Self::cache_dir().ok()?.join(format!("{}.json", key));
+        let content = fs::read_to_string(&path).ok()?;
+        let entry: CacheEntry = match serde_json::from_str(&content) {
+            Ok(entry) => entry,
+            Err(e) => {
+                debug!("Ignoring unreadable response cache entry {}: {}", key, e);
+                return None;
+            }
+        };
+
+        let age = (Utc::now() - entry.created_at).to_std().unwrap_or_default();
+        if self.max_age.is_some_and(|max_age| age > max_age) {
+            debug!("Response cache entry {} from {} has expired", key, entry.model);
+            return None;
+        }
+
+        let mut response = entry.response;
+        response.usage = None;
+        Some(response)
+    }
+
+    /// Store a response under `key`
+    pub fn store(&self, key: &str, model: &str, response: &ChatResponse) -> Result<()> {
+        let dir = Self::cache_dir()?;
+        fs::create_dir_all(&dir)?;
+
+        let entry = CacheEntry {
+            created_at: Utc::now(),
+            model: model.to_string(),
+            response: response.clone(),
+        };
+        let path = dir.join(format!("{}.json", key));
+        fs::write(&path, serde_json::to_string(&entry)?)
+            .with_context(|| format!("Failed to write {}", path.display()))?;
+        Ok(())
+    }
+
+    /// Number of cached responses and their total size in bytes
+    pub fn stats() -> Result<(usize, u64)> {
+        let dir = Self::cache_dir()?;
+        if !dir.exists() {
+            return Ok((0, 0));
+        }
+
+        let mut count = 0;
+        let mut bytes = 0;
+        for entry in fs::read_dir(&dir)? {
+            let entry = entry?;
+            if entry.path().extension().is_some_and(|ext| ext == "json") {
+                count += 1;
+                bytes += entry.metadata()?.len();
+            }
+        }
+        Ok((count, bytes))
+    }
+
+    /// Remove every cached response. Returns the number of entries removed.
+    pub fn clear() -> Result<usize> {
+        let dir = Self::cache_dir()?;
+        if !dir.exists() {
+            return Ok(0);
+        }
+
+        let mut removed = 0;
+        for entry in fs::read_dir(&dir)? {
+            let path = entry?.path();
+            if path.extension().is_some_and(|ext| ext == "json") {
+                fs::remove_file(&path)?;
+                removed += 1;
+            }
+        }
+        Ok(removed)
+    }
+}
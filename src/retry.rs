@@ -0,0 +1,177 @@
+use gamecode_backend::{BackoffStrategy, RetryConfig};
+use serde::{Deserialize, Serialize};
+use std::future::Future;
+use std::time::Duration;
+use tracing::debug;
+
+/// How the delay grows between retries
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Backoff {
+    /// `initial * multiplier^(n-1)`
+    Exponential,
+    /// `initial * n`
+    Linear,
+    /// `initial` every time
+    Fixed,
+}
+
+impl std::str::FromStr for Backoff {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "exponential" => Ok(Self::Exponential),
+            "linear" => Ok(Self::Linear),
+            "fixed" => Ok(Self::Fixed),
+            other => anyhow::bail!("Unknown backoff '{}' (expected exponential, linear or fixed)", other),
+        }
+    }
+}
+
+/// Settings for the `[retry]` section; unset fields keep the built-in defaults
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct RetryDefaults {
+    pub max_retries: Option<usize>,
+    pub initial_delay_ms: Option<u64>,
+    pub backoff: Option<Backoff>,
+    /// Growth factor for exponential backoff
+    pub multiplier: Option<f64>,
+    /// Upper bound on any single delay
+    pub max_delay_ms: Option<u64>,
+    /// Randomize each delay by up to this fraction (0.0-1.0) either way
+    pub jitter: Option<f64>,
+}
+
+/// When and how often a failed backend request is retried
+#[derive(Debug, Clone)]
+pub struct RetryPolicy {
+    pub max_retries: usize,
+    pub initial_delay: Duration,
+    pub backoff: Backoff,
+    pub multiplier: f64,
+    pub max_delay: Duration,
+    pub jitter: f64,
+}
+
+impl Default for RetryPolicy {
+    fn default() -> Self {
+        Self {
+            max_retries: 3,
+            initial_delay: Duration::from_millis(500),
+            backoff: Backoff::Exponential,
+            multiplier: 3.0,
+            max_delay: Duration::from_secs(60),
+            jitter: 0.0,
+        }
+    }
+}
+
+impl RetryPolicy {
+    /// The defaults overridden by whatever the config sets
+    pub fn from_config(defaults: &RetryDefaults) -> Self {
+        let builtin = Self::default();
+        Self {
+            max_retries: defaults.max_retries.unwrap_or(builtin.max_retries),
+            initial_delay: defaults.initial_delay_ms.map(Duration::from_millis).unwrap_or(builtin.initial_delay),
+            backoff: defaults.backoff.unwrap_or(builtin.backoff),
+            multiplier: defaults.multiplier.unwrap_or(builtin.multiplier),
+            max_delay: defaults.max_delay_ms.map(Duration::from_millis).unwrap_or(builtin.max_delay),
+            jitter: defaults.jitter.unwrap_or(builtin.jitter).clamp(0.0, 1.0),
+        }
+    }
+
+    /// Delay before retry number `retry` (starting at 1), capped and jittered
+    pub fn delay(&self, retry: usize) -> Duration {
+        let initial = self.initial_delay.as_secs_f64();
+        let base = match self.backoff {
+            Backoff::Exponential => initial * self.multiplier.max(1.0).powi(retry.saturating_sub(1) as i32),
+            Backoff::Linear => initial * retry as f64,
+            Backoff::Fixed => initial,
+        };
+        let capped = base.min(self.max_delay.as_secs_f64());
+        let jittered = if self.jitter > 0.0 {
+            // uuid's v4 generator is the only randomness we depend on
+            let unit = (uuid::Uuid::new_v4().as_u128() % 10_000) as f64 / 10_000.0;
+            capped * (1.0 + self.jitter * (unit * 2.0 - 1.0))
+        } else {
+            capped
+        };
+        Duration::from_secs_f64(jittered.max(0.0))
+    }
+
+    /// Backend configuration for a single attempt; retries happen in `run`
+    pub fn single_attempt(&self) -> RetryConfig {
+        RetryConfig {
+            max_retries: 0,
+            initial_delay: self.initial_delay,
+            backoff_strategy: BackoffStrategy::Exponential { multiplier: 1 },
+            verbose: false,
+        }
+    }
+
+    /// Call `attempt` until it succeeds, fails with an error that retrying
+    /// won't fix, or the retries run out. `on_retry` is told the retry number,
+    /// the limit, the delay and the error before each wait.
+    pub async fn run<T, E, F, Fut>(
+        &self,
+        mut attempt: F,
+        mut on_retry: impl FnMut(usize, usize, Duration, &str),
+    ) -> Result<T, E>
+    where
+        E: std::fmt::Display,
+        F: FnMut() -> Fut,
+        Fut: Future<Output = Result<T, E>>,
+    {
+        let mut retry = 0;
+        loop {
+            match attempt().await {
+                Ok(value) => return Ok(value),
+                Err(e) => {
+                    let message = e.to_string();
+                    if retry >= self.max_retries || !is_transient(&message) {
+                        return Err(e);
+                    }
+                    retry += 1;
+                    let delay = self.delay(retry);
+                    debug!("Retry {}/{} in {:?}: {}", retry, self.max_retries, delay, message);
+                    on_retry(retry, self.max_retries, delay, &message);
+                    tokio::time::sleep(delay).await;
+                }
+            }
+        }
+    }
+}
+
+/// Errors worth retrying: throttling, timeouts, dropped connections and
+/// server-side failures. Validation and access errors fail immediately.
+pub fn is_transient(error: &str) -> bool {
+    const PATTERNS: &[&str] = &[
+        "throttl",
+        "too many requests",
+        "rate limit",
+        "rate exceeded",
+        "serviceunavailable",
+        "service unavailable",
+        "internalserver",
+        "internal server",
+        "modelnotready",
+        "not ready",
+        "timeout",
+        "timed out",
+        "dispatch failure",
+        "connection",
+        "io error",
+    ];
+    let error = error.to_lowercase();
+    PATTERNS.iter().any(|pattern| error.contains(pattern))
+}
+
+/// Whether an error is throttling rather than some other transient failure
+pub fn is_rate_limit(error: &str) -> bool {
+    let error = error.to_lowercase();
+    ["throttl", "too many requests", "rate limit", "rate exceeded"]
+        .iter()
+        .any(|pattern| error.contains(pattern))
+}
@@ -0,0 +1,528 @@
+use anyhow::{Context, Result};
+use axum::extract::{Path, Request, State};
+use axum::http::{header, HeaderMap, StatusCode};
+use axum::middleware::{self, Next};
+use axum::response::sse::{Event as SseEvent, KeepAlive, Sse};
+use axum::response::{IntoResponse, Response};
+use axum::routing::{get, post};
+use axum::{Json, Router};
+use crate::agent::{Agent, AgentEvent, RunOutcome};
+use crate::budget::Budget;
+use crate::config::Config;
+use crate::converse::ConverseClient;
+use crate::guardrails::Guardrail;
+use crate::hooks::{HookOutcome, Hooks, PreRequest};
+use crate::inference::{self, InferenceSettings};
+use crate::mcp_tool_dispatcher::McpToolDispatcher;
+use crate::models::{self, ModelFamily};
+use crate::prompt_template;
+use crate::redaction::Redactor;
+use crate::retry::RetryPolicy;
+use crate::session_store::{self, SessionStore};
+use crate::sub_agent::{Parent, SubAgent};
+use crate::thinking::ThinkingLog;
+use crate::tool_filter::ToolFilter;
+use crate::tool_log::ToolLog;
+use crate::usage::UsageLedger;
+use gamecode_backend::{
+    LLMBackend, Message as BackendMessage, MessageRole as BackendMessageRole, Tool as BackendTool,
+};
+use gamecode_context::session::{Message as ContextMessage, MessageRole as ContextMessageRole, Session};
+use gamecode_prompt::PromptManager;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::collections::{HashMap, HashSet};
+use std::convert::Infallible;
+use std::net::SocketAddr;
+use std::sync::Arc;
+use tokio::sync::{mpsc, Mutex};
+use tokio_stream::wrappers::UnboundedReceiverStream;
+use tokio_stream::{Stream, StreamExt};
+use tracing::debug;
+use uuid::Uuid;
+
+/// Backend, tools and sessions shared by the requests of a long-running
+/// server (`gamecode serve` and `gamecode mcp serve`)
+pub struct ServerState {
+    pub backend: Arc<dyn LLMBackend>,
+    pub region: String,
+    /// Bedrock model ID used when a request doesn't name one
+    pub model: String,
+    pub config: Config,
+    pub tools: Vec<BackendTool>,
+    pub dispatcher: Option<Arc<McpToolDispatcher>>,
+    /// Run every tool call; otherwise only tools in `approved_tools` run
+    pub approve_all: bool,
+    pub sessions: Mutex<Box<dyn SessionStore>>,
+    /// Masks secrets in prompts and tool results; None if disabled
+    pub redactor: Option<Arc<Redactor>>,
+    /// The configured Bedrock guardrail, applied to every turn
+    pub guardrail: Option<Arc<Guardrail>>,
+    /// For turns with stop sequences or cache points, which the backend can't send
+    pub converse: ConverseClient,
+    /// One lock per session, so concurrent requests take turns
+    turns: Mutex<HashMap<Uuid, Arc<Mutex<()>>>>,
+}
+
+/// A session as listed by the APIs
+#[derive(Debug, Serialize)]
+pub struct SessionSummary {
+    pub id: Uuid,
+    pub created_at: chrono::DateTime<chrono::Utc>,
+    pub message_count: usize,
+}
+
+impl ServerState {
+    /// Connect to Bedrock and the configured MCP servers. `model` is a
+    /// `--model` value; tools are filtered by the config like the CLI's.
+    pub async fn new(region: &str, model: Option<String>, approve_all: bool, no_tools: bool) -> Result<Self> {
+        let config = Config::load()?;
+        let model = crate::requested_model(model, &config);
+        let model = crate::map_model_name(model.as_deref().unwrap_or(crate::DEFAULT_MODEL), &config, region);
+        let backend: Arc<dyn LLMBackend> = Arc::from(crate::create_backend(region).await?);
+
+        let family = ModelFamily::from_model_id(&model);
+        let (tools, dispatcher) = if no_tools || !models::supports_tools(&model) {
+            (Vec::new(), None)
+        } else {
+            let filter = ToolFilter::new(config.tools.clone(), config.deny_tools.clone())
+                .read_only(config.read_only, config.tool_capabilities.clone());
+            let dispatcher = McpToolDispatcher::new().await?;
+            let mut tools = dispatcher.get_registry().await.lock().await.to_bedrock_tools_filtered(&filter);
+            dispatcher.enable_sampling(backend.clone(), model.clone()).await;
+            tools.extend(filter.builtin_tools(&config));
+            for tool in &mut tools {
+                family.sanitize_tool_schema(&mut tool.input_schema);
+            }
+            (tools, Some(Arc::new(dispatcher)))
+        };
+
+        let redactor = Redactor::from_config(&config.redaction)?.map(Arc::new);
+        let guardrail = match config.guardrail.clone() {
+            Some(guardrail) => Some(Arc::new(Guardrail::new(region, guardrail).await)),
+            None => None,
+        };
+        let converse = ConverseClient::new(region).await;
+        let sessions = session_store::open(&config)?;
+        Ok(Self {
+            backend,
+            region: region.to_string(),
+            model,
+            config,
+            tools,
+            dispatcher,
+            approve_all,
+            sessions: Mutex::new(sessions),
+            redactor,
+            guardrail,
+            converse,
+            turns: Mutex::new(HashMap::new()),
+        })
+    }
+
+    pub async fn list_sessions(&self) -> Result<Vec<SessionSummary>> {
+        let sessions = self.sessions.lock().await.list_sessions()?;
+        Ok(sessions
+            .into_iter()
+            .map(|info| SessionSummary {
+                id: info.id,
+                created_at: info.created_at,
+                message_count: info.message_count,
+            })
+            .collect())
+    }
+
+    /// Start a session with a literal system prompt, a stored prompt by name,
+    /// or the default prompt
+    pub async fn create_session(&self, system_prompt: Option<String>, prompt: Option<&str>) -> Result<Session> {
+        let system_prompt = match system_prompt {
+            Some(literal) => literal,
+            None => {
+                let prompts = PromptManager::new().context("Failed to create prompt manager")?;
+                let template = match prompt {
+                    Some(name) => prompts
+                        .load_prompt(name)
+                        .with_context(|| format!("Failed to load prompt '{}'", name))?,
+                    None => prompts.load_default().context("Failed to load default prompt")?,
+                };
+                let load_include = |name: &str| -> Result<String> { Ok(prompts.load_prompt(name)?) };
+                prompt_template::expand_includes(&template, &load_include)?
+            }
+        };
+
+        let mut sessions = self.sessions.lock().await;
+        let mut session = sessions.new_session()?;
+        sessions.add_message(&mut session, ContextMessage::new(ContextMessageRole::System, system_prompt))?;
+        sessions.save_session(&session)?;
+        Ok(session)
+    }
+
+    pub async fn load_session(&self, id: &Uuid) -> Result<Session> {
+        self.sessions
+            .lock()
+            .await
+            .load_session(id)
+            .with_context(|| format!("Session {} not found", id))
+    }
+
+    /// Add a user message to a session, run the agent, and store what it said.
+    /// Tool calls run if `approve_all` is set or the tool is in `approved_tools`.
+    pub async fn run_turn(
+        &self,
+        id: Uuid,
+        content: String,
+        model: Option<String>,
+        on_event: impl FnMut(&AgentEvent) + Send,
+    ) -> Result<RunOutcome> {
+        let approved: HashSet<String> = self.config.approved_tools.iter().cloned().collect();
+        let approve_all = self.approve_all;
+        self.run_turn_with(id, content, model, move |name, _| approve_all || approved.contains(name), on_event)
+            .await
+    }
+
+    /// Like `run_turn`, asking `approve` before each tool call. Turns on the
+    /// same session run one at a time.
+    pub async fn run_turn_with(
+        &self,
+        id: Uuid,
+        content: String,
+        model: Option<String>,
+        approve: impl Fn(&str, &Value) -> bool + Send + Sync + 'static,
+        mut on_event: impl FnMut(&AgentEvent) + Send,
+    ) -> Result<RunOutcome> {
+        let turn = self.turns.lock().await.entry(id).or_default().clone();
+        let _turn = turn.lock().await;
+
+        let mut session = self.load_session(&id).await?;
+        let content = match &self.redactor {
+            Some(redactor) => redactor.redact_reporting(&content, "prompt"),
+            None => content,
+        };
+        let hooks = Hooks::new(self.config.hooks.clone(), Some(id.to_string()));
+        let prompt_model = match &model {
+            Some(model) => crate::map_model_name(model, &self.config, &self.region),
+            None => self.model.clone(),
+        };
+        let content = match hooks.pre_request(PreRequest { model: prompt_model, prompt: content }).await? {
+            HookOutcome::Continue(checked) => checked.prompt,
+            HookOutcome::Veto(reason) => anyhow::bail!("Prompt rejected by the pre_request hook: {}", reason),
+        };
+        {
+            let mut sessions = self.sessions.lock().await;
+            sessions.add_message(&mut session, ContextMessage::new(ContextMessageRole::User, content))?;
+            sessions.save_session(&session)?;
+        }
+
+        let model_id = match &model {
+            Some(model) => crate::map_model_name(model, &self.config, &self.region),
+            None => self.model.clone(),
+        };
+        debug!("Running session {} with {}", id, model_id);
+        let mut settings = inference::resolve(
+            InferenceSettings::default(),
+            &self.config.inference,
+            model.as_deref(),
+            &model_id,
+        );
+        if let Some(limit) = inference::output_limit(&self.config.model_limits, model.as_deref(), &model_id) {
+            if let Some(max_tokens) = settings.clamp_max_tokens(limit) {
+                debug!("max_tokens {} exceeds the limit of {} for {}; using {}", max_tokens, limit, model_id, limit);
+            }
+        }
+        // Stop sequences and cache points need the Converse API
+        let prompt_cache = self.config.prompt_cache && models::supports_prompt_cache(&model_id);
+        let direct = settings.stop_sequences.is_some() || prompt_cache;
+
+        let mut agent = Agent::new(self.backend.clone(), model_id.clone())
+            .tools(self.tools.clone(), self.dispatcher.clone())
+            .inference(settings)
+            .retry(RetryPolicy::from_config(&self.config.retry))
+            .approve_tools_with(approve)
+            .budget(Budget::load(self.config.budget.clone(), &id)?)
+            .hooks(hooks)
+            .session(id);
+        if let Some(redactor) = &self.redactor {
+            agent = agent.redact_with(redactor.clone());
+        }
+        if let Some(guardrail) = &self.guardrail {
+            agent = agent.guardrail(guardrail.clone());
+        }
+        if direct {
+            agent = agent.converse(self.converse.clone().cache_points(prompt_cache));
+        }
+        let sub_agent = SubAgent::for_parent(Parent {
+            backend: self.backend.clone(),
+            model: &model_id,
+            config: &self.config,
+            region: &self.region,
+            tools: &self.tools,
+            dispatcher: self.dispatcher.clone(),
+            retry: RetryPolicy::from_config(&self.config.retry),
+            approve_all: self.approve_all,
+            // Approved tools run here whether or not they change files
+            write_tools: HashSet::new(),
+            session_id: id,
+            redactor: self.redactor.clone(),
+        });
+        if let Some(sub_agent) = sub_agent {
+            agent = agent.sub_agent(sub_agent);
+        }
+
+        let mut messages: Vec<BackendMessage> = session
+            .messages
+            .iter()
+            .map(|m| {
+                let role = match m.role {
+                    ContextMessageRole::System => BackendMessageRole::System,
+                    ContextMessageRole::User | ContextMessageRole::Tool => BackendMessageRole::User,
+                    ContextMessageRole::Assistant => BackendMessageRole::Assistant,
+                };
+                BackendMessage::text(role, m.content.clone())
+            })
+            .collect();
+
+        // Record the conversation the same way the CLI does: responses as assistant
+        // messages and a note for each tool call
+        let mut transcript = Vec::new();
+        let mut prompt_blocked = false;
+        let mut on_agent_event = |event: AgentEvent| {
+            match &event {
+                AgentEvent::Intervened { stage, .. } if stage == "prompt" => prompt_blocked = true,
+                AgentEvent::Text { text } => {
+                    transcript.push(ContextMessage::new(ContextMessageRole::Assistant, text.clone()))
+                }
+                AgentEvent::ToolResult { name, is_error, .. } => transcript.push(ContextMessage::new(
+                    ContextMessageRole::System,
+                    format!("Tool {} {}", name, if *is_error { "failed" } else { "completed" }),
+                )),
+                _ => {}
+            }
+            on_event(&event);
+        };
+        let result = agent.run(&mut messages, &mut on_agent_event).await;
+
+        let mut sessions = self.sessions.lock().await;
+        if prompt_blocked {
+            // The guardrail answered instead of the model; drop the prompt so it isn't sent again
+            if let Some(index) = session.messages.iter().rposition(|m| matches!(m.role, ContextMessageRole::User)) {
+                session.messages.remove(index);
+                ToolLog::open(&session.id)?.truncate(index)?;
+                ThinkingLog::open(&session.id)?.truncate(index)?;
+            }
+        } else {
+            for message in transcript {
+                sessions.add_message(&mut session, message)?;
+            }
+        }
+        sessions.save_session(&session)?;
+        drop(sessions);
+
+        let outcome = result?;
+        if let Err(e) = UsageLedger::record(&session.id, &outcome.model, outcome.usage) {
+            debug!("Failed to record usage: {}", e);
+        }
+        Ok(outcome)
+    }
+}
+
+/// Who may call the HTTP API. With a token, every request must carry it as
+/// `Authorization: Bearer <token>`. Without one, only requests addressed to
+/// loopback or `hosts` are served, so a web page can't reach the API through
+/// DNS rebinding.
+#[derive(Debug, Clone, Default)]
+pub struct Access {
+    pub token: Option<String>,
+    /// Host names accepted in `Host` and `Origin` besides localhost
+    pub hosts: Vec<String>,
+}
+
+impl Access {
+    fn check(&self, headers: &HeaderMap) -> Result<(), ApiError> {
+        let header = |name: header::HeaderName| headers.get(name).and_then(|value| value.to_str().ok());
+
+        if let Some(token) = &self.token {
+            let presented = header(header::AUTHORIZATION).and_then(|value| value.strip_prefix("Bearer "));
+            return match presented {
+                Some(presented) if constant_time_eq(presented.as_bytes(), token.as_bytes()) => Ok(()),
+                _ => Err(ApiError(StatusCode::UNAUTHORIZED, anyhow::anyhow!("Missing or invalid bearer token"))),
+            };
+        }
+
+        let host = header(header::HOST).unwrap_or_default();
+        if !self.allows(host) {
+            return Err(ApiError(StatusCode::FORBIDDEN, anyhow::anyhow!("Host '{}' is not allowed", host)));
+        }
+        if let Some(origin) = header(header::ORIGIN) {
+            let authority = origin.split_once("://").map(|(_, rest)| rest).unwrap_or_default();
+            if !self.allows(authority) {
+                return Err(ApiError(StatusCode::FORBIDDEN, anyhow::anyhow!("Origin '{}' is not allowed", origin)));
+            }
+        }
+        Ok(())
+    }
+
+    /// Whether `authority` (a host with an optional port) names this server
+    fn allows(&self, authority: &str) -> bool {
+        let name = match authority.strip_prefix('[') {
+            Some(bracketed) => bracketed.split(']').next().unwrap_or_default(),
+            None => authority.split(':').next().unwrap_or_default(),
+        };
+        matches!(name, "localhost" | "127.0.0.1" | "::1") || self.hosts.iter().any(|host| host.eq_ignore_ascii_case(name))
+    }
+}
+
+fn constant_time_eq(a: &[u8], b: &[u8]) -> bool {
+    a.len() == b.len() && a.iter().zip(b).fold(0, |diff, (x, y)| diff | (x ^ y)) == 0
+}
+
+async fn check_access(State(access): State<Arc<Access>>, request: Request, next: Next) -> Response {
+    match access.check(request.headers()) {
+        Ok(()) => next.run(request).await,
+        Err(e) => e.into_response(),
+    }
+}
+
+/// Serve the HTTP API on `addr` until the process is stopped
+pub async fn serve(addr: SocketAddr, state: ServerState, access: Access) -> Result<()> {
+    let app = Router::new()
+        .route("/health", get(|| async { "ok" }))
+        .route("/sessions", get(list_sessions).post(create_session))
+        .route("/sessions/:id", get(get_session))
+        .route("/sessions/:id/messages", post(send_message))
+        .route("/sessions/:id/events", post(stream_message))
+        .with_state(Arc::new(state))
+        .layer(middleware::from_fn_with_state(Arc::new(access), check_access));
+
+    let listener = tokio::net::TcpListener::bind(addr)
+        .await
+        .with_context(|| format!("Failed to listen on {}", addr))?;
+    axum::serve(listener, app).await.context("HTTP server failed")
+}
+
+/// Errors are returned as `{"error": "..."}`
+struct ApiError(StatusCode, anyhow::Error);
+
+impl<E: Into<anyhow::Error>> From<E> for ApiError {
+    fn from(error: E) -> Self {
+        Self(StatusCode::INTERNAL_SERVER_ERROR, error.into())
+    }
+}
+
+impl IntoResponse for ApiError {
+    fn into_response(self) -> Response {
+        let body = Json(serde_json::json!({ "error": format!("{:#}", self.1) }));
+        (self.0, body).into_response()
+    }
+}
+
+#[derive(Serialize)]
+struct SessionMessage {
+    role: String,
+    content: String,
+}
+
+#[derive(Serialize)]
+struct SessionDetail {
+    id: Uuid,
+    messages: Vec<SessionMessage>,
+}
+
+#[derive(Default, Deserialize)]
+#[serde(default)]
+struct CreateSession {
+    /// Literal system prompt
+    system_prompt: Option<String>,
+    /// Name of a stored prompt to use as the system prompt
+    prompt: Option<String>,
+}
+
+#[derive(Deserialize)]
+struct SendMessage {
+    content: String,
+    /// Model alias or ID for this message only
+    #[serde(default)]
+    model: Option<String>,
+}
+
+async fn list_sessions(State(state): State<Arc<ServerState>>) -> Result<Json<Vec<SessionSummary>>, ApiError> {
+    Ok(Json(state.list_sessions().await?))
+}
+
+async fn create_session(
+    State(state): State<Arc<ServerState>>,
+    body: Option<Json<CreateSession>>,
+) -> Result<(StatusCode, Json<SessionSummary>), ApiError> {
+    let request = body.map(|Json(body)| body).unwrap_or_default();
+    let session = state.create_session(request.system_prompt, request.prompt.as_deref()).await?;
+    Ok((
+        StatusCode::CREATED,
+        Json(SessionSummary {
+            id: session.id,
+            created_at: chrono::Utc::now(),
+            message_count: session.messages.len(),
+        }),
+    ))
+}
+
+/// Load a session, answering 404 if it doesn't exist
+async fn find_session(state: &ServerState, id: &Uuid) -> Result<Session, ApiError> {
+    state.load_session(id).await.map_err(|e| ApiError(StatusCode::NOT_FOUND, e))
+}
+
+async fn get_session(
+    State(state): State<Arc<ServerState>>,
+    Path(id): Path<Uuid>,
+) -> Result<Json<SessionDetail>, ApiError> {
+    let session = find_session(&state, &id).await?;
+    Ok(Json(SessionDetail {
+        id: session.id,
+        messages: session
+            .messages
+            .iter()
+            .map(|m| SessionMessage {
+                role: format!("{:?}", m.role).to_lowercase(),
+                content: m.content.clone(),
+            })
+            .collect(),
+    }))
+}
+
+async fn send_message(
+    State(state): State<Arc<ServerState>>,
+    Path(id): Path<Uuid>,
+    Json(request): Json<SendMessage>,
+) -> Result<Json<RunOutcome>, ApiError> {
+    find_session(&state, &id).await?;
+    let outcome = state.run_turn(id, request.content, request.model, |_| {}).await?;
+    Ok(Json(outcome))
+}
+
+/// Like `send_message`, but streams each `AgentEvent` as a server-sent event,
+/// followed by a `done` event with the outcome (or an `error` event)
+async fn stream_message(
+    State(state): State<Arc<ServerState>>,
+    Path(id): Path<Uuid>,
+    Json(request): Json<SendMessage>,
+) -> Result<Sse<impl Stream<Item = Result<SseEvent, Infallible>>>, ApiError> {
+    find_session(&state, &id).await?;
+
+    let (sender, receiver) = mpsc::unbounded_channel();
+    tokio::spawn(async move {
+        let events = sender.clone();
+        let result = state
+            .run_turn(id, request.content, request.model, move |event| {
+                let data = serde_json::to_string(event).unwrap_or_default();
+                events.send(SseEvent::default().event("agent").data(data)).ok();
+            })
+            .await;
+        let final_event = match result {
+            Ok(outcome) => SseEvent::default()
+                .event("done")
+                .data(serde_json::to_string(&outcome).unwrap_or_default()),
+            Err(e) => SseEvent::default().event("error").data(format!("{:#}", e)),
+        };
+        sender.send(final_event).ok();
+    });
+
+    Ok(Sse::new(UnboundedReceiverStream::new(receiver).map(Ok)).keep_alive(KeepAlive::default()))
+}
@@ -0,0 +1,155 @@
+use anyhow::{bail, Context, Result};
+use chrono::{DateTime, Duration, Utc};
+use crate::session_store::{SessionInfo, SessionStore};
+use crate::thinking::{ThinkingLog, ThinkingRecord};
+use crate::tool_log::{ToolCallRecord, ToolLog};
+use flate2::read::GzDecoder;
+use flate2::write::GzEncoder;
+use flate2::Compression;
+use gamecode_context::session::Session;
+use serde::{Deserialize, Serialize};
+use std::fs::{self, File};
+use std::io::{BufReader, BufWriter, Write};
+use std::path::PathBuf;
+use tracing::debug;
+use uuid::Uuid;
+
+/// Everything kept for an archived session
+#[derive(Debug, Serialize, Deserialize)]
+struct ArchivedSession {
+    archived_at: DateTime<Utc>,
+    /// When the session was last saved, if its store tracked it
+    updated_at: Option<DateTime<Utc>>,
+    session: Session,
+    tool_calls: Vec<ToolCallRecord>,
+    #[serde(default)]
+    thinking: Vec<ThinkingRecord>,
+}
+
+/// An archived session as listed
+#[derive(Debug, Clone)]
+pub struct ArchivedInfo {
+    pub id: Uuid,
+    pub created_at: DateTime<Utc>,
+    pub archived_at: DateTime<Utc>,
+    pub message_count: usize,
+}
+
+/// Sessions moved out of the session store into gzipped JSON files under
+/// `~/.local/share/gamecode/archive/`, one per session with its tool calls
+/// and thinking
+pub struct SessionArchive {
+    dir: PathBuf,
+}
+
+impl SessionArchive {
+    pub fn open() -> Result<Self> {
+        let home = home::home_dir().context("Failed to get home directory")?;
+        Ok(Self {
+            dir: home.join(".local").join("share").join("gamecode").join("archive"),
+        })
+    }
+
+    fn path(&self, id: &Uuid) -> PathBuf {
+        self.dir.join(format!("{}.json.gz", id))
+    }
+
+    /// Move a session, its tool calls and its thinking from `store` into the archive
+    pub fn archive(&self, store: &mut dyn SessionStore, info: &SessionInfo) -> Result<()> {
+        let session = store.load_session(&info.id)?;
+        let tool_log = ToolLog::open(&info.id)?;
+        let thinking_log = ThinkingLog::open(&info.id)?;
+        let archived = ArchivedSession {
+            archived_at: Utc::now(),
+            updated_at: info.updated_at,
+            session,
+            tool_calls: tool_log.load()?,
+            thinking: thinking_log.load()?,
+        };
+
+        fs::create_dir_all(&self.dir)?;
+        let path = self.path(&info.id);
+        // Written under a temporary name so a failed write leaves no partial archive
+        let partial = path.with_extension("gz.partial");
+        let mut encoder = GzEncoder::new(BufWriter::new(File::create(&partial)?), Compression::default());
+        serde_json::to_writer(&mut encoder, &archived).context("Failed to write the archive")?;
+        encoder.finish()?.flush()?;
+        fs::rename(&partial, &path).with_context(|| format!("Failed to write {}", path.display()))?;
+
+        // Deleting the session drops its tool calls and thinking too
+        if let Err(e) = store.delete_session(&info.id) {
+            let _ = fs::remove_file(&path);
+            return Err(e);
+        }
+        debug!("Archived session {} to {}", info.id, path.display());
+        Ok(())
+    }
+
+    /// Put an archived session back into `store`
+    pub fn unarchive(&self, store: &mut dyn SessionStore, id: &Uuid) -> Result<Session> {
+        let path = self.path(id);
+        if !path.exists() {
+            bail!("Session {} is not archived", id);
+        }
+        let archived = self.read(id)?;
+        store.import_session(&archived.session, archived.updated_at.unwrap_or_else(Utc::now))?;
+        ToolLog::open(id)?.restore(&archived.tool_calls)?;
+        ThinkingLog::open(id)?.restore(&archived.thinking)?;
+        fs::remove_file(&path).with_context(|| format!("Failed to remove {}", path.display()))?;
+        Ok(archived.session)
+    }
+
+    /// Archived sessions, oldest first
+    pub fn list(&self) -> Result<Vec<ArchivedInfo>> {
+        if !self.dir.exists() {
+            return Ok(Vec::new());
+        }
+        let mut sessions = Vec::new();
+        for entry in fs::read_dir(&self.dir)? {
+            let path = entry?.path();
+            let Some(id) = path
+                .file_name()
+                .and_then(|name| name.to_str())
+                .and_then(|name| name.strip_suffix(".json.gz"))
+                .and_then(|id| Uuid::parse_str(id).ok())
+            else {
+                continue;
+            };
+            match self.read(&id) {
+                Ok(archived) => sessions.push(ArchivedInfo {
+                    id,
+                    created_at: archived.session.created_at.with_timezone(&Utc),
+                    archived_at: archived.archived_at,
+                    message_count: archived.session.messages.len(),
+                }),
+                Err(e) => debug!("Skipping unreadable archive {}: {}", path.display(), e),
+            }
+        }
+        sessions.sort_by_key(|session| session.created_at);
+        Ok(sessions)
+    }
+
+    fn read(&self, id: &Uuid) -> Result<ArchivedSession> {
+        let path = self.path(id);
+        let file = File::open(&path).with_context(|| format!("Failed to open {}", path.display()))?;
+        serde_json::from_reader(GzDecoder::new(BufReader::new(file)))
+            .with_context(|| format!("Corrupt archive: {}", path.display()))
+    }
+}
+
+/// Parse an age like `30d`, `12h`, `2w` or `45m`
+pub fn parse_age(value: &str) -> Result<Duration> {
+    let value = value.trim();
+    let digits = value.len() - value.trim_start_matches(|c: char| c.is_ascii_digit()).len();
+    let (number, unit) = value.split_at(digits);
+    let Ok(number) = number.parse::<i64>() else {
+        bail!("Invalid age '{}': expected a number and a unit, like 30d", value);
+    };
+    match unit {
+        "m" => Ok(Duration::minutes(number)),
+        "h" => Ok(Duration::hours(number)),
+        "d" | "" => Ok(Duration::days(number)),
+        "w" => Ok(Duration::weeks(number)),
+        _ => bail!("Invalid age '{}': the unit must be m, h, d or w", value),
+    }
+}
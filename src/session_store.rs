@@ -0,0 +1,577 @@
+use anyhow::{bail, Context, Result};
+use chrono::{DateTime, Utc};
+use crate::config::Config;
+use crate::file_backups::SessionBackups;
+use crate::thinking::ThinkingLog;
+use crate::tool_log::ToolLog;
+use gamecode_context::session::{Message, MessageRole, Session};
+use gamecode_context::SessionManager;
+use rusqlite::{params, Connection, OptionalExtension, Transaction};
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::path::PathBuf;
+use tracing::debug;
+use uuid::Uuid;
+
+/// Where sessions are kept, chosen with `storage` in the `[sessions]` section
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum StorageKind {
+    /// One JSON file per session (the original format)
+    #[default]
+    File,
+    /// A single SQLite database with indexed metadata and messages
+    Sqlite,
+}
+
+/// Settings for the `[sessions]` section
+#[derive(Debug, Default, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct SessionsConfig {
+    pub storage: StorageKind,
+    /// Default location for `sessions sync`, as `s3://bucket/prefix`
+    pub sync_bucket: Option<String>,
+}
+
+/// A session as listed, without its messages
+#[derive(Debug, Clone, Serialize)]
+pub struct SessionInfo {
+    pub id: Uuid,
+    pub created_at: DateTime<Utc>,
+    /// When the session was last saved; not tracked by the file store
+    pub updated_at: Option<DateTime<Utc>>,
+    pub message_count: usize,
+}
+
+/// A message matching a search
+#[derive(Debug, Clone, Serialize)]
+pub struct SearchHit {
+    pub session_id: Uuid,
+    /// Position of the message in its session, from 0
+    pub index: usize,
+    pub role: String,
+    pub content: String,
+}
+
+/// Totals over every stored session
+#[derive(Debug, Default, Clone, Serialize)]
+pub struct StoreStats {
+    pub sessions: usize,
+    pub messages: usize,
+    /// Characters of message content
+    pub content_chars: usize,
+    pub oldest: Option<DateTime<Utc>>,
+    pub newest: Option<DateTime<Utc>>,
+}
+
+/// Storage for sessions. Messages added with `add_message` are kept when the
+/// session is next saved; SQLite storage writes them right away.
+pub trait SessionStore: Send {
+    fn new_session(&mut self) -> Result<Session>;
+    fn load_session(&mut self, id: &Uuid) -> Result<Session>;
+    /// The most recently used session, or a new one if there are none
+    fn load_latest(&mut self) -> Result<Session>;
+    fn save_session(&mut self, session: &Session) -> Result<()>;
+    /// Store a session copied from elsewhere, keeping the time it was last
+    /// updated there where the store tracks it
+    fn import_session(&mut self, session: &Session, _updated_at: DateTime<Utc>) -> Result<()> {
+        self.save_session(session)
+    }
+    fn add_message(&mut self, session: &mut Session, message: Message) -> Result<()>;
+    fn list_sessions(&self) -> Result<Vec<SessionInfo>>;
+    fn delete_session(&mut self, id: &Uuid) -> Result<()>;
+    /// Messages containing `query`, ignoring ASCII case, newest sessions first
+    fn search(&self, query: &str, limit: usize) -> Result<Vec<SearchHit>>;
+    fn stats(&self) -> Result<StoreStats>;
+}
+
+/// The session store selected in config
+pub fn open(config: &Config) -> Result<Box<dyn SessionStore>> {
+    match config.sessions.storage {
+        StorageKind::File => Ok(Box::new(FileStore::new()?)),
+        StorageKind::Sqlite => {
+            // The first time SQLite storage is used, the JSON sessions are brought along
+            let is_new = !SqliteStore::db_path()?.exists();
+            let mut store = SqliteStore::open()?;
+            if is_new {
+                let imported = store.import(&FileStore::new()?)?;
+                if imported > 0 {
+                    status!("📦 Imported {} session(s) into {}", imported, SqliteStore::db_path()?.display());
+                }
+            }
+            Ok(Box::new(store))
+        }
+    }
+}
+
+/// The session store selected in the loaded config
+pub fn open_configured() -> Result<Box<dyn SessionStore>> {
+    open(&Config::load()?)
+}
+
+/// Sessions as JSON files, managed by `gamecode_context`
+pub struct FileStore {
+    manager: SessionManager,
+}
+
+impl FileStore {
+    pub fn new() -> Result<Self> {
+        Ok(Self { manager: SessionManager::new()? })
+    }
+
+    /// Where `gamecode_context` keeps the session's JSON file
+    fn session_path(id: &Uuid) -> Result<PathBuf> {
+        let home = home::home_dir().context("Failed to get home directory")?;
+        Ok(home
+            .join(".local")
+            .join("share")
+            .join("gamecode")
+            .join("sessions")
+            .join(format!("{}.json", id)))
+    }
+}
+
+impl SessionStore for FileStore {
+    fn new_session(&mut self) -> Result<Session> {
+        Ok(self.manager.new_session()?)
+    }
+
+    fn load_session(&mut self, id: &Uuid) -> Result<Session> {
+        Ok(self.manager.load_session(id)?)
+    }
+
+    fn load_latest(&mut self) -> Result<Session> {
+        Ok(self.manager.load_latest()?)
+    }
+
+    fn save_session(&mut self, session: &Session) -> Result<()> {
+        Ok(self.manager.save_session(session)?)
+    }
+
+    fn add_message(&mut self, session: &mut Session, message: Message) -> Result<()> {
+        Ok(self.manager.add_message(session, message)?)
+    }
+
+    fn list_sessions(&self) -> Result<Vec<SessionInfo>> {
+        Ok(self
+            .manager
+            .list_sessions()?
+            .into_iter()
+            .map(|info| SessionInfo {
+                id: info.id,
+                created_at: info.created_at.into(),
+                updated_at: None,
+                message_count: info.message_count,
+            })
+            .collect())
+    }
+
+    fn delete_session(&mut self, id: &Uuid) -> Result<()> {
+        let path = Self::session_path(id)?;
+        if !path.exists() {
+            bail!("Session {} not found", id);
+        }
+        std::fs::remove_file(&path).with_context(|| format!("Failed to remove {}", path.display()))?;
+        remove_side_files(id)
+    }
+
+    // Loads every session, which is what makes the file store slow to search
+    fn search(&self, query: &str, limit: usize) -> Result<Vec<SearchHit>> {
+        let query = query.to_lowercase();
+        let mut sessions = self.list_sessions()?;
+        sessions.sort_by(|a, b| b.created_at.cmp(&a.created_at));
+        let mut manager = SessionManager::new()?;
+        let mut hits = Vec::new();
+        for info in sessions {
+            let session = match manager.load_session(&info.id) {
+                Ok(session) => session,
+                Err(e) => {
+                    debug!("Skipping unreadable session {}: {}", info.id, e);
+                    continue;
+                }
+            };
+            for (index, message) in session.messages.iter().enumerate() {
+                if message.content.to_lowercase().contains(&query) {
+                    hits.push(SearchHit {
+                        session_id: session.id,
+                        index,
+                        role: role_name(&message.role).to_string(),
+                        content: message.content.clone(),
+                    });
+                    if hits.len() >= limit {
+                        return Ok(hits);
+                    }
+                }
+            }
+        }
+        Ok(hits)
+    }
+
+    fn stats(&self) -> Result<StoreStats> {
+        let sessions = self.list_sessions()?;
+        let mut manager = SessionManager::new()?;
+        let mut stats = StoreStats {
+            sessions: sessions.len(),
+            oldest: sessions.iter().map(|s| s.created_at).min(),
+            newest: sessions.iter().map(|s| s.created_at).max(),
+            ..StoreStats::default()
+        };
+        for info in &sessions {
+            stats.messages += info.message_count;
+            if let Ok(session) = manager.load_session(&info.id) {
+                stats.content_chars += session.messages.iter().map(|m| m.content.chars().count()).sum::<usize>();
+            }
+        }
+        Ok(stats)
+    }
+}
+
+// What is kept per session outside the store: recorded tool calls,
+// thinking and file backups. Usage stays in the ledger, which daily
+// budgets are counted from.
+fn remove_side_files(id: &Uuid) -> Result<()> {
+    ToolLog::open(id)?.remove()?;
+    ThinkingLog::open(id)?.remove()?;
+    SessionBackups::open(id)?.remove()
+}
+
+/// Sessions in `~/.local/share/gamecode/sessions.db`. Each message is a row,
+/// so listing, searching and statistics don't need to parse every session
+/// and saving only writes the messages that changed. The session row keeps
+/// the session's other fields as JSON.
+pub struct SqliteStore {
+    conn: Connection,
+}
+
+impl SqliteStore {
+    pub fn db_path() -> Result<PathBuf> {
+        let home = home::home_dir().context("Failed to get home directory")?;
+        Ok(home
+            .join(".local")
+            .join("share")
+            .join("gamecode")
+            .join("sessions.db"))
+    }
+
+    /// Open the database, creating it on first use
+    pub fn open() -> Result<Self> {
+        let path = Self::db_path()?;
+        if let Some(parent) = path.parent() {
+            std::fs::create_dir_all(parent)?;
+        }
+        let conn = Connection::open(&path).with_context(|| format!("Failed to open {}", path.display()))?;
+        conn.execute_batch(
+            "CREATE TABLE IF NOT EXISTS sessions (
+                 id TEXT PRIMARY KEY,
+                 created_at TEXT NOT NULL,
+                 updated_at TEXT NOT NULL,
+                 message_count INTEGER NOT NULL,
+                 data TEXT NOT NULL
+             );
+             CREATE INDEX IF NOT EXISTS sessions_updated_at ON sessions (updated_at);
+             CREATE TABLE IF NOT EXISTS messages (
+                 session_id TEXT NOT NULL REFERENCES sessions (id) ON DELETE CASCADE,
+                 idx INTEGER NOT NULL,
+                 role TEXT NOT NULL,
+                 content TEXT NOT NULL,
+                 data TEXT NOT NULL DEFAULT '{}',
+                 PRIMARY KEY (session_id, idx)
+             );
+             PRAGMA foreign_keys = ON;",
+        )
+        .context("Failed to create the session tables")?;
+        let mut store = Self { conn };
+        store.migrate()?;
+        Ok(store)
+    }
+
+    // Databases from before messages kept their own fields stored each
+    // session whole as JSON in `sessions.data`; split those into rows
+    fn migrate(&mut self) -> Result<()> {
+        let current: bool = self.conn.query_row(
+            "SELECT COUNT(*) > 0 FROM pragma_table_info('messages') WHERE name = 'data'",
+            [],
+            |row| row.get(0),
+        )?;
+        if current {
+            return Ok(());
+        }
+        let tx = self.conn.transaction()?;
+        tx.execute("ALTER TABLE messages ADD COLUMN data TEXT NOT NULL DEFAULT '{}'", [])?;
+        let sessions = tx
+            .prepare("SELECT data, updated_at FROM sessions")?
+            .query_map([], |row| Ok((row.get::<_, String>(0)?, row.get::<_, String>(1)?)))?
+            .collect::<rusqlite::Result<Vec<_>>>()?;
+        tx.execute("DELETE FROM messages", [])?;
+        for (data, updated_at) in sessions {
+            let session: Session = serde_json::from_str(&data).context("Failed to parse a stored session")?;
+            write_session(&tx, &session, parse_time(&updated_at)?)?;
+        }
+        tx.commit()?;
+        Ok(())
+    }
+
+    /// Copy the sessions of `source` that aren't stored here yet. Returns
+    /// how many were copied.
+    pub fn import(&mut self, source: &FileStore) -> Result<usize> {
+        let mut manager = SessionManager::new()?;
+        let mut imported = 0;
+        for info in source.list_sessions()? {
+            let exists: bool = self
+                .conn
+                .query_row("SELECT 1 FROM sessions WHERE id = ?1", params![info.id.to_string()], |_| Ok(true))
+                .optional()?
+                .unwrap_or(false);
+            if exists {
+                continue;
+            }
+            match manager.load_session(&info.id) {
+                Ok(session) => {
+                    self.write(&session, info.created_at)?;
+                    imported += 1;
+                }
+                Err(e) => notice!("⚠️  Skipping unreadable session {}: {}", info.id, e),
+            }
+        }
+        Ok(imported)
+    }
+
+    fn write(&mut self, session: &Session, updated_at: DateTime<Utc>) -> Result<()> {
+        let tx = self.conn.transaction()?;
+        write_session(&tx, session, updated_at)?;
+        tx.commit()?;
+        Ok(())
+    }
+}
+
+// Bring the stored copy of `session` up to date, marking it updated at
+// `updated_at`. Stored messages are kept up to the first one that differs;
+// only the rest are deleted and inserted. Appending writes just the new
+// messages, while removing one (a redo, or a prompt a guardrail blocked)
+// rewrites from there on.
+fn write_session(tx: &Transaction, session: &Session, updated_at: DateTime<Utc>) -> Result<()> {
+    write_session_row(tx, session, updated_at)?;
+
+    let id = session.id.to_string();
+    let rows = session.messages.iter().map(message_row).collect::<Result<Vec<_>>>()?;
+    let stored = tx
+        .prepare("SELECT role, content, data FROM messages WHERE session_id = ?1 ORDER BY idx")?
+        .query_map(params![id], |row| Ok((row.get::<_, String>(0)?, row.get::<_, String>(1)?, row.get::<_, String>(2)?)))?
+        .collect::<rusqlite::Result<Vec<_>>>()?;
+    let unchanged = stored
+        .iter()
+        .zip(&rows)
+        .take_while(|((role, content, data), row)| (role.as_str(), content.as_str(), data) == (row.0, row.1, &row.2))
+        .count();
+
+    tx.execute("DELETE FROM messages WHERE session_id = ?1 AND idx >= ?2", params![id, unchanged])?;
+    let mut insert =
+        tx.prepare("INSERT INTO messages (session_id, idx, role, content, data) VALUES (?1, ?2, ?3, ?4, ?5)")?;
+    for (index, (role, content, data)) in rows.iter().enumerate().skip(unchanged) {
+        insert.execute(params![id, index, role, content, data])?;
+    }
+    Ok(())
+}
+
+// Store the session's own fields and message count, leaving its messages
+fn write_session_row(tx: &Transaction, session: &Session, updated_at: DateTime<Utc>) -> Result<()> {
+    let id = session.id.to_string();
+    let mut fields = serde_json::to_value(session).context("Failed to serialize session")?;
+    if let Some(fields) = fields.as_object_mut() {
+        fields.remove("messages");
+    }
+    tx.execute(
+        "INSERT INTO sessions (id, created_at, updated_at, message_count, data)
+         VALUES (?1, ?2, ?3, ?4, ?5)
+         ON CONFLICT (id) DO UPDATE SET
+             updated_at = excluded.updated_at,
+             message_count = excluded.message_count,
+             data = excluded.data",
+        params![id, session.created_at.to_rfc3339(), updated_at.to_rfc3339(), session.messages.len(), fields.to_string()],
+    )?;
+    Ok(())
+}
+
+/// How a role is stored and shown
+pub fn role_name(role: &MessageRole) -> &'static str {
+    match role {
+        MessageRole::System => "system",
+        MessageRole::User => "user",
+        MessageRole::Assistant => "assistant",
+        MessageRole::Tool => "tool",
+    }
+}
+
+fn parse_role(name: &str) -> Result<MessageRole> {
+    Ok(match name {
+        "system" => MessageRole::System,
+        "user" => MessageRole::User,
+        "assistant" => MessageRole::Assistant,
+        "tool" => MessageRole::Tool,
+        other => bail!("Unknown message role '{}'", other),
+    })
+}
+
+// A message as its role, its content and its other fields as JSON
+fn message_row(message: &Message) -> Result<(&'static str, &str, String)> {
+    let mut fields = serde_json::to_value(message).context("Failed to serialize message")?;
+    if let Some(fields) = fields.as_object_mut() {
+        fields.remove("role");
+        fields.remove("content");
+    }
+    Ok((role_name(&message.role), &message.content, fields.to_string()))
+}
+
+fn parse_time(value: &str) -> rusqlite::Result<DateTime<Utc>> {
+    DateTime::parse_from_rfc3339(value)
+        .map(|time| time.with_timezone(&Utc))
+        .map_err(|e| rusqlite::Error::FromSqlConversionFailure(0, rusqlite::types::Type::Text, Box::new(e)))
+}
+
+fn parse_id(value: &str) -> rusqlite::Result<Uuid> {
+    Uuid::parse_str(value)
+        .map_err(|e| rusqlite::Error::FromSqlConversionFailure(0, rusqlite::types::Type::Text, Box::new(e)))
+}
+
+impl SessionStore for SqliteStore {
+    fn new_session(&mut self) -> Result<Session> {
+        let session = Session::new();
+        self.write(&session, Utc::now())?;
+        Ok(session)
+    }
+
+    fn load_session(&mut self, id: &Uuid) -> Result<Session> {
+        let data: Option<String> = self
+            .conn
+            .query_row("SELECT data FROM sessions WHERE id = ?1", params![id.to_string()], |row| row.get(0))
+            .optional()?;
+        let data = data.with_context(|| format!("Session {} not found", id))?;
+        let mut session: Value = serde_json::from_str(&data).with_context(|| format!("Failed to parse session {}", id))?;
+
+        let rows = self
+            .conn
+            .prepare("SELECT role, content, data FROM messages WHERE session_id = ?1 ORDER BY idx")?
+            .query_map(params![id.to_string()], |row| Ok((row.get::<_, String>(0)?, row.get::<_, String>(1)?, row.get::<_, String>(2)?)))?
+            .collect::<rusqlite::Result<Vec<_>>>()?;
+        let mut messages = Vec::with_capacity(rows.len());
+        for (role, content, data) in rows {
+            let mut message: Value = serde_json::from_str(&data).with_context(|| format!("Failed to parse session {}", id))?;
+            if let Some(fields) = message.as_object_mut() {
+                fields.insert("role".to_string(), serde_json::to_value(parse_role(&role)?)?);
+                fields.insert("content".to_string(), Value::String(content));
+            }
+            messages.push(message);
+        }
+        if let Some(fields) = session.as_object_mut() {
+            fields.insert("messages".to_string(), Value::Array(messages));
+        }
+        serde_json::from_value(session).with_context(|| format!("Failed to parse session {}", id))
+    }
+
+    fn load_latest(&mut self) -> Result<Session> {
+        let latest: Option<String> = self
+            .conn
+            .query_row("SELECT id FROM sessions ORDER BY updated_at DESC LIMIT 1", [], |row| row.get(0))
+            .optional()?;
+        match latest {
+            Some(id) => self.load_session(&Uuid::parse_str(&id)?),
+            None => self.new_session(),
+        }
+    }
+
+    fn save_session(&mut self, session: &Session) -> Result<()> {
+        self.write(session, Utc::now())
+    }
+
+    fn import_session(&mut self, session: &Session, updated_at: DateTime<Utc>) -> Result<()> {
+        self.write(session, updated_at)
+    }
+
+    // Writes just the new message; `save_session` catches up anything else
+    fn add_message(&mut self, session: &mut Session, message: Message) -> Result<()> {
+        session.messages.push(message);
+        let index = session.messages.len() - 1;
+        let (role, content, data) = message_row(&session.messages[index])?;
+        let id = session.id.to_string();
+
+        let tx = self.conn.transaction()?;
+        write_session_row(&tx, session, Utc::now())?;
+        tx.execute("DELETE FROM messages WHERE session_id = ?1 AND idx >= ?2", params![id, index])?;
+        tx.execute(
+            "INSERT INTO messages (session_id, idx, role, content, data) VALUES (?1, ?2, ?3, ?4, ?5)",
+            params![id, index, role, content, data],
+        )?;
+        tx.commit()?;
+        Ok(())
+    }
+
+    fn list_sessions(&self) -> Result<Vec<SessionInfo>> {
+        let mut query = self
+            .conn
+            .prepare("SELECT id, created_at, updated_at, message_count FROM sessions ORDER BY created_at")?;
+        let sessions = query
+            .query_map([], |row| {
+                Ok(SessionInfo {
+                    id: parse_id(&row.get::<_, String>(0)?)?,
+                    created_at: parse_time(&row.get::<_, String>(1)?)?,
+                    updated_at: Some(parse_time(&row.get::<_, String>(2)?)?),
+                    message_count: row.get(3)?,
+                })
+            })?
+            .collect::<rusqlite::Result<Vec<_>>>()?;
+        Ok(sessions)
+    }
+
+    fn delete_session(&mut self, id: &Uuid) -> Result<()> {
+        let deleted = self.conn.execute("DELETE FROM sessions WHERE id = ?1", params![id.to_string()])?;
+        if deleted == 0 {
+            bail!("Session {} not found", id);
+        }
+        remove_side_files(id)
+    }
+
+    fn search(&self, query: &str, limit: usize) -> Result<Vec<SearchHit>> {
+        let pattern = format!(
+            "%{}%",
+            query.replace('\\', "\\\\").replace('%', "\\%").replace('_', "\\_")
+        );
+        let mut statement = self.conn.prepare(
+            "SELECT m.session_id, m.idx, m.role, m.content
+             FROM messages m JOIN sessions s ON s.id = m.session_id
+             WHERE m.content LIKE ?1 ESCAPE '\\'
+             ORDER BY s.updated_at DESC, m.idx
+             LIMIT ?2",
+        )?;
+        let hits = statement
+            .query_map(params![pattern, limit], |row| {
+                Ok(SearchHit {
+                    session_id: parse_id(&row.get::<_, String>(0)?)?,
+                    index: row.get(1)?,
+                    role: row.get(2)?,
+                    content: row.get(3)?,
+                })
+            })?
+            .collect::<rusqlite::Result<Vec<_>>>()?;
+        Ok(hits)
+    }
+
+    fn stats(&self) -> Result<StoreStats> {
+        let (sessions, oldest, newest): (usize, Option<String>, Option<String>) = self.conn.query_row(
+            "SELECT COUNT(*), MIN(created_at), MAX(updated_at) FROM sessions",
+            [],
+            |row| Ok((row.get(0)?, row.get(1)?, row.get(2)?)),
+        )?;
+        let (messages, content_chars): (usize, Option<usize>) = self.conn.query_row(
+            "SELECT COUNT(*), SUM(LENGTH(content)) FROM messages",
+            [],
+            |row| Ok((row.get(0)?, row.get(1)?)),
+        )?;
+        Ok(StoreStats {
+            sessions,
+            messages,
+            content_chars: content_chars.unwrap_or(0),
+            oldest: oldest.as_deref().map(parse_time).transpose()?,
+            newest: newest.as_deref().map(parse_time).transpose()?,
+        })
+    }
+}
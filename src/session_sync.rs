@@ -0,0 +1,274 @@
+use anyhow::{bail, Context, Result};
+use aws_config::SdkConfig;
+use aws_sdk_s3::primitives::ByteStream;
+use aws_sdk_s3::Client;
+use chrono::{DateTime, Utc};
+use crate::session_store::{SessionInfo, SessionStore};
+use gamecode_context::session::Session;
+use std::cmp::Ordering;
+use std::collections::{BTreeSet, HashMap};
+use std::str::FromStr;
+use tracing::debug;
+use uuid::Uuid;
+
+// Object metadata recording the session's state when it was pushed
+const UPDATED_AT_KEY: &str = "updated-at";
+const MESSAGE_COUNT_KEY: &str = "message-count";
+
+/// Where sessions are synced to: `s3://bucket/prefix`, one `<id>.json`
+/// object per session under the prefix
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct S3Location {
+    pub bucket: String,
+    /// Key prefix without surrounding slashes; may be empty
+    pub prefix: String,
+}
+
+impl FromStr for S3Location {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        let rest = s
+            .strip_prefix("s3://")
+            .with_context(|| format!("Expected a location like s3://bucket/prefix, got '{}'", s))?;
+        let (bucket, prefix) = rest.split_once('/').unwrap_or((rest, ""));
+        if bucket.is_empty() {
+            bail!("No bucket in '{}'", s);
+        }
+        Ok(Self {
+            bucket: bucket.to_string(),
+            prefix: prefix.trim_matches('/').to_string(),
+        })
+    }
+}
+
+impl std::fmt::Display for S3Location {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "s3://{}/{}", self.bucket, self.prefix)
+    }
+}
+
+impl S3Location {
+    fn key(&self, id: &Uuid) -> String {
+        if self.prefix.is_empty() {
+            format!("{}.json", id)
+        } else {
+            format!("{}/{}.json", self.prefix, id)
+        }
+    }
+
+    fn list_prefix(&self) -> Option<String> {
+        (!self.prefix.is_empty()).then(|| format!("{}/", self.prefix))
+    }
+}
+
+/// Which way sessions are copied
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub enum SyncMode {
+    /// Upload local sessions that are newer than (or missing from) the bucket
+    Push,
+    /// Download sessions from the bucket that are newer than (or missing from) the local store
+    Pull,
+    /// Both: the newer copy of each session wins
+    #[default]
+    Merge,
+}
+
+impl FromStr for SyncMode {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        match s.to_lowercase().as_str() {
+            "push" => Ok(Self::Push),
+            "pull" => Ok(Self::Pull),
+            "merge" => Ok(Self::Merge),
+            _ => bail!("Unknown sync mode '{}': expected push, pull or merge", s),
+        }
+    }
+}
+
+/// What a sync did
+#[derive(Debug, Default)]
+pub struct SyncReport {
+    pub pushed: Vec<Uuid>,
+    pub pulled: Vec<Uuid>,
+    /// Sessions already the same on both sides, or newer on the side this mode doesn't write
+    pub unchanged: usize,
+    /// Sessions that couldn't be read
+    pub skipped: usize,
+}
+
+// A session's object in the bucket, as described by its metadata
+struct RemoteSession {
+    updated_at: Option<DateTime<Utc>>,
+    message_count: Option<usize>,
+}
+
+/// Copies sessions between the local store and an S3 bucket. Credentials
+/// come from the same AWS configuration as the Bedrock backend, so
+/// `--aws-profile` and `--assume-role` apply here too.
+pub struct SessionSync {
+    client: Client,
+    location: S3Location,
+}
+
+impl SessionSync {
+    pub fn new(location: S3Location, sdk_config: &SdkConfig) -> Self {
+        Self {
+            client: Client::new(sdk_config),
+            location,
+        }
+    }
+
+    /// Sync every session. Sessions are compared by when they were last
+    /// updated where both sides know it (SQLite storage records it), and
+    /// otherwise by message count.
+    pub async fn run(&self, store: &mut dyn SessionStore, mode: SyncMode) -> Result<SyncReport> {
+        let local: HashMap<Uuid, SessionInfo> =
+            store.list_sessions()?.into_iter().map(|info| (info.id, info)).collect();
+        let remote = self.list_remote().await?;
+        let ids: BTreeSet<Uuid> = local.keys().chain(remote.keys()).copied().collect();
+
+        let mut report = SyncReport::default();
+        for id in ids {
+            let order = match (local.get(&id), remote.get(&id)) {
+                (Some(local), Some(remote)) => compare(local, remote),
+                (Some(_), None) => Ordering::Greater,
+                _ => Ordering::Less,
+            };
+            let copied = match order {
+                Ordering::Greater if mode != SyncMode::Pull => self.push(store, &local[&id]).await?,
+                Ordering::Less if mode != SyncMode::Push => self.pull(store, &id, &remote[&id]).await?,
+                _ => {
+                    report.unchanged += 1;
+                    continue;
+                }
+            };
+            match (copied, order) {
+                (false, _) => report.skipped += 1,
+                (true, Ordering::Greater) => report.pushed.push(id),
+                (true, _) => report.pulled.push(id),
+            }
+        }
+        Ok(report)
+    }
+
+    async fn list_remote(&self) -> Result<HashMap<Uuid, RemoteSession>> {
+        let mut remote = HashMap::new();
+        let mut pages = self
+            .client
+            .list_objects_v2()
+            .bucket(&self.location.bucket)
+            .set_prefix(self.location.list_prefix())
+            .into_paginator()
+            .send();
+        while let Some(page) = pages.next().await {
+            let page = page.with_context(|| format!("Failed to list {}", self.location))?;
+            for object in page.contents() {
+                let Some(key) = object.key() else {
+                    continue;
+                };
+                // Only <prefix>/<id>.json, not objects in nested "directories"
+                let Some(id) = key
+                    .rsplit('/')
+                    .next()
+                    .and_then(|name| name.strip_suffix(".json"))
+                    .and_then(|id| Uuid::parse_str(id).ok())
+                    .filter(|id| self.location.key(id) == key)
+                else {
+                    debug!("Ignoring {} in the sync bucket", key);
+                    continue;
+                };
+
+                let head = self
+                    .client
+                    .head_object()
+                    .bucket(&self.location.bucket)
+                    .key(key)
+                    .send()
+                    .await
+                    .with_context(|| format!("Failed to read metadata of {}", key))?;
+                let metadata = head.metadata();
+                remote.insert(
+                    id,
+                    RemoteSession {
+                        updated_at: metadata
+                            .and_then(|m| m.get(UPDATED_AT_KEY))
+                            .and_then(|value| DateTime::parse_from_rfc3339(value).ok())
+                            .map(|time| time.with_timezone(&Utc)),
+                        message_count: metadata
+                            .and_then(|m| m.get(MESSAGE_COUNT_KEY))
+                            .and_then(|value| value.parse().ok()),
+                    },
+                );
+            }
+        }
+        Ok(remote)
+    }
+
+    // Upload a local session; false if it couldn't be read
+    async fn push(&self, store: &mut dyn SessionStore, info: &SessionInfo) -> Result<bool> {
+        let session = match store.load_session(&info.id) {
+            Ok(session) => session,
+            Err(e) => {
+                notice!("⚠️  Skipping unreadable session {}: {}", info.id, e);
+                return Ok(false);
+            }
+        };
+        let body = serde_json::to_vec(&session).context("Failed to serialize session")?;
+        let key = self.location.key(&info.id);
+        let mut request = self
+            .client
+            .put_object()
+            .bucket(&self.location.bucket)
+            .key(&key)
+            .content_type("application/json")
+            .body(ByteStream::from(body))
+            .metadata(MESSAGE_COUNT_KEY, session.messages.len().to_string());
+        if let Some(updated_at) = info.updated_at {
+            request = request.metadata(UPDATED_AT_KEY, updated_at.to_rfc3339());
+        }
+        request
+            .send()
+            .await
+            .with_context(|| format!("Failed to upload s3://{}/{}", self.location.bucket, key))?;
+        Ok(true)
+    }
+
+    // Download a session into the local store; false if it couldn't be parsed
+    async fn pull(&self, store: &mut dyn SessionStore, id: &Uuid, remote: &RemoteSession) -> Result<bool> {
+        let key = self.location.key(id);
+        let object = self
+            .client
+            .get_object()
+            .bucket(&self.location.bucket)
+            .key(&key)
+            .send()
+            .await
+            .with_context(|| format!("Failed to download s3://{}/{}", self.location.bucket, key))?;
+        let body = object
+            .body
+            .collect()
+            .await
+            .with_context(|| format!("Failed to read s3://{}/{}", self.location.bucket, key))?
+            .into_bytes();
+        let session: Session = match serde_json::from_slice(&body) {
+            Ok(session) => session,
+            Err(e) => {
+                notice!("⚠️  Skipping unreadable session {} in the bucket: {}", id, e);
+                return Ok(false);
+            }
+        };
+        // Keep the bucket's timestamp so the next sync sees both copies as equal
+        store.import_session(&session, remote.updated_at.unwrap_or_else(Utc::now))?;
+        Ok(true)
+    }
+}
+
+// Whether the local copy of a session is newer (Greater) than the bucket's
+fn compare(local: &SessionInfo, remote: &RemoteSession) -> Ordering {
+    match (local.updated_at, remote.updated_at) {
+        (Some(local), Some(remote)) => local.cmp(&remote),
+        _ => local.message_count.cmp(&remote.message_count.unwrap_or(0)),
+    }
+}
@@ -0,0 +1,221 @@
+use anyhow::{anyhow, bail, Result};
+use flag_rs::Context;
+use std::fmt::Display;
+use std::str::FromStr;
+use std::time::Duration;
+
+use gamecode_cli::config::Config;
+use gamecode_cli::inference::InferenceSettings;
+use gamecode_cli::output::OutputFormat;
+use gamecode_cli::retry::RetryPolicy;
+use gamecode_cli::tool_result::DEFAULT_MAX_TOOL_RESULT_BYTES;
+use gamecode_cli::agent::{DEFAULT_MAX_TOOL_FAILURES, DEFAULT_MAX_TOOL_TURNS, DEFAULT_TOOL_CONCURRENCY};
+use gamecode_cli::requested_model;
+
+/// Prefix of the environment variables that stand in for flags
+pub const ENV_PREFIX: &str = "GAMECODE_";
+
+/// Region used when neither flags, environment nor config choose one
+pub const DEFAULT_REGION: &str = "us-west-2";
+
+/// Environment variable for `--<flag>`, e.g. `GAMECODE_NO_TOOLS` for `--no-tools`
+pub fn env_var(flag: &str) -> String {
+    format!("{}{}", ENV_PREFIX, flag.to_uppercase().replace('-', "_"))
+}
+
+/// Value of the environment variable for `--<flag>`, if set and not empty
+pub fn env_value(flag: &str) -> Option<String> {
+    std::env::var(env_var(flag)).ok().filter(|v| !v.is_empty())
+}
+
+/// Flag values for one command, each falling back to its `GAMECODE_*`
+/// environment variable when not given on the command line. Config file
+/// values come after both, so callers apply them to what this returns.
+pub struct Flags<'a> {
+    ctx: &'a Context,
+}
+
+impl<'a> Flags<'a> {
+    pub fn new(ctx: &'a Context) -> Self {
+        Self { ctx }
+    }
+
+    /// `--<name>`, then its environment variable
+    pub fn value(&self, name: &str) -> Option<String> {
+        self.ctx.flag(name).cloned().or_else(|| env_value(name))
+    }
+
+    /// `--<name>` parsed as `T`. A value that doesn't parse is an error
+    /// naming the flag or variable it came from, never a silent default.
+    pub fn parse<T>(&self, name: &str) -> Result<Option<T>>
+    where
+        T: FromStr,
+        T::Err: Display,
+    {
+        let (source, raw) = match self.ctx.flag(name) {
+            Some(raw) => (format!("--{}", name), raw.clone()),
+            None => match env_value(name) {
+                Some(raw) => (env_var(name), raw),
+                None => return Ok(None),
+            },
+        };
+        raw.trim()
+            .parse()
+            .map(Some)
+            .map_err(|e| anyhow!("Invalid {} value '{}': {}", source, raw, e))
+    }
+
+    /// `--<name>` as a comma-separated list
+    pub fn list(&self, name: &str) -> Option<Vec<String>> {
+        self.value(name).map(|raw| {
+            raw.split(',')
+                .map(|s| s.trim().to_string())
+                .filter(|s| !s.is_empty())
+                .collect()
+        })
+    }
+
+    /// Whether the boolean flag `--<name>` is on. Its environment variable
+    /// takes `1`/`0`, `true`/`false`, `yes`/`no` or `on`/`off`.
+    pub fn enabled(&self, name: &str) -> Result<bool> {
+        if let Some(value) = self.ctx.flag(name) {
+            return Ok(value == "true");
+        }
+        match env_value(name) {
+            None => Ok(false),
+            Some(value) => match value.to_lowercase().as_str() {
+                "1" | "true" | "yes" | "on" => Ok(true),
+                "0" | "false" | "no" | "off" => Ok(false),
+                _ => bail!("Invalid {} value '{}' (expected true or false)", env_var(name), value),
+            },
+        }
+    }
+}
+
+/// Settings for a model run, resolved once from flags, then `GAMECODE_*`
+/// environment variables, then config, then built-in defaults. A command
+/// that lacks one of these flags still gets the environment and config
+/// value, so subcommands agree with the main command.
+#[derive(Debug)]
+pub struct Settings {
+    /// AWS region
+    pub region: String,
+    /// Model alias or ID as requested, before alias mapping
+    pub model: Option<String>,
+    /// Run tool calls without asking
+    pub auto_approve: bool,
+    /// Offer no tools to the model
+    pub no_tools: bool,
+    /// Offer only read-only tools
+    pub read_only: bool,
+    /// If set, only tools matching these patterns are offered
+    pub allow_tools: Option<Vec<String>>,
+    /// Tools matching these patterns are never offered (config and flag combined)
+    pub deny_tools: Vec<String>,
+    /// Generation parameters given for this run; config defaults are applied per model
+    pub inference: InferenceSettings,
+    /// Limit on each backend request and tool call
+    pub timeout: Option<Duration>,
+    /// Consecutive tool-requesting turns before the run stops
+    pub max_tool_turns: usize,
+    /// Consecutive failed tool calls before the run aborts
+    pub max_tool_failures: usize,
+    /// Tool calls from one turn run at once
+    pub tool_concurrency: usize,
+    /// Tool results larger than this are truncated
+    pub max_tool_result_bytes: usize,
+    /// Retries and backoff for backend requests
+    pub retry: RetryPolicy,
+    pub output_format: OutputFormat,
+    /// Answer identical requests from the response cache
+    pub use_cache: bool,
+    /// Mark the system prompt and tools for Bedrock prompt caching
+    pub prompt_cache: bool,
+}
+
+impl Settings {
+    pub fn resolve(flags: &Flags, config: &Config) -> Result<Self> {
+        // Flags replace the configured allowlist; deny lists are combined
+        let mut deny_tools = config.deny_tools.clone();
+        deny_tools.extend(flags.list("deny-tools").unwrap_or_default());
+
+        let inference = InferenceSettings {
+            temperature: flags.parse("temperature")?,
+            top_p: flags.parse("top-p")?,
+            max_tokens: flags.parse("max-tokens")?,
+            // Repeated --stop values arrive comma-joined. Unlike other lists
+            // they aren't trimmed, as whitespace can be part of a sequence
+            stop_sequences: flags.value("stop").map(|raw| {
+                raw.split(',').filter(|stop| !stop.is_empty()).map(str::to_string).collect()
+            }),
+        };
+
+        // [retry] in config overrides the built-in policy
+        let mut retry = RetryPolicy::from_config(&config.retry);
+        if let Some(max_retries) = flags.parse("max-retries")? {
+            retry.max_retries = max_retries;
+        }
+        if let Some(delay) = flags.parse("initial-retry-delay-ms")? {
+            retry.initial_delay = Duration::from_millis(delay);
+        }
+        if let Some(backoff) = flags.parse("backoff")? {
+            retry.backoff = backoff;
+        }
+        if let Some(multiplier) = flags.parse("backoff-multiplier")? {
+            retry.multiplier = multiplier;
+        }
+        if let Some(delay) = flags.parse("max-retry-delay-ms")? {
+            retry.max_delay = Duration::from_millis(delay);
+        }
+        if let Some(jitter) = flags.parse::<f64>("retry-jitter")? {
+            if !(0.0..=1.0).contains(&jitter) {
+                bail!("--retry-jitter must be between 0.0 and 1.0, got {}", jitter);
+            }
+            retry.jitter = jitter;
+        }
+
+        let tool_concurrency = flags.parse("tool-concurrency")?.unwrap_or(DEFAULT_TOOL_CONCURRENCY);
+        if tool_concurrency == 0 {
+            bail!("--tool-concurrency must be at least 1");
+        }
+
+        Ok(Self {
+            region: flags
+                .value("region")
+                .or_else(|| config.region.clone())
+                .unwrap_or_else(|| DEFAULT_REGION.to_string()),
+            model: requested_model(flags.value("model"), config),
+            auto_approve: flags.enabled("yes")?,
+            no_tools: flags.enabled("no-tools")?,
+            read_only: flags.enabled("read-only")? || config.read_only,
+            allow_tools: flags.list("tools").or_else(|| config.tools.clone()),
+            deny_tools,
+            inference,
+            // 0 means no limit
+            timeout: flags.parse::<u64>("timeout")?.filter(|secs| *secs > 0).map(Duration::from_secs),
+            max_tool_turns: flags.parse("max-tool-turns")?.unwrap_or(DEFAULT_MAX_TOOL_TURNS),
+            max_tool_failures: flags.parse("max-tool-failures")?.unwrap_or(DEFAULT_MAX_TOOL_FAILURES),
+            tool_concurrency,
+            max_tool_result_bytes: flags
+                .parse("max-tool-result-bytes")?
+                .or(config.max_tool_result_bytes)
+                .unwrap_or(DEFAULT_MAX_TOOL_RESULT_BYTES),
+            retry,
+            output_format: flags.parse("output-format")?.unwrap_or(OutputFormat::Text),
+            use_cache: !flags.enabled("no-cache")? && (flags.enabled("cache")? || config.cache.enabled),
+            prompt_cache: flags.enabled("prompt-cache")? || config.prompt_cache,
+        })
+    }
+
+    /// Settings for a subcommand, with config loaded and errors in the form
+    /// flag-rs reports
+    pub fn for_command(ctx: &Context) -> Result<Self, flag_rs::Error> {
+        let config = Config::load().map_err(command_error)?;
+        Self::resolve(&Flags::new(ctx), &config).map_err(command_error)
+    }
+}
+
+/// An error from resolving settings, as a subcommand returns it
+pub fn command_error(e: anyhow::Error) -> flag_rs::Error {
+    flag_rs::Error::Custom(format!("{:#}", e).into())
+}
@@ -0,0 +1,237 @@
+use anyhow::{bail, Context, Result};
+use crate::attachments::fenced_block;
+use crate::tool_log::ToolCallRecord;
+use gamecode_context::session::{MessageRole, Session};
+use pulldown_cmark::{html, Event, Options, Parser};
+use serde_json::json;
+
+const GIST_API: &str = "https://api.github.com/gists";
+
+const STYLE: &str = r#"
+body { font-family: -apple-system, BlinkMacSystemFont, "Segoe UI", Helvetica, Arial, sans-serif;
+       max-width: 52rem; margin: 2rem auto; padding: 0 1rem; line-height: 1.5; color: #1f2328; background: #fff; }
+header { border-bottom: 1px solid #d0d7de; margin-bottom: 1.5rem; }
+header p { color: #656d76; margin-top: 0; }
+.message { border: 1px solid #d0d7de; border-radius: 6px; margin: 1rem 0; padding: 0 1rem; }
+.message > .role { font-weight: 600; margin: 0.75rem 0 0; }
+.user { background: #f6f8fa; }
+.note { color: #656d76; font-style: italic; margin: 0.5rem 0; }
+details { border: 1px solid #d0d7de; border-radius: 6px; margin: 0.5rem 0; padding: 0.25rem 0.75rem; }
+details.error summary { color: #cf222e; }
+summary { cursor: pointer; font-family: ui-monospace, SFMono-Regular, Menlo, monospace; font-size: 0.9em; }
+pre { background: #f6f8fa; border-radius: 6px; padding: 0.75rem; overflow-x: auto; }
+code { font-family: ui-monospace, SFMono-Regular, Menlo, monospace; font-size: 0.9em; }
+@media (prefers-color-scheme: dark) {
+  body { color: #e6edf3; background: #0d1117; }
+  header, .message, details { border-color: #30363d; }
+  .user, pre { background: #161b22; }
+  header p, .note { color: #8d96a0; }
+}
+"#;
+
+/// One entry of a shared transcript
+enum Entry<'a> {
+    SystemPrompt(&'a str),
+    User(&'a str),
+    Assistant(&'a str),
+    /// Recorded calls of one tool turn
+    ToolCalls(Vec<&'a ToolCallRecord>),
+    /// A tool message without a recorded call (older sessions, other clients)
+    ToolMessage(&'a str),
+    Note(&'a str),
+}
+
+/// A session prepared for sharing, with tool calls collapsed
+pub struct Transcript<'a> {
+    session: &'a Session,
+    tool_calls: &'a [ToolCallRecord],
+}
+
+impl<'a> Transcript<'a> {
+    /// `tool_calls` are the session's recorded calls, from its tool log
+    pub fn new(session: &'a Session, tool_calls: &'a [ToolCallRecord]) -> Self {
+        Self { session, tool_calls }
+    }
+
+    fn entries(&self) -> Vec<Entry<'a>> {
+        let mut entries = Vec::new();
+        for (index, message) in self.session.messages.iter().enumerate() {
+            let content = message.content.as_str();
+            let calls: Vec<&ToolCallRecord> =
+                self.tool_calls.iter().filter(|call| call.message_index == index).collect();
+            let entry = match message.role {
+                MessageRole::User => Entry::User(content),
+                MessageRole::Assistant => Entry::Assistant(content),
+                MessageRole::Tool => Entry::ToolMessage(content),
+                MessageRole::System if !calls.is_empty() => Entry::ToolCalls(calls),
+                MessageRole::System if index == 0 => Entry::SystemPrompt(content),
+                MessageRole::System => Entry::Note(content),
+            };
+            entries.push(entry);
+        }
+        entries
+    }
+
+    fn title(&self) -> String {
+        format!("gamecode session {}", self.session.id)
+    }
+
+    fn subtitle(&self) -> String {
+        format!(
+            "Started {} · {} messages",
+            self.session.created_at.format("%Y-%m-%d %H:%M UTC"),
+            self.session.messages.len()
+        )
+    }
+
+    /// The transcript as Markdown, with tool calls in `<details>` blocks
+    /// (which GitHub renders collapsed)
+    pub fn markdown(&self) -> String {
+        let mut out = format!("# {}\n\n_{}_\n", self.title(), self.subtitle());
+        for entry in self.entries() {
+            out.push('\n');
+            match entry {
+                Entry::SystemPrompt(content) => out.push_str(&details_markdown("System prompt", content)),
+                Entry::User(content) => out.push_str(&format!("### 👤 User\n\n{}\n", content.trim_end())),
+                Entry::Assistant(content) => out.push_str(&format!("### 🤖 Assistant\n\n{}\n", content.trim_end())),
+                Entry::ToolCalls(calls) => {
+                    for call in calls {
+                        out.push_str(&details_markdown(&call_summary(call), &call_body(call)));
+                    }
+                }
+                Entry::ToolMessage(content) => out.push_str(&details_markdown("🔧 Tool", content)),
+                Entry::Note(content) => out.push_str(&format!("_{}_\n", content.trim())),
+            }
+        }
+        out
+    }
+
+    /// The transcript as a standalone HTML page, with tool calls and the
+    /// system prompt collapsed
+    pub fn html(&self) -> String {
+        let mut body = String::new();
+        for entry in self.entries() {
+            match entry {
+                Entry::SystemPrompt(content) => body.push_str(&details_html("System prompt", content, false)),
+                Entry::User(content) => body.push_str(&message_html("user", "👤 User", content)),
+                Entry::Assistant(content) => body.push_str(&message_html("assistant", "🤖 Assistant", content)),
+                Entry::ToolCalls(calls) => {
+                    for call in calls {
+                        body.push_str(&details_html(&call_summary(call), &call_body(call), call.is_error));
+                    }
+                }
+                Entry::ToolMessage(content) => body.push_str(&details_html("🔧 Tool", content, false)),
+                Entry::Note(content) => body.push_str(&format!("<p class=\"note\">{}</p>\n", escape(content.trim()))),
+            }
+        }
+        format!(
+            "<!DOCTYPE html>\n<html lang=\"en\">\n<head>\n<meta charset=\"utf-8\">\n\
+             <meta name=\"viewport\" content=\"width=device-width, initial-scale=1\">\n\
+             <title>{title}</title>\n<style>{style}</style>\n</head>\n<body>\n\
+             <header>\n<h1>{title}</h1>\n<p>{subtitle}</p>\n</header>\n{body}</body>\n</html>\n",
+            title = escape(&self.title()),
+            style = STYLE,
+            subtitle = escape(&self.subtitle()),
+            body = body
+        )
+    }
+}
+
+fn call_summary(call: &ToolCallRecord) -> String {
+    format!("🔧 {}{}", call.name, if call.is_error { " (failed)" } else { "" })
+}
+
+fn call_body(call: &ToolCallRecord) -> String {
+    let input = serde_json::to_string_pretty(&call.input).unwrap_or_else(|_| call.input.to_string());
+    format!("{}\n\n{}", fenced_block("Input", "json", &input), fenced_block("Result", "", &call.result))
+}
+
+fn details_markdown(summary: &str, content: &str) -> String {
+    format!("<details>\n<summary>{}</summary>\n\n{}\n\n</details>\n", escape(summary), content.trim_end())
+}
+
+fn details_html(summary: &str, content: &str, is_error: bool) -> String {
+    format!(
+        "<details{}>\n<summary>{}</summary>\n{}</details>\n",
+        if is_error { " class=\"error\"" } else { "" },
+        escape(summary),
+        markdown_to_html(content)
+    )
+}
+
+fn message_html(class: &str, role: &str, content: &str) -> String {
+    format!(
+        "<section class=\"message {}\">\n<p class=\"role\">{}</p>\n{}</section>\n",
+        class,
+        role,
+        markdown_to_html(content)
+    )
+}
+
+// Raw HTML in messages is shown as text rather than rendered, so a
+// transcript can't inject markup into the page
+fn markdown_to_html(markdown: &str) -> String {
+    let options = Options::ENABLE_TABLES | Options::ENABLE_STRIKETHROUGH | Options::ENABLE_TASKLISTS;
+    let events = Parser::new_ext(markdown, options).map(|event| match event {
+        Event::Html(raw) | Event::InlineHtml(raw) => Event::Text(raw),
+        other => other,
+    });
+    let mut out = String::new();
+    html::push_html(&mut out, events);
+    out
+}
+
+fn escape(text: &str) -> String {
+    text.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+}
+
+/// Upload `content` as a secret gist and return its URL. Uses `GITHUB_TOKEN`
+/// or `GH_TOKEN`, else the token of a logged-in GitHub CLI.
+pub async fn create_gist(file_name: &str, description: &str, content: &str) -> Result<String> {
+    let token = github_token()?;
+    let payload = json!({
+        "description": description,
+        "public": false,
+        "files": { file_name: { "content": content } },
+    });
+    let response = reqwest::Client::new()
+        .post(GIST_API)
+        .bearer_auth(token)
+        .header(reqwest::header::ACCEPT, "application/vnd.github+json")
+        .header(reqwest::header::USER_AGENT, concat!("gamecode-cli/", env!("CARGO_PKG_VERSION")))
+        .header(reqwest::header::CONTENT_TYPE, "application/json")
+        .body(payload.to_string())
+        .send()
+        .await
+        .context("Failed to reach GitHub")?;
+
+    let status = response.status();
+    let body = response.text().await.context("Failed to read the GitHub response")?;
+    if !status.is_success() {
+        bail!("GitHub refused the gist ({}): {}", status, body.trim());
+    }
+    let gist: serde_json::Value = serde_json::from_str(&body).context("Unexpected response from GitHub")?;
+    gist["html_url"]
+        .as_str()
+        .map(|url| url.to_string())
+        .context("GitHub's response has no gist URL")
+}
+
+fn github_token() -> Result<String> {
+    if let Some(token) = ["GITHUB_TOKEN", "GH_TOKEN"]
+        .iter()
+        .find_map(|name| std::env::var(name).ok().filter(|token| !token.is_empty()))
+    {
+        return Ok(token);
+    }
+    let output = std::process::Command::new("gh").args(["auth", "token"]).output();
+    match output {
+        Ok(output) if output.status.success() => {
+            Ok(String::from_utf8_lossy(&output.stdout).trim().to_string())
+        }
+        _ => bail!("Sharing as a gist needs a GitHub token: set GITHUB_TOKEN or log in with `gh auth login`"),
+    }
+}
@@ -0,0 +1,80 @@
+use std::io::{IsTerminal, Write};
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+use tokio::task::JoinHandle;
+
+const FRAMES: &[&str] = &["⠋", "⠙", "⠹", "⠸", "⠼", "⠴", "⠦", "⠧", "⠇", "⠏"];
+const TICK: Duration = Duration::from_millis(100);
+
+/// Extra text shown after the elapsed time (e.g. retry status), shared with the backend status callback
+pub type SpinnerDetail = Arc<Mutex<Option<String>>>;
+
+/// Animated "waiting for the model" line on stderr
+pub struct Spinner {
+    task: Option<JoinHandle<()>>,
+}
+
+impl Spinner {
+    /// Whether a spinner can be shown: stderr is a styled terminal and `--quiet` is off
+    pub fn enabled() -> bool {
+        std::io::stderr().is_terminal()
+            && crate::status::use_color(true)
+            && !crate::status::is_quiet()
+    }
+
+    /// Start animating `label`; does nothing if spinners are disabled
+    pub fn start(label: &str, detail: SpinnerDetail) -> Self {
+        if !Self::enabled() {
+            return Self { task: None };
+        }
+
+        if let Ok(mut detail) = detail.lock() {
+            *detail = None;
+        }
+        let label = label.to_string();
+        let started = Instant::now();
+        let task = tokio::spawn(async move {
+            for frame in FRAMES.iter().cycle() {
+                let extra = detail
+                    .lock()
+                    .ok()
+                    .and_then(|d| d.clone())
+                    .map(|d| format!(" ({})", d))
+                    .unwrap_or_default();
+                let mut stderr = std::io::stderr().lock();
+                let _ = write!(
+                    stderr,
+                    "\r\x1b[2K{} {} {:.1}s{}",
+                    frame,
+                    label,
+                    started.elapsed().as_secs_f32(),
+                    extra
+                );
+                let _ = stderr.flush();
+                drop(stderr);
+                tokio::time::sleep(TICK).await;
+            }
+        });
+        Self { task: Some(task) }
+    }
+
+    /// Stop animating and clear the line so output starts at column 0
+    pub fn stop(mut self) {
+        self.clear();
+    }
+
+    fn clear(&mut self) {
+        if let Some(task) = self.task.take() {
+            task.abort();
+            let mut stderr = std::io::stderr().lock();
+            let _ = write!(stderr, "\r\x1b[2K");
+            let _ = stderr.flush();
+        }
+    }
+}
+
+impl Drop for Spinner {
+    fn drop(&mut self) {
+        self.clear();
+    }
+}
@@ -0,0 +1,113 @@
+use anyhow::Result;
+use std::io::IsTerminal;
+use std::str::FromStr;
+use std::sync::atomic::{AtomicBool, AtomicU8, Ordering};
+
+/// Set by `--quiet`; suppresses progress output but never warnings or errors
+static QUIET: AtomicBool = AtomicBool::new(false);
+
+/// `ColorChoice` as set by `--color`
+static COLOR: AtomicU8 = AtomicU8::new(ColorChoice::Auto as u8);
+
+/// When styled output (ANSI escapes and emoji) is used
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ColorChoice {
+    /// Only when writing to a terminal and `NO_COLOR` is not set
+    Auto = 0,
+    Always = 1,
+    Never = 2,
+}
+
+impl FromStr for ColorChoice {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        match s {
+            "auto" => Ok(Self::Auto),
+            "always" => Ok(Self::Always),
+            "never" => Ok(Self::Never),
+            other => anyhow::bail!("Unknown color mode '{}' (expected auto, always or never)", other),
+        }
+    }
+}
+
+pub fn set_quiet(quiet: bool) {
+    QUIET.store(quiet, Ordering::Relaxed);
+}
+
+pub fn is_quiet() -> bool {
+    QUIET.load(Ordering::Relaxed)
+}
+
+pub fn set_color(choice: ColorChoice) {
+    COLOR.store(choice as u8, Ordering::Relaxed);
+}
+
+/// Whether styled output should be written to a stream that is (or isn't) a terminal
+pub fn use_color(is_terminal: bool) -> bool {
+    match COLOR.load(Ordering::Relaxed) {
+        1 => true,
+        2 => false,
+        _ => is_terminal && std::env::var_os("NO_COLOR").is_none_or(|v| v.is_empty()),
+    }
+}
+
+/// Write a line to stderr, without emoji or ANSI styling when stderr isn't styled
+pub fn eprint_line(line: &str) {
+    if use_color(std::io::stderr().is_terminal()) {
+        eprintln!("{}", line);
+    } else {
+        eprintln!("{}", plain(line));
+    }
+}
+
+/// Remove ANSI escape sequences and emoji (with the spacing that follows them)
+pub fn plain(text: &str) -> String {
+    let mut out = String::with_capacity(text.len());
+    let mut chars = text.chars().peekable();
+    while let Some(c) = chars.next() {
+        if c == '\x1b' {
+            // Skip a CSI sequence up to its final byte
+            if chars.peek() == Some(&'[') {
+                for c in chars.by_ref() {
+                    if c.is_ascii_alphabetic() {
+                        break;
+                    }
+                }
+            }
+        } else if is_emoji(c) {
+            while chars.peek().is_some_and(|&next| next == ' ' || is_emoji(next)) {
+                chars.next();
+            }
+        } else {
+            out.push(c);
+        }
+    }
+    out
+}
+
+fn is_emoji(c: char) -> bool {
+    matches!(
+        c as u32,
+        0x2139 | 0x2300..=0x23FF | 0x2600..=0x27BF | 0x2B00..=0x2BFF | 0x1F000..=0x1FAFF | 0xFE0F | 0x200D
+    )
+}
+
+/// Print a progress/status line to stderr unless `--quiet` is set.
+/// Stdout is reserved for model output.
+#[macro_export]
+macro_rules! status {
+    ($($arg:tt)*) => {
+        if !$crate::status::is_quiet() {
+            $crate::status::eprint_line(&format!($($arg)*));
+        }
+    };
+}
+
+/// Print a warning or notice to stderr; shown even with `--quiet`
+#[macro_export]
+macro_rules! notice {
+    ($($arg:tt)*) => {
+        $crate::status::eprint_line(&format!($($arg)*))
+    };
+}
@@ -0,0 +1,316 @@
+use crate::agent::{Agent, StopReason, ToolOutcome};
+use crate::config::Config;
+use crate::logging::EVENT_TARGET;
+use crate::mcp_protocol::ToolSchema;
+use crate::mcp_tool_dispatcher::McpToolDispatcher;
+use crate::redaction::Redactor;
+use crate::retry::RetryPolicy;
+use crate::tool_filter::glob_match;
+use crate::usage::UsageLedger;
+use gamecode_backend::{LLMBackend, Message as BackendMessage, MessageRole as BackendMessageRole, Tool as BackendTool};
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Value};
+use std::collections::HashSet;
+use std::sync::Arc;
+use tracing::{debug, info};
+use uuid::Uuid;
+
+/// Name the built-in delegation tool is offered to the model under
+pub const TOOL_NAME: &str = "agent";
+
+/// Tool turn limit of a sub-agent when `[sub_agent] max_turns` is not set
+pub const DEFAULT_MAX_TURNS: usize = 10;
+
+/// System prompt of a sub-agent whose call doesn't give one
+const DEFAULT_SYSTEM_PROMPT: &str = "You are a sub-agent working on one task delegated by another agent. \
+Use the tools available to complete it, then reply with a concise, self-contained answer: \
+the delegating agent sees only your final reply, not your tool calls.";
+
+/// Settings for the `[sub_agent]` section
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct SubAgentConfig {
+    /// Offer the `agent` tool to the model
+    pub enabled: bool,
+    /// Model alias or ID sub-agents use unless a call names one; defaults to the parent's model
+    pub model: Option<String>,
+    /// Other models (aliases or IDs) a call may ask for
+    pub models: Vec<String>,
+    /// Most tool turns a sub-agent may take, whatever a call asks for
+    pub max_turns: usize,
+}
+
+impl Default for SubAgentConfig {
+    fn default() -> Self {
+        Self {
+            enabled: true,
+            model: None,
+            models: Vec::new(),
+            max_turns: DEFAULT_MAX_TURNS,
+        }
+    }
+}
+
+/// Schema of the built-in `agent` tool
+pub fn schema(config: &SubAgentConfig) -> ToolSchema {
+    let mut properties = json!({
+        "task": {
+            "type": "string",
+            "description": "The task, with everything the sub-agent needs to know: it does not see this conversation"
+        },
+        "system_prompt": {
+            "type": "string",
+            "description": "Instructions for the sub-agent (optional)"
+        },
+        "tools": {
+            "type": "array",
+            "items": { "type": "string" },
+            "description": "Names of the tools the sub-agent may use, * wildcards allowed (default: all of yours except agent)"
+        },
+        "max_turns": {
+            "type": "integer",
+            "description": format!("Most tool turns the sub-agent may take (at most {})", config.max_turns)
+        }
+    });
+    if !config.models.is_empty() {
+        properties["model"] = json!({
+            "type": "string",
+            "enum": config.models,
+            "description": "Model for the sub-agent, e.g. a cheaper one for simple searches (optional)"
+        });
+    }
+
+    ToolSchema {
+        name: TOOL_NAME.to_string(),
+        description: "Delegate a self-contained task to a sub-agent with its own conversation, instructions \
+and tool subset, and get back only its final answer. Use it to split large tasks into independent parts \
+or to keep long searches out of your own context."
+            .to_string(),
+        input_schema: json!({
+            "type": "object",
+            "properties": properties,
+            "required": ["task"]
+        }),
+        annotations: None,
+    }
+}
+
+/// What a run offering the `agent` tool passes on to its sub-agents
+pub struct Parent<'a> {
+    pub backend: Arc<dyn LLMBackend>,
+    /// Bedrock model ID
+    pub model: &'a str,
+    pub config: &'a Config,
+    pub region: &'a str,
+    pub tools: &'a [BackendTool],
+    pub dispatcher: Option<Arc<McpToolDispatcher>>,
+    pub retry: RetryPolicy,
+    /// Run every tool without asking, as with `--yes`
+    pub approve_all: bool,
+    /// Qualified names of tools that change files, which need confirming
+    /// even when approved
+    pub write_tools: HashSet<String>,
+    pub session_id: Uuid,
+    pub redactor: Option<Arc<Redactor>>,
+}
+
+/// Runs the `agent` tool: a bounded sub-conversation on the parent's
+/// backend and tools, answered with the sub-agent's final response.
+/// Sub-agents can't delegate further.
+pub struct SubAgent {
+    backend: Arc<dyn LLMBackend>,
+    /// Bedrock model ID used when a call doesn't name a model
+    model: String,
+    /// Models a call may ask for, as named in config and as Bedrock model IDs
+    models: Vec<(String, String)>,
+    max_turns: usize,
+    tools: Vec<BackendTool>,
+    dispatcher: Option<Arc<McpToolDispatcher>>,
+    retry: RetryPolicy,
+    approve: Arc<dyn Fn(&str, &Value) -> bool + Send + Sync>,
+    session_id: Option<Uuid>,
+    redactor: Option<Arc<Redactor>>,
+}
+
+impl SubAgent {
+    /// A sub-agent runner offering `tools` (the parent's, minus this one).
+    /// `parent_model` is a Bedrock model ID.
+    pub fn new(
+        backend: Arc<dyn LLMBackend>,
+        parent_model: &str,
+        config: &Config,
+        region: &str,
+        tools: Vec<BackendTool>,
+        dispatcher: Option<Arc<McpToolDispatcher>>,
+    ) -> Self {
+        let model = config
+            .sub_agent
+            .model
+            .as_deref()
+            .map(|m| crate::map_model_name(m, config, region))
+            .unwrap_or_else(|| parent_model.to_string());
+        Self {
+            backend,
+            model,
+            models: config
+                .sub_agent
+                .models
+                .iter()
+                .map(|m| (m.clone(), crate::map_model_name(m, config, region)))
+                .collect(),
+            max_turns: config.sub_agent.max_turns,
+            tools: tools.into_iter().filter(|tool| tool.name != TOOL_NAME).collect(),
+            dispatcher,
+            retry: RetryPolicy::default(),
+            approve: Arc::new(|_, _| false),
+            session_id: None,
+            redactor: None,
+        }
+    }
+
+    /// The runner for the `agent` tool, if `parent` offers it. Sub-agents
+    /// can't stop to ask, so they only run tools that would need no approval
+    /// or file change confirmation in the parent.
+    pub fn for_parent(parent: Parent) -> Option<Arc<Self>> {
+        if !parent.tools.iter().any(|tool| tool.name == TOOL_NAME) {
+            return None;
+        }
+        let approved: HashSet<String> = parent.config.approved_tools.iter().cloned().collect();
+        let approve_all = parent.approve_all;
+        let write_tools = parent.write_tools;
+        let mut sub_agent = Self::new(
+            parent.backend,
+            parent.model,
+            parent.config,
+            parent.region,
+            parent.tools.to_vec(),
+            parent.dispatcher,
+        )
+        .retry(parent.retry)
+        .approve_tools_with(move |name, _| approve_all || (approved.contains(name) && !write_tools.contains(name)))
+        .session(parent.session_id);
+        if let Some(redactor) = parent.redactor {
+            sub_agent = sub_agent.redact_with(redactor);
+        }
+        Some(Arc::new(sub_agent))
+    }
+
+    pub fn retry(mut self, retry: RetryPolicy) -> Self {
+        self.retry = retry;
+        self
+    }
+
+    /// Ask `policy` before each of the sub-agent's tool calls. By default
+    /// none run: a sub-agent can't stop to ask the user.
+    pub fn approve_tools_with(
+        mut self,
+        policy: impl Fn(&str, &Value) -> bool + Send + Sync + 'static,
+    ) -> Self {
+        self.approve = Arc::new(policy);
+        self
+    }
+
+    /// Mask secrets in the sub-agent's tool results with the parent's redactor
+    pub fn redact_with(mut self, redactor: Arc<Redactor>) -> Self {
+        self.redactor = Some(redactor);
+        self
+    }
+
+    /// Record the sub-agent's token usage against `session_id`
+    pub fn session(mut self, session_id: Uuid) -> Self {
+        self.session_id = Some(session_id);
+        self
+    }
+
+    /// Run the sub-agent the model asked for with `input`
+    pub async fn call(&self, input: &Value) -> ToolOutcome {
+        let Some(task) = input.get("task").and_then(Value::as_str).filter(|t| !t.trim().is_empty()) else {
+            return ToolOutcome::error("The agent tool needs a task".to_string());
+        };
+        let system_prompt = input
+            .get("system_prompt")
+            .and_then(Value::as_str)
+            .unwrap_or(DEFAULT_SYSTEM_PROMPT);
+        let max_turns = input
+            .get("max_turns")
+            .and_then(Value::as_u64)
+            .map_or(self.max_turns, |turns| (turns as usize).min(self.max_turns));
+
+        let model = match input.get("model").and_then(Value::as_str) {
+            None => self.model.clone(),
+            Some(requested) => match self.models.iter().find(|(name, _)| name == requested) {
+                Some((_, id)) => id.clone(),
+                None => {
+                    let allowed: Vec<&str> = self.models.iter().map(|(name, _)| name.as_str()).collect();
+                    return ToolOutcome::error(format!(
+                        "Model '{}' is not available to sub-agents (allowed: {})",
+                        requested,
+                        allowed.join(", ")
+                    ));
+                }
+            },
+        };
+
+        let tools: Vec<BackendTool> = match input.get("tools").and_then(Value::as_array) {
+            Some(patterns) => {
+                let patterns: Vec<&str> = patterns.iter().filter_map(Value::as_str).collect();
+                self.tools
+                    .iter()
+                    .filter(|tool| patterns.iter().any(|pattern| glob_match(pattern, &tool.name)))
+                    .cloned()
+                    .collect()
+            }
+            None => self.tools.clone(),
+        };
+
+        info!(target: EVENT_TARGET, model = %model, tools = tools.len(), max_turns, "sub-agent started");
+        status!("\n🤖 Sub-agent ({}, {} tools): {}", model, tools.len(), task.lines().next().unwrap_or(""));
+        let approve = self.approve.clone();
+        let mut agent = Agent::new(self.backend.clone(), model.clone())
+            .tools(tools, self.dispatcher.clone())
+            .retry(self.retry.clone())
+            .max_tool_turns(max_turns)
+            .approve_tools_with(move |name, input| approve(name, input));
+        if let Some(redactor) = &self.redactor {
+            agent = agent.redact_with(redactor.clone());
+        }
+        let mut messages = vec![
+            BackendMessage::text(BackendMessageRole::System, system_prompt.to_string()),
+            BackendMessage::text(BackendMessageRole::User, task.to_string()),
+        ];
+        let mut on_event = |event: crate::agent::AgentEvent| debug!("Sub-agent event: {:?}", event);
+        let outcome = match agent.run(&mut messages, &mut on_event).await {
+            Ok(outcome) => outcome,
+            Err(e) => return ToolOutcome::error(format!("Sub-agent failed: {:#}", e)),
+        };
+
+        info!(
+            target: EVENT_TARGET,
+            model = %model,
+            tool_turns = outcome.tool_turns,
+            input_tokens = outcome.usage.input_tokens,
+            output_tokens = outcome.usage.output_tokens,
+            "sub-agent finished"
+        );
+        if let Some(session_id) = &self.session_id {
+            if let Err(e) = UsageLedger::record(session_id, &outcome.model, outcome.usage) {
+                debug!("Failed to record sub-agent usage: {}", e);
+            }
+        }
+
+        match outcome.stop_reason {
+            StopReason::EndTurn | StopReason::ToolUse | StopReason::StopSequence | StopReason::Extracted => {
+                ToolOutcome::success(outcome.text)
+            }
+            StopReason::MaxToolTurns => ToolOutcome::success(format!(
+                "(The sub-agent stopped after {} tool turns without finishing.)\n\n{}",
+                outcome.tool_turns, outcome.text
+            )),
+            StopReason::Vetoed => ToolOutcome::error("The sub-agent's answer was withheld by policy".to_string()),
+            StopReason::Guardrail => ToolOutcome::error(format!(
+                "The guardrail intervened on the sub-agent's answer: {}",
+                outcome.text
+            )),
+        }
+    }
+}
@@ -0,0 +1,129 @@
+use anyhow::{bail, Context, Result};
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use std::fs::{self, OpenOptions};
+use std::io::Write;
+use std::path::PathBuf;
+use uuid::Uuid;
+
+/// Thinking budget when `--thinking-budget` is not given
+pub const DEFAULT_BUDGET: u32 = 4096;
+
+/// Smallest budget Anthropic accepts
+pub const MIN_BUDGET: u32 = 1024;
+
+/// Room left for the answer on top of the thinking budget when no
+/// `--max-tokens` is given
+const ANSWER_TOKENS: u32 = 4096;
+
+/// Output token limit for a thinking request. `--max-tokens` is the total,
+/// thinking included, as Bedrock counts it, so it has to leave room for an
+/// answer; without it the answer gets `ANSWER_TOKENS` on top of the budget.
+pub fn max_tokens(budget: u32, max_tokens: Option<u32>) -> Result<u32> {
+    match max_tokens {
+        Some(limit) if limit > budget => Ok(limit),
+        Some(limit) => bail!(
+            "--max-tokens ({}) covers thinking and answer, so it must be larger than the thinking budget ({})",
+            limit,
+            budget
+        ),
+        None => Ok(budget + ANSWER_TOKENS),
+    }
+}
+
+/// Reasoning behind one answer of a session
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ThinkingRecord {
+    /// Position in the session of the answer
+    pub message_index: usize,
+    pub model: String,
+    pub thinking: String,
+    pub timestamp: DateTime<Utc>,
+}
+
+/// The reasoning of a session's answers, kept apart from the answers so it
+/// is never sent back to the model, one JSON record per line in
+/// `~/.local/share/gamecode/thinking/<session-id>.jsonl`
+pub struct ThinkingLog {
+    path: PathBuf,
+}
+
+impl ThinkingLog {
+    pub fn open(session_id: &Uuid) -> Result<Self> {
+        let home = home::home_dir().context("Failed to get home directory")?;
+        Ok(Self {
+            path: home
+                .join(".local")
+                .join("share")
+                .join("gamecode")
+                .join("thinking")
+                .join(format!("{}.jsonl", session_id)),
+        })
+    }
+
+    /// Replace the records with `records`, as when restoring a session
+    pub fn restore(&self, records: &[ThinkingRecord]) -> Result<()> {
+        if records.is_empty() {
+            return self.remove();
+        }
+        if let Some(parent) = self.path.parent() {
+            fs::create_dir_all(parent)?;
+        }
+        let mut content = String::new();
+        for record in records {
+            content.push_str(&serde_json::to_string(record)?);
+            content.push('\n');
+        }
+        fs::write(&self.path, content).with_context(|| format!("Failed to write {}", self.path.display()))
+    }
+
+    /// Drop the reasoning behind answers at or after `from_index`, for when
+    /// the session's messages from there on are removed
+    pub fn truncate(&self, from_index: usize) -> Result<()> {
+        let records = self.load()?;
+        let kept: Vec<ThinkingRecord> = records.iter().filter(|r| r.message_index < from_index).cloned().collect();
+        if kept.len() == records.len() {
+            return Ok(());
+        }
+        self.restore(&kept)
+    }
+
+    pub fn remove(&self) -> Result<()> {
+        if self.path.exists() {
+            fs::remove_file(&self.path).with_context(|| format!("Failed to remove {}", self.path.display()))?;
+        }
+        Ok(())
+    }
+
+    pub fn append(&self, message_index: usize, model: &str, thinking: &str) -> Result<()> {
+        if let Some(parent) = self.path.parent() {
+            fs::create_dir_all(parent)?;
+        }
+        let record = ThinkingRecord {
+            message_index,
+            model: model.to_string(),
+            thinking: thinking.to_string(),
+            timestamp: Utc::now(),
+        };
+        let mut file = OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(&self.path)
+            .with_context(|| format!("Failed to open {}", self.path.display()))?;
+        writeln!(file, "{}", serde_json::to_string(&record)?)?;
+        Ok(())
+    }
+
+    /// Every record, oldest first; empty if the session has none
+    pub fn load(&self) -> Result<Vec<ThinkingRecord>> {
+        if !self.path.exists() {
+            return Ok(Vec::new());
+        }
+        let content = fs::read_to_string(&self.path)
+            .with_context(|| format!("Failed to read {}", self.path.display()))?;
+        Ok(content
+            .lines()
+            .filter_map(|line| serde_json::from_str(line).ok())
+            .collect())
+    }
+}
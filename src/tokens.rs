@@ -0,0 +1,65 @@
+use gamecode_backend::{ContentBlock, Message as BackendMessage, Tool as BackendTool};
+use std::sync::OnceLock;
+use tiktoken_rs::CoreBPE;
+use tracing::debug;
+
+/// Share of a model's context window (in percent) a request may use before
+/// the CLI warns about it
+pub const CONTEXT_WARNING_PERCENT: u64 = 80;
+
+/// Tokens Bedrock adds around each message for its role and framing
+const MESSAGE_OVERHEAD: u64 = 4;
+
+/// Tokens added around each tool definition
+const TOOL_OVERHEAD: u64 = 8;
+
+// The cl100k_base encoding, loaded on first use; None if it failed to load
+static ENCODING: OnceLock<Option<CoreBPE>> = OnceLock::new();
+
+/// Offline token count of `text`.
+///
+/// Bedrock models don't publish their tokenizers, so this uses the
+/// cl100k_base BPE encoding, which comes within roughly 10-20% of Claude's
+/// counts for English prose and code. If the encoding can't be loaded it
+/// falls back to about four characters per token.
+pub fn count(text: &str) -> u64 {
+    let encoding = ENCODING.get_or_init(|| match tiktoken_rs::cl100k_base() {
+        Ok(encoding) => Some(encoding),
+        Err(e) => {
+            debug!("Failed to load tokenizer, estimating from length: {}", e);
+            None
+        }
+    });
+    match encoding {
+        Some(encoding) => encoding.encode_with_special_tokens(text).len() as u64,
+        None => (text.chars().count() as u64).div_ceil(4),
+    }
+}
+
+/// Tokens of one message, including tool calls and results
+pub fn count_message(message: &BackendMessage) -> u64 {
+    let content: u64 = message
+        .content
+        .iter()
+        .map(|block| match block {
+            ContentBlock::Text(text) => count(text),
+            ContentBlock::ToolResult { result, .. } => count(result),
+            other => serde_json::to_string(other).map_or(0, |json| count(&json)),
+        })
+        .sum();
+    content + MESSAGE_OVERHEAD
+}
+
+pub fn count_messages(messages: &[BackendMessage]) -> u64 {
+    messages.iter().map(count_message).sum()
+}
+
+/// Tokens of the tool definitions offered with a request
+pub fn count_tools(tools: &[BackendTool]) -> u64 {
+    tools
+        .iter()
+        .map(|tool| {
+            count(&tool.name) + count(&tool.description) + count(&tool.input_schema.to_string()) + TOOL_OVERHEAD
+        })
+        .sum()
+}
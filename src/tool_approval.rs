@@ -0,0 +1,88 @@
+use anyhow::Result;
+use crate::config::Config;
+use serde_json::Value;
+use std::collections::HashSet;
+use std::io::{IsTerminal, Write};
+use tracing::debug;
+
+/// Asks the user before each tool execution, remembering "always" answers
+pub struct ToolApprover {
+    auto_approve: bool,
+    approved: HashSet<String>,
+    notify: bool,
+}
+
+impl ToolApprover {
+    pub fn new(auto_approve: bool, config: &Config) -> Self {
+        Self {
+            auto_approve,
+            approved: config.approved_tools.iter().cloned().collect(),
+            notify: false,
+        }
+    }
+
+    /// Send a desktop notification whenever approval is needed
+    pub fn notify(mut self, notify: bool) -> Self {
+        self.notify = notify;
+        self
+    }
+
+    /// Returns true if the tool call may run
+    pub fn approve(&mut self, tool_name: &str, input: &Value) -> Result<bool> {
+        if self.auto_approve || self.approved.contains(tool_name) {
+            debug!("Tool '{}' pre-approved", tool_name);
+            return Ok(true);
+        }
+
+        if !std::io::stdin().is_terminal() {
+            notice!(
+                "⚠️  Tool '{}' requires approval but stdin is not a terminal; denying (use --yes to auto-approve)",
+                tool_name
+            );
+            return Ok(false);
+        }
+
+        if self.notify {
+            crate::notify::send("gamecode needs approval", &format!("Allow tool {}?", tool_name));
+        }
+
+        notice!(
+            "\n🔧 The model wants to run {} with params: {}",
+            tool_name,
+            serde_json::to_string_pretty(input).unwrap_or_else(|_| input.to_string())
+        );
+
+        loop {
+            eprint!("   Allow? [y]es / [n]o / [a]lways for this tool: ");
+            std::io::stderr().flush().ok();
+
+            let mut answer = String::new();
+            if std::io::stdin().read_line(&mut answer)? == 0 {
+                return Ok(false);
+            }
+
+            match answer.trim().to_lowercase().as_str() {
+                "y" | "yes" => return Ok(true),
+                "n" | "no" | "" => return Ok(false),
+                "a" | "always" => {
+                    self.remember(tool_name)?;
+                    return Ok(true);
+                }
+                _ => continue,
+            }
+        }
+    }
+
+    /// Persist a tool in the config allowlist so future runs don't ask again
+    fn remember(&mut self, tool_name: &str) -> Result<()> {
+        self.approved.insert(tool_name.to_string());
+
+        let mut config = Config::load_global()?;
+        if !config.approved_tools.iter().any(|t| t == tool_name) {
+            config.approved_tools.push(tool_name.to_string());
+            config.save()?;
+            eprintln!("   '{}' added to approved tools in {}", tool_name, Config::config_path()?.display());
+        }
+        Ok(())
+    }
+}
@@ -0,0 +1,51 @@
+use crate::mcp_protocol::ToolSchema;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+
+/// Whether a tool only observes the system or can change it
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum ToolCapability {
+    ReadOnly,
+    Write,
+}
+
+/// Name fragments that indicate a tool writes files or executes commands
+const WRITE_KEYWORDS: &[&str] = &[
+    "write", "edit", "delete", "remove", "create", "move", "rename", "mkdir", "exec", "shell",
+    "run", "command", "patch", "apply", "insert", "replace", "append", "update", "kill",
+    "install", "commit", "push", "upload", "chmod",
+];
+
+/// Classify a tool, in order of precedence:
+/// 1. an explicit entry in the user's `tool_capabilities` config (bare or qualified name)
+/// 2. the MCP `readOnlyHint`/`destructiveHint` annotations advertised by the server
+/// 3. a keyword heuristic on the tool name
+pub fn classify(
+    server_name: &str,
+    tool: &ToolSchema,
+    overrides: &HashMap<String, ToolCapability>,
+) -> ToolCapability {
+    let full_name = format!("{}_{}", server_name, tool.name);
+    if let Some(capability) = overrides.get(&full_name).or_else(|| overrides.get(&tool.name)) {
+        return *capability;
+    }
+
+    if let Some(annotations) = &tool.annotations {
+        if annotations.get("readOnlyHint").and_then(|v| v.as_bool()) == Some(true) {
+            return ToolCapability::ReadOnly;
+        }
+        if annotations.get("destructiveHint").and_then(|v| v.as_bool()) == Some(true)
+            || annotations.get("readOnlyHint").and_then(|v| v.as_bool()) == Some(false)
+        {
+            return ToolCapability::Write;
+        }
+    }
+
+    let name = tool.name.to_lowercase();
+    if WRITE_KEYWORDS.iter().any(|keyword| name.contains(keyword)) {
+        ToolCapability::Write
+    } else {
+        ToolCapability::ReadOnly
+    }
+}
@@ -0,0 +1,104 @@
+use crate::config::Config;
+use crate::mcp_protocol::ToolSchema;
+use crate::tool_capabilities::{self, ToolCapability};
+use crate::{fetch_url, sub_agent};
+use gamecode_backend::Tool as BackendTool;
+use std::collections::HashMap;
+
+/// Pseudo server name built-in tools are matched under by `--allow-tools`/`--deny-tools`
+pub const BUILTIN_SERVER: &str = "builtin";
+
+/// Decides which tools are exposed to the model for a run.
+///
+/// Patterns match either the bare tool name (`read_file`) or the
+/// server-qualified name (`gamecode_read_file`) and may use `*` wildcards.
+#[derive(Debug, Default, Clone)]
+pub struct ToolFilter {
+    allow: Option<Vec<String>>,
+    deny: Vec<String>,
+    read_only: bool,
+    capabilities: HashMap<String, ToolCapability>,
+}
+
+impl ToolFilter {
+    pub fn new(allow: Option<Vec<String>>, deny: Vec<String>) -> Self {
+        Self {
+            allow,
+            deny,
+            ..Self::default()
+        }
+    }
+
+    /// Only offer tools classified as read-only
+    pub fn read_only(mut self, read_only: bool, capabilities: HashMap<String, ToolCapability>) -> Self {
+        self.read_only = read_only;
+        self.capabilities = capabilities;
+        self
+    }
+
+    /// Whether a tool from `server_name` may be offered to the model
+    pub fn allows(&self, server_name: &str, tool: &ToolSchema) -> bool {
+        if self.read_only
+            && tool_capabilities::classify(server_name, tool, &self.capabilities) == ToolCapability::Write
+        {
+            return false;
+        }
+
+        let tool_name = tool.name.as_str();
+        let full_name = format!("{}_{}", server_name, tool_name);
+        let matches = |pattern: &String| {
+            glob_match(pattern, tool_name) || glob_match(pattern, &full_name)
+        };
+
+        if self.deny.iter().any(matches) {
+            return false;
+        }
+        match &self.allow {
+            Some(allow) => allow.iter().any(matches),
+            None => true,
+        }
+    }
+
+    /// The built-in tools to offer alongside MCP tools, subject to the same filters
+    pub fn builtin_tools(&self, config: &Config) -> Vec<BackendTool> {
+        let mut schemas = vec![fetch_url::schema()];
+        if config.sub_agent.enabled {
+            schemas.push(sub_agent::schema(&config.sub_agent));
+        }
+        schemas
+            .into_iter()
+            .filter(|schema| self.allows(BUILTIN_SERVER, schema))
+            .map(|schema| BackendTool {
+                name: schema.name,
+                description: schema.description,
+                input_schema: schema.input_schema,
+            })
+            .collect()
+    }
+}
+
+/// Minimal glob matching supporting `*` (any run of characters)
+pub fn glob_match(pattern: &str, name: &str) -> bool {
+    let parts: Vec<&str> = pattern.split('*').collect();
+    if parts.len() == 1 {
+        return pattern == name;
+    }
+
+    let mut rest = name;
+    for (i, part) in parts.iter().enumerate() {
+        if i == 0 {
+            match rest.strip_prefix(part) {
+                Some(r) => rest = r,
+                None => return false,
+            }
+        } else if i == parts.len() - 1 {
+            return rest.ends_with(part);
+        } else {
+            match rest.find(part) {
+                Some(idx) => rest = &rest[idx + part.len()..],
+                None => return false,
+            }
+        }
+    }
+    true
+}
@@ -0,0 +1,129 @@
+use anyhow::{Context, Result};
+use chrono::{DateTime, Utc};
+use crate::output::ToolCallReport;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::fs::{self, OpenOptions};
+use std::io::Write;
+use std::path::PathBuf;
+use tracing::debug;
+use uuid::Uuid;
+
+/// A tool call made during a session and what it returned
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ToolCallRecord {
+    /// Position in the session of the message summarizing the call's tool turn
+    pub message_index: usize,
+    pub id: String,
+    pub name: String,
+    pub input: Value,
+    pub result: String,
+    pub is_error: bool,
+    pub timestamp: DateTime<Utc>,
+}
+
+/// The tool calls of a session, one JSON record per line in
+/// `~/.local/share/gamecode/tool_calls/<session-id>.jsonl`. Sessions keep only
+/// a summary message per tool turn; this keeps the calls themselves.
+pub struct ToolLog {
+    path: PathBuf,
+}
+
+impl ToolLog {
+    pub fn path(session_id: &Uuid) -> Result<PathBuf> {
+        let home = home::home_dir().context("Failed to get home directory")?;
+        Ok(home
+            .join(".local")
+            .join("share")
+            .join("gamecode")
+            .join("tool_calls")
+            .join(format!("{}.jsonl", session_id)))
+    }
+
+    pub fn open(session_id: &Uuid) -> Result<Self> {
+        Ok(Self { path: Self::path(session_id)? })
+    }
+
+    /// Record the calls of one tool turn, summarized at `message_index`
+    pub fn append(&self, message_index: usize, calls: &[ToolCallReport]) -> Result<()> {
+        if calls.is_empty() {
+            return Ok(());
+        }
+        if let Some(parent) = self.path.parent() {
+            fs::create_dir_all(parent)?;
+        }
+        let mut file = OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(&self.path)
+            .with_context(|| format!("Failed to open {}", self.path.display()))?;
+        let timestamp = Utc::now();
+        for call in calls {
+            let record = ToolCallRecord {
+                message_index,
+                id: call.id.clone(),
+                name: call.name.clone(),
+                input: call.input.clone(),
+                result: call.result.clone(),
+                is_error: call.is_error,
+                timestamp,
+            };
+            writeln!(file, "{}", serde_json::to_string(&record)?)?;
+        }
+        Ok(())
+    }
+
+    /// Replace the recorded calls with `records`, as when restoring a session
+    pub fn restore(&self, records: &[ToolCallRecord]) -> Result<()> {
+        if records.is_empty() {
+            return self.remove();
+        }
+        if let Some(parent) = self.path.parent() {
+            fs::create_dir_all(parent)?;
+        }
+        let mut content = String::new();
+        for record in records {
+            content.push_str(&serde_json::to_string(record)?);
+            content.push('\n');
+        }
+        fs::write(&self.path, content).with_context(|| format!("Failed to write {}", self.path.display()))
+    }
+
+    /// Drop the calls of tool turns at or after `from_index`, for when the
+    /// session's messages from there on are removed
+    pub fn truncate(&self, from_index: usize) -> Result<()> {
+        let records = self.load()?;
+        let kept: Vec<ToolCallRecord> = records.iter().filter(|r| r.message_index < from_index).cloned().collect();
+        if kept.len() == records.len() {
+            return Ok(());
+        }
+        self.restore(&kept)
+    }
+
+    pub fn remove(&self) -> Result<()> {
+        if self.path.exists() {
+            fs::remove_file(&self.path).with_context(|| format!("Failed to remove {}", self.path.display()))?;
+        }
+        Ok(())
+    }
+
+    /// Every recorded call, oldest first; empty if none were recorded
+    pub fn load(&self) -> Result<Vec<ToolCallRecord>> {
+        if !self.path.exists() {
+            return Ok(Vec::new());
+        }
+        let content = fs::read_to_string(&self.path)
+            .with_context(|| format!("Failed to read {}", self.path.display()))?;
+        Ok(content
+            .lines()
+            .filter(|line| !line.trim().is_empty())
+            .filter_map(|line| match serde_json::from_str(line) {
+                Ok(record) => Some(record),
+                Err(e) => {
+                    debug!("Skipping unreadable tool call record: {}", e);
+                    None
+                }
+            })
+            .collect())
+    }
+}
@@ -0,0 +1,41 @@
+/// Default cap on tool output sent back to the model, in bytes
+pub const DEFAULT_MAX_TOOL_RESULT_BYTES: usize = 50_000;
+
+/// Shorten `content` to roughly `max_bytes` by keeping its head and tail and
+/// replacing the middle with a marker. Returns the original if it already fits.
+pub fn truncate_middle(content: &str, max_bytes: usize) -> Option<String> {
+    if content.len() <= max_bytes {
+        return None;
+    }
+
+    // Keep more of the head than the tail: the start of a file or listing
+    // usually carries more context, the end carries the final status
+    let head_budget = max_bytes * 2 / 3;
+    let tail_budget = max_bytes - head_budget;
+
+    let head_end = floor_char_boundary(content, head_budget);
+    let tail_start = ceil_char_boundary(content, content.len() - tail_budget);
+
+    let omitted = tail_start - head_end;
+    Some(format!(
+        "{}\n\n[... {} bytes truncated from tool output ({} bytes total) ...]\n\n{}",
+        &content[..head_end],
+        omitted,
+        content.len(),
+        &content[tail_start..]
+    ))
+}
+
+fn floor_char_boundary(s: &str, mut index: usize) -> usize {
+    while index > 0 && !s.is_char_boundary(index) {
+        index -= 1;
+    }
+    index
+}
+
+fn ceil_char_boundary(s: &str, mut index: usize) -> usize {
+    while index < s.len() && !s.is_char_boundary(index) {
+        index += 1;
+    }
+    index
+}
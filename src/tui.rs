@@ -0,0 +1,396 @@
+use anyhow::{Context, Result};
+use crate::agent::{AgentEvent, RunOutcome, StopReason};
+use crate::file_edits::FileEdit;
+use crate::server::ServerState;
+use crate::usage::TokenUsage;
+use gamecode_context::session::MessageRole as ContextMessageRole;
+use ratatui::crossterm::event::{self, Event, KeyCode, KeyEvent, KeyEventKind, KeyModifiers};
+use ratatui::layout::{Constraint, Layout, Rect};
+use ratatui::style::{Color, Modifier, Style};
+use ratatui::text::{Line, Span};
+use ratatui::widgets::{Block, Paragraph, Wrap};
+use ratatui::{DefaultTerminal, Frame};
+use serde_json::Value;
+use std::collections::HashSet;
+use std::sync::{mpsc as std_mpsc, Arc, Mutex};
+use std::time::Duration;
+use tokio::sync::mpsc;
+use tokio::task::JoinHandle;
+use uuid::Uuid;
+
+/// How long to wait for a key before redrawing with new agent events
+const FRAME_INTERVAL: Duration = Duration::from_millis(50);
+
+/// Lines moved by PageUp/PageDown
+const PAGE: u16 = 10;
+
+/// What the agent task tells the UI
+enum UiMessage {
+    Event(AgentEvent),
+    /// A tool call is waiting for the user; answer on `reply`
+    Approve {
+        name: String,
+        input: Value,
+        reply: std_mpsc::Sender<bool>,
+    },
+    Done(Result<RunOutcome>),
+}
+
+/// One item of the conversation pane
+enum Entry {
+    User(String),
+    Assistant(String),
+    ToolCall { name: String, input: Value },
+    ToolResult { name: String, content: String, is_error: bool },
+    Notice(String),
+    Error(String),
+}
+
+/// A tool call shown in the approval pane
+struct Pending {
+    name: String,
+    input: Value,
+    /// Diff of the file the call would change, if it is a recognizable edit
+    diff: Option<String>,
+    reply: std_mpsc::Sender<bool>,
+}
+
+/// Interactive front end for supervising agent runs: the conversation, the
+/// tool call waiting for approval (with a diff for file edits) and token
+/// usage in separate panes. Turns run through `ServerState::run_turn_with`,
+/// so sessions, hooks and redaction behave as in `serve` and the daemon.
+struct App {
+    state: Arc<ServerState>,
+    session_id: Uuid,
+    entries: Vec<Entry>,
+    input: String,
+    pending: Option<Pending>,
+    usage: TokenUsage,
+    turn: Option<JoinHandle<()>>,
+    /// Tools the user chose to always allow for the rest of this TUI session
+    always: Arc<Mutex<HashSet<String>>>,
+    /// Lines scrolled back from the end of the conversation
+    scroll_back: u16,
+    tx: mpsc::UnboundedSender<UiMessage>,
+}
+
+/// Run the TUI on `session_id` until the user quits
+pub async fn run(state: ServerState, session_id: Uuid) -> Result<()> {
+    let state = Arc::new(state);
+    let session = state.load_session(&session_id).await?;
+    let (tx, mut rx) = mpsc::unbounded_channel();
+
+    let mut app = App {
+        state,
+        session_id,
+        entries: Vec::new(),
+        input: String::new(),
+        pending: None,
+        usage: TokenUsage::default(),
+        turn: None,
+        always: Arc::new(Mutex::new(HashSet::new())),
+        scroll_back: 0,
+        tx,
+    };
+    for message in &session.messages {
+        match message.role {
+            ContextMessageRole::User => app.entries.push(Entry::User(message.content.clone())),
+            ContextMessageRole::Assistant => app.entries.push(Entry::Assistant(message.content.clone())),
+            _ => {}
+        }
+    }
+
+    let mut terminal = ratatui::init();
+    let result = app.event_loop(&mut terminal, &mut rx).await;
+    ratatui::restore();
+
+    if let Some(turn) = app.turn.take() {
+        turn.abort();
+    }
+    result
+}
+
+impl App {
+    async fn event_loop(
+        &mut self,
+        terminal: &mut DefaultTerminal,
+        rx: &mut mpsc::UnboundedReceiver<UiMessage>,
+    ) -> Result<()> {
+        loop {
+            while let Ok(message) = rx.try_recv() {
+                self.handle_message(message);
+            }
+            terminal.draw(|frame| self.draw(frame)).context("Failed to draw the TUI")?;
+
+            if !event::poll(FRAME_INTERVAL)? {
+                continue;
+            }
+            if let Event::Key(key) = event::read()? {
+                if key.kind == KeyEventKind::Press && !self.handle_key(key) {
+                    return Ok(());
+                }
+            }
+        }
+    }
+
+    fn handle_message(&mut self, message: UiMessage) {
+        match message {
+            UiMessage::Event(AgentEvent::Text { text }) => self.entries.push(Entry::Assistant(text)),
+            UiMessage::Event(AgentEvent::ToolCall { name, input, .. }) => {
+                self.entries.push(Entry::ToolCall { name, input })
+            }
+            UiMessage::Event(AgentEvent::ToolResult { name, content, is_error, .. }) => {
+                self.entries.push(Entry::ToolResult { name, content, is_error })
+            }
+            UiMessage::Event(AgentEvent::Usage { usage, .. }) => self.usage.add(usage),
+            UiMessage::Event(AgentEvent::Fallback { to, .. }) => {
+                self.entries.push(Entry::Notice(format!("Falling back to {}", to)))
+            }
+            UiMessage::Event(_) => {}
+            UiMessage::Approve { name, input, reply } => {
+                let diff = FileEdit::detect(&input).map(|edit| edit.unified_diff());
+                self.pending = Some(Pending { name, input, diff, reply });
+            }
+            UiMessage::Done(result) => {
+                self.turn = None;
+                match result {
+                    Ok(outcome) if outcome.stop_reason == StopReason::MaxToolTurns => self.entries.push(
+                        Entry::Notice(format!("Stopped after {} tool turns", outcome.tool_turns)),
+                    ),
+                    Ok(outcome) if outcome.stop_reason == StopReason::Vetoed => {
+                        self.entries.push(Entry::Notice("Response withheld by the post_response hook".to_string()))
+                    }
+                    Ok(outcome) if outcome.stop_reason == StopReason::Guardrail => {
+                        self.entries.push(Entry::Notice("The guardrail intervened".to_string()))
+                    }
+                    Ok(_) => {}
+                    Err(e) => self.entries.push(Entry::Error(format!("{:#}", e))),
+                }
+            }
+        }
+        self.scroll_back = 0;
+    }
+
+    /// Returns false when the user quits
+    fn handle_key(&mut self, key: KeyEvent) -> bool {
+        let ctrl = key.modifiers.contains(KeyModifiers::CONTROL);
+        if key.code == KeyCode::Esc || (ctrl && key.code == KeyCode::Char('c')) {
+            if let Some(pending) = self.pending.take() {
+                let _ = pending.reply.send(false);
+            }
+            return false;
+        }
+
+        match key.code {
+            KeyCode::PageUp => self.scroll_back = self.scroll_back.saturating_add(PAGE),
+            KeyCode::PageDown => self.scroll_back = self.scroll_back.saturating_sub(PAGE),
+            KeyCode::Up => self.scroll_back = self.scroll_back.saturating_add(1),
+            KeyCode::Down => self.scroll_back = self.scroll_back.saturating_sub(1),
+            _ if self.pending.is_some() => self.answer(key.code),
+            KeyCode::Enter => self.submit(),
+            KeyCode::Backspace => {
+                self.input.pop();
+            }
+            KeyCode::Char(c) if !ctrl => self.input.push(c),
+            _ => {}
+        }
+        true
+    }
+
+    /// y runs the pending tool call, n refuses it, a runs it and every later call of the same tool
+    fn answer(&mut self, key: KeyCode) {
+        let approved = match key {
+            KeyCode::Char('y') | KeyCode::Char('Y') => true,
+            KeyCode::Char('n') | KeyCode::Char('N') => false,
+            KeyCode::Char('a') | KeyCode::Char('A') => {
+                if let (Some(pending), Ok(mut always)) = (&self.pending, self.always.lock()) {
+                    always.insert(pending.name.clone());
+                }
+                true
+            }
+            _ => return,
+        };
+        if let Some(pending) = self.pending.take() {
+            if !approved {
+                self.entries.push(Entry::Notice(format!("Denied {}", pending.name)));
+            }
+            let _ = pending.reply.send(approved);
+        }
+    }
+
+    fn submit(&mut self) {
+        let prompt = self.input.trim().to_string();
+        if prompt.is_empty() || self.turn.is_some() {
+            return;
+        }
+        self.input.clear();
+        self.entries.push(Entry::User(prompt.clone()));
+
+        let approve_all = self.state.approve_all;
+        let approved: HashSet<String> = self.state.config.approved_tools.iter().cloned().collect();
+        let always = self.always.clone();
+        let approvals = self.tx.clone();
+        // The agent asks from its own task; block it until the user answers
+        let policy = move |name: &str, input: &Value| {
+            if approve_all || approved.contains(name) || always.lock().is_ok_and(|always| always.contains(name)) {
+                return true;
+            }
+            let (reply, answer) = std_mpsc::channel();
+            let request = UiMessage::Approve { name: name.to_string(), input: input.clone(), reply };
+            if approvals.send(request).is_err() {
+                return false;
+            }
+            tokio::task::block_in_place(|| answer.recv().unwrap_or(false))
+        };
+
+        let state = self.state.clone();
+        let session_id = self.session_id;
+        let tx = self.tx.clone();
+        self.turn = Some(tokio::spawn(async move {
+            let events = tx.clone();
+            let on_event = move |event: &AgentEvent| {
+                let _ = events.send(UiMessage::Event(event.clone()));
+            };
+            let result = state.run_turn_with(session_id, prompt, None, policy, on_event).await;
+            let _ = tx.send(UiMessage::Done(result));
+        }));
+    }
+
+    fn draw(&self, frame: &mut Frame) {
+        let [main, input, status] =
+            Layout::vertical([Constraint::Min(3), Constraint::Length(3), Constraint::Length(1)]).areas(frame.area());
+
+        if let Some(pending) = &self.pending {
+            let [conversation, approval] =
+                Layout::horizontal([Constraint::Percentage(55), Constraint::Percentage(45)]).areas(main);
+            self.draw_conversation(frame, conversation);
+            draw_approval(frame, approval, pending);
+        } else {
+            self.draw_conversation(frame, main);
+        }
+
+        let title = if self.pending.is_some() {
+            " y approve · n deny · a always allow · Esc quit "
+        } else if self.turn.is_some() {
+            " Working… (Esc quits) "
+        } else {
+            " Prompt · Enter send · PgUp/PgDn scroll · Esc quit "
+        };
+        frame.render_widget(Paragraph::new(self.input.as_str()).block(Block::bordered().title(title)), input);
+        if self.pending.is_none() && self.turn.is_none() {
+            let x = input.x + 1 + (self.input.chars().count() as u16).min(input.width.saturating_sub(3));
+            frame.set_cursor_position((x, input.y + 1));
+        }
+
+        let mut summary = format!(
+            " {} · session {} · {} in / {} out tokens",
+            self.state.model,
+            &self.session_id.to_string()[..8],
+            self.usage.input_tokens,
+            self.usage.output_tokens,
+        );
+        if let Some(cost) = self.usage.cost(&self.state.model) {
+            summary.push_str(&format!(" · ${:.4}", cost));
+        }
+        frame.render_widget(
+            Paragraph::new(summary).style(Style::default().fg(Color::Black).bg(Color::Gray)),
+            status,
+        );
+    }
+
+    fn draw_conversation(&self, frame: &mut Frame, area: Rect) {
+        let lines: Vec<Line> = self.entries.iter().flat_map(entry_lines).collect();
+        let height = area.height.saturating_sub(2);
+        let total = wrapped_height(&lines, area.width.saturating_sub(2));
+        let top = total.saturating_sub(height).saturating_sub(self.scroll_back);
+        let paragraph = Paragraph::new(lines)
+            .block(Block::bordered().title(" Conversation "))
+            .wrap(Wrap { trim: false })
+            .scroll((top, 0));
+        frame.render_widget(paragraph, area);
+    }
+}
+
+fn draw_approval(frame: &mut Frame, area: Rect, pending: &Pending) {
+    let mut lines = vec![
+        Line::from(vec![
+            Span::raw("Run "),
+            Span::styled(pending.name.clone(), Style::default().fg(Color::Yellow).add_modifier(Modifier::BOLD)),
+            Span::raw("?"),
+        ]),
+        Line::raw(""),
+    ];
+    match &pending.diff {
+        Some(diff) if !diff.is_empty() => {
+            for line in diff.lines() {
+                let color = match line.chars().next() {
+                    Some('+') => Color::Green,
+                    Some('-') => Color::Red,
+                    Some('@') => Color::Cyan,
+                    _ => Color::Reset,
+                };
+                lines.push(Line::styled(line.to_string(), Style::default().fg(color)));
+            }
+        }
+        _ => {
+            let input = serde_json::to_string_pretty(&pending.input).unwrap_or_default();
+            lines.extend(input.lines().map(|line| Line::raw(line.to_string())));
+        }
+    }
+    let paragraph = Paragraph::new(lines)
+        .block(Block::bordered().title(" Approval ").border_style(Style::default().fg(Color::Yellow)))
+        .wrap(Wrap { trim: false });
+    frame.render_widget(paragraph, area);
+}
+
+fn entry_lines(entry: &Entry) -> Vec<Line<'static>> {
+    let mut lines = match entry {
+        Entry::User(text) => {
+            let style = Style::default().fg(Color::Green).add_modifier(Modifier::BOLD);
+            text.lines()
+                .enumerate()
+                .map(|(i, line)| {
+                    let prefix = if i == 0 { "> " } else { "  " };
+                    Line::from(vec![Span::styled(prefix, style), Span::styled(line.to_string(), style)])
+                })
+                .collect()
+        }
+        Entry::Assistant(text) => text.lines().map(|line| Line::raw(line.to_string())).collect(),
+        Entry::ToolCall { name, input } => vec![Line::styled(
+            format!("🔧 {} {}", name, shorten(&input.to_string(), 120)),
+            Style::default().fg(Color::Yellow),
+        )],
+        Entry::ToolResult { name, content, is_error } => {
+            let (mark, color) = if *is_error { ("✗", Color::Red) } else { ("✓", Color::DarkGray) };
+            let first = content.lines().next().unwrap_or("");
+            vec![Line::styled(
+                format!("   {} {}: {} ({} bytes)", mark, name, shorten(first, 100), content.len()),
+                Style::default().fg(color),
+            )]
+        }
+        Entry::Notice(text) => vec![Line::styled(
+            text.clone(),
+            Style::default().fg(Color::DarkGray).add_modifier(Modifier::ITALIC),
+        )],
+        Entry::Error(text) => vec![Line::styled(format!("Error: {}", text), Style::default().fg(Color::Red))],
+    };
+    if matches!(entry, Entry::User(_) | Entry::Assistant(_)) {
+        lines.push(Line::raw(""));
+    }
+    lines
+}
+
+fn shorten(text: &str, max_chars: usize) -> String {
+    match text.char_indices().nth(max_chars) {
+        Some((end, _)) => format!("{}…", &text[..end]),
+        None => text.to_string(),
+    }
+}
+
+/// Rows `lines` take when wrapped to `width` columns, close enough to keep
+/// the end of the conversation in view
+fn wrapped_height(lines: &[Line], width: u16) -> u16 {
+    let width = usize::from(width.max(1));
+    let rows: usize = lines.iter().map(|line| line.width().max(1).div_ceil(width)).sum();
+    rows.min(usize::from(u16::MAX)) as u16
+}
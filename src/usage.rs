@@ -0,0 +1,136 @@
+use anyhow::{Context, Result};
+use chrono::{DateTime, Utc};
+use crate::models;
+use serde::{Deserialize, Serialize};
+use std::fs::{self, OpenOptions};
+use std::io::Write;
+use std::path::PathBuf;
+use tracing::debug;
+use uuid::Uuid;
+
+/// Token counts for one or more backend requests
+#[derive(Debug, Default, Clone, Copy, Serialize, Deserialize)]
+pub struct TokenUsage {
+    /// Input tokens not read from or written to the prompt cache
+    pub input_tokens: u64,
+    pub output_tokens: u64,
+    /// Input tokens read from the prompt cache
+    #[serde(default, skip_serializing_if = "is_zero")]
+    pub cache_read_tokens: u64,
+    /// Input tokens written to the prompt cache
+    #[serde(default, skip_serializing_if = "is_zero")]
+    pub cache_write_tokens: u64,
+}
+
+fn is_zero(count: &u64) -> bool {
+    *count == 0
+}
+
+impl TokenUsage {
+    pub fn add(&mut self, other: TokenUsage) {
+        self.input_tokens += other.input_tokens;
+        self.output_tokens += other.output_tokens;
+        self.cache_read_tokens += other.cache_read_tokens;
+        self.cache_write_tokens += other.cache_write_tokens;
+    }
+
+    pub fn total(&self) -> u64 {
+        self.input_tokens + self.cache_read_tokens + self.cache_write_tokens + self.output_tokens
+    }
+
+    /// Estimated cost in USD, if the model is in the built-in price table.
+    /// Cache reads are billed at a tenth of the input price and cache writes
+    /// at the input price.
+    pub fn cost(&self, model: &str) -> Option<f64> {
+        let input = self.input_tokens + self.cache_write_tokens;
+        models::lookup(model).map(|info| {
+            info.cost(input, self.output_tokens) + info.cost(self.cache_read_tokens, 0) / 10.0
+        })
+    }
+}
+
+/// One backend request as recorded in the usage ledger
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct UsageRecord {
+    pub timestamp: DateTime<Utc>,
+    pub session_id: Uuid,
+    pub model: String,
+    #[serde(flatten)]
+    pub usage: TokenUsage,
+}
+
+/// Append-only ledger of token usage at `~/.local/share/gamecode/usage.jsonl`
+pub struct UsageLedger;
+
+impl UsageLedger {
+    pub fn ledger_path() -> Result<PathBuf> {
+        let home = home::home_dir().context("Failed to get home directory")?;
+        Ok(home
+            .join(".local")
+            .join("share")
+            .join("gamecode")
+            .join("usage.jsonl"))
+    }
+
+    pub fn record(session_id: &Uuid, model: &str, usage: TokenUsage) -> Result<()> {
+        let path = Self::ledger_path()?;
+        if let Some(parent) = path.parent() {
+            fs::create_dir_all(parent)?;
+        }
+
+        let record = UsageRecord {
+            timestamp: Utc::now(),
+            session_id: *session_id,
+            model: model.to_string(),
+            usage,
+        };
+        let mut file = OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(&path)
+            .with_context(|| format!("Failed to open {}", path.display()))?;
+        writeln!(file, "{}", serde_json::to_string(&record)?)?;
+        Ok(())
+    }
+
+    /// All recorded requests; unreadable lines are skipped
+    pub fn load() -> Result<Vec<UsageRecord>> {
+        let path = Self::ledger_path()?;
+        if !path.exists() {
+            return Ok(Vec::new());
+        }
+
+        let content = fs::read_to_string(&path)
+            .with_context(|| format!("Failed to read {}", path.display()))?;
+        Ok(content
+            .lines()
+            .filter_map(|line| match serde_json::from_str(line) {
+                Ok(record) => Some(record),
+                Err(e) => {
+                    debug!("Skipping malformed usage record: {}", e);
+                    None
+                }
+            })
+            .collect())
+    }
+}
+
+/// One-line summary printed after a request
+pub fn format_summary(model: &str, request: TokenUsage, session: TokenUsage) -> String {
+    let cost = match session.cost(model) {
+        Some(cost) => format!(", ~${:.4}", cost),
+        None => String::new(),
+    };
+    let cache = match (request.cache_read_tokens, request.cache_write_tokens) {
+        (0, 0) => String::new(),
+        (read, written) => format!(" ({} cache read, {} cache write)", read, written),
+    };
+    format!(
+        "📊 {} in{} / {} out tokens (session: {} tokens{})",
+        request.input_tokens,
+        cache,
+        request.output_tokens,
+        session.total(),
+        cost
+    )
+}